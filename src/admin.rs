@@ -0,0 +1,180 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! JSON-RPC server for operators of the signer node.
+//!
+//! It has no authentication, so it should be bound to a loopback address. It serves
+//! `getevidence`, which returns the evidence of misbehavior of round masters recorded since the
+//! node got started, in the same format as the lines of the evidence file, and `getrejections`,
+//! which returns the number of rejected candidate blocks for each reason.
+
+use crate::json_rpc_server::{rpc_error, serve_connection};
+use crate::signer_node::evidence::EvidenceStore;
+use crate::signer_node::validation::CandidateValidator;
+use serde_json::Value;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+pub struct AdminServer {
+    listener: TcpListener,
+    evidence: Arc<EvidenceStore>,
+    validator: Arc<CandidateValidator>,
+}
+
+impl AdminServer {
+    pub fn bind(
+        address: &str,
+        evidence: Arc<EvidenceStore>,
+        validator: Arc<CandidateValidator>,
+    ) -> std::io::Result<Self> {
+        Ok(AdminServer {
+            listener: TcpListener::bind(address)?,
+            evidence,
+            validator,
+        })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serves requests one by one until the process exits.
+    pub fn run(self) {
+        for stream in self.listener.incoming() {
+            let result = stream.and_then(|stream| {
+                serve_connection(stream, &None, |method, params| {
+                    dispatch(&self.evidence, &self.validator, method, params)
+                })
+            });
+            if let Err(e) = result {
+                log::warn!("Failed to handle admin RPC connection: {:?}", e);
+            }
+        }
+    }
+
+    /// Serves requests on a new thread.
+    pub fn spawn(self) -> JoinHandle<()> {
+        std::thread::Builder::new()
+            .name("AdminServerThread".to_string())
+            .spawn(move || self.run())
+            .unwrap()
+    }
+}
+
+/// Calls the method. Errors are JSON-RPC error objects.
+pub fn dispatch(
+    evidence: &EvidenceStore,
+    validator: &CandidateValidator,
+    method: &str,
+    _params: &[Value],
+) -> Result<Value, Value> {
+    match method {
+        "getevidence" => {
+            serde_json::to_value(evidence.records()).map_err(|e| rpc_error(-1, &format!("{:?}", e)))
+        }
+        "getrejections" => serde_json::to_value(validator.rejections())
+            .map_err(|e| rpc_error(-1, &format!("{:?}", e))),
+        _ => Err(rpc_error(-32601, "Method not found")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::federation::Federations;
+    use crate::net::SignerID;
+    use crate::rpc::tests::{safety, MockRpc};
+    use crate::signer_node::evidence::{Evidence, EvidenceRecord};
+    use crate::signer_node::master_signature::SignedBlock;
+    use crate::signer_node::validation::{CandidateContext, HeightCheck};
+    use crate::tests::helper::blocks::{get_block, master_sign};
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::federation;
+    use serde_json::json;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    fn call(address: SocketAddr, method: &str) -> Value {
+        let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": []}).to_string();
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+        let body = &reply[reply.find("\r\n\r\n").unwrap() + 4..];
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[test]
+    fn test_getevidence() {
+        let evidence = Arc::new(EvidenceStore::default());
+        let server = AdminServer::bind(
+            "127.0.0.1:0",
+            evidence.clone(),
+            Arc::new(CandidateValidator::default()),
+        )
+        .unwrap();
+        let address = server.local_addr().unwrap();
+        server.spawn();
+
+        assert_eq!(call(address, "getevidence")["result"], json!([]));
+
+        evidence.record(Evidence::InvalidCompletedBlock {
+            block_height: 1,
+            master: SignerID::new(TEST_KEYS.pubkeys()[0]),
+            candidate: get_block(0),
+            candidate_signature: master_sign(SignedBlock::Candidate, 1, &get_block(0), 0),
+            completed: get_block(1),
+            completed_signature: master_sign(SignedBlock::Completed, 1, &get_block(1), 0),
+            reason: "the block is different from the candidate block".to_string(),
+        });
+        let result = call(address, "getevidence")["result"].clone();
+        let records: Vec<EvidenceRecord> = serde_json::from_value(result).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].evidence, evidence.records()[0].evidence);
+
+        assert_eq!(
+            call(address, "unknown")["error"]["message"],
+            "Method not found"
+        );
+    }
+
+    #[test]
+    fn test_getrejections() {
+        let validator = Arc::new(CandidateValidator::new(vec![Box::new(HeightCheck)]));
+        let server = AdminServer::bind(
+            "127.0.0.1:0",
+            Arc::new(EvidenceStore::default()),
+            validator.clone(),
+        )
+        .unwrap();
+        let address = server.local_addr().unwrap();
+        server.spawn();
+
+        assert_eq!(call(address, "getrejections")["result"], json!({}));
+
+        // The coinbase of the block is for the block height 1.
+        let federations = Federations::new(vec![federation(0)]);
+        let rpc = MockRpc {
+            return_block: safety(get_block(0)),
+        };
+        let context = CandidateContext {
+            block_height: 2,
+            federations: &federations,
+            rpc: &rpc,
+        };
+        assert!(validator.validate(&get_block(0), &context).is_err());
+        assert!(validator.validate(&get_block(0), &context).is_err());
+        assert_eq!(
+            call(address, "getrejections")["result"],
+            json!({"height-mismatch": 2})
+        );
+    }
+}
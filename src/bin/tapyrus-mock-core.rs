@@ -0,0 +1,84 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+extern crate clap;
+extern crate env_logger;
+extern crate tapyrus;
+extern crate tapyrus_signer;
+
+use clap::{App, Arg};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tapyrus::blockdata::block::Block;
+use tapyrus::consensus::encode::deserialize;
+use tapyrus::network::constants::Network;
+use tapyrus::{Address, PublicKey};
+use tapyrus_signer::cli::setup::create_genesis::create_genesis_block;
+use tapyrus_signer::simulation::chain::MockChain;
+use tapyrus_signer::simulation::rpc_server::RpcServer;
+
+/// This command launches the stand-in of tapyrus-core, which serves the RPCs used by signers on an
+/// in-memory chain.
+fn main() {
+    let matches = App::new("tapyrus-mock-core")
+        .args(&[
+            Arg::with_name("bind")
+                .long("bind")
+                .takes_value(true)
+                .default_value("127.0.0.1:12381")
+                .help("address and port to listen on"),
+            Arg::with_name("rpcuser")
+                .long("rpcuser")
+                .takes_value(true)
+                .help("username for the basic authentication"),
+            Arg::with_name("rpcpassword")
+                .long("rpcpassword")
+                .takes_value(true)
+                .help("password for the basic authentication"),
+            Arg::with_name("aggregated-public-key")
+                .long("aggregated-public-key")
+                .takes_value(true)
+                .required_unless("genesis")
+                .help("aggregated public key of the first federation. The genesis block is created with it"),
+            Arg::with_name("genesis")
+                .long("genesis")
+                .takes_value(true)
+                .help("genesis block with a hex format string, which has the aggregated public key of the first federation in the xfield"),
+        ])
+        .get_matches();
+
+    env_logger::init();
+
+    let genesis: Block = match matches.value_of("genesis") {
+        Some(hex) => hex::decode(hex)
+            .ok()
+            .and_then(|raw| deserialize(&raw).ok())
+            .expect("genesis should be a block"),
+        None => {
+            let aggregated_public_key = matches
+                .value_of("aggregated-public-key")
+                .and_then(|hex| PublicKey::from_str(hex).ok())
+                .expect("aggregated-public-key should be a public key");
+            let address = Address::p2pkh(&aggregated_public_key, Network::Dev);
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system time is before the unix epoch")
+                .as_secs() as u32;
+            create_genesis_block(&aggregated_public_key, &address, time)
+        }
+    };
+
+    let server = RpcServer::bind(
+        matches.value_of("bind").unwrap(),
+        MockChain::new(genesis),
+        matches.value_of("rpcuser").map(str::to_string),
+        matches.value_of("rpcpassword").map(str::to_string),
+    )
+    .expect("failed to bind the address");
+    println!(
+        "Listening on {}",
+        server.local_addr().expect("failed to get the address")
+    );
+    server.run();
+}
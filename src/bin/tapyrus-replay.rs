@@ -0,0 +1,89 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+extern crate clap;
+extern crate tapyrus;
+extern crate tapyrus_signer;
+
+use clap::{App, Arg};
+use std::str::FromStr;
+use tapyrus::network::constants::Network;
+use tapyrus::{Address, PublicKey};
+use tapyrus_signer::federation::Federations;
+use tapyrus_signer::signer_node::recorder::read_records;
+use tapyrus_signer::signer_node::replay::{replay, ReplayConnectionManager, ReplayRpc};
+use tapyrus_signer::signer_node::{
+    NodeParameters, SignerNode, ROUND_INTERVAL_DEFAULT_SECS, ROUND_LIMIT_DEFAULT_SECS,
+};
+
+/// This command replays a recording made by tapyrus-signerd with `--record-file`, and reports the
+/// first message which leads to a different state from the recorded one.
+fn main() {
+    let matches = App::new("tapyrus-replay")
+        .args(&[
+            Arg::with_name("recording")
+                .long("recording")
+                .required(true)
+                .takes_value(true)
+                .help("path to the recording"),
+            Arg::with_name("public-key")
+                .long("public-key")
+                .required(true)
+                .takes_value(true)
+                .help("public key of the signer who made the recording"),
+            Arg::with_name("federations-file")
+                .long("federations-file")
+                .required(true)
+                .takes_value(true)
+                .help("path to the federations file of the signer who made the recording"),
+        ])
+        .get_matches();
+
+    let public_key = matches
+        .value_of("public-key")
+        .and_then(|hex| PublicKey::from_str(hex).ok())
+        .expect("public-key should be a public key");
+    let federations_file = matches.value_of("federations-file").unwrap();
+    let federations_toml = std::fs::read_to_string(federations_file)
+        .expect(&format!("Couldn't open {}", federations_file));
+    let federations = Federations::from_pubkey_and_toml(&public_key, &federations_toml)
+        .expect("federations file is invalid");
+    let recording = matches.value_of("recording").unwrap();
+    let records = read_records(recording).expect(&format!("Couldn't read {}", recording));
+
+    // The address is used only when the node creates a block, which it doesn't in replays.
+    let address = Address::p2pkh(&public_key, Network::Prod);
+    let params = NodeParameters::new(
+        address,
+        public_key,
+        ReplayRpc,
+        ROUND_INTERVAL_DEFAULT_SECS,
+        ROUND_LIMIT_DEFAULT_SECS,
+        true,
+        false,
+        federations,
+    );
+    let mut node = SignerNode::new(ReplayConnectionManager, params);
+
+    let report = replay(&mut node, records);
+    println!(
+        "replayed {} messages, skipped {} timer and tip events",
+        report.replayed, report.skipped
+    );
+    match report.divergence {
+        None => println!("no divergence"),
+        Some(divergence) => {
+            println!("first divergence at record {}", divergence.index + 1);
+            println!(
+                "expected: {}",
+                serde_json::to_string(&divergence.expected).unwrap()
+            );
+            println!(
+                "actual: {}",
+                serde_json::to_string(&divergence.actual).unwrap()
+            );
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,23 +1,58 @@
 extern crate tapyrus_signer;
 
-use clap::App;
+use clap::{App, Arg};
 use tapyrus_signer::cli::setup::aggregate::AggregateCommand;
+use tapyrus_signer::cli::setup::check_federations::CheckFederationsCommand;
 use tapyrus_signer::cli::setup::compute_sig::ComputeSigCommand;
+use tapyrus_signer::cli::setup::contribute_psb::ContributePsbCommand;
 use tapyrus_signer::cli::setup::create_block_vss::CreateBlockVssCommand;
+use tapyrus_signer::cli::setup::create_federation::CreateFederationCommand;
+use tapyrus_signer::cli::setup::create_genesis::CreateGenesisCommand;
 use tapyrus_signer::cli::setup::create_key::CreateKeyCommand;
 use tapyrus_signer::cli::setup::create_node_vss::CreateNodeVssCommand;
+use tapyrus_signer::cli::setup::create_psb::CreatePsbCommand;
+use tapyrus_signer::cli::setup::create_recovery_share::CreateRecoveryShareCommand;
+use tapyrus_signer::cli::setup::decode_block::DecodeBlockCommand;
+use tapyrus_signer::cli::setup::decode_vss::DecodeVssCommand;
+use tapyrus_signer::cli::setup::error_to_json;
+use tapyrus_signer::cli::setup::finalize_psb::FinalizePsbCommand;
+use tapyrus_signer::cli::setup::inspect_psb::InspectPsbCommand;
+use tapyrus_signer::cli::setup::recover_share::RecoverShareCommand;
 use tapyrus_signer::cli::setup::sign::SignCommand;
 use tapyrus_signer::cli::setup::traits::Response;
+use tapyrus_signer::cli::setup::verify_evidence::VerifyEvidenceCommand;
+use tapyrus_signer::cli::setup::verify_federation::VerifyFederationCommand;
 use tapyrus_signer::errors::Error;
 
 fn main() {
     let matches = App::new("Setup")
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .help("output format. With json, the result and the error are printed as a JSON document"),
+        )
         .subcommand(CreateKeyCommand::args())
         .subcommand(CreateNodeVssCommand::args())
         .subcommand(AggregateCommand::args())
         .subcommand(CreateBlockVssCommand::args())
         .subcommand(SignCommand::args())
         .subcommand(ComputeSigCommand::args())
+        .subcommand(CreateRecoveryShareCommand::args())
+        .subcommand(RecoverShareCommand::args())
+        .subcommand(CreateGenesisCommand::args())
+        .subcommand(CreatePsbCommand::args())
+        .subcommand(ContributePsbCommand::args())
+        .subcommand(InspectPsbCommand::args())
+        .subcommand(FinalizePsbCommand::args())
+        .subcommand(VerifyFederationCommand::args())
+        .subcommand(CheckFederationsCommand::args())
+        .subcommand(VerifyEvidenceCommand::args())
+        .subcommand(CreateFederationCommand::args())
+        .subcommand(DecodeVssCommand::args())
+        .subcommand(DecodeBlockCommand::args())
         .get_matches();
     let result: Result<Box<dyn Response>, Error> = match matches.subcommand_name() {
         Some("createkey") => CreateKeyCommand::execute(
@@ -48,11 +83,98 @@ fn main() {
                 .subcommand_matches("computesig")
                 .expect("invalid args"),
         ),
-        None => return println!("No subcommand was used"),
+        Some("createrecoveryshare") => CreateRecoveryShareCommand::execute(
+            matches
+                .subcommand_matches("createrecoveryshare")
+                .expect("invalid args"),
+        ),
+        Some("recovershare") => RecoverShareCommand::execute(
+            matches
+                .subcommand_matches("recovershare")
+                .expect("invalid args"),
+        ),
+        Some("creategenesis") => CreateGenesisCommand::execute(
+            matches
+                .subcommand_matches("creategenesis")
+                .expect("invalid args"),
+        ),
+        Some("createpsb") => CreatePsbCommand::execute(
+            matches
+                .subcommand_matches("createpsb")
+                .expect("invalid args"),
+        ),
+        Some("contributepsb") => ContributePsbCommand::execute(
+            matches
+                .subcommand_matches("contributepsb")
+                .expect("invalid args"),
+        ),
+        Some("inspectpsb") => InspectPsbCommand::execute(
+            matches
+                .subcommand_matches("inspectpsb")
+                .expect("invalid args"),
+        ),
+        Some("finalizepsb") => FinalizePsbCommand::execute(
+            matches
+                .subcommand_matches("finalizepsb")
+                .expect("invalid args"),
+        ),
+        Some("verifyfederation") => VerifyFederationCommand::execute(
+            matches
+                .subcommand_matches("verifyfederation")
+                .expect("invalid args"),
+        ),
+        Some("checkfederations") => CheckFederationsCommand::execute(
+            matches
+                .subcommand_matches("checkfederations")
+                .expect("invalid args"),
+        ),
+        Some("verifyevidence") => VerifyEvidenceCommand::execute(
+            matches
+                .subcommand_matches("verifyevidence")
+                .expect("invalid args"),
+        ),
+        Some("createfederation") => CreateFederationCommand::execute(
+            matches
+                .subcommand_matches("createfederation")
+                .expect("invalid args"),
+        ),
+        Some("decodevss") => DecodeVssCommand::execute(
+            matches
+                .subcommand_matches("decodevss")
+                .expect("invalid args"),
+        ),
+        Some("decodeblock") => DecodeBlockCommand::execute(
+            matches
+                .subcommand_matches("decodeblock")
+                .expect("invalid args"),
+        ),
+        None => {
+            eprintln!("No subcommand was used");
+            std::process::exit(1);
+        }
         _ => unreachable!(),
     };
+    let format = match matches.subcommand() {
+        (_, Some(sub_matches)) => sub_matches.value_of("format"),
+        _ => None,
+    }
+    .or(matches.value_of("format"))
+    .unwrap_or("text");
     match result {
-        Ok(response) => println!("{}", response),
-        Err(e) => println!("{}", e),
+        Ok(response) => {
+            if format == "json" {
+                println!("{}", response.to_json());
+            } else {
+                println!("{}", response);
+            }
+        }
+        Err(e) => {
+            if format == "json" {
+                eprintln!("{}", error_to_json(&e));
+            } else {
+                eprintln!("{}", e);
+            }
+            std::process::exit(1);
+        }
     }
 }
@@ -10,17 +10,32 @@ extern crate redis;
 extern crate tapyrus;
 extern crate tapyrus_signer;
 
-use tapyrus::PublicKey;
+use tapyrus::{PrivateKey, PublicKey};
 
 use daemonize::Daemonize;
 use std::fs::OpenOptions;
 use std::path::Path;
 use std::sync::atomic::Ordering;
-use std::time::Duration;
-use tapyrus_signer::command_args::{CommandArgs, RedisConfig, RpcConfig};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tapyrus_signer::admin::AdminServer;
+use tapyrus_signer::command_args::{
+    CommandArgs, GeneralConfig, MiddlewareConfig, RedisConfig, RpcConfig, ValidationConfig,
+};
+use tapyrus_signer::failover::{Endpoint, FailoverRpc, HEALTH_CHECK_TIMEOUT_SECS};
 use tapyrus_signer::federation::Federations;
+use tapyrus_signer::federation_check::check_federations;
+use tapyrus_signer::middleware::{build_layers, LayerSettings, Layered};
 use tapyrus_signer::net::{ConnectionManager, RedisManager};
-use tapyrus_signer::rpc::Rpc;
+use tapyrus_signer::notification::subscribe_hashblock;
+use tapyrus_signer::rpc::{Rpc, TapyrusApi, DEFAULT_RETRY_BACKOFF_MILLIS};
+use tapyrus_signer::signer_node::evidence::EvidenceStore;
+use tapyrus_signer::signer_node::production::BlockProductionPolicy;
+use tapyrus_signer::signer_node::recorder::Recorder;
+use tapyrus_signer::signer_node::schedule::RoundSchedule;
+use tapyrus_signer::signer_node::validation::{
+    build_checks, CandidateValidator, ValidationSettings,
+};
 use tapyrus_signer::signer_node::{NodeParameters, SignerNode};
 use tapyrus_signer::util::{set_stop_signal_handler, signal_to_string};
 
@@ -49,24 +64,65 @@ fn main() {
     let signer_config = configs.signer_config();
 
     let con = connect_signer_network(configs.redis_config());
-    let rpc = connect_rpc(configs.rpc_config());
+    let rpc_config = configs.rpc_config();
+    let rpc = connect_rpc(&rpc_config);
 
     let federations = load_federations(
         &signer_config.public_key(),
         signer_config.federations_file(),
     );
+    check_federations_on_chain(
+        &rpc,
+        &federations,
+        signer_config.federation_check(),
+        signer_config.federation_check_cache(),
+    );
+    let con = add_middleware(con, configs.middleware_config(), &federations);
 
-    let params = NodeParameters::new(
+    let mut params = NodeParameters::new(
         signer_config.to_address(),
         signer_config.public_key(),
         rpc,
         round_duration,
         general_config.round_limit(),
         general_config.skip_waiting_ibd(),
+        general_config.precompute_block_vss(),
         federations,
     );
+    params.private_key = signer_config
+        .private_key_file()
+        .map(|path| load_private_key(&signer_config.public_key(), path));
+    params.candidate_validator = Arc::new(candidate_validator(configs.validation_config()));
+    if let Some(path) = general_config.evidence_file() {
+        params.evidence =
+            Arc::new(EvidenceStore::create(path).expect(&format!("Couldn't open {}", path)));
+    }
+    if let Some(epoch) = general_config.round_schedule_epoch() {
+        if round_duration == 0 {
+            panic!("round-schedule-epoch needs a positive round-duration.");
+        }
+        params.round_schedule = Some(RoundSchedule::new(epoch, round_duration));
+    }
+    params.block_production = block_production_policy(&general_config);
+    if let Some(address) = general_config.admin_rpc_bind() {
+        AdminServer::bind(
+            address,
+            params.evidence.clone(),
+            params.candidate_validator.clone(),
+        )
+        .expect(&format!("Couldn't bind the admin RPC to {}", address))
+        .spawn();
+        log::info!("Admin RPC is served on {}", address);
+    }
 
     let node = &mut SignerNode::new(con, params);
+    if let Some(path) = general_config.record_file() {
+        let recorder = Recorder::create(path).expect(&format!("Couldn't open {}", path));
+        node.set_recorder(recorder);
+    }
+    if let Some(endpoint) = rpc_config.zmq_pub_hashblock() {
+        node.set_tip_notifications(subscribe_hashblock(endpoint));
+    }
     node.start();
 }
 
@@ -92,14 +148,43 @@ fn daemonize(pid: &str, log_file: &str) {
     }
 }
 
-fn connect_rpc(rpc_config: RpcConfig) -> Rpc {
-    let url = format!("http://{}:{}", rpc_config.host(), rpc_config.port());
+fn connect_rpc(rpc_config: &RpcConfig) -> FailoverRpc<Rpc> {
+    let urls = rpc_config.endpoints();
     let user = rpc_config.user_name().map(str::to_string);
     let pass = rpc_config.password().map(str::to_string);
-    let rpc = tapyrus_signer::rpc::Rpc::new(url.clone(), user.clone(), pass);
+    let endpoints = urls
+        .iter()
+        .map(|url| {
+            let client = || match rpc_config.cookie_file() {
+                Some(path) => Rpc::with_cookie_file(url.clone(), path.to_path_buf()),
+                None => Rpc::new(url.clone(), user.clone(), pass.clone()),
+            };
+            let rpc = client()
+                .timeout(Duration::from_secs(rpc_config.timeout()))
+                .retries(
+                    rpc_config.retries(),
+                    Duration::from_millis(DEFAULT_RETRY_BACKOFF_MILLIS),
+                );
+            let rpc = rpc_config
+                .method_timeouts()
+                .iter()
+                .fold(rpc, |rpc, (method, secs)| {
+                    rpc.method_timeout(method, Duration::from_secs(*secs))
+                });
+            let health_check_api = client()
+                .timeout(Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS))
+                .retries(0, Duration::from_millis(0));
+            Endpoint {
+                name: url.clone(),
+                api: rpc,
+                health_check_api,
+            }
+        })
+        .collect();
+    let rpc = FailoverRpc::new(endpoints);
     rpc.test_connection().expect(&format!(
         "RPC connect failed. Please confirm RPC connection info. url: {}, user: '{}' ,",
-        url,
+        urls.join(", "),
         user.unwrap_or("".to_string())
     ));
     rpc
@@ -136,6 +221,61 @@ fn start_unix_signal_handling() {
     });
 }
 
+fn add_middleware<C: ConnectionManager>(
+    con: C,
+    config: MiddlewareConfig,
+    federations: &Federations,
+) -> Layered<C> {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    let settings = LayerSettings {
+        metrics_interval: Duration::from_secs(config.metrics_interval()),
+        rate_limit: config.rate_limit(),
+        drop_rate: config.drop_rate(),
+        seed,
+    };
+    let layers = build_layers(&config.layers(), &settings, federations)
+        .unwrap_or_else(|e| panic!("middleware: {:?}", e));
+    Layered::new(con, layers)
+}
+
+fn candidate_validator(config: ValidationConfig) -> CandidateValidator {
+    let settings = ValidationSettings {
+        max_time_skew: config.max_time_skew(),
+        max_block_size: config.max_block_size(),
+        coinbase_addresses: config.coinbase_addresses(),
+    };
+    let checks =
+        build_checks(&config.checks(), &settings).unwrap_or_else(|e| panic!("validation: {:?}", e));
+    let validator = CandidateValidator::new(checks);
+    log::info!("Candidate block checks: {:?}", validator.names());
+    validator
+}
+
+fn block_production_policy(config: &GeneralConfig) -> BlockProductionPolicy {
+    let name = config.block_production();
+    let policy = BlockProductionPolicy::from_name(name, config.max_block_interval())
+        .unwrap_or_else(|| panic!("block-production: unknown policy {}", name));
+    log::info!("Block production policy: {:?}", policy);
+    policy
+}
+
+fn load_private_key(pubkey: &PublicKey, path: &Path) -> PrivateKey {
+    let wif = std::fs::read_to_string(path).expect(&format!(
+        "Can't open private_key_file. path: {:?} Error",
+        path
+    ));
+    let private_key = PrivateKey::from_wif(wif.trim())
+        .unwrap_or_else(|e| panic!("private_key_file: Invalid WIF. {:?}", e));
+    let secp = tapyrus::secp256k1::Secp256k1::new();
+    if PublicKey::from_private_key(&secp, &private_key) != *pubkey {
+        panic!("private_key_file: The private key is not for the public key.");
+    }
+    private_key
+}
+
 fn load_federations(pubkey: &PublicKey, path: &Path) -> Federations {
     let federations_toml = std::fs::read_to_string(path).expect(&format!(
         "Can't open federations_file. path: {:?} Error",
@@ -161,6 +301,46 @@ fn load_federations(pubkey: &PublicKey, path: &Path) -> Federations {
     }
 }
 
+/// Compares the federations with the chain. With the "strict" check, the node refuses to start on
+/// divergences, because the rounds with the wrong aggregated public key would fail forever. RPC
+/// errors only get logged even then, so that a hiccup of tapyrus-core doesn't stop the node.
+fn check_federations_on_chain<T: TapyrusApi>(
+    rpc: &T,
+    federations: &Federations,
+    check: &str,
+    cache: Option<&Path>,
+) {
+    let strict = match check {
+        "strict" => true,
+        "warn" => false,
+        "skip" => return,
+        _ => panic!(
+            "federation-check must be one of strict, warn and skip. value: {}",
+            check
+        ),
+    };
+    log::info!("Check the federations against the chain.");
+    let divergences = match check_federations(rpc, federations, cache) {
+        Ok(divergences) => divergences,
+        Err(e) => {
+            log::warn!(
+                "federations_file: Failed to check against the chain. {:?}",
+                e
+            );
+            return;
+        }
+    };
+    for divergence in &divergences {
+        log::error!("federations_file: Diverges from the chain. {}", divergence);
+    }
+    if strict && !divergences.is_empty() {
+        panic!(
+            "federations_file: {} divergences from the chain. Please fix the federations file or start with `--federation-check warn`.",
+            divergences.len()
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{connect_rpc, connect_signer_network, load_federations};
@@ -181,11 +361,15 @@ mod tests {
                 port: Some("9999"),
                 username: None,
                 password: None,
+                cookie_file: None,
+                timeout: None,
+                retries: None,
+                zmq_pub_hashblock: None,
             },
             toml_config: None,
         };
 
-        connect_rpc(config);
+        connect_rpc(&config);
     }
 
     #[test]
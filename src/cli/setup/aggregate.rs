@@ -1,4 +1,6 @@
 use crate::cli::setup::index_of;
+use crate::cli::setup::list_values_of;
+use crate::cli::setup::secret_value_of;
 use crate::cli::setup::traits::Response;
 use crate::cli::setup::vss_to_shared_secret_map;
 use crate::crypto::vss::Vss;
@@ -12,6 +14,7 @@ use curv::cryptographic_primitives::secret_sharing::feldman_vss::ShamirSecretSha
 use curv::elliptic::curves::traits::ECPoint;
 use curv::elliptic::curves::traits::ECScalar;
 use curv::FE;
+use serde_json::{json, Value};
 use std::fmt;
 use std::str::FromStr;
 use tapyrus::{PrivateKey, PublicKey};
@@ -30,7 +33,15 @@ impl AggregateResponse {
     }
 }
 
-impl Response for AggregateResponse {}
+impl Response for AggregateResponse {
+    fn to_json(&self) -> Value {
+        let secret = format!("{:0>64}", self.node_shared_secret.to_big_int().to_hex());
+        json!({
+            "aggregated-public-key": self.aggregated_public_key.to_string(),
+            "node-secret-share": secret,
+        })
+    }
+}
 
 impl fmt::Display for AggregateResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -43,14 +54,13 @@ pub struct AggregateCommand {}
 
 impl<'a> AggregateCommand {
     pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
-        let private_key: PrivateKey = matches
-            .value_of("private-key")
-            .and_then(|key| PrivateKey::from_wif(key).ok())
+        let private_key: PrivateKey = secret_value_of(matches, "private-key")?
+            .and_then(|key| PrivateKey::from_wif(&key).ok())
             .ok_or(Error::InvalidArgs("private-key".to_string()))?;
 
-        let vss_vec: Vec<Vss> = matches
-            .values_of("vss")
+        let vss_vec: Vec<Vss> = list_values_of(matches, "vss")?
             .ok_or(Error::InvalidArgs("vss is invalid".to_string()))?
+            .iter()
             .map(|s| Vss::from_str(s).map_err(|_| Error::InvalidSS))
             .collect::<Result<Vec<Vss>, _>>()?;
 
@@ -86,12 +96,12 @@ impl<'a> AggregateCommand {
                 .required(true)
                 .multiple(true)
                 .takes_value(true)
-                .help("secret values (Vss) of the all signers. These values is generated by `tapyrus-setup createnodevss`"),
+                .help("secret values (Vss) of the all signers. These values is generated by `tapyrus-setup createnodevss` (`@<path>` reads them from the file which has one value per line or a JSON array)"),
             Arg::with_name("private-key")
                 .long("private-key")
                 .required(true)
                 .takes_value(true)
-                .help("private key of this signer with an extend WIF format"),
+                .help("private key of this signer with an extend WIF format (`@<path>` or `-` reads it from the file or stdin)"),
         ])
     }
 }
@@ -0,0 +1,271 @@
+use crate::cli::setup::secret_value_of;
+use crate::cli::setup::traits::Response;
+use crate::command_args::{DEFAULT_RPC_HOST, DEFAULT_RPC_PORT};
+use crate::errors::Error;
+use crate::federation::Federations;
+use crate::federation_check::{aggregated_public_key_changes, compare, walk_start, Divergence};
+use crate::rpc::Rpc;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tapyrus::PublicKey;
+
+pub struct CheckFederationsResponse {
+    tip: u64,
+    divergences: Vec<Divergence>,
+}
+
+impl CheckFederationsResponse {
+    fn new(tip: u64, divergences: Vec<Divergence>) -> Self {
+        CheckFederationsResponse {
+            tip: tip,
+            divergences: divergences,
+        }
+    }
+}
+
+impl Response for CheckFederationsResponse {
+    fn to_json(&self) -> Value {
+        let divergences: Vec<Value> = self
+            .divergences
+            .iter()
+            .map(|divergence| {
+                json!({
+                    "block-height": divergence.block_height(),
+                    "message": divergence.to_string(),
+                })
+            })
+            .collect();
+        json!({
+            "consistent": self.divergences.is_empty(),
+            "tip": self.tip,
+            "divergences": divergences,
+        })
+    }
+}
+
+impl fmt::Display for CheckFederationsResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "checked block headers up to height {}", self.tip)?;
+        if self.divergences.is_empty() {
+            return write!(f, "result: consistent");
+        }
+        writeln!(f, "divergences:")?;
+        for divergence in &self.divergences {
+            writeln!(
+                f,
+                "  at block height {}: {}",
+                divergence.block_height(),
+                divergence
+            )?;
+        }
+        write!(
+            f,
+            "result: inconsistent ({} divergences)",
+            self.divergences.len()
+        )
+    }
+}
+
+/// Compares the federations file with the aggregated public keys in the xfields of the blocks on
+/// the chain, like tapyrus-signerd does at startup.
+pub struct CheckFederationsCommand {}
+
+impl<'a> CheckFederationsCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let public_key: PublicKey = matches
+            .value_of("public-key")
+            .and_then(|hex| PublicKey::from_str(hex).ok())
+            .ok_or(Error::InvalidArgs("public-key".to_string()))?;
+
+        let path = matches
+            .value_of("federations-file")
+            .ok_or(Error::InvalidArgs("federations-file".to_string()))?;
+
+        let toml = std::fs::read_to_string(path)?;
+        let federations = Federations::from_pubkey_and_toml_without_validation(&public_key, &toml)?;
+
+        let url = format!(
+            "http://{}:{}",
+            matches.value_of("rpc-host").unwrap_or(DEFAULT_RPC_HOST),
+            matches.value_of("rpc-port").unwrap_or(DEFAULT_RPC_PORT)
+        );
+        let rpc = match matches.value_of("rpc-cookie-file") {
+            Some(path) => Rpc::with_cookie_file(url, PathBuf::from(path)),
+            None => Rpc::new(
+                url,
+                matches.value_of("rpc-user").map(str::to_string),
+                secret_value_of(matches, "rpc-pass")?,
+            ),
+        };
+        let checked = aggregated_public_key_changes(&rpc, walk_start(&federations), None)?;
+        Ok(Box::new(CheckFederationsResponse::new(
+            checked.height,
+            compare(&federations, &checked.changes, checked.height),
+        )))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("checkfederations").args(&[
+            Arg::with_name("public-key")
+                .long("public-key")
+                .required(true)
+                .takes_value(true)
+                .help("compressed public key of the signer who runs the node with a hex format string"),
+            Arg::with_name("federations-file")
+                .long("federations-file")
+                .required(true)
+                .takes_value(true)
+                .help("path to the federations file to be checked"),
+            Arg::with_name("rpc-host")
+                .long("rpc-host")
+                .takes_value(true)
+                .help("TapyrusCore RPC endpoint host. The default is 127.0.0.1"),
+            Arg::with_name("rpc-port")
+                .long("rpc-port")
+                .takes_value(true)
+                .help("TapyrusCore RPC endpoint port number. The default is 2377"),
+            Arg::with_name("rpc-user")
+                .long("rpc-user")
+                .takes_value(true)
+                .help("TapyrusCore RPC user name"),
+            Arg::with_name("rpc-pass")
+                .long("rpc-pass")
+                .takes_value(true)
+                .help("TapyrusCore RPC user password (`@<path>` or `-` reads it from the file or stdin)"),
+            Arg::with_name("rpc-cookie-file")
+                .long("rpc-cookie-file")
+                .takes_value(true)
+                .conflicts_with_all(&["rpc-user", "rpc-pass"])
+                .help("path to the .cookie file of TapyrusCore, which is used instead of rpc-user and rpc-pass"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::setup::create_genesis::create_genesis_block;
+    use crate::simulation::chain::MockChain;
+    use crate::simulation::rpc_server::RpcServer;
+    use crate::tests::helper::address;
+    use crate::tests::helper::keys::TEST_KEYS;
+
+    fn execute(toml: &str, file_name: &str) -> Value {
+        execute_with_auth(toml, file_name, None, vec![])
+    }
+
+    /// Runs the command against the server which requires the credential with the args to pass
+    /// it.
+    fn execute_with_auth(
+        toml: &str,
+        file_name: &str,
+        credential: Option<(&str, &str)>,
+        auth_args: Vec<String>,
+    ) -> Value {
+        let genesis = create_genesis_block(
+            &TEST_KEYS.aggregated(),
+            &address(&TEST_KEYS.key[0]),
+            1585197531,
+        );
+        let server = RpcServer::bind(
+            "127.0.0.1:0",
+            MockChain::new(genesis),
+            credential.map(|(user, _)| user.to_string()),
+            credential.map(|(_, pass)| pass.to_string()),
+        )
+        .unwrap();
+        let port = server.local_addr().unwrap().port().to_string();
+        server.spawn();
+
+        let path = std::env::temp_dir().join(file_name);
+        std::fs::write(&path, toml).unwrap();
+        let public_key = TEST_KEYS.pubkeys()[0].to_string();
+        let mut args = vec![
+            "checkfederations".to_string(),
+            "--public-key".to_string(),
+            public_key,
+            "--federations-file".to_string(),
+            path.to_str().unwrap().to_string(),
+            "--rpc-host".to_string(),
+            "127.0.0.1".to_string(),
+            "--rpc-port".to_string(),
+            port,
+        ];
+        args.extend(auth_args);
+        let matches = CheckFederationsCommand::args().get_matches_from(args);
+        let response = CheckFederationsCommand::execute(&matches);
+        std::fs::remove_file(&path).unwrap();
+        response.unwrap().to_json()
+    }
+
+    #[test]
+    fn test_execute() {
+        let toml = format!(
+            "[[federation]]\nblock-height = 0\naggregated-public-key = \"{}\"\n",
+            TEST_KEYS.aggregated()
+        );
+        let json = execute(&toml, "checkfederations_test_execute.toml");
+        assert_eq!(json["consistent"], true);
+        assert_eq!(json["tip"], 0);
+    }
+
+    #[test]
+    fn test_execute_wrong_aggregated_public_key() {
+        let toml = format!(
+            "[[federation]]\nblock-height = 0\naggregated-public-key = \"{}\"\n",
+            TEST_KEYS.pubkeys()[0]
+        );
+        let json = execute(
+            &toml,
+            "checkfederations_test_execute_wrong_aggregated_public_key.toml",
+        );
+        assert_eq!(json["consistent"], false);
+        assert_eq!(json["divergences"][0]["block-height"], 0);
+    }
+
+    #[test]
+    fn test_execute_with_secret() {
+        let toml = format!(
+            "[[federation]]\nblock-height = 0\naggregated-public-key = \"{}\"\n",
+            TEST_KEYS.aggregated()
+        );
+        let pass_path = std::env::temp_dir().join("checkfederations_test_rpc_pass.txt");
+        std::fs::write(&pass_path, "pass\n").unwrap();
+        let json = execute_with_auth(
+            &toml,
+            "checkfederations_test_execute_with_secret.toml",
+            Some(("user", "pass")),
+            vec![
+                "--rpc-user".to_string(),
+                "user".to_string(),
+                format!("--rpc-pass=@{}", pass_path.to_str().unwrap()),
+            ],
+        );
+        std::fs::remove_file(&pass_path).unwrap();
+        assert_eq!(json["consistent"], true);
+    }
+
+    #[test]
+    fn test_execute_with_cookie_file() {
+        let toml = format!(
+            "[[federation]]\nblock-height = 0\naggregated-public-key = \"{}\"\n",
+            TEST_KEYS.aggregated()
+        );
+        let cookie_path = std::env::temp_dir().join("checkfederations_test_rpc_cookie.cookie");
+        std::fs::write(&cookie_path, "__cookie__:secret").unwrap();
+        let json = execute_with_auth(
+            &toml,
+            "checkfederations_test_execute_with_cookie_file.toml",
+            Some(("__cookie__", "secret")),
+            vec![format!(
+                "--rpc-cookie-file={}",
+                cookie_path.to_str().unwrap()
+            )],
+        );
+        std::fs::remove_file(&cookie_path).unwrap();
+        assert_eq!(json["consistent"], true);
+    }
+}
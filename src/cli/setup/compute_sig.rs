@@ -1,4 +1,6 @@
 use crate::cli::setup::index_of;
+use crate::cli::setup::list_values_of;
+use crate::cli::setup::secret_value_of;
 use crate::cli::setup::traits::Response;
 use crate::cli::setup::vss_to_bidirectional_shared_secret_map;
 use crate::cli::setup::vss_to_shared_secret_map;
@@ -15,6 +17,7 @@ use clap::{App, Arg, ArgMatches, SubCommand};
 use curv::cryptographic_primitives::secret_sharing::feldman_vss::ShamirSecretSharing;
 use curv::elliptic::curves::traits::{ECPoint, ECScalar};
 use curv::{BigInt, FE, GE};
+use serde_json::{json, Value};
 use std::collections::BTreeMap;
 use std::fmt;
 use std::str::FromStr;
@@ -34,7 +37,11 @@ impl ComputeSigResponse {
     }
 }
 
-impl Response for ComputeSigResponse {}
+impl Response for ComputeSigResponse {
+    fn to_json(&self) -> Value {
+        json!({ "block": hex::encode(serialize(&self.block_with_signature)) })
+    }
+}
 
 impl fmt::Display for ComputeSigResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -46,9 +53,8 @@ pub struct ComputeSigCommand {}
 
 impl<'a> ComputeSigCommand {
     pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
-        let private_key: PrivateKey = matches
-            .value_of("private-key")
-            .and_then(|key| PrivateKey::from_wif(key).ok())
+        let private_key: PrivateKey = secret_value_of(matches, "private-key")?
+            .and_then(|key| PrivateKey::from_wif(&key).ok())
             .ok_or(Error::InvalidArgs("private-key".to_string()))?;
 
         let threshold: usize = matches
@@ -61,9 +67,8 @@ impl<'a> ComputeSigCommand {
             .and_then(|hex| PublicKey::from_str(hex).ok())
             .ok_or(Error::InvalidArgs("aggregated-public-key".to_string()))?;
 
-        let node_secret_share: FE = matches
-            .value_of("node-secret-share")
-            .and_then(|s| BigInt::from_str_radix(s, 16).ok())
+        let node_secret_share: FE = secret_value_of(matches, "node-secret-share")?
+            .and_then(|s| BigInt::from_str_radix(&s, 16).ok())
             .map(|i| ECScalar::from(&i))
             .ok_or(Error::InvalidArgs("node-secret-share".to_string()))?;
 
@@ -73,21 +78,21 @@ impl<'a> ComputeSigCommand {
             .and_then(|hex| deserialize::<Block>(&hex).ok())
             .ok_or(Error::InvalidArgs("block".to_string()))?;
 
-        let node_vss_vec: Vec<Vss> = matches
-            .values_of("node-vss")
+        let node_vss_vec: Vec<Vss> = list_values_of(matches, "node-vss")?
             .ok_or(Error::InvalidArgs("node-vss".to_string()))?
+            .iter()
             .map(|s| Vss::from_str(s).map_err(|_| Error::InvalidArgs("node-vss".to_string())))
             .collect::<Result<Vec<Vss>, _>>()?;
 
-        let block_vss_vec: Vec<Vss> = matches
-            .values_of("block-vss")
+        let block_vss_vec: Vec<Vss> = list_values_of(matches, "block-vss")?
             .ok_or(Error::InvalidArgs("block-vss".to_string()))?
+            .iter()
             .map(|s| Vss::from_str(s).map_err(|_| Error::InvalidArgs("block-vss".to_string())))
             .collect::<Result<Vec<Vss>, _>>()?;
 
-        let keyed_local_sigs: Vec<(LocalSig, PublicKey)> = matches
-            .values_of("sig")
+        let keyed_local_sigs: Vec<(LocalSig, PublicKey)> = list_values_of(matches, "sig")?
             .ok_or(Error::InvalidArgs("local-sig is invalid".to_string()))?
+            .iter()
             .map(|s| {
                 if s.len() != 194 {
                     return Err(Error::InvalidArgs("sig".to_string()));
@@ -181,7 +186,7 @@ impl<'a> ComputeSigCommand {
                 .long("private-key")
                 .required(true)
                 .takes_value(true)
-                .help("private key of this signer with an extend WIF format"),
+                .help("private key of this signer with an extend WIF format (`@<path>` or `-` reads it from the file or stdin)"),
             Arg::with_name("threshold")
                 .long("threshold")
                 .required(true)
@@ -196,7 +201,7 @@ impl<'a> ComputeSigCommand {
                 .long("node-secret-share")
                 .required(true)
                 .takes_value(true)
-                .help("secret key share of the signers with a hex string format"),
+                .help("secret key share of the signers with a hex string format (`@<path>` or `-` reads it from the file or stdin)"),
             Arg::with_name("aggregated-public-key")
                 .long("aggregated-public-key")
                 .required(true)
@@ -207,19 +212,19 @@ impl<'a> ComputeSigCommand {
                 .required(true)
                 .multiple(true)
                 .takes_value(true)
-                .help("the node VSSs generated by tapyrus-setup createnodevss command"),
+                .help("the node VSSs generated by tapyrus-setup createnodevss command (`@<path>` reads them from the file which has one value per line or a JSON array)"),
             Arg::with_name("block-vss")
                 .long("block-vss")
                 .required(true)
                 .multiple(true)
                 .takes_value(true)
-                .help("the block VSSs generated by tapyrus-setup createblockvss command"),
+                .help("the block VSSs generated by tapyrus-setup createblockvss command (`@<path>` reads them from the file which has one value per line or a JSON array)"),
             Arg::with_name("sig")
                 .long("sig")
                 .required(true)
                 .multiple(true)
                 .takes_value(true)
-                .help("the local signatures generated by tapyrus-setup sign command"),
+                .help("the local signatures generated by tapyrus-setup sign command (`@<path>` reads them from the file which has one value per line or a JSON array)"),
         ])
     }
 }
@@ -0,0 +1,123 @@
+use crate::cli::setup::inspect_psb::InspectPsbResponse;
+use crate::cli::setup::psb::PartiallySignedBlock;
+use crate::cli::setup::secret_value_of;
+use crate::cli::setup::traits::Response;
+use crate::errors::Error;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use curv::elliptic::curves::traits::ECScalar;
+use curv::{BigInt, FE};
+use tapyrus::PrivateKey;
+
+pub struct ContributePsbCommand {}
+
+impl<'a> ContributePsbCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let path = matches
+            .value_of("psb")
+            .ok_or(Error::InvalidArgs("psb".to_string()))?;
+
+        let private_key: PrivateKey = secret_value_of(matches, "private-key")?
+            .and_then(|key| PrivateKey::from_wif(&key).ok())
+            .ok_or(Error::InvalidArgs("private-key".to_string()))?;
+
+        let node_secret_share: Option<FE> = match secret_value_of(matches, "node-secret-share")? {
+            Some(s) => Some(
+                BigInt::from_str_radix(&s, 16)
+                    .map(|i| ECScalar::from(&i))
+                    .map_err(|_| Error::InvalidArgs("node-secret-share".to_string()))?,
+            ),
+            None => None,
+        };
+
+        let mut psb = PartiallySignedBlock::read(path)?;
+        let mut updated = psb.contribute_block_vss(&private_key)?;
+        if let Some(node_secret_share) = node_secret_share {
+            updated |= psb.contribute_local_sig(&private_key, &node_secret_share)?;
+        }
+        if updated {
+            psb.write(path, false)?;
+        }
+        Ok(Box::new(InspectPsbResponse::new(psb)))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("contributepsb").args(&[
+            Arg::with_name("psb")
+                .long("psb")
+                .required(true)
+                .takes_value(true)
+                .help("path to the partially signed block file, which is updated in place"),
+            Arg::with_name("private-key")
+                .long("private-key")
+                .required(true)
+                .takes_value(true)
+                .help("private key of this signer with an extend WIF format (`@<path>` or `-` reads it from the file or stdin)"),
+            Arg::with_name("node-secret-share")
+                .long("node-secret-share")
+                .takes_value(true)
+                .help("secret key share of the signers with a hex string format. If it is given, the local signature is added once all block VSSs are collected (`@<path>` or `-` reads it from the file or stdin)"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::blocks::get_block;
+    use crate::tests::helper::keys::TEST_KEYS;
+
+    fn create_psb(path: &str) {
+        PartiallySignedBlock::new(get_block(0), 3, TEST_KEYS.aggregated(), TEST_KEYS.pubkeys())
+            .unwrap()
+            .write(path, false)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_execute() {
+        let path = std::env::temp_dir().join("contributepsb_test_execute.json");
+        let path = path.to_str().unwrap();
+        create_psb(path);
+
+        let wif = TEST_KEYS.key[0].to_wif();
+        let matches = ContributePsbCommand::args().get_matches_from(vec![
+            "contributepsb",
+            "--psb",
+            path,
+            "--private-key",
+            &wif,
+        ]);
+        let response = ContributePsbCommand::execute(&matches);
+        assert!(response.is_ok());
+
+        let psb = PartiallySignedBlock::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(psb.has_block_vss(&TEST_KEYS.pubkeys()[0]));
+        assert!(!psb.has_block_vss(&TEST_KEYS.pubkeys()[1]));
+    }
+
+    #[test]
+    fn test_execute_invalid_node_secret_share() {
+        let path = std::env::temp_dir().join("contributepsb_test_execute_invalid_nss.json");
+        let path = path.to_str().unwrap();
+        create_psb(path);
+
+        let wif = TEST_KEYS.key[0].to_wif();
+        let matches = ContributePsbCommand::args().get_matches_from(vec![
+            "contributepsb",
+            "--psb",
+            path,
+            "--private-key",
+            &wif,
+            "--node-secret-share",
+            "x",
+        ]);
+        let response = ContributePsbCommand::execute(&matches);
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidArgs(\"node-secret-share\")"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::cli::setup::index_of;
+use crate::cli::setup::secret_value_of;
 use crate::cli::setup::traits::Response;
 use crate::crypto::vss::{Commitment, Vss};
 use crate::errors::Error;
@@ -6,6 +7,7 @@ use crate::rpc::Rpc;
 use crate::signer_node::NodeParameters;
 
 use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
 use std::collections::BTreeMap;
 use std::fmt;
 use std::str::FromStr;
@@ -21,7 +23,16 @@ impl CreateBlockVssResponse {
     }
 }
 
-impl Response for CreateBlockVssResponse {}
+impl Response for CreateBlockVssResponse {
+    fn to_json(&self) -> Value {
+        let vss: Vec<Value> = self
+            .vss
+            .iter()
+            .map(|(k, v)| json!({ "public-key": k.to_string(), "vss": v.to_string() }))
+            .collect();
+        json!({ "vss": vss })
+    }
+}
 
 impl fmt::Display for CreateBlockVssResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -39,9 +50,8 @@ pub struct CreateBlockVssCommand {}
 
 impl<'a> CreateBlockVssCommand {
     pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
-        let private_key: PrivateKey = matches
-            .value_of("private-key")
-            .and_then(|key| PrivateKey::from_wif(key).ok())
+        let private_key: PrivateKey = secret_value_of(matches, "private-key")?
+            .and_then(|key| PrivateKey::from_wif(&key).ok())
             .ok_or(Error::InvalidArgs("private-key".to_string()))?;
 
         let mut public_keys: Vec<PublicKey> = matches
@@ -104,7 +114,7 @@ impl<'a> CreateBlockVssCommand {
                 .long("private-key")
                 .required(true)
                 .takes_value(true)
-                .help("private key of this signer with an extend WIF format"),
+                .help("private key of this signer with an extend WIF format (`@<path>` or `-` reads it from the file or stdin)"),
             Arg::with_name("threshold")
                 .long("threshold")
                 .required(true)
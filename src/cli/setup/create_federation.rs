@@ -0,0 +1,257 @@
+use crate::cli::setup::list_values_of;
+use crate::cli::setup::traits::Response;
+use crate::crypto::vss::Vss;
+use crate::errors::Error;
+use crate::federation::{Federation, Federations};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use tapyrus::PublicKey;
+
+pub struct CreateFederationResponse {
+    block_height: u64,
+    entry: String,
+}
+
+impl CreateFederationResponse {
+    fn new(block_height: u64, entry: String) -> Self {
+        CreateFederationResponse {
+            block_height: block_height,
+            entry: entry,
+        }
+    }
+}
+
+impl Response for CreateFederationResponse {
+    fn to_json(&self) -> Value {
+        json!({
+            "block-height": self.block_height,
+            "entry": self.entry,
+        })
+    }
+}
+
+impl fmt::Display for CreateFederationResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.entry.trim_end())
+    }
+}
+
+pub struct CreateFederationCommand {}
+
+impl<'a> CreateFederationCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let public_key: PublicKey = matches
+            .value_of("public-key")
+            .and_then(|hex| PublicKey::from_str(hex).ok())
+            .ok_or(Error::InvalidArgs("public-key".to_string()))?;
+
+        let block_height: u64 = matches
+            .value_of("block-height")
+            .and_then(|s| s.parse::<u64>().ok())
+            .ok_or(Error::InvalidArgs("block-height".to_string()))?;
+
+        let threshold: u8 = matches
+            .value_of("threshold")
+            .and_then(|s| s.parse::<u8>().ok())
+            .ok_or(Error::InvalidArgs("threshold".to_string()))?;
+
+        let aggregated_public_key: PublicKey = matches
+            .value_of("aggregated-public-key")
+            .and_then(|hex| PublicKey::from_str(hex).ok())
+            .ok_or(Error::InvalidArgs("aggregated-public-key".to_string()))?;
+
+        let node_vss: Vec<Vss> = list_values_of(matches, "node-vss")?
+            .ok_or(Error::InvalidArgs("node-vss".to_string()))?
+            .iter()
+            .map(|s| Vss::from_str(s).map_err(|_| Error::InvalidArgs("node-vss".to_string())))
+            .collect::<Result<Vec<Vss>, _>>()?;
+
+        let path = matches
+            .value_of("federations-file")
+            .ok_or(Error::InvalidArgs("federations-file".to_string()))?;
+
+        let federation = Federation::new(
+            public_key,
+            block_height,
+            Some(threshold),
+            Some(node_vss),
+            aggregated_public_key,
+        );
+        federation.validate()?;
+        federation.validate_aggregated_public_key()?;
+
+        // The federations file is created if it doesn't exist yet.
+        let current = if Path::new(path).exists() {
+            std::fs::read_to_string(path)?
+        } else {
+            String::new()
+        };
+        if !current.trim().is_empty() {
+            let federations =
+                Federations::from_pubkey_and_toml_without_validation(&public_key, &current)?;
+            if federations.iter().any(|f| f.block_height() == block_height) {
+                return Err(Error::InvalidFederation(
+                    Some(block_height),
+                    "The federations file already has the federation at the block height.",
+                ));
+            }
+            if federations.len() > 0 && federations.last().block_height() > block_height {
+                return Err(Error::InvalidFederation(
+                    Some(block_height),
+                    "The block height should be higher than the one of the last federation in the federations file.",
+                ));
+            }
+        }
+
+        let entry = federation.to_toml()?;
+        let mut contents = current;
+        if !contents.is_empty() {
+            if !contents.ends_with('\n') {
+                contents.push('\n');
+            }
+            contents.push('\n');
+        }
+        contents.push_str(&entry);
+        std::fs::write(path, contents)?;
+
+        Ok(Box::new(CreateFederationResponse::new(block_height, entry)))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("createfederation").args(&[
+            Arg::with_name("public-key")
+                .long("public-key")
+                .required(true)
+                .takes_value(true)
+                .help("compressed public key of the signer who runs the node with a hex format string"),
+            Arg::with_name("block-height")
+                .long("block-height")
+                .required(true)
+                .takes_value(true)
+                .help("the block height where the federation gets started"),
+            Arg::with_name("threshold")
+                .long("threshold")
+                .required(true)
+                .takes_value(true)
+                .help("the minimum number of signers required to sign block"),
+            Arg::with_name("aggregated-public-key")
+                .long("aggregated-public-key")
+                .required(true)
+                .takes_value(true)
+                .help("aggregated public key of all signers, which is generated by `tapyrus-setup aggregate`"),
+            Arg::with_name("node-vss")
+                .long("node-vss")
+                .required(true)
+                .multiple(true)
+                .takes_value(true)
+                .help("node vss which this signer received from the all signers (`@<path>` reads them from the file which has one value per line or a JSON array)"),
+            Arg::with_name("federations-file")
+                .long("federations-file")
+                .required(true)
+                .takes_value(true)
+                .help("path to the federations file which the federation is appended to. It is created if it doesn't exist"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::NODE_VSS;
+
+    fn args<'a>(public_key: &'a str, block_height: &'a str, path: &'a str) -> Vec<&'a str> {
+        let mut args = vec![
+            "createfederation",
+            "--public-key",
+            public_key,
+            "--block-height",
+            block_height,
+            "--threshold",
+            "3",
+            "--aggregated-public-key",
+            "030d856ac9f5871c3785a2d76e3a5d9eca6fcce70f4de63339671dfb9d1f33edb0",
+            "--federations-file",
+            path,
+        ];
+        for vss in NODE_VSS[0].iter() {
+            args.push("--node-vss");
+            args.push(vss);
+        }
+        args
+    }
+
+    #[test]
+    fn test_execute() {
+        let path = std::env::temp_dir().join("createfederation_test_execute.toml");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let public_key = TEST_KEYS.pubkeys()[0].to_string();
+
+        let matches =
+            CreateFederationCommand::args().get_matches_from(args(&public_key, "0", path));
+        assert!(CreateFederationCommand::execute(&matches).is_ok());
+        let matches =
+            CreateFederationCommand::args().get_matches_from(args(&public_key, "100", path));
+        assert!(CreateFederationCommand::execute(&matches).is_ok());
+
+        let toml = std::fs::read_to_string(path).unwrap();
+        let federations = Federations::from_pubkey_and_toml(&TEST_KEYS.pubkeys()[0], &toml);
+        assert_eq!(federations.unwrap().len(), 2);
+
+        // It refuses the duplicated block height and the height below the last federation.
+        let matches =
+            CreateFederationCommand::args().get_matches_from(args(&public_key, "100", path));
+        let response = CreateFederationCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidFederation(Some(100), \"The federations file already has the federation at the block height.\")"
+        );
+        let matches =
+            CreateFederationCommand::args().get_matches_from(args(&public_key, "50", path));
+        let response = CreateFederationCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidFederation(Some(50), \"The block height should be higher than the one of the last federation in the federations file.\")"
+        );
+
+        // The file is not changed by the refused commands.
+        assert_eq!(std::fs::read_to_string(path).unwrap(), toml);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_execute_invalid_federation() {
+        let path = std::env::temp_dir().join("createfederation_test_execute_invalid.toml");
+        let path = path.to_str().unwrap();
+
+        // The node VSSs are not sent to this signer.
+        let public_key = TEST_KEYS.pubkeys()[1].to_string();
+        let matches =
+            CreateFederationCommand::args().get_matches_from(args(&public_key, "0", path));
+        let response = CreateFederationCommand::execute(&matches);
+        match response {
+            Err(Error::InvalidFederation(Some(0), _)) => {}
+            _ => panic!("it should be invalid federation"),
+        }
+        assert!(!Path::new(path).exists());
+    }
+
+    #[test]
+    fn test_execute_invalid_threshold() {
+        let path = std::env::temp_dir().join("createfederation_test_execute_threshold.toml");
+        let path = path.to_str().unwrap();
+        let public_key = TEST_KEYS.pubkeys()[0].to_string();
+        let mut args = args(&public_key, "0", path);
+        args[6] = "x";
+        let matches = CreateFederationCommand::args().get_matches_from(args);
+        let response = CreateFederationCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidArgs(\"threshold\")"
+        );
+    }
+}
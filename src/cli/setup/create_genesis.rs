@@ -0,0 +1,292 @@
+use crate::cli::setup::psb::PartiallySignedBlock;
+use crate::cli::setup::traits::Response;
+use crate::errors::Error;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tapyrus::blockdata::block::{Block, BlockHeader, XField};
+use tapyrus::blockdata::script::Builder;
+use tapyrus::blockdata::transaction::{OutPoint, Transaction, TxIn, TxOut};
+use tapyrus::consensus::encode::serialize;
+use tapyrus::hash_types::{BlockHash, TxMerkleNode};
+use tapyrus::{Address, PublicKey};
+
+/// The amount of the coinbase output in the genesis block, which is the same as tapyrus-genesis.
+const GENESIS_REWARD: u64 = 50 * 100_000_000;
+
+/// Creates the genesis block without proof. It has the aggregated public key in the xfield and the
+/// coinbase transaction which pays to the address.
+pub fn create_genesis_block(
+    aggregated_public_key: &PublicKey,
+    address: &Address,
+    time: u32,
+) -> Block {
+    // The prevout index of the coinbase is the block height in Tapyrus.
+    let mut previous_output = OutPoint::null();
+    previous_output.vout = 0;
+    let coinbase = Transaction {
+        version: 1,
+        lock_time: 0,
+        input: vec![TxIn {
+            previous_output: previous_output,
+            script_sig: Builder::new()
+                .push_slice(&aggregated_public_key.to_bytes())
+                .into_script(),
+            sequence: 0xffffffff,
+            witness: vec![],
+        }],
+        output: vec![TxOut {
+            value: GENESIS_REWARD,
+            script_pubkey: address.script_pubkey(),
+        }],
+    };
+
+    let mut block = Block {
+        header: BlockHeader {
+            version: 1,
+            prev_blockhash: BlockHash::default(),
+            merkle_root: TxMerkleNode::default(),
+            im_merkle_root: TxMerkleNode::default(),
+            time: time,
+            xfield: XField::AggregatePublicKey(*aggregated_public_key),
+            proof: None,
+        },
+        txdata: vec![coinbase],
+    };
+    block.header.merkle_root = block.merkle_root();
+    block.header.im_merkle_root = block.immutable_merkle_root();
+    block
+}
+
+pub struct CreateGenesisResponse {
+    block: Block,
+    psb: Option<String>,
+}
+
+impl CreateGenesisResponse {
+    fn new(block: Block, psb: Option<String>) -> Self {
+        CreateGenesisResponse {
+            block: block,
+            psb: psb,
+        }
+    }
+}
+
+impl Response for CreateGenesisResponse {
+    fn to_json(&self) -> Value {
+        json!({
+            "block": hex::encode(serialize(&self.block)),
+            "psb": self.psb,
+        })
+    }
+}
+
+impl fmt::Display for CreateGenesisResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(serialize(&self.block)))
+    }
+}
+
+pub struct CreateGenesisCommand {}
+
+impl<'a> CreateGenesisCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let aggregated_public_key: PublicKey = matches
+            .value_of("aggregated-public-key")
+            .and_then(|hex| PublicKey::from_str(hex).ok())
+            .ok_or(Error::InvalidArgs("aggregated-public-key".to_string()))?;
+
+        let address: Address = matches
+            .value_of("address")
+            .and_then(|s| Address::from_str(s).ok())
+            .ok_or(Error::InvalidArgs("address".to_string()))?;
+
+        let time: u32 = match matches.value_of("time") {
+            Some(s) => s
+                .parse::<u32>()
+                .map_err(|_| Error::InvalidArgs("time".to_string()))?,
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system time is before the unix epoch")
+                .as_secs() as u32,
+        };
+
+        let block = create_genesis_block(&aggregated_public_key, &address, time);
+
+        // The partially signed block is created only when the path is given.
+        let psb = match matches.value_of("psb") {
+            Some(path) => {
+                let threshold: usize = matches
+                    .value_of("threshold")
+                    .and_then(|s| s.parse::<usize>().ok())
+                    .ok_or(Error::InvalidArgs("threshold".to_string()))?;
+
+                let public_keys: Vec<PublicKey> = matches
+                    .values_of("public-key")
+                    .ok_or(Error::InvalidArgs("public-key".to_string()))?
+                    .map(|key| PublicKey::from_str(key).map_err(|_| Error::InvalidKey))
+                    .collect::<Result<Vec<PublicKey>, _>>()?;
+
+                PartiallySignedBlock::new(
+                    block.clone(),
+                    threshold,
+                    aggregated_public_key,
+                    public_keys,
+                )?
+                .write(path, true)?;
+                Some(path.to_string())
+            }
+            None => None,
+        };
+        Ok(Box::new(CreateGenesisResponse::new(block, psb)))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("creategenesis").args(&[
+            Arg::with_name("aggregated-public-key")
+                .long("aggregated-public-key")
+                .required(true)
+                .takes_value(true)
+                .help("aggregated public key of all signers, which is set to the xfield of the genesis block"),
+            Arg::with_name("address")
+                .long("address")
+                .required(true)
+                .takes_value(true)
+                .help("address which the coinbase transaction of the genesis block pays to"),
+            Arg::with_name("time")
+                .long("time")
+                .takes_value(true)
+                .help("block time of the genesis block as a unix timestamp. The current time is used by default"),
+            Arg::with_name("psb")
+                .long("psb")
+                .takes_value(true)
+                .help("path to the partially signed block file to be created for the genesis block. `--threshold` and `--public-key` are required with it"),
+            Arg::with_name("threshold")
+                .long("threshold")
+                .takes_value(true)
+                .help("the minimum number of signers required to sign block"),
+            Arg::with_name("public-key")
+                .long("public-key")
+                .multiple(true)
+                .takes_value(true)
+                .help("compressed public key of the each signer with a hex format string"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sign::Sign;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::{federation, node_vss};
+
+    /// The genesis block which is created by tapyrus-genesis.
+    const GENESIS: &str = "010000000000000000000000000000000000000000000000000000000000000000000000c0d6961ad2819f74eb6d085f04f9cceb0a9a6d5c153fd3c39fc47c3ca0bb548f85fbd09a5f7d8ac4c9552e52931ef6672984f64e52ad6d05d1cdb18907da8527db317c5e012103addb2555f37abf8f28f11f498bec7bd1460e7243c1813847c49a7ae326a97d1c00010100000001000000000000000000000000000000000000000000000000000000000000000000000000222103addb2555f37abf8f28f11f498bec7bd1460e7243c1813847c49a7ae326a97d1cffffffff0100f2052a010000001976a914a15f16ea2ba840d178e4c19781abca5f4fb1b4c288ac00000000";
+
+    #[test]
+    fn test_create_genesis_block() {
+        let aggregated_public_key = PublicKey::from_str(
+            "03addb2555f37abf8f28f11f498bec7bd1460e7243c1813847c49a7ae326a97d1c",
+        )
+        .unwrap();
+        let address = Address::from_str("1FiFhpYZHCZt33L4hh7C4jM2uKKueAxcH7").unwrap();
+        let block = create_genesis_block(&aggregated_public_key, &address, 1585197531);
+        assert_eq!(hex::encode(serialize(&block)), GENESIS);
+    }
+
+    #[test]
+    fn test_execute_and_sign() {
+        let path = std::env::temp_dir().join("creategenesis_test_execute_and_sign.json");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let aggregated_public_key = TEST_KEYS.aggregated().to_string();
+        let public_keys: Vec<String> = TEST_KEYS
+            .pubkeys()
+            .iter()
+            .map(|pk| pk.to_string())
+            .collect();
+        let mut args = vec![
+            "creategenesis",
+            "--aggregated-public-key",
+            &aggregated_public_key,
+            "--address",
+            "1FiFhpYZHCZt33L4hh7C4jM2uKKueAxcH7",
+            "--time",
+            "1585197531",
+            "--psb",
+            path,
+            "--threshold",
+            "3",
+        ];
+        for public_key in &public_keys {
+            args.push("--public-key");
+            args.push(public_key);
+        }
+        let matches = CreateGenesisCommand::args().get_matches_from(args);
+        let response = CreateGenesisCommand::execute(&matches).unwrap();
+        assert_eq!(response.to_json()["psb"], path);
+
+        // The federation signs the genesis block with the PSB.
+        let mut psb = PartiallySignedBlock::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        for i in 0..5 {
+            psb.contribute_block_vss(&TEST_KEYS.key[i]).unwrap();
+        }
+        for i in 0..3 {
+            let node_secret_share = federation(i).node_secret_share().x_i;
+            psb.contribute_local_sig(&TEST_KEYS.key[i], &node_secret_share)
+                .unwrap();
+        }
+        let block = psb.finalize(&node_vss(0)).unwrap();
+        match block.header.xfield {
+            XField::AggregatePublicKey(public_key) => {
+                assert_eq!(public_key, TEST_KEYS.aggregated())
+            }
+            _ => panic!("the genesis block should have the aggregated public key"),
+        }
+        assert!(Sign::verify_proof(&block, &TEST_KEYS.aggregated()).is_ok());
+    }
+
+    #[test]
+    fn test_execute_without_threshold() {
+        let path = std::env::temp_dir().join("creategenesis_test_execute_without_threshold.json");
+        let path = path.to_str().unwrap();
+        let aggregated_public_key = TEST_KEYS.aggregated().to_string();
+        let matches = CreateGenesisCommand::args().get_matches_from(vec![
+            "creategenesis",
+            "--aggregated-public-key",
+            &aggregated_public_key,
+            "--address",
+            "1FiFhpYZHCZt33L4hh7C4jM2uKKueAxcH7",
+            "--psb",
+            path,
+        ]);
+        let response = CreateGenesisCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidArgs(\"threshold\")"
+        );
+    }
+
+    #[test]
+    fn test_execute_invalid_address() {
+        let aggregated_public_key = TEST_KEYS.aggregated().to_string();
+        let matches = CreateGenesisCommand::args().get_matches_from(vec![
+            "creategenesis",
+            "--aggregated-public-key",
+            &aggregated_public_key,
+            "--address",
+            "x",
+        ]);
+        let response = CreateGenesisCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidArgs(\"address\")"
+        );
+    }
+}
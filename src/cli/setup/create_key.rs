@@ -2,6 +2,7 @@ use crate::cli::setup::traits::Response;
 use crate::errors::Error;
 use crate::key::generate_key_pair;
 use clap::{App, ArgMatches, SubCommand};
+use serde_json::{json, Value};
 use std::fmt;
 use tapyrus::{PrivateKey, PublicKey};
 
@@ -19,7 +20,14 @@ impl CreateKeyResponse {
     }
 }
 
-impl Response for CreateKeyResponse {}
+impl Response for CreateKeyResponse {
+    fn to_json(&self) -> Value {
+        json!({
+            "private-key": self.private_key.to_wif(),
+            "public-key": hex::encode(&self.public_key.key.serialize()[..]),
+        })
+    }
+}
 
 impl fmt::Display for CreateKeyResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -61,4 +69,22 @@ mod test {
         let response = CreateKeyResponse::new(private_key, public_key);
         assert_eq!(format!("{}", response), "KzZtqg6QKr4TaifSWNsSXfH1h7eHDKJL7rGV3w9Tx2tL83GhPdLv 0369d39154c0d011db02085392142e369d920c2531fe38e14484546ee6713465d6")
     }
+
+    #[test]
+    fn test_to_json() {
+        let private_key =
+            PrivateKey::from_wif("KzZtqg6QKr4TaifSWNsSXfH1h7eHDKJL7rGV3w9Tx2tL83GhPdLv").unwrap();
+        let public_key = PublicKey::from_str(
+            "0369d39154c0d011db02085392142e369d920c2531fe38e14484546ee6713465d6",
+        )
+        .unwrap();
+        let response = CreateKeyResponse::new(private_key, public_key);
+        assert_eq!(
+            response.to_json(),
+            json!({
+                "private-key": "KzZtqg6QKr4TaifSWNsSXfH1h7eHDKJL7rGV3w9Tx2tL83GhPdLv",
+                "public-key": "0369d39154c0d011db02085392142e369d920c2531fe38e14484546ee6713465d6",
+            })
+        );
+    }
 }
@@ -1,9 +1,11 @@
+use crate::cli::setup::secret_value_of;
 use crate::cli::setup::traits::Response;
 use crate::crypto::vss::{Commitment, Vss};
 use crate::errors::Error;
 use crate::rpc::Rpc;
 use crate::signer_node::node_parameters::NodeParameters;
 use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
 use std::collections::BTreeMap;
 use std::fmt;
 use std::str::FromStr;
@@ -19,7 +21,16 @@ impl CreateNodeVssResponse {
     }
 }
 
-impl Response for CreateNodeVssResponse {}
+impl Response for CreateNodeVssResponse {
+    fn to_json(&self) -> Value {
+        let vss: Vec<Value> = self
+            .vss
+            .iter()
+            .map(|(k, v)| json!({ "public-key": k.to_string(), "vss": v.to_string() }))
+            .collect();
+        json!({ "vss": vss })
+    }
+}
 
 impl fmt::Display for CreateNodeVssResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -37,9 +48,8 @@ pub struct CreateNodeVssCommand {}
 
 impl<'a> CreateNodeVssCommand {
     pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
-        let private_key: PrivateKey = matches
-            .value_of("private-key")
-            .and_then(|key| PrivateKey::from_wif(key).ok())
+        let private_key: PrivateKey = secret_value_of(matches, "private-key")?
+            .and_then(|key| PrivateKey::from_wif(&key).ok())
             .ok_or(Error::InvalidArgs("private-key".to_string()))?;
 
         let mut public_keys: Vec<PublicKey> = matches
@@ -93,7 +103,7 @@ impl<'a> CreateNodeVssCommand {
                 .long("private-key")
                 .required(true)
                 .takes_value(true)
-                .help("private key of this signer with an extend WIF format"),
+                .help("private key of this signer with an extend WIF format (`@<path>` or `-` reads it from the file or stdin)"),
             Arg::with_name("threshold")
                 .long("threshold")
                 .required(true)
@@ -126,6 +136,33 @@ mod tests {
         assert!(response.is_ok());
     }
 
+    #[test]
+    fn test_execute_with_private_key_file() {
+        let path = std::env::temp_dir().join("createnodevss_test_private_key.txt");
+        std::fs::write(
+            &path,
+            "cQYYBMFS9dRR3Mt16gW4jixCqSiMhCwuDMHUBs6WeHMTxMnsq8Gh\n",
+        )
+        .unwrap();
+        let arg = format!("@{}", path.to_str().unwrap());
+        let matches = CreateNodeVssCommand::args().get_matches_from(vec![
+            "createnodevss",
+            "--threshold",
+            "2",
+            "--public-key",
+            "03842d51608d08bee79587fb3b54ea68f5279e13fac7d72515a7205e6672858ca2",
+            "--public-key",
+            "03e568e3a5641ac21930b51f92fb6dd201fb46faae560b108cf3a96380da08dee1",
+            "--public-key",
+            "02a1c8965ed06987fa6d7e0f552db707065352283ab3c1471510b12a76a5905287",
+            "--private-key",
+            &arg,
+        ]);
+        let response = CreateNodeVssCommand::execute(&matches);
+        std::fs::remove_file(&path).unwrap();
+        assert!(response.is_ok());
+    }
+
     #[test]
     fn test_execute_invalid_public_key() {
         let matches = CreateNodeVssCommand::args().get_matches_from(vec![
@@ -0,0 +1,148 @@
+use crate::cli::setup::inspect_psb::InspectPsbResponse;
+use crate::cli::setup::psb::PartiallySignedBlock;
+use crate::cli::setup::traits::Response;
+use crate::errors::Error;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::str::FromStr;
+use tapyrus::blockdata::block::Block;
+use tapyrus::consensus::encode::deserialize;
+use tapyrus::PublicKey;
+
+pub struct CreatePsbCommand {}
+
+impl<'a> CreatePsbCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let block: Block = matches
+            .value_of("block")
+            .and_then(|s| hex::decode(s).ok())
+            .and_then(|hex| deserialize::<Block>(&hex).ok())
+            .ok_or(Error::InvalidArgs("block".to_string()))?;
+
+        let threshold: usize = matches
+            .value_of("threshold")
+            .and_then(|s| s.parse::<usize>().ok())
+            .ok_or(Error::InvalidArgs("threshold".to_string()))?;
+
+        let aggregated_public_key: PublicKey = matches
+            .value_of("aggregated-public-key")
+            .and_then(|hex| PublicKey::from_str(hex).ok())
+            .ok_or(Error::InvalidArgs("aggregated-public-key".to_string()))?;
+
+        let public_keys: Vec<PublicKey> = matches
+            .values_of("public-key")
+            .ok_or(Error::InvalidArgs("public-key".to_string()))?
+            .map(|key| PublicKey::from_str(key).map_err(|_| Error::InvalidKey))
+            .collect::<Result<Vec<PublicKey>, _>>()?;
+
+        let path = matches
+            .value_of("psb")
+            .ok_or(Error::InvalidArgs("psb".to_string()))?;
+
+        let psb = PartiallySignedBlock::new(block, threshold, aggregated_public_key, public_keys)?;
+        psb.write(path, true)?;
+        Ok(Box::new(InspectPsbResponse::new(psb)))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("createpsb").args(&[
+            Arg::with_name("block")
+                .long("block")
+                .required(true)
+                .takes_value(true)
+                .help("block to be signed as a hex string format"),
+            Arg::with_name("threshold")
+                .long("threshold")
+                .required(true)
+                .takes_value(true)
+                .help("the minimum number of signers required to sign block"),
+            Arg::with_name("aggregated-public-key")
+                .long("aggregated-public-key")
+                .required(true)
+                .takes_value(true)
+                .help("aggregated public key of all signers"),
+            Arg::with_name("public-key")
+                .long("public-key")
+                .required(true)
+                .multiple(true)
+                .takes_value(true)
+                .help("compressed public key of the each signer with a hex format string"),
+            Arg::with_name("psb")
+                .long("psb")
+                .required(true)
+                .takes_value(true)
+                .help("path to the partially signed block file to be created"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: &str = "010000000000000000000000000000000000000000000000000000000000000000000000c0d6961ad2819f74eb6d085f04f9cceb0a9a6d5c153fd3c39fc47c3ca0bb548f85fbd09a5f7d8ac4c9552e52931ef6672984f64e52ad6d05d1cdb18907da8527db317c5e012103addb2555f37abf8f28f11f498bec7bd1460e7243c1813847c49a7ae326a97d1c00010100000001000000000000000000000000000000000000000000000000000000000000000000000000222103addb2555f37abf8f28f11f498bec7bd1460e7243c1813847c49a7ae326a97d1cffffffff0100f2052a010000001976a914a15f16ea2ba840d178e4c19781abca5f4fb1b4c288ac00000000";
+
+    fn args<'a>(threshold: &'a str, path: &'a str) -> Vec<&'a str> {
+        vec![
+            "createpsb",
+            "--block",
+            BLOCK,
+            "--threshold",
+            threshold,
+            "--aggregated-public-key",
+            "03addb2555f37abf8f28f11f498bec7bd1460e7243c1813847c49a7ae326a97d1c",
+            "--public-key",
+            "03b8ad9e3271a20d5eb2b622e455fcffa5c9c90e38b192772b2e1b58f6b442e78d",
+            "--public-key",
+            "0313f2a73541e6d55a75a80a6da819885c6ed6e56ecff19f5e928c4ea202ca7c90",
+            "--public-key",
+            "023cb7d6326e33332d04d026be1a04cdaf084703d8dc75322182d8fb314a03a877",
+            "--psb",
+            path,
+        ]
+    }
+
+    #[test]
+    fn test_execute() {
+        let path = std::env::temp_dir().join("createpsb_test_execute.json");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let matches = CreatePsbCommand::args().get_matches_from(args("2", path));
+        let response = CreatePsbCommand::execute(&matches);
+        assert!(response.is_ok());
+
+        let psb = PartiallySignedBlock::read(path).unwrap();
+        assert_eq!(psb.threshold(), 2);
+        assert_eq!(psb.public_keys().len(), 3);
+
+        // It should not overwrite the existing file.
+        let response = CreatePsbCommand::execute(&matches);
+        std::fs::remove_file(path).unwrap();
+        match response {
+            Err(Error::ConfigFileIOError(_)) => {}
+            _ => panic!("it should not overwrite the existing file"),
+        }
+    }
+
+    #[test]
+    fn test_execute_invalid_threshold() {
+        let path = std::env::temp_dir().join("createpsb_test_execute_invalid_threshold.json");
+        let path = path.to_str().unwrap();
+
+        let matches = CreatePsbCommand::args().get_matches_from(args("x", path));
+        let response = CreatePsbCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidArgs(\"threshold\")"
+        );
+
+        let matches = CreatePsbCommand::args().get_matches_from(args("4", path));
+        let response = CreatePsbCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidPartiallySignedBlock(\"threshold should be between 1 and the number of signers\")"
+        );
+        assert!(!std::path::Path::new(path).exists());
+    }
+}
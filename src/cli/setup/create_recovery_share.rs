@@ -0,0 +1,158 @@
+use crate::cli::setup::list_values_of;
+use crate::cli::setup::secret_value_of;
+use crate::cli::setup::traits::Response;
+use crate::crypto::recovery::Recovery;
+use crate::crypto::vss::Vss;
+use crate::errors::Error;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use tapyrus::{PrivateKey, PublicKey};
+
+pub struct CreateRecoveryShareResponse {
+    shares: Vec<Vss>,
+}
+
+impl CreateRecoveryShareResponse {
+    fn new(shares: Vec<Vss>) -> Self {
+        CreateRecoveryShareResponse { shares: shares }
+    }
+}
+
+impl Response for CreateRecoveryShareResponse {
+    fn to_json(&self) -> Value {
+        let shares: Vec<Value> = self
+            .shares
+            .iter()
+            .map(|v| json!({ "public-key": v.sender_public_key.to_string(), "recovery-share": v.to_string() }))
+            .collect();
+        json!({ "recovery-shares": shares })
+    }
+}
+
+impl fmt::Display for CreateRecoveryShareResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = self
+            .shares
+            .iter()
+            .map(|v| format!("{}:{}", v.sender_public_key, v))
+            .collect::<Vec<String>>()
+            .join("\n");
+        write!(f, "{}", c)
+    }
+}
+
+pub struct CreateRecoveryShareCommand {}
+
+impl<'a> CreateRecoveryShareCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let private_key: PrivateKey = secret_value_of(matches, "private-key")?
+            .and_then(|key| PrivateKey::from_wif(&key).ok())
+            .ok_or(Error::InvalidArgs("private-key".to_string()))?;
+
+        let node_vss: Vec<Vss> = list_values_of(matches, "node-vss")?
+            .ok_or(Error::InvalidArgs("node-vss".to_string()))?
+            .iter()
+            .map(|s| Vss::from_str(s).map_err(|_| Error::InvalidSS))
+            .collect::<Result<Vec<Vss>, _>>()?;
+
+        let helpers: Vec<PublicKey> = matches
+            .values_of("helper-public-key")
+            .ok_or(Error::InvalidArgs("helper-public-key".to_string()))?
+            .map(|key| PublicKey::from_str(key).map_err(|_| Error::InvalidKey))
+            .collect::<Result<Vec<PublicKey>, _>>()?;
+
+        let lost_public_key: PublicKey = matches
+            .value_of("lost-public-key")
+            .and_then(|key| PublicKey::from_str(key).ok())
+            .ok_or(Error::InvalidArgs("lost-public-key".to_string()))?;
+
+        let shares =
+            Recovery::create_recovery_shares(&private_key, &node_vss, &helpers, &lost_public_key)?;
+        Ok(Box::new(CreateRecoveryShareResponse::new(shares)))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("createrecoveryshare").args(&[
+            Arg::with_name("private-key")
+                .long("private-key")
+                .required(true)
+                .takes_value(true)
+                .help("private key of this signer with an extend WIF format (`@<path>` or `-` reads it from the file or stdin)"),
+            Arg::with_name("node-vss")
+                .long("node-vss")
+                .required(true)
+                .multiple(true)
+                .takes_value(true)
+                .help("node vss which this signer received from the all signers. These values are in the node-vss field of the federations file (`@<path>` reads them from the file which has one value per line or a JSON array)"),
+            Arg::with_name("helper-public-key")
+                .long("helper-public-key")
+                .required(true)
+                .multiple(true)
+                .takes_value(true)
+                .help("compressed public key of the each signer who helps the recovery, including this signer"),
+            Arg::with_name("lost-public-key")
+                .long("lost-public-key")
+                .required(true)
+                .takes_value(true)
+                .help("compressed public key of the signer who lost its node vss"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::NODE_VSS;
+
+    #[test]
+    fn test_execute() {
+        let pubkeys = TEST_KEYS.pubkeys();
+        let mut args = vec![
+            "createrecoveryshare".to_string(),
+            "--private-key".to_string(),
+            TEST_KEYS.key[0].to_wif(),
+            "--lost-public-key".to_string(),
+            pubkeys[4].to_string(),
+        ];
+        for vss in NODE_VSS[0].iter() {
+            args.push("--node-vss".to_string());
+            args.push(vss.to_string());
+        }
+        for pk in &pubkeys[0..3] {
+            args.push("--helper-public-key".to_string());
+            args.push(pk.to_string());
+        }
+        let matches = CreateRecoveryShareCommand::args().get_matches_from(args);
+        let response = CreateRecoveryShareCommand::execute(&matches);
+        assert!(response.is_ok());
+    }
+
+    #[test]
+    fn test_execute_with_lost_signer_as_helper() {
+        let pubkeys = TEST_KEYS.pubkeys();
+        let mut args = vec![
+            "createrecoveryshare".to_string(),
+            "--private-key".to_string(),
+            TEST_KEYS.key[0].to_wif(),
+            "--lost-public-key".to_string(),
+            pubkeys[2].to_string(),
+        ];
+        for vss in NODE_VSS[0].iter() {
+            args.push("--node-vss".to_string());
+            args.push(vss.to_string());
+        }
+        for pk in &pubkeys[0..3] {
+            args.push("--helper-public-key".to_string());
+            args.push(pk.to_string());
+        }
+        let matches = CreateRecoveryShareCommand::args().get_matches_from(args);
+        let response = CreateRecoveryShareCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidArgs(\"helpers should not include the lost signer\")"
+        );
+    }
+}
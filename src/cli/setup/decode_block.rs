@@ -0,0 +1,214 @@
+use crate::cli::setup::traits::Response;
+use crate::errors::Error;
+use crate::sign::Sign;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use tapyrus::blockdata::block::{Block, XField};
+use tapyrus::consensus::encode::{deserialize, serialize};
+use tapyrus::PublicKey;
+
+pub struct DecodeBlockResponse {
+    block: Block,
+    /// The result of the proof verification. It is None if no aggregated public key is given.
+    valid_proof: Option<bool>,
+}
+
+impl DecodeBlockResponse {
+    fn new(block: Block, valid_proof: Option<bool>) -> Self {
+        DecodeBlockResponse {
+            block: block,
+            valid_proof: valid_proof,
+        }
+    }
+
+    fn xfield(&self) -> String {
+        match &self.block.header.xfield {
+            XField::None => "none".to_string(),
+            XField::AggregatePublicKey(public_key) => {
+                format!("aggregated public key {}", public_key)
+            }
+            XField::Unknown(xfield_type, data) => format!(
+                "unknown (type: {}, data: {})",
+                xfield_type,
+                hex::encode(data)
+            ),
+        }
+    }
+
+    fn proof(&self) -> Option<String> {
+        self.block
+            .header
+            .proof
+            .as_ref()
+            .map(|proof| hex::encode(serialize(proof)))
+    }
+
+    fn proof_status(&self) -> &'static str {
+        match (self.block.header.proof.is_some(), self.valid_proof) {
+            (false, _) => "none",
+            (true, None) => "not verified",
+            (true, Some(true)) => "valid",
+            (true, Some(false)) => "invalid",
+        }
+    }
+}
+
+impl Response for DecodeBlockResponse {
+    fn to_json(&self) -> Value {
+        let header = &self.block.header;
+        json!({
+            "hash": header.block_hash().to_string(),
+            "signature-hash": header.signature_hash().to_string(),
+            "version": header.version,
+            "previous-block-hash": header.prev_blockhash.to_string(),
+            "merkle-root": header.merkle_root.to_string(),
+            "immutable-merkle-root": header.im_merkle_root.to_string(),
+            "time": header.time,
+            "xfield": self.xfield(),
+            "proof": self.proof(),
+            "valid-proof": self.valid_proof,
+            "transactions": self.block.txdata.len(),
+        })
+    }
+}
+
+impl fmt::Display for DecodeBlockResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = &self.block.header;
+        writeln!(f, "hash: {}", header.block_hash())?;
+        writeln!(f, "signature hash: {}", header.signature_hash())?;
+        writeln!(f, "version: {}", header.version)?;
+        writeln!(f, "previous block hash: {}", header.prev_blockhash)?;
+        writeln!(f, "merkle root: {}", header.merkle_root)?;
+        writeln!(f, "immutable merkle root: {}", header.im_merkle_root)?;
+        writeln!(f, "time: {}", header.time)?;
+        writeln!(f, "xfield: {}", self.xfield())?;
+        writeln!(
+            f,
+            "proof: {}",
+            self.proof().unwrap_or_else(|| "none".to_string())
+        )?;
+        writeln!(f, "proof verification: {}", self.proof_status())?;
+        write!(f, "transactions: {}", self.block.txdata.len())
+    }
+}
+
+pub struct DecodeBlockCommand {}
+
+impl<'a> DecodeBlockCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let block: Block = matches
+            .value_of("block")
+            .and_then(|s| hex::decode(s).ok())
+            .and_then(|hex| deserialize::<Block>(&hex).ok())
+            .ok_or(Error::InvalidArgs("block".to_string()))?;
+
+        let aggregated_public_key: Option<PublicKey> =
+            match matches.value_of("aggregated-public-key") {
+                Some(hex) => Some(
+                    PublicKey::from_str(hex)
+                        .map_err(|_| Error::InvalidArgs("aggregated-public-key".to_string()))?,
+                ),
+                None => None,
+            };
+
+        let valid_proof = match (&block.header.proof, aggregated_public_key) {
+            (Some(_), Some(public_key)) => Some(Sign::verify_proof(&block, &public_key).is_ok()),
+            _ => None,
+        };
+        Ok(Box::new(DecodeBlockResponse::new(block, valid_proof)))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("decodeblock").args(&[
+            Arg::with_name("block")
+                .long("block")
+                .required(true)
+                .takes_value(true)
+                .help("block with a hex string format"),
+            Arg::with_name("aggregated-public-key")
+                .long("aggregated-public-key")
+                .takes_value(true)
+                .help("aggregated public key of the federation which signed the block. If it is given, the proof of the block is verified with it"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::setup::psb::PartiallySignedBlock;
+    use crate::tests::helper::blocks::get_block;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::{federation, node_vss};
+
+    fn signed_block() -> Block {
+        let mut psb =
+            PartiallySignedBlock::new(get_block(0), 3, TEST_KEYS.aggregated(), TEST_KEYS.pubkeys())
+                .unwrap();
+        for i in 0..5 {
+            psb.contribute_block_vss(&TEST_KEYS.key[i]).unwrap();
+        }
+        for i in 0..3 {
+            let node_secret_share = federation(i).node_secret_share().x_i;
+            psb.contribute_local_sig(&TEST_KEYS.key[i], &node_secret_share)
+                .unwrap();
+        }
+        psb.finalize(&node_vss(0)).unwrap()
+    }
+
+    #[test]
+    fn test_execute() {
+        let block = hex::encode(serialize(&signed_block()));
+        let aggregated_public_key = TEST_KEYS.aggregated().to_string();
+        let matches = DecodeBlockCommand::args().get_matches_from(vec![
+            "decodeblock",
+            "--block",
+            &block,
+            "--aggregated-public-key",
+            &aggregated_public_key,
+        ]);
+        let response = DecodeBlockCommand::execute(&matches).unwrap();
+        let json = response.to_json();
+        assert_eq!(json["valid-proof"], true);
+        assert_eq!(json["xfield"], "none");
+        assert!(format!("{}", response).contains("proof verification: valid"));
+
+        // The proof is not verified with the other key.
+        let other = TEST_KEYS.pubkeys()[0].to_string();
+        let matches = DecodeBlockCommand::args().get_matches_from(vec![
+            "decodeblock",
+            "--block",
+            &block,
+            "--aggregated-public-key",
+            &other,
+        ]);
+        let response = DecodeBlockCommand::execute(&matches).unwrap();
+        assert_eq!(response.to_json()["valid-proof"], false);
+    }
+
+    #[test]
+    fn test_execute_without_proof() {
+        let block = hex::encode(serialize(&get_block(0)));
+        let matches =
+            DecodeBlockCommand::args().get_matches_from(vec!["decodeblock", "--block", &block]);
+        let response = DecodeBlockCommand::execute(&matches).unwrap();
+        let json = response.to_json();
+        assert_eq!(json["proof"], Value::Null);
+        assert_eq!(json["valid-proof"], Value::Null);
+        assert!(format!("{}", response).contains("proof verification: none"));
+    }
+
+    #[test]
+    fn test_execute_invalid_block() {
+        let matches =
+            DecodeBlockCommand::args().get_matches_from(vec!["decodeblock", "--block", "x"]);
+        let response = DecodeBlockCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidArgs(\"block\")"
+        );
+    }
+}
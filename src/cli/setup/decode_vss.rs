@@ -0,0 +1,288 @@
+use crate::cli::setup::traits::Response;
+use crate::crypto::vss::{Commitment, Vss};
+use crate::errors::Error;
+use crate::rpc::Rpc;
+use crate::signer_node::NodeParameters;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use curv::arithmetic::traits::Converter;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    ShamirSecretSharing, VerifiableSS,
+};
+use curv::elliptic::curves::traits::ECScalar;
+use curv::FE;
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use tapyrus::PublicKey;
+
+pub struct DecodeVssResponse {
+    vss: Vss,
+    show_secrets: bool,
+    valid_commitments: bool,
+    /// The result of the share validation. It is None if the share is not validated.
+    valid_share: Option<bool>,
+}
+
+impl DecodeVssResponse {
+    fn new(
+        vss: Vss,
+        show_secrets: bool,
+        valid_commitments: bool,
+        valid_share: Option<bool>,
+    ) -> Self {
+        DecodeVssResponse {
+            vss: vss,
+            show_secrets: show_secrets,
+            valid_commitments: valid_commitments,
+            valid_share: valid_share,
+        }
+    }
+
+    fn secret(&self, secret: &FE) -> String {
+        if self.show_secrets {
+            format!("{:0>64}", secret.to_big_int().to_hex())
+        } else {
+            "<redacted>".to_string()
+        }
+    }
+
+    fn share(&self) -> &'static str {
+        match self.valid_share {
+            Some(true) => "valid",
+            Some(false) => "invalid",
+            None => "not validated",
+        }
+    }
+}
+
+fn commitments_to_strings(commitments: &Vec<Commitment>) -> Vec<String> {
+    commitments.iter().map(|c| c.to_string()).collect()
+}
+
+impl Response for DecodeVssResponse {
+    fn to_json(&self) -> Value {
+        json!({
+            "sender-public-key": self.vss.sender_public_key.to_string(),
+            "receiver-public-key": self.vss.receiver_public_key.to_string(),
+            "threshold": self.vss.positive_commitments.len(),
+            "positive-commitments": commitments_to_strings(&self.vss.positive_commitments),
+            "positive-secret": self.secret(&self.vss.positive_secret),
+            "negative-commitments": commitments_to_strings(&self.vss.negative_commitments),
+            "negative-secret": self.secret(&self.vss.negative_secret),
+            "valid-commitments": self.valid_commitments,
+            "valid-share": self.valid_share,
+        })
+    }
+}
+
+impl fmt::Display for DecodeVssResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "sender public key: {}", self.vss.sender_public_key)?;
+        writeln!(f, "receiver public key: {}", self.vss.receiver_public_key)?;
+        writeln!(f, "threshold: {}", self.vss.positive_commitments.len())?;
+        writeln!(f, "positive commitments:")?;
+        for c in &self.vss.positive_commitments {
+            writeln!(f, "  {}", c)?;
+        }
+        writeln!(
+            f,
+            "positive secret: {}",
+            self.secret(&self.vss.positive_secret)
+        )?;
+        writeln!(f, "negative commitments:")?;
+        for c in &self.vss.negative_commitments {
+            writeln!(f, "  {}", c)?;
+        }
+        writeln!(
+            f,
+            "negative secret: {}",
+            self.secret(&self.vss.negative_secret)
+        )?;
+        writeln!(
+            f,
+            "commitments: {}",
+            if self.valid_commitments {
+                "valid"
+            } else {
+                "invalid"
+            }
+        )?;
+        write!(f, "share: {}", self.share())
+    }
+}
+
+pub struct DecodeVssCommand {}
+
+impl<'a> DecodeVssCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let vss: Vss = matches
+            .value_of("vss")
+            .and_then(|s| Vss::from_str(s).ok())
+            .ok_or(Error::InvalidArgs("vss".to_string()))?;
+
+        let public_keys: Option<Vec<PublicKey>> = match matches.values_of("public-key") {
+            Some(values) => {
+                let mut public_keys = values
+                    .map(|key| PublicKey::from_str(key).map_err(|_| Error::InvalidKey))
+                    .collect::<Result<Vec<PublicKey>, _>>()?;
+                NodeParameters::<Rpc>::sort_publickey(&mut public_keys);
+                Some(public_keys)
+            }
+            None => None,
+        };
+
+        let show_secrets = matches.is_present("show-secrets");
+
+        let valid_commitments = !vss.positive_commitments.is_empty()
+            && vss
+                .positive_commitments
+                .iter()
+                .chain(vss.negative_commitments.iter())
+                .all(|c| c.is_valid_point());
+
+        // The share can be validated only when the index of the receiver is known.
+        let valid_share = match public_keys {
+            Some(public_keys) if valid_commitments => {
+                let index = public_keys
+                    .iter()
+                    .position(|pk| *pk == vss.receiver_public_key)
+                    .ok_or(Error::InvalidArgs(
+                        "public-key should include the receiver of the vss".to_string(),
+                    ))?
+                    + 1;
+                let params = ShamirSecretSharing {
+                    threshold: vss.positive_commitments.len() - 1,
+                    share_count: public_keys.len(),
+                };
+                let validate = |commitments: &Vec<Commitment>, secret: &FE| {
+                    VerifiableSS {
+                        parameters: params.clone(),
+                        commitments: commitments.iter().map(|c| c.to_point()).collect(),
+                    }
+                    .validate_share(secret, index)
+                    .is_ok()
+                };
+                Some(
+                    validate(&vss.positive_commitments, &vss.positive_secret)
+                        && validate(&vss.negative_commitments, &vss.negative_secret),
+                )
+            }
+            Some(_) => Some(false),
+            None => None,
+        };
+
+        Ok(Box::new(DecodeVssResponse::new(
+            vss,
+            show_secrets,
+            valid_commitments,
+            valid_share,
+        )))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("decodevss").args(&[
+            Arg::with_name("vss")
+                .long("vss")
+                .required(true)
+                .takes_value(true)
+                .help("node vss or block vss with a hex format string"),
+            Arg::with_name("public-key")
+                .long("public-key")
+                .multiple(true)
+                .takes_value(true)
+                .help("compressed public key of the each signer with a hex format string. If they are given, the share in the vss is validated against the commitments"),
+            Arg::with_name("show-secrets")
+                .long("show-secrets")
+                .help("print the secret shares in the vss. They are redacted by default"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::NODE_VSS;
+    use curv::BigInt;
+
+    fn args<'a>(vss: &'a str, public_keys: &'a Vec<String>) -> Vec<&'a str> {
+        let mut args = vec!["decodevss", "--vss", vss];
+        for public_key in public_keys {
+            args.push("--public-key");
+            args.push(public_key);
+        }
+        args
+    }
+
+    #[test]
+    fn test_execute() {
+        let public_keys: Vec<String> = TEST_KEYS
+            .pubkeys()
+            .iter()
+            .map(|pk| pk.to_string())
+            .collect();
+        let matches = DecodeVssCommand::args().get_matches_from(args(NODE_VSS[0][0], &public_keys));
+        let response = DecodeVssCommand::execute(&matches).unwrap();
+        let json = response.to_json();
+        assert_eq!(json["threshold"], 3);
+        assert_eq!(json["positive-secret"], "<redacted>");
+        assert_eq!(json["valid-commitments"], true);
+        assert_eq!(json["valid-share"], true);
+        assert!(format!("{}", response).ends_with("share: valid"));
+    }
+
+    #[test]
+    fn test_execute_show_secrets() {
+        let matches = DecodeVssCommand::args().get_matches_from(vec![
+            "decodevss",
+            "--vss",
+            NODE_VSS[0][0],
+            "--show-secrets",
+        ]);
+        let response = DecodeVssCommand::execute(&matches).unwrap();
+        let json = response.to_json();
+        let vss = Vss::from_str(NODE_VSS[0][0]).unwrap();
+        assert_eq!(
+            json["positive-secret"],
+            format!("{:0>64}", vss.positive_secret.to_big_int().to_hex())
+        );
+        assert_eq!(json["valid-share"], Value::Null);
+    }
+
+    #[test]
+    fn test_execute_invalid_share() {
+        let mut vss = Vss::from_str(NODE_VSS[0][0]).unwrap();
+        let one: FE = ECScalar::from(&BigInt::from(1));
+        vss.positive_secret = vss.positive_secret + one;
+        let vss = vss.to_string();
+        let public_keys: Vec<String> = TEST_KEYS
+            .pubkeys()
+            .iter()
+            .map(|pk| pk.to_string())
+            .collect();
+        let matches = DecodeVssCommand::args().get_matches_from(args(&vss, &public_keys));
+        let response = DecodeVssCommand::execute(&matches).unwrap();
+        assert_eq!(response.to_json()["valid-share"], false);
+    }
+
+    #[test]
+    fn test_execute_invalid_commitment() {
+        // Replace the y coordinate of the first positive commitment.
+        let mut vss = NODE_VSS[0][0].to_string();
+        let start = (33 + 33 + 2 + 32) * 2;
+        vss.replace_range(start..start + 64, &"1".repeat(64));
+        let matches = DecodeVssCommand::args().get_matches_from(vec!["decodevss", "--vss", &vss]);
+        let response = DecodeVssCommand::execute(&matches).unwrap();
+        assert_eq!(response.to_json()["valid-commitments"], false);
+    }
+
+    #[test]
+    fn test_execute_invalid_vss() {
+        let matches = DecodeVssCommand::args().get_matches_from(vec!["decodevss", "--vss", "x"]);
+        let response = DecodeVssCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidArgs(\"vss\")"
+        );
+    }
+}
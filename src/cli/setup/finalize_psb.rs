@@ -0,0 +1,135 @@
+use crate::cli::setup::list_values_of;
+use crate::cli::setup::psb::PartiallySignedBlock;
+use crate::cli::setup::traits::Response;
+use crate::crypto::vss::Vss;
+use crate::errors::Error;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use tapyrus::blockdata::block::Block;
+use tapyrus::consensus::encode::serialize;
+
+pub struct FinalizePsbResponse {
+    block_with_signature: Block,
+}
+
+impl FinalizePsbResponse {
+    fn new(block_with_signature: Block) -> Self {
+        FinalizePsbResponse {
+            block_with_signature: block_with_signature,
+        }
+    }
+}
+
+impl Response for FinalizePsbResponse {
+    fn to_json(&self) -> Value {
+        json!({ "block": hex::encode(serialize(&self.block_with_signature)) })
+    }
+}
+
+impl fmt::Display for FinalizePsbResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(serialize(&self.block_with_signature)))
+    }
+}
+
+pub struct FinalizePsbCommand {}
+
+impl<'a> FinalizePsbCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let path = matches
+            .value_of("psb")
+            .ok_or(Error::InvalidArgs("psb".to_string()))?;
+
+        let node_vss_vec: Vec<Vss> = list_values_of(matches, "node-vss")?
+            .ok_or(Error::InvalidArgs("node-vss".to_string()))?
+            .iter()
+            .map(|s| Vss::from_str(s).map_err(|_| Error::InvalidArgs("node-vss".to_string())))
+            .collect::<Result<Vec<Vss>, _>>()?;
+
+        let psb = PartiallySignedBlock::read(path)?;
+        let block = psb.finalize(&node_vss_vec)?;
+        Ok(Box::new(FinalizePsbResponse::new(block)))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("finalizepsb").args(&[
+            Arg::with_name("psb")
+                .long("psb")
+                .required(true)
+                .takes_value(true)
+                .help("path to the partially signed block file"),
+            Arg::with_name("node-vss")
+                .long("node-vss")
+                .required(true)
+                .multiple(true)
+                .takes_value(true)
+                .help(
+                    "the node VSSs of any signer generated by tapyrus-setup createnodevss command (`@<path>` reads them from the file which has one value per line or a JSON array)",
+                ),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::blocks::get_block;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::{federation, NODE_VSS};
+
+    fn args(path: &str) -> Vec<&str> {
+        let mut args = vec!["finalizepsb", "--psb", path];
+        for vss in NODE_VSS[0].iter() {
+            args.push("--node-vss");
+            args.push(vss);
+        }
+        args
+    }
+
+    #[test]
+    fn test_execute() {
+        let path = std::env::temp_dir().join("finalizepsb_test_execute.json");
+        let path = path.to_str().unwrap();
+        let mut psb =
+            PartiallySignedBlock::new(get_block(0), 3, TEST_KEYS.aggregated(), TEST_KEYS.pubkeys())
+                .unwrap();
+        for i in 0..5 {
+            psb.contribute_block_vss(&TEST_KEYS.key[i]).unwrap();
+        }
+        for i in 2..5 {
+            let node_secret_share = federation(i).node_secret_share().x_i;
+            psb.contribute_local_sig(&TEST_KEYS.key[i], &node_secret_share)
+                .unwrap();
+        }
+        psb.write(path, false).unwrap();
+
+        let matches = FinalizePsbCommand::args().get_matches_from(args(path));
+        let response = FinalizePsbCommand::execute(&matches);
+        std::fs::remove_file(path).unwrap();
+        assert!(response.is_ok());
+    }
+
+    #[test]
+    fn test_execute_without_enough_local_sigs() {
+        let path = std::env::temp_dir().join("finalizepsb_test_execute_without_sigs.json");
+        let path = path.to_str().unwrap();
+        let mut psb =
+            PartiallySignedBlock::new(get_block(0), 3, TEST_KEYS.aggregated(), TEST_KEYS.pubkeys())
+                .unwrap();
+        for i in 0..5 {
+            psb.contribute_block_vss(&TEST_KEYS.key[i]).unwrap();
+        }
+        psb.write(path, false).unwrap();
+
+        let matches = FinalizePsbCommand::args().get_matches_from(args(path));
+        let response = FinalizePsbCommand::execute(&matches);
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidPartiallySignedBlock(\"local signatures are not enough\")"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::cli::setup::psb::PartiallySignedBlock;
+use crate::cli::setup::traits::Response;
+use crate::errors::Error;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::fmt;
+
+pub struct InspectPsbResponse {
+    psb: PartiallySignedBlock,
+}
+
+impl InspectPsbResponse {
+    /// This response is also used by createpsb and contributepsb to show the status of the PSB.
+    pub fn new(psb: PartiallySignedBlock) -> Self {
+        InspectPsbResponse { psb: psb }
+    }
+}
+
+impl Response for InspectPsbResponse {
+    fn to_json(&self) -> Value {
+        let signers: Vec<Value> = self
+            .psb
+            .public_keys()
+            .iter()
+            .map(|public_key| {
+                json!({
+                    "public-key": public_key.to_string(),
+                    "block-vss": self.psb.has_block_vss(public_key),
+                    "local-sig": self.psb.has_local_sig(public_key),
+                })
+            })
+            .collect();
+        json!({
+            "block-hash-for-signing": self.psb.block().header.signature_hash().to_string(),
+            "aggregated-public-key": self.psb.aggregated_public_key().to_string(),
+            "threshold": self.psb.threshold(),
+            "signers": signers,
+            "ready-to-sign": self.psb.is_ready_to_sign(),
+            "ready-to-finalize": self.psb.is_ready_to_finalize(),
+        })
+    }
+}
+
+impl fmt::Display for InspectPsbResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes_or_no = |b: bool| if b { "yes" } else { "no" };
+        writeln!(
+            f,
+            "block hash for signing: {}",
+            self.psb.block().header.signature_hash()
+        )?;
+        writeln!(
+            f,
+            "aggregated public key: {}",
+            self.psb.aggregated_public_key()
+        )?;
+        writeln!(f, "threshold: {}", self.psb.threshold())?;
+        writeln!(f, "signers:")?;
+        for public_key in self.psb.public_keys() {
+            writeln!(
+                f,
+                "  {}: block-vss: {}, local-sig: {}",
+                public_key,
+                yes_or_no(self.psb.has_block_vss(public_key)),
+                yes_or_no(self.psb.has_local_sig(public_key)),
+            )?;
+        }
+        writeln!(
+            f,
+            "ready to sign: {}",
+            yes_or_no(self.psb.is_ready_to_sign())
+        )?;
+        write!(
+            f,
+            "ready to finalize: {}",
+            yes_or_no(self.psb.is_ready_to_finalize())
+        )
+    }
+}
+
+pub struct InspectPsbCommand {}
+
+impl<'a> InspectPsbCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let path = matches
+            .value_of("psb")
+            .ok_or(Error::InvalidArgs("psb".to_string()))?;
+        let psb = PartiallySignedBlock::read(path)?;
+        Ok(Box::new(InspectPsbResponse::new(psb)))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("inspectpsb").args(&[Arg::with_name("psb")
+            .long("psb")
+            .required(true)
+            .takes_value(true)
+            .help("path to the partially signed block file")])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::blocks::get_block;
+    use crate::tests::helper::keys::TEST_KEYS;
+
+    #[test]
+    fn test_execute() {
+        let path = std::env::temp_dir().join("inspectpsb_test_execute.json");
+        let path = path.to_str().unwrap();
+        let mut psb =
+            PartiallySignedBlock::new(get_block(0), 3, TEST_KEYS.aggregated(), TEST_KEYS.pubkeys())
+                .unwrap();
+        psb.contribute_block_vss(&TEST_KEYS.key[0]).unwrap();
+        psb.write(path, false).unwrap();
+
+        let matches = InspectPsbCommand::args().get_matches_from(vec!["inspectpsb", "--psb", path]);
+        let response = InspectPsbCommand::execute(&matches);
+        std::fs::remove_file(path).unwrap();
+
+        let output = format!("{}", response.unwrap());
+        assert!(output.contains(&format!(
+            "{}: block-vss: yes, local-sig: no",
+            TEST_KEYS.pubkeys()[0]
+        )));
+        assert!(output.contains(&format!(
+            "{}: block-vss: no, local-sig: no",
+            TEST_KEYS.pubkeys()[1]
+        )));
+        assert!(output.contains("ready to sign: no"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let mut psb =
+            PartiallySignedBlock::new(get_block(0), 3, TEST_KEYS.aggregated(), TEST_KEYS.pubkeys())
+                .unwrap();
+        psb.contribute_block_vss(&TEST_KEYS.key[0]).unwrap();
+
+        let json = InspectPsbResponse::new(psb).to_json();
+        assert_eq!(json["threshold"], 3);
+        assert_eq!(
+            json["signers"][0]["public-key"],
+            TEST_KEYS.pubkeys()[0].to_string()
+        );
+        assert_eq!(json["signers"][0]["block-vss"], true);
+        assert_eq!(json["signers"][1]["block-vss"], false);
+        assert_eq!(json["ready-to-sign"], false);
+        assert_eq!(json["ready-to-finalize"], false);
+    }
+
+    #[test]
+    fn test_execute_file_not_found() {
+        let matches = InspectPsbCommand::args().get_matches_from(vec![
+            "inspectpsb",
+            "--psb",
+            "/path/to/not_found.json",
+        ]);
+        let response = InspectPsbCommand::execute(&matches);
+        match response {
+            Err(Error::ConfigFileIOError(_)) => {}
+            _ => panic!("it should fail to read the file"),
+        }
+    }
+}
@@ -1,20 +1,38 @@
 use crate::crypto::vss::Vss;
+use crate::errors::Error;
 use crate::net::SignerID;
 use crate::signer_node::BidirectionalSharedSecretMap;
 use crate::signer_node::SharedSecret;
 use crate::signer_node::SharedSecretMap;
+use clap::ArgMatches;
 use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
     ShamirSecretSharing, VerifiableSS,
 };
+use serde_json::{json, Value};
+use std::io::Read;
 use tapyrus::{PrivateKey, PublicKey};
 
 pub mod aggregate;
+pub mod check_federations;
 pub mod compute_sig;
+pub mod contribute_psb;
 pub mod create_block_vss;
+pub mod create_federation;
+pub mod create_genesis;
 pub mod create_key;
 pub mod create_node_vss;
+pub mod create_psb;
+pub mod create_recovery_share;
+pub mod decode_block;
+pub mod decode_vss;
+pub mod finalize_psb;
+pub mod inspect_psb;
+pub mod psb;
+pub mod recover_share;
 pub mod sign;
 pub mod traits;
+pub mod verify_evidence;
+pub mod verify_federation;
 
 pub fn index_of(private_key: &PrivateKey, public_keys: &Vec<PublicKey>) -> usize {
     let secp = tapyrus::secp256k1::Secp256k1::new();
@@ -26,6 +44,110 @@ pub fn index_of(private_key: &PrivateKey, public_keys: &Vec<PublicKey>) -> usize
     pos + 1
 }
 
+/// Returns the value of a secret-bearing argument. Secrets given on the command line are visible
+/// to the other users through the process list and remain in the shell history, so the value
+/// `-` reads the secret from stdin and `@<path>` reads it from the file instead.
+pub fn secret_value_of(matches: &ArgMatches, name: &str) -> Result<Option<String>, Error> {
+    let value = match matches.value_of(name) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let secret = if value == "-" {
+        let mut buf = String::new();
+        std::io::stdin().read_to_string(&mut buf)?;
+        buf
+    } else if value.starts_with('@') {
+        std::fs::read_to_string(&value[1..])?
+    } else {
+        return Ok(Some(value.to_string()));
+    };
+    Ok(Some(secret.trim().to_string()))
+}
+
+/// Returns the values of an argument which takes a long list of hex strings. Each value
+/// `@<path>` is expanded to the entries in the file, which is either a JSON array of strings or
+/// a text with one entry per line.
+pub fn list_values_of(matches: &ArgMatches, name: &str) -> Result<Option<Vec<String>>, Error> {
+    let values = match matches.values_of(name) {
+        Some(values) => values,
+        None => return Ok(None),
+    };
+    let mut list = Vec::new();
+    for value in values {
+        if value.starts_with('@') {
+            let content = std::fs::read_to_string(&value[1..])?;
+            list.extend(parse_list(&content)?);
+        } else {
+            list.push(value.to_string());
+        }
+    }
+    Ok(Some(list))
+}
+
+fn parse_list(content: &str) -> Result<Vec<String>, Error> {
+    let content = content.trim();
+    if content.starts_with('[') {
+        let list: Vec<String> = serde_json::from_str(content)?;
+        Ok(list.iter().map(|s| s.trim().to_string()).collect())
+    } else {
+        Ok(content
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_string())
+            .collect())
+    }
+}
+
+/// Returns the structured error object which `tapyrus-setup --format json` prints on failure.
+/// Scripts can branch on `code` without parsing `message`.
+pub fn error_to_json(error: &Error) -> Value {
+    json!({
+        "error": {
+            "code": error_code(error),
+            "message": error.to_string(),
+        }
+    })
+}
+
+/// Returns the stable code of the error which `tapyrus-setup --format json` prints. Codes are
+/// listed explicitly, so renaming a variant or changing its fields doesn't change the code.
+fn error_code(error: &Error) -> &'static str {
+    match error {
+        Error::JsonRpc(..) => "JsonRpc",
+        Error::Json(..) => "Json",
+        Error::InvalidLength(..) => "InvalidLength",
+        Error::InvalidArgs(..) => "InvalidArgs",
+        Error::BitcoinConsensusEncodeError(..) => "BitcoinConsensusEncodeError",
+        Error::InvalidRequest(..) => "InvalidRequest",
+        Error::DuplicatedMessage => "DuplicatedMessage",
+        Error::InvalidLocalSignature => "InvalidLocalSignature",
+        Error::InvalidAggregatedSignature => "InvalidAggregatedSignature",
+        Error::InvalidBlock => "InvalidBlock",
+        Error::InvalidKey => "InvalidKey",
+        Error::InvalidNodeState => "InvalidNodeState",
+        Error::InvalidSS => "InvalidSS",
+        Error::InvalidSig => "InvalidSig",
+        Error::TimerAlreadyStarted => "TimerAlreadyStarted",
+        Error::InvalidTomlFormat(..) => "InvalidTomlFormat",
+        Error::ConfigFileIOError(..) => "ConfigFileIOError",
+        Error::InvalidPublicKeyFormat(..) => "InvalidPublicKeyFormat",
+        Error::RedisError(..) => "RedisError",
+        Error::IncompleteBlock => "IncompleteBlock",
+        Error::InvalidFederation(..) => "InvalidFederation",
+        Error::InvalidAggregatedPublicKey => "InvalidAggregatedPublicKey",
+        Error::UnsupportedXField => "UnsupportedXField",
+        Error::InvalidNonce => "InvalidNonce",
+        Error::InvalidPartiallySignedBlock(..) => "InvalidPartiallySignedBlock",
+        Error::NoHealthyRpcEndpoint => "NoHealthyRpcEndpoint",
+        Error::RpcTimeout(..) => "RpcTimeout",
+        Error::RpcConnectionFailed(..) => "RpcConnectionFailed",
+        Error::RpcAuthFailed => "RpcAuthFailed",
+        Error::RpcCookieFile(..) => "RpcCookieFile",
+        Error::InvalidRpcResponse(..) => "InvalidRpcResponse",
+    }
+}
+
 pub fn vss_to_shared_secret_map(
     node_vss_vec: &Vec<Vss>,
     params: &ShamirSecretSharing,
@@ -112,4 +234,103 @@ mod tests {
 
         assert_eq!(index_of(&private_key, &public_keys), 2);
     }
+
+    #[test]
+    fn test_error_to_json() {
+        let json = error_to_json(&Error::InvalidArgs("private-key".to_string()));
+        assert_eq!(json["error"]["code"], "InvalidArgs");
+        assert_eq!(json["error"]["message"], "InvalidArgs(\"private-key\")");
+
+        let json = error_to_json(&Error::InvalidSS);
+        assert_eq!(json["error"]["code"], "InvalidSS");
+        assert_eq!(json["error"]["message"], "InvalidSS");
+
+        let json = error_to_json(&Error::InvalidFederation(Some(10), "invalid"));
+        assert_eq!(json["error"]["code"], "InvalidFederation");
+    }
+
+    fn matches<'a>(args: Vec<&str>) -> ArgMatches<'a> {
+        clap::App::new("test")
+            .arg(
+                clap::Arg::with_name("secret")
+                    .long("secret")
+                    .takes_value(true),
+            )
+            .arg(
+                clap::Arg::with_name("list")
+                    .long("list")
+                    .multiple(true)
+                    .takes_value(true),
+            )
+            .get_matches_from(args)
+    }
+
+    #[test]
+    fn test_secret_value_of() {
+        let path = std::env::temp_dir().join("setup_test_secret_value_of.txt");
+        std::fs::write(&path, "secret\n").unwrap();
+        let arg = format!("@{}", path.to_str().unwrap());
+
+        let result = secret_value_of(&matches(vec!["test", "--secret", &arg]), "secret");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap(), Some("secret".to_string()));
+
+        let result = secret_value_of(&matches(vec!["test", "--secret", "value"]), "secret");
+        assert_eq!(result.unwrap(), Some("value".to_string()));
+
+        let result = secret_value_of(&matches(vec!["test"]), "secret");
+        assert_eq!(result.unwrap(), None);
+
+        let result = secret_value_of(
+            &matches(vec!["test", "--secret", "@/path/to/not_found.txt"]),
+            "secret",
+        );
+        match result {
+            Err(Error::ConfigFileIOError(_)) => {}
+            _ => panic!("it should fail to read the file"),
+        }
+    }
+
+    #[test]
+    fn test_list_values_of() {
+        let lines = std::env::temp_dir().join("setup_test_list_values_of.txt");
+        std::fs::write(&lines, "a\n\n  b \n").unwrap();
+        let json = std::env::temp_dir().join("setup_test_list_values_of.json");
+        std::fs::write(&json, "[\"c\", \"d\"]").unwrap();
+        let lines_arg = format!("@{}", lines.to_str().unwrap());
+        let json_arg = format!("@{}", json.to_str().unwrap());
+
+        let result = list_values_of(
+            &matches(vec![
+                "test", "--list", &lines_arg, "--list", "x", "--list", &json_arg,
+            ]),
+            "list",
+        );
+        std::fs::remove_file(&lines).unwrap();
+        std::fs::remove_file(&json).unwrap();
+        assert_eq!(
+            result.unwrap(),
+            Some(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "x".to_string(),
+                "c".to_string(),
+                "d".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_list_values_of_invalid_json() {
+        let path = std::env::temp_dir().join("setup_test_list_values_of_invalid.json");
+        std::fs::write(&path, "[\"c\", 1]").unwrap();
+        let arg = format!("@{}", path.to_str().unwrap());
+
+        let result = list_values_of(&matches(vec!["test", "--list", &arg]), "list");
+        std::fs::remove_file(&path).unwrap();
+        match result {
+            Err(Error::Json(_)) => {}
+            _ => panic!("it should fail to parse the file"),
+        }
+    }
 }
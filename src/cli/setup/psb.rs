@@ -0,0 +1,652 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Partially signed block.
+//!
+//! A file which accumulates the block VSSs and the local signatures of the signers while it is
+//! passed between offline signers. It replaces passing all inputs of `sign` and `computesig` as
+//! command line arguments in one place.
+//!
+//! The block VSSs are stored in the file for all receivers, so the secrets in them are masked with
+//! a key derived from ECDH between the sender and the receiver. Only the receiver can remove the
+//! mask, and the commitments are kept as is.
+//!
+//! The masks also commit to the signature hash of the block. If the block in the file is replaced
+//! while the block VSSs are kept, the unmasked secrets don't match the commitments and the signer
+//! refuses to sign, because signing two messages with the same block VSSs leaks its node secret
+//! share.
+use crate::cli::setup::{vss_to_bidirectional_shared_secret_map, vss_to_shared_secret_map};
+use crate::crypto::multi_party_schnorr::{LocalSig, SharedKeys};
+use crate::crypto::vss::{Commitment, Vss};
+use crate::errors::Error;
+use crate::rpc::Rpc;
+use crate::sign::Sign;
+use crate::signer_node::{NodeParameters, ToVerifiableSS};
+use crate::util::sum_point;
+
+use curv::arithmetic::traits::{Converter, Modulo};
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    ShamirSecretSharing, VerifiableSS,
+};
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::str::FromStr;
+use tapyrus::blockdata::block::Block;
+use tapyrus::consensus::encode::{deserialize, serialize};
+use tapyrus::util::prime::jacobi;
+use tapyrus::{PrivateKey, PublicKey};
+
+pub struct PartiallySignedBlock {
+    /// The block to be signed.
+    block: Block,
+    /// The minimum number of signers required to sign the block.
+    threshold: usize,
+    aggregated_public_key: PublicKey,
+    /// Public keys of all signers, sorted in the same order as the signer node.
+    public_keys: Vec<PublicKey>,
+    /// Block VSSs keyed by the sender and the receiver. Their secrets are masked.
+    block_vss: BTreeMap<(PublicKey, PublicKey), Vss>,
+    local_sigs: BTreeMap<PublicKey, LocalSig>,
+}
+
+impl PartiallySignedBlock {
+    pub fn new(
+        block: Block,
+        threshold: usize,
+        aggregated_public_key: PublicKey,
+        public_keys: Vec<PublicKey>,
+    ) -> Result<Self, Error> {
+        let mut public_keys = public_keys;
+        NodeParameters::<Rpc>::sort_publickey(&mut public_keys);
+        public_keys.dedup();
+
+        if threshold < 1 || threshold > public_keys.len() {
+            return Err(Error::InvalidPartiallySignedBlock(
+                "threshold should be between 1 and the number of signers",
+            ));
+        }
+        to_point(&aggregated_public_key)?;
+
+        Ok(PartiallySignedBlock {
+            block,
+            threshold,
+            aggregated_public_key,
+            public_keys,
+            block_vss: BTreeMap::new(),
+            local_sigs: BTreeMap::new(),
+        })
+    }
+
+    pub fn read(path: &str) -> Result<Self, Error> {
+        let contents = std::fs::read_to_string(path)?;
+        PartiallySignedBlock::from_str(&contents)
+    }
+
+    /// Writes the PSB to the file. If `create_new` is true, it fails when the file already exists.
+    pub fn write(&self, path: &str, create_new: bool) -> Result<(), Error> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .create_new(create_new)
+            .open(path)?;
+        file.write_all(self.to_string().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn aggregated_public_key(&self) -> &PublicKey {
+        &self.aggregated_public_key
+    }
+
+    pub fn public_keys(&self) -> &Vec<PublicKey> {
+        &self.public_keys
+    }
+
+    /// Returns true if the signer has sent block VSSs to all signers.
+    pub fn has_block_vss(&self, sender: &PublicKey) -> bool {
+        self.public_keys
+            .iter()
+            .all(|receiver| self.block_vss.contains_key(&(*sender, *receiver)))
+    }
+
+    pub fn has_local_sig(&self, public_key: &PublicKey) -> bool {
+        self.local_sigs.contains_key(public_key)
+    }
+
+    /// Returns true if all signers have sent their block VSSs, so that each signer can create its
+    /// local signature.
+    pub fn is_ready_to_sign(&self) -> bool {
+        self.public_keys.iter().all(|pk| self.has_block_vss(pk))
+    }
+
+    /// Returns true if the local signatures are enough to compute the block proof.
+    pub fn is_ready_to_finalize(&self) -> bool {
+        self.local_sigs.len() >= self.threshold
+    }
+
+    /// Creates block VSSs for all signers and adds them.
+    /// Returns false if the signer has already added them.
+    pub fn contribute_block_vss(&mut self, private_key: &PrivateKey) -> Result<bool, Error> {
+        let public_key = self.public_key_of(private_key)?;
+        if self.has_block_vss(&public_key) {
+            return Ok(false);
+        }
+
+        let index = self.index_of(&public_key)?;
+        let scalar = to_scalar(private_key)?;
+        let (
+            _key,
+            vss_scheme_for_positive,
+            secret_shares_for_positive,
+            vss_scheme_for_negative,
+            secret_shares_for_negative,
+        ) = Vss::create_block_shares(index, self.threshold, self.public_keys.len());
+
+        for (j, receiver) in self.public_keys.iter().enumerate() {
+            let vss = Vss {
+                sender_public_key: public_key,
+                receiver_public_key: *receiver,
+                positive_commitments: vss_scheme_for_positive
+                    .commitments
+                    .iter()
+                    .map(|c| Commitment::from(c))
+                    .collect(),
+                positive_secret: secret_shares_for_positive[j],
+                negative_commitments: vss_scheme_for_negative
+                    .commitments
+                    .iter()
+                    .map(|c| Commitment::from(c))
+                    .collect(),
+                negative_secret: secret_shares_for_negative[j],
+            };
+            let (mask_for_positive, mask_for_negative) =
+                masks(&scalar, receiver, &vss, &self.block)?;
+            let masked = Vss {
+                positive_secret: vss.positive_secret + mask_for_positive,
+                negative_secret: vss.negative_secret + mask_for_negative,
+                ..vss
+            };
+            self.block_vss.insert((public_key, *receiver), masked);
+        }
+        Ok(true)
+    }
+
+    /// Creates the local signature of the signer from the block VSSs sent to it and adds it.
+    /// Returns false if the signer has already added it or some block VSSs are still missing.
+    /// Returns an error if any block VSS is not bound to the block in the file.
+    pub fn contribute_local_sig(
+        &mut self,
+        private_key: &PrivateKey,
+        node_secret_share: &FE,
+    ) -> Result<bool, Error> {
+        let public_key = self.public_key_of(private_key)?;
+        if self.has_local_sig(&public_key) || !self.is_ready_to_sign() {
+            return Ok(false);
+        }
+
+        let index = self.index_of(&public_key)?;
+        let scalar = to_scalar(private_key)?;
+        let block_vss_vec = self
+            .public_keys
+            .iter()
+            .map(|sender| {
+                let vss = &self.block_vss[&(*sender, public_key)];
+                let (mask_for_positive, mask_for_negative) =
+                    masks(&scalar, sender, vss, &self.block)?;
+                let unmasked = Vss {
+                    positive_secret: vss.positive_secret + negate(&mask_for_positive),
+                    negative_secret: vss.negative_secret + negate(&mask_for_negative),
+                    ..vss.clone()
+                };
+                if !self.is_valid_share(&unmasked, index) {
+                    return Err(Error::InvalidPartiallySignedBlock(
+                        "block VSS is not bound to the block",
+                    ));
+                }
+                Ok(unmasked)
+            })
+            .collect::<Result<Vec<Vss>, Error>>()?;
+
+        let shared_block_secrets =
+            vss_to_bidirectional_shared_secret_map(&block_vss_vec, &self.sharing_params());
+        let priv_shared_keys = SharedKeys {
+            y: to_point(&self.aggregated_public_key)?,
+            x_i: *node_secret_share,
+        };
+        let (_, _, local_sig) = Vss::create_local_sig_from_shares(
+            &priv_shared_keys,
+            index,
+            &shared_block_secrets,
+            &self.block,
+        )?;
+        self.local_sigs.insert(public_key, local_sig);
+        Ok(true)
+    }
+
+    /// Verifies the local signatures and returns the block with the aggregated signature as its
+    /// proof.
+    ///
+    /// * `node_vss` - The node VSSs of any signer. Only their commitments are used.
+    pub fn finalize(&self, node_vss: &Vec<Vss>) -> Result<Block, Error> {
+        if !self.is_ready_to_sign() {
+            return Err(Error::InvalidPartiallySignedBlock("block VSSs are missing"));
+        }
+        if !self.is_ready_to_finalize() {
+            return Err(Error::InvalidPartiallySignedBlock(
+                "local signatures are not enough",
+            ));
+        }
+        let mut senders: Vec<PublicKey> = node_vss.iter().map(|v| v.sender_public_key).collect();
+        NodeParameters::<Rpc>::sort_publickey(&mut senders);
+        if senders != self.public_keys {
+            return Err(Error::InvalidArgs(
+                "node-vss should be sent from all signers".to_string(),
+            ));
+        }
+
+        let params = self.sharing_params();
+        let key_gen_vss_vec: Vec<VerifiableSS> =
+            vss_to_shared_secret_map(node_vss, &params).to_vss();
+
+        // Commitments of a sender are the same for all receivers, so any receiver can be used.
+        let receiver = self.public_keys[0];
+        let block_vss_vec: Vec<&Vss> = self
+            .public_keys
+            .iter()
+            .map(|sender| &self.block_vss[&(*sender, receiver)])
+            .collect();
+        let r_for_positive = sum_point(
+            &block_vss_vec
+                .iter()
+                .map(|vss| vss.positive_commitments[0].to_point())
+                .collect(),
+        );
+        let y = r_for_positive.y_coor().expect("can not get y_coor");
+        let is_positive = jacobi(&Converter::to_vec(&y)) == 1;
+        let eph_vss_vec: Vec<VerifiableSS> = block_vss_vec
+            .iter()
+            .map(|vss| {
+                let commitments = if is_positive {
+                    &vss.positive_commitments
+                } else {
+                    &vss.negative_commitments
+                };
+                VerifiableSS {
+                    parameters: params.clone(),
+                    commitments: commitments.iter().map(|c| c.to_point()).collect(),
+                }
+            })
+            .collect();
+        let r = sum_point(&eph_vss_vec.iter().map(|vss| vss.commitments[0]).collect());
+
+        let parties = self
+            .local_sigs
+            .keys()
+            .map(|pk| self.index_of(pk).map(|i| i - 1))
+            .collect::<Result<Vec<usize>, _>>()?;
+        let local_sigs: Vec<LocalSig> = self.local_sigs.values().cloned().collect();
+        let vss_sum =
+            LocalSig::verify_local_sigs(&local_sigs, &parties[..], &key_gen_vss_vec, &eph_vss_vec)?;
+        let signature = Sign::aggregate(&vss_sum, &local_sigs, &parties[..], r);
+        let hash = self.block.header.signature_hash();
+        signature.verify(&hash[..], &to_point(&self.aggregated_public_key)?)?;
+
+        let sig_hex = Sign::format_signature(&signature);
+        let sig: tapyrus::util::signature::Signature =
+            deserialize(&hex::decode(sig_hex).map_err(|_| Error::InvalidSig)?)?;
+        let mut block = self.block.clone();
+        block.header.proof = Some(sig);
+        Ok(block)
+    }
+
+    /// Returns true if both secrets of the unmasked block VSS match its commitments.
+    fn is_valid_share(&self, vss: &Vss, index: usize) -> bool {
+        let is_valid = |commitments: &Vec<Commitment>, secret: &FE| {
+            VerifiableSS {
+                parameters: self.sharing_params(),
+                commitments: commitments.iter().map(|c| c.to_point()).collect(),
+            }
+            .validate_share(secret, index)
+            .is_ok()
+        };
+        is_valid(&vss.positive_commitments, &vss.positive_secret)
+            && is_valid(&vss.negative_commitments, &vss.negative_secret)
+    }
+
+    fn sharing_params(&self) -> ShamirSecretSharing {
+        ShamirSecretSharing {
+            threshold: self.threshold - 1,
+            share_count: self.public_keys.len(),
+        }
+    }
+
+    fn public_key_of(&self, private_key: &PrivateKey) -> Result<PublicKey, Error> {
+        let secp = tapyrus::secp256k1::Secp256k1::new();
+        let public_key = PublicKey::from_private_key(&secp, private_key);
+        self.index_of(&public_key)?;
+        Ok(public_key)
+    }
+
+    /// Returns 1-based index of the signer.
+    fn index_of(&self, public_key: &PublicKey) -> Result<usize, Error> {
+        self.public_keys
+            .iter()
+            .position(|pk| pk == public_key)
+            .map(|i| i + 1)
+            .ok_or(Error::InvalidArgs(format!(
+                "{} is not a signer of the block",
+                public_key
+            )))
+    }
+}
+
+impl fmt::Display for PartiallySignedBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ser = SerPartiallySignedBlock {
+            block: hex::encode(serialize(&self.block)),
+            threshold: self.threshold,
+            aggregated_public_key: self.aggregated_public_key,
+            public_keys: self.public_keys.clone(),
+            block_vss: self.block_vss.values().cloned().collect(),
+            local_sigs: self
+                .local_sigs
+                .iter()
+                .map(|(public_key, local_sig)| format_local_sig(local_sig, public_key))
+                .collect(),
+        };
+        let json = serde_json::to_string_pretty(&ser).map_err(|_| fmt::Error)?;
+        write!(f, "{}", json)
+    }
+}
+
+impl FromStr for PartiallySignedBlock {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let ser: SerPartiallySignedBlock = serde_json::from_str(s)?;
+        let block = hex::decode(&ser.block)
+            .ok()
+            .and_then(|hex| deserialize::<Block>(&hex).ok())
+            .ok_or(Error::InvalidPartiallySignedBlock("block is invalid"))?;
+
+        let mut psb = PartiallySignedBlock::new(
+            block,
+            ser.threshold,
+            ser.aggregated_public_key,
+            ser.public_keys,
+        )?;
+        for vss in ser.block_vss {
+            if psb.index_of(&vss.sender_public_key).is_err()
+                || psb.index_of(&vss.receiver_public_key).is_err()
+                || vss.positive_commitments.len() != psb.threshold
+                || vss.negative_commitments.len() != psb.threshold
+                || !vss
+                    .positive_commitments
+                    .iter()
+                    .chain(vss.negative_commitments.iter())
+                    .all(|c| c.is_valid_point())
+            {
+                return Err(Error::InvalidPartiallySignedBlock("block VSS is invalid"));
+            }
+            psb.block_vss
+                .insert((vss.sender_public_key, vss.receiver_public_key), vss);
+        }
+
+        for s in ser.local_sigs {
+            let (local_sig, public_key) = parse_local_sig(&s)?;
+            if psb.index_of(&public_key).is_err() {
+                return Err(Error::InvalidPartiallySignedBlock(
+                    "local signature is invalid",
+                ));
+            }
+            psb.local_sigs.insert(public_key, local_sig);
+        }
+        Ok(psb)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerPartiallySignedBlock {
+    block: String,
+    threshold: usize,
+    #[serde(rename = "aggregated-public-key")]
+    aggregated_public_key: PublicKey,
+    #[serde(rename = "public-keys")]
+    public_keys: Vec<PublicKey>,
+    #[serde(rename = "block-vss", default)]
+    block_vss: Vec<Vss>,
+    #[serde(rename = "local-sigs", default)]
+    local_sigs: Vec<String>,
+}
+
+/// Formats the local signature in the same way as the output of `tapyrus-setup sign`.
+fn format_local_sig(local_sig: &LocalSig, public_key: &PublicKey) -> String {
+    format!(
+        "{:0>64}{:0>64}{}",
+        local_sig.gamma_i.to_big_int().to_hex(),
+        local_sig.e.to_big_int().to_hex(),
+        hex::encode(&public_key.key.serialize()[..]),
+    )
+}
+
+fn parse_local_sig(s: &str) -> Result<(LocalSig, PublicKey), Error> {
+    let invalid = || Error::InvalidPartiallySignedBlock("local signature is invalid");
+    if s.len() != 194 || !s.is_ascii() {
+        return Err(invalid());
+    }
+    let gamma_i = BigInt::from_str_radix(&s[0..64], 16).map_err(|_| invalid())?;
+    let e = BigInt::from_str_radix(&s[64..128], 16).map_err(|_| invalid())?;
+    let public_key = PublicKey::from_str(&s[128..]).map_err(|_| invalid())?;
+    Ok((
+        LocalSig {
+            gamma_i: ECScalar::from(&gamma_i),
+            e: ECScalar::from(&e),
+        },
+        public_key,
+    ))
+}
+
+fn to_point(public_key: &PublicKey) -> Result<GE, Error> {
+    let bytes = public_key.key.serialize_uncompressed();
+    GE::from_bytes(&bytes[1..]).map_err(|_| Error::InvalidKey)
+}
+
+fn to_scalar(private_key: &PrivateKey) -> Result<FE, Error> {
+    let n = Sign::private_key_to_big_int(private_key.key).ok_or(Error::InvalidKey)?;
+    Ok(ECScalar::from(&n))
+}
+
+/// Masks for the positive and negative secrets of the block VSS. The sender and the receiver
+/// derive the same masks from ECDH of their keys. The commitment of the VSS is also hashed, so
+/// masks are different each time the sender creates new block VSSs, and the signature hash of the
+/// block is hashed to bind the VSS to the block.
+fn masks(scalar: &FE, other: &PublicKey, vss: &Vss, block: &Block) -> Result<(FE, FE), Error> {
+    let shared = to_point(other)? * scalar;
+    let commitment = vss.positive_commitments[0].to_point();
+    let sighash = block.header.signature_hash();
+    let mask = |tag: u8| -> FE {
+        let mut hasher = Sha256::new();
+        hasher.input(&shared.get_element().serialize()[..]);
+        hasher.input(&vss.sender_public_key.key.serialize()[..]);
+        hasher.input(&vss.receiver_public_key.key.serialize()[..]);
+        hasher.input(&commitment.get_element().serialize()[..]);
+        hasher.input(&sighash[..]);
+        hasher.input(&[tag]);
+        ECScalar::from(&BigInt::from(&hasher.result()[..]))
+    };
+    Ok((mask(0), mask(1)))
+}
+
+fn negate(fe: &FE) -> FE {
+    ECScalar::from(&BigInt::mod_sub(&FE::q(), &fe.to_big_int(), &FE::q()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::blocks::get_block;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::{federation, node_vss};
+
+    fn node_secret_share(i: usize) -> FE {
+        federation(i).node_secret_share().x_i
+    }
+
+    fn psb() -> PartiallySignedBlock {
+        PartiallySignedBlock::new(
+            get_block(0),
+            3,
+            TEST_KEYS.aggregated(),
+            TEST_KEYS.unsorted_pubkeys(),
+        )
+        .unwrap()
+    }
+
+    /// Serializes and deserializes the PSB as if it is passed to the next signer.
+    fn pass(psb: &PartiallySignedBlock) -> PartiallySignedBlock {
+        PartiallySignedBlock::from_str(&psb.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_new() {
+        let psb = psb();
+        assert_eq!(psb.public_keys(), &TEST_KEYS.pubkeys());
+        assert!(!psb.is_ready_to_sign());
+        assert!(!psb.is_ready_to_finalize());
+
+        let result =
+            PartiallySignedBlock::new(get_block(0), 6, TEST_KEYS.aggregated(), TEST_KEYS.pubkeys());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_sign_and_finalize() {
+        let mut psb = psb();
+        for i in 0..5 {
+            assert!(psb.contribute_block_vss(&TEST_KEYS.key[i]).unwrap());
+            // Block VSSs are not added twice.
+            assert!(!psb.contribute_block_vss(&TEST_KEYS.key[i]).unwrap());
+            psb = pass(&psb);
+        }
+        assert!(psb.is_ready_to_sign());
+
+        for i in 0..3 {
+            assert!(psb
+                .contribute_local_sig(&TEST_KEYS.key[i], &node_secret_share(i))
+                .unwrap());
+            psb = pass(&psb);
+            assert!(psb.has_local_sig(&TEST_KEYS.pubkeys()[i]));
+        }
+        assert!(psb.is_ready_to_finalize());
+
+        let block = psb.finalize(&node_vss(4)).unwrap();
+        assert!(block.header.proof.is_some());
+        assert_eq!(
+            block.header.signature_hash(),
+            get_block(0).header.signature_hash()
+        );
+    }
+
+    #[test]
+    fn test_contribute_local_sig_without_all_block_vss() {
+        let mut psb = psb();
+        for i in 0..4 {
+            psb.contribute_block_vss(&TEST_KEYS.key[i]).unwrap();
+        }
+        assert!(!psb
+            .contribute_local_sig(&TEST_KEYS.key[0], &node_secret_share(0))
+            .unwrap());
+        assert!(!psb.has_local_sig(&TEST_KEYS.pubkeys()[0]));
+    }
+
+    #[test]
+    fn test_block_vss_is_masked() {
+        let mut psb = psb();
+        psb.contribute_block_vss(&TEST_KEYS.key[0]).unwrap();
+
+        let sender = TEST_KEYS.pubkeys()[0];
+        let receiver = TEST_KEYS.pubkeys()[1];
+        let vss = &psb.block_vss[&(sender, receiver)];
+        let verifiable_ss = VerifiableSS {
+            parameters: psb.sharing_params(),
+            commitments: vss
+                .positive_commitments
+                .iter()
+                .map(|c| c.to_point())
+                .collect(),
+        };
+        assert!(verifiable_ss
+            .validate_share(&vss.positive_secret, 2)
+            .is_err());
+    }
+
+    #[test]
+    fn test_finalize_without_enough_local_sigs() {
+        let mut psb = psb();
+        for i in 0..5 {
+            psb.contribute_block_vss(&TEST_KEYS.key[i]).unwrap();
+        }
+        psb.contribute_local_sig(&TEST_KEYS.key[0], &node_secret_share(0))
+            .unwrap();
+        assert!(psb.finalize(&node_vss(0)).is_err());
+    }
+
+    #[test]
+    fn test_contribute_by_non_signer() {
+        let mut psb = PartiallySignedBlock::new(
+            get_block(0),
+            2,
+            TEST_KEYS.aggregated(),
+            TEST_KEYS.pubkeys()[0..3].to_vec(),
+        )
+        .unwrap();
+        assert!(psb.contribute_block_vss(&TEST_KEYS.key[4]).is_err());
+    }
+
+    #[test]
+    fn test_from_str_with_invalid_local_sig() {
+        let mut json: serde_json::Value = serde_json::from_str(&psb().to_string()).unwrap();
+        json["local-sigs"] = serde_json::json!(["x"]);
+        assert!(PartiallySignedBlock::from_str(&json.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_contribute_local_sig_for_replaced_block() {
+        let mut psb = psb();
+        for i in 0..5 {
+            psb.contribute_block_vss(&TEST_KEYS.key[i]).unwrap();
+        }
+        // The block is replaced while the block VSSs are kept.
+        psb.block = get_block(1);
+        psb = pass(&psb);
+
+        let result = psb.contribute_local_sig(&TEST_KEYS.key[0], &node_secret_share(0));
+        assert!(result.is_err());
+        assert!(!psb.has_local_sig(&TEST_KEYS.pubkeys()[0]));
+    }
+
+    #[test]
+    fn test_from_str_with_invalid_commitment() {
+        let mut psb = psb();
+        psb.contribute_block_vss(&TEST_KEYS.key[0]).unwrap();
+
+        let key = (TEST_KEYS.pubkeys()[0], TEST_KEYS.pubkeys()[1]);
+        let mut vss = psb.block_vss[&key].clone();
+        vss.positive_commitments[1] = Commitment::new(BigInt::from(1), BigInt::from(1));
+        psb.block_vss.insert(key, vss);
+        assert!(PartiallySignedBlock::from_str(&psb.to_string()).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::cli::setup::list_values_of;
+use crate::cli::setup::secret_value_of;
+use crate::cli::setup::traits::Response;
+use crate::crypto::recovery::Recovery;
+use crate::crypto::vss::Vss;
+use crate::errors::Error;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use tapyrus::{PrivateKey, PublicKey};
+
+pub struct RecoverShareResponse {
+    node_vss: Vec<Vss>,
+}
+
+impl RecoverShareResponse {
+    fn new(node_vss: Vec<Vss>) -> Self {
+        RecoverShareResponse { node_vss: node_vss }
+    }
+}
+
+impl Response for RecoverShareResponse {
+    fn to_json(&self) -> Value {
+        let node_vss: Vec<Value> = self
+            .node_vss
+            .iter()
+            .map(|v| json!({ "public-key": v.sender_public_key.to_string(), "vss": v.to_string() }))
+            .collect();
+        json!({ "node-vss": node_vss })
+    }
+}
+
+impl fmt::Display for RecoverShareResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = self
+            .node_vss
+            .iter()
+            .map(|v| format!("{}:{}", v.sender_public_key, v))
+            .collect::<Vec<String>>()
+            .join("\n");
+        write!(f, "{}", c)
+    }
+}
+
+pub struct RecoverShareCommand {}
+
+impl<'a> RecoverShareCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let private_key: PrivateKey = secret_value_of(matches, "private-key")?
+            .and_then(|key| PrivateKey::from_wif(&key).ok())
+            .ok_or(Error::InvalidArgs("private-key".to_string()))?;
+
+        let aggregated_public_key: PublicKey = matches
+            .value_of("aggregated-public-key")
+            .and_then(|hex| PublicKey::from_str(hex).ok())
+            .ok_or(Error::InvalidArgs("aggregated-public-key".to_string()))?;
+
+        let shares: Vec<Vss> = list_values_of(matches, "recovery-share")?
+            .ok_or(Error::InvalidArgs("recovery-share".to_string()))?
+            .iter()
+            .map(|s| Vss::from_str(s).map_err(|_| Error::InvalidSS))
+            .collect::<Result<Vec<Vss>, _>>()?;
+
+        let secp = tapyrus::secp256k1::Secp256k1::new();
+        let public_key = PublicKey::from_private_key(&secp, &private_key);
+
+        let node_vss = Recovery::recover_node_vss(&public_key, &aggregated_public_key, &shares)?;
+        Ok(Box::new(RecoverShareResponse::new(node_vss)))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("recovershare").args(&[
+            Arg::with_name("private-key")
+                .long("private-key")
+                .required(true)
+                .takes_value(true)
+                .help("private key of this signer with an extend WIF format (`@<path>` or `-` reads it from the file or stdin)"),
+            Arg::with_name("aggregated-public-key")
+                .long("aggregated-public-key")
+                .required(true)
+                .takes_value(true)
+                .help("aggregated public key of the federation, which the recovered node VSSs are checked against"),
+            Arg::with_name("recovery-share")
+                .long("recovery-share")
+                .required(true)
+                .multiple(true)
+                .takes_value(true)
+                .help("recovery shares which the all helpers created. These values are generated by `tapyrus-setup createrecoveryshare` (`@<path>` reads them from the file which has one value per line or a JSON array)"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::node_vss;
+
+    #[test]
+    fn test_execute() {
+        let pubkeys = TEST_KEYS.pubkeys();
+        let helpers = pubkeys[1..4].to_vec();
+
+        let mut args = vec![
+            "recovershare".to_string(),
+            "--private-key".to_string(),
+            TEST_KEYS.key[0].to_wif(),
+            "--aggregated-public-key".to_string(),
+            TEST_KEYS.aggregated().to_string(),
+        ];
+        for i in 1..4 {
+            let shares = Recovery::create_recovery_shares(
+                &TEST_KEYS.key[i],
+                &node_vss(i),
+                &helpers,
+                &pubkeys[0],
+            )
+            .unwrap();
+            for share in shares {
+                args.push("--recovery-share".to_string());
+                args.push(share.to_string());
+            }
+        }
+        let matches = RecoverShareCommand::args().get_matches_from(args);
+        let response = RecoverShareCommand::execute(&matches).unwrap();
+
+        let expected = node_vss(0)
+            .iter()
+            .map(|v| format!("{}:{}", v.sender_public_key, v))
+            .collect::<Vec<String>>()
+            .join("\n");
+        assert_eq!(format!("{}", response), expected);
+    }
+
+    #[test]
+    fn test_execute_invalid_recovery_share() {
+        let matches = RecoverShareCommand::args().get_matches_from(vec![
+            "recovershare",
+            "--private-key",
+            "cQYYBMFS9dRR3Mt16gW4jixCqSiMhCwuDMHUBs6WeHMTxMnsq8Gh",
+            "--aggregated-public-key",
+            "033cfe7fa1be58191b9108883543e921d31dc7726e051ee773e0ea54786ce438f8",
+            "--recovery-share",
+            "x",
+        ]);
+        let response = RecoverShareCommand::execute(&matches);
+        assert_eq!(format!("{}", response.err().unwrap()), "InvalidSS");
+    }
+
+    #[test]
+    fn test_execute_with_other_aggregated_public_key() {
+        let pubkeys = TEST_KEYS.pubkeys();
+        let helpers = pubkeys[1..4].to_vec();
+
+        let mut args = vec![
+            "recovershare".to_string(),
+            "--private-key".to_string(),
+            TEST_KEYS.key[0].to_wif(),
+            "--aggregated-public-key".to_string(),
+            pubkeys[1].to_string(),
+        ];
+        for i in 1..4 {
+            let shares = Recovery::create_recovery_shares(
+                &TEST_KEYS.key[i],
+                &node_vss(i),
+                &helpers,
+                &pubkeys[0],
+            )
+            .unwrap();
+            for share in shares {
+                args.push("--recovery-share".to_string());
+                args.push(share.to_string());
+            }
+        }
+        let matches = RecoverShareCommand::args().get_matches_from(args);
+        let response = RecoverShareCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidAggregatedPublicKey"
+        );
+    }
+}
@@ -1,4 +1,6 @@
 use crate::cli::setup::index_of;
+use crate::cli::setup::list_values_of;
+use crate::cli::setup::secret_value_of;
 use crate::cli::setup::traits::Response;
 use crate::cli::setup::vss_to_bidirectional_shared_secret_map;
 use crate::crypto::multi_party_schnorr::LocalSig;
@@ -13,6 +15,7 @@ use curv::arithmetic::traits::Converter;
 use curv::cryptographic_primitives::secret_sharing::feldman_vss::ShamirSecretSharing;
 use curv::elliptic::curves::traits::{ECPoint, ECScalar};
 use curv::{BigInt, FE, GE};
+use serde_json::{json, Value};
 use std::fmt;
 use std::str::FromStr;
 use tapyrus::blockdata::block::Block;
@@ -33,7 +36,14 @@ impl SignResponse {
     }
 }
 
-impl Response for SignResponse {}
+impl Response for SignResponse {
+    fn to_json(&self) -> Value {
+        json!({
+            "sig": self.to_string(),
+            "public-key": hex::encode(&self.public_key.key.serialize()[..]),
+        })
+    }
+}
 
 impl fmt::Display for SignResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -51,9 +61,8 @@ pub struct SignCommand {}
 
 impl<'a> SignCommand {
     pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
-        let private_key: PrivateKey = matches
-            .value_of("private-key")
-            .and_then(|key| PrivateKey::from_wif(key).ok())
+        let private_key: PrivateKey = secret_value_of(matches, "private-key")?
+            .and_then(|key| PrivateKey::from_wif(&key).ok())
             .ok_or(Error::InvalidArgs("private-key".to_string()))?;
 
         let threshold: usize = matches
@@ -67,9 +76,8 @@ impl<'a> SignCommand {
             .and_then(|hex| deserialize::<Block>(&hex).ok())
             .ok_or(Error::InvalidArgs("block".to_string()))?;
 
-        let node_secret_share: FE = matches
-            .value_of("node-secret-share")
-            .and_then(|s| BigInt::from_str_radix(s, 16).ok())
+        let node_secret_share: FE = secret_value_of(matches, "node-secret-share")?
+            .and_then(|s| BigInt::from_str_radix(&s, 16).ok())
             .map(|i| ECScalar::from(&i))
             .ok_or(Error::InvalidArgs("node-secret-share".to_string()))?;
 
@@ -78,9 +86,9 @@ impl<'a> SignCommand {
             .and_then(|hex| PublicKey::from_str(hex).ok())
             .ok_or(Error::InvalidArgs("aggregated-public-key".to_string()))?;
 
-        let block_vss_vec: Vec<Vss> = matches
-            .values_of("block-vss")
+        let block_vss_vec: Vec<Vss> = list_values_of(matches, "block-vss")?
             .ok_or(Error::InvalidArgs("block-vss".to_string()))?
+            .iter()
             .map(|s| Vss::from_str(s).map_err(|_| Error::InvalidArgs("block-vss".to_string())))
             .collect::<Result<Vec<Vss>, _>>()?;
 
@@ -122,7 +130,7 @@ impl<'a> SignCommand {
                 .long("private-key")
                 .required(true)
                 .takes_value(true)
-                .help("private key of this signer with an extend WIF format"),
+                .help("private key of this signer with an extend WIF format (`@<path>` or `-` reads it from the file or stdin)"),
             Arg::with_name("threshold")
                 .long("threshold")
                 .required(true)
@@ -137,7 +145,7 @@ impl<'a> SignCommand {
                 .long("node-secret-share")
                 .required(true)
                 .takes_value(true)
-                .help("secret key share of the signers with a hex string format"),
+                .help("secret key share of the signers with a hex string format (`@<path>` or `-` reads it from the file or stdin)"),
             Arg::with_name("aggregated-public-key")
                 .long("aggregated-public-key")
                 .required(true)
@@ -148,7 +156,7 @@ impl<'a> SignCommand {
                 .required(true)
                 .multiple(true)
                 .takes_value(true)
-                .help("the block VSSs generated by tapyrus-setup createblockvss command"),
+                .help("the block VSSs generated by tapyrus-setup createblockvss command (`@<path>` reads them from the file which has one value per line or a JSON array)"),
         ])
     }
 }
@@ -1,3 +1,8 @@
+use serde_json::Value;
 use std::fmt::Display;
 
-pub trait Response: Display {}
+pub trait Response: Display {
+    /// Returns the JSON document which is printed instead of the `Display` output when
+    /// `tapyrus-setup` is run with `--format json`.
+    fn to_json(&self) -> Value;
+}
@@ -0,0 +1,187 @@
+use crate::cli::setup::traits::Response;
+use crate::errors::Error;
+use crate::federation::Federations;
+use crate::signer_node::evidence::{read_evidence, EvidenceRecord};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use tapyrus::PublicKey;
+
+pub struct VerifyEvidenceResponse {
+    /// The records and the results of the verification.
+    results: Vec<(EvidenceRecord, Result<(), String>)>,
+}
+
+impl VerifyEvidenceResponse {
+    fn new(results: Vec<(EvidenceRecord, Result<(), String>)>) -> Self {
+        VerifyEvidenceResponse { results: results }
+    }
+}
+
+impl Response for VerifyEvidenceResponse {
+    fn to_json(&self) -> Value {
+        let evidence: Vec<Value> = self
+            .results
+            .iter()
+            .map(|(record, result)| {
+                json!({
+                    "time": record.time,
+                    "kind": record.evidence.kind(),
+                    "block-height": record.evidence.block_height(),
+                    "master": record.evidence.master().pubkey.to_string(),
+                    "verified": result.is_ok(),
+                    "message": result.as_ref().err(),
+                })
+            })
+            .collect();
+        json!({ "evidence": evidence })
+    }
+}
+
+impl fmt::Display for VerifyEvidenceResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (record, result) in &self.results {
+            let result = match result {
+                Ok(()) => "verified".to_string(),
+                Err(message) => format!("not verified: {}", message),
+            };
+            writeln!(
+                f,
+                "{} at block height {} by the master {}: {}",
+                record.evidence.kind(),
+                record.evidence.block_height(),
+                record.evidence.master(),
+                result
+            )?;
+        }
+        let verified = self.results.iter().filter(|(_, r)| r.is_ok()).count();
+        write!(f, "result: {} of {} verified", verified, self.results.len())
+    }
+}
+
+/// Verifies the evidence which tapyrus-signerd recorded in the evidence file, so that it can be
+/// checked by another member of the federation.
+pub struct VerifyEvidenceCommand {}
+
+impl<'a> VerifyEvidenceCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let public_key: PublicKey = matches
+            .value_of("public-key")
+            .and_then(|hex| PublicKey::from_str(hex).ok())
+            .ok_or(Error::InvalidArgs("public-key".to_string()))?;
+
+        let federations_file = matches
+            .value_of("federations-file")
+            .ok_or(Error::InvalidArgs("federations-file".to_string()))?;
+        let toml = std::fs::read_to_string(federations_file)?;
+        let federations = Federations::from_pubkey_and_toml(&public_key, &toml)?;
+
+        let evidence_file = matches
+            .value_of("evidence-file")
+            .ok_or(Error::InvalidArgs("evidence-file".to_string()))?;
+        let results = read_evidence(evidence_file)?
+            .into_iter()
+            .map(|record| {
+                let result = record.evidence.verify(&federations);
+                (record, result)
+            })
+            .collect();
+        Ok(Box::new(VerifyEvidenceResponse::new(results)))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("verifyevidence").args(&[
+            Arg::with_name("public-key")
+                .long("public-key")
+                .required(true)
+                .takes_value(true)
+                .help("compressed public key of the signer who verifies the evidence with a hex format string"),
+            Arg::with_name("federations-file")
+                .long("federations-file")
+                .required(true)
+                .takes_value(true)
+                .help("path to the federations file of the signer"),
+            Arg::with_name("evidence-file")
+                .long("evidence-file")
+                .required(true)
+                .takes_value(true)
+                .help("path to the evidence file which tapyrus-signerd recorded"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::SignerID;
+    use crate::signer_node::equivocation::CandidateDigest;
+    use crate::signer_node::evidence::{Evidence, EvidenceStore};
+    use crate::signer_node::master_signature::SignedBlock;
+    use crate::tests::helper::blocks::{get_block, master_sign};
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::federation;
+
+    fn digest(sender: usize, block: u8) -> CandidateDigest {
+        CandidateDigest::new(
+            1,
+            SignerID::new(TEST_KEYS.pubkeys()[0]),
+            get_block(block).header,
+            SignerID::new(TEST_KEYS.pubkeys()[sender]),
+            master_sign(SignedBlock::Candidate, 1, &get_block(block), 0),
+        )
+    }
+
+    #[test]
+    fn test_execute() {
+        let federations_path = std::env::temp_dir().join("verifyevidence_test_federations.toml");
+        std::fs::write(&federations_path, federation(1).to_toml().unwrap()).unwrap();
+        let evidence_path = std::env::temp_dir().join("verifyevidence_test_evidence.jsonl");
+        let _ = std::fs::remove_file(&evidence_path);
+
+        let store = EvidenceStore::create(evidence_path.to_str().unwrap()).unwrap();
+        let master = SignerID::new(TEST_KEYS.pubkeys()[0]);
+        store.record(Evidence::Equivocation {
+            block_height: 1,
+            master,
+            digests: (digest(1, 0), digest(2, 1)),
+        });
+        store.record(Evidence::Equivocation {
+            block_height: 1,
+            master,
+            digests: (digest(1, 0), digest(2, 0)),
+        });
+        // The completed block is not signed by the master.
+        store.record(Evidence::InvalidCompletedBlock {
+            block_height: 1,
+            master,
+            candidate: get_block(0),
+            candidate_signature: master_sign(SignedBlock::Candidate, 1, &get_block(0), 0),
+            completed: get_block(1),
+            completed_signature: master_sign(SignedBlock::Completed, 1, &get_block(1), 1),
+            reason: "the block is different from the candidate block".to_string(),
+        });
+
+        let public_key = TEST_KEYS.pubkeys()[1].to_string();
+        let matches = VerifyEvidenceCommand::args().get_matches_from(vec![
+            "verifyevidence",
+            "--public-key",
+            &public_key,
+            "--federations-file",
+            federations_path.to_str().unwrap(),
+            "--evidence-file",
+            evidence_path.to_str().unwrap(),
+        ]);
+        let response = VerifyEvidenceCommand::execute(&matches);
+        std::fs::remove_file(&federations_path).unwrap();
+        std::fs::remove_file(&evidence_path).unwrap();
+
+        let json = response.unwrap().to_json();
+        assert_eq!(json["evidence"][0]["kind"], "equivocation");
+        assert_eq!(json["evidence"][0]["verified"], true);
+        assert_eq!(json["evidence"][1]["verified"], false);
+        assert_eq!(json["evidence"][1]["message"], "the digests don't conflict");
+        assert_eq!(json["evidence"][2]["kind"], "invalid-completed-block");
+        assert_eq!(json["evidence"][2]["verified"], false);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::cli::setup::traits::Response;
+use crate::errors::Error;
+use crate::federation::{Federation, Federations, SigningScheme};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+use tapyrus::PublicKey;
+
+pub struct VerifyFederationResponse {
+    federations: Federations,
+    problems: Vec<Error>,
+}
+
+impl VerifyFederationResponse {
+    fn new(federations: Federations, problems: Vec<Error>) -> Self {
+        VerifyFederationResponse {
+            federations: federations,
+            problems: problems,
+        }
+    }
+
+    fn signers(federation: &Federation) -> Vec<String> {
+        if federation.is_member() {
+            federation
+                .signers()
+                .iter()
+                .map(|signer| signer.pubkey.to_string())
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    fn signing_scheme(federation: &Federation) -> &'static str {
+        match federation.signing_scheme() {
+            SigningScheme::Vss => "vss",
+            SigningScheme::Frost => "frost",
+        }
+    }
+
+    /// Returns the block height and the message of the problem.
+    fn describe(problem: &Error) -> (Option<u64>, String) {
+        match problem {
+            Error::InvalidFederation(height, message) => (*height, message.to_string()),
+            e => (None, e.to_string()),
+        }
+    }
+}
+
+impl Response for VerifyFederationResponse {
+    fn to_json(&self) -> Value {
+        let federations: Vec<Value> = self
+            .federations
+            .iter()
+            .map(|federation| {
+                json!({
+                    "block-height": federation.block_height(),
+                    "aggregated-public-key": federation.aggregated_public_key().to_string(),
+                    "signing-scheme": Self::signing_scheme(federation),
+                    "member": federation.is_member(),
+                    "threshold": federation.threshold(),
+                    "signers": Self::signers(federation),
+                })
+            })
+            .collect();
+        let problems: Vec<Value> = self
+            .problems
+            .iter()
+            .map(|problem| {
+                let (height, message) = Self::describe(problem);
+                json!({ "block-height": height, "message": message })
+            })
+            .collect();
+        json!({
+            "valid": self.problems.is_empty(),
+            "federations": federations,
+            "problems": problems,
+        })
+    }
+}
+
+impl fmt::Display for VerifyFederationResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for federation in self.federations.iter() {
+            writeln!(
+                f,
+                "federation at block height {}:",
+                federation.block_height()
+            )?;
+            writeln!(
+                f,
+                "  aggregated public key: {}",
+                federation.aggregated_public_key()
+            )?;
+            writeln!(f, "  signing scheme: {}", Self::signing_scheme(federation))?;
+            if !federation.is_member() {
+                writeln!(f, "  this signer is not a member of the federation")?;
+                continue;
+            }
+            if let Some(threshold) = federation.threshold() {
+                writeln!(f, "  threshold: {}", threshold)?;
+            }
+            let signers = Self::signers(federation);
+            writeln!(f, "  signers: {}", signers.len())?;
+            for signer in signers {
+                writeln!(f, "    {}", signer)?;
+            }
+        }
+
+        if self.problems.is_empty() {
+            return write!(f, "result: valid");
+        }
+        writeln!(f, "problems:")?;
+        for problem in &self.problems {
+            match Self::describe(problem) {
+                (Some(height), message) => {
+                    writeln!(f, "  at block height {}: {}", height, message)?
+                }
+                (None, message) => writeln!(f, "  {}", message)?,
+            }
+        }
+        write!(f, "result: invalid ({} problems)", self.problems.len())
+    }
+}
+
+pub struct VerifyFederationCommand {}
+
+impl<'a> VerifyFederationCommand {
+    pub fn execute(matches: &ArgMatches) -> Result<Box<dyn Response>, Error> {
+        let public_key: PublicKey = matches
+            .value_of("public-key")
+            .and_then(|hex| PublicKey::from_str(hex).ok())
+            .ok_or(Error::InvalidArgs("public-key".to_string()))?;
+
+        let path = matches
+            .value_of("federations-file")
+            .ok_or(Error::InvalidArgs("federations-file".to_string()))?;
+
+        let toml = std::fs::read_to_string(path)?;
+        let federations = Federations::from_pubkey_and_toml_without_validation(&public_key, &toml)?;
+        let problems = federations.problems();
+        Ok(Box::new(VerifyFederationResponse::new(
+            federations,
+            problems,
+        )))
+    }
+
+    pub fn args<'b>() -> App<'a, 'b> {
+        SubCommand::with_name("verifyfederation").args(&[
+            Arg::with_name("public-key")
+                .long("public-key")
+                .required(true)
+                .takes_value(true)
+                .help("compressed public key of the signer who runs the node with a hex format string"),
+            Arg::with_name("federations-file")
+                .long("federations-file")
+                .required(true)
+                .takes_value(true)
+                .help("path to the federations file to be verified"),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::NODE_VSS;
+
+    const PUBLIC_KEY: &str = "02472012cf49fca573ca1f63deafe59df842f0bbe77e9ac7e67b211bb074b72506";
+
+    #[test]
+    fn test_execute() {
+        let path = std::env::temp_dir().join("verifyfederation_test_execute.toml");
+        let nodevss = NODE_VSS[0]
+            .iter()
+            .map(|vss| format!("\"{}\"", vss))
+            .collect::<Vec<String>>()
+            .join(",");
+        let toml = format!(
+            "[[federation]]\nblock-height = 0\nthreshold = 3\naggregated-public-key = \"{}\"\nnode-vss = [{}]\n",
+            TEST_KEYS.aggregated(),
+            nodevss
+        );
+        std::fs::write(&path, toml).unwrap();
+
+        let public_key = TEST_KEYS.pubkeys()[0].to_string();
+        let matches = VerifyFederationCommand::args().get_matches_from(vec![
+            "verifyfederation",
+            "--public-key",
+            &public_key,
+            "--federations-file",
+            path.to_str().unwrap(),
+        ]);
+        let response = VerifyFederationCommand::execute(&matches);
+        std::fs::remove_file(&path).unwrap();
+
+        let response = response.unwrap();
+        let json = response.to_json();
+        assert_eq!(json["valid"], true);
+        assert_eq!(json["federations"][0]["block-height"], 0);
+        assert_eq!(json["federations"][0]["threshold"], 3);
+        assert_eq!(
+            json["federations"][0]["signers"].as_array().unwrap().len(),
+            5
+        );
+        assert!(format!("{}", response).ends_with("result: valid"));
+    }
+
+    #[test]
+    fn test_execute_wrong_aggregated_public_key() {
+        // The aggregated public key of the federation at 100 height in this file is not derived
+        // from its nodevss.
+        let matches = VerifyFederationCommand::args().get_matches_from(vec![
+            "verifyfederation",
+            "--public-key",
+            PUBLIC_KEY,
+            "--federations-file",
+            "tests/resources/federations.toml",
+        ]);
+        let response = VerifyFederationCommand::execute(&matches).unwrap();
+        let json = response.to_json();
+        assert_eq!(json["valid"], false);
+        assert_eq!(json["federations"][0]["threshold"], 3);
+        assert_eq!(json["federations"][1]["threshold"], 2);
+        assert_eq!(json["problems"].as_array().unwrap().len(), 1);
+        assert_eq!(json["problems"][0]["block-height"], 100);
+        assert_eq!(
+            json["problems"][0]["message"],
+            "The aggregated public key is not equal to the sum of the commitments in the nodevss."
+        );
+    }
+
+    #[test]
+    fn test_execute_invalid_federation() {
+        let matches = VerifyFederationCommand::args().get_matches_from(vec![
+            "verifyfederation",
+            "--public-key",
+            PUBLIC_KEY,
+            "--federations-file",
+            "tests/resources/federations_has_invalid_federation.toml",
+        ]);
+        let response = VerifyFederationCommand::execute(&matches).unwrap();
+        let json = response.to_json();
+        assert_eq!(json["valid"], false);
+        assert_eq!(json["problems"][0]["block-height"], 0);
+        assert!(format!("{}", response).contains(
+            "at block height 0: The nodevss has wrong vss which has wrong number of commitments."
+        ));
+    }
+
+    #[test]
+    fn test_execute_invalid_toml_format() {
+        let matches = VerifyFederationCommand::args().get_matches_from(vec![
+            "verifyfederation",
+            "--public-key",
+            PUBLIC_KEY,
+            "--federations-file",
+            "tests/resources/federations_invalid_toml_format.toml",
+        ]);
+        match VerifyFederationCommand::execute(&matches) {
+            Err(Error::InvalidTomlFormat(_)) => {}
+            _ => panic!("it should fail to parse the file"),
+        }
+    }
+
+    #[test]
+    fn test_execute_invalid_public_key() {
+        let matches = VerifyFederationCommand::args().get_matches_from(vec![
+            "verifyfederation",
+            "--public-key",
+            "x",
+            "--federations-file",
+            "tests/resources/federations.toml",
+        ]);
+        let response = VerifyFederationCommand::execute(&matches);
+        assert_eq!(
+            format!("{}", response.err().unwrap()),
+            "InvalidArgs(\"public-key\")"
+        );
+    }
+}
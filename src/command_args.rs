@@ -4,10 +4,13 @@
 
 use std::str::FromStr;
 
+use crate::rpc::{DEFAULT_RETRIES, DEFAULT_TIMEOUT_SECS};
+use crate::signer_node::validation::{DEFAULT_MAX_BLOCK_SIZE, DEFAULT_MAX_TIME_SKEW_SECS};
 use crate::signer_node::{ROUND_INTERVAL_DEFAULT_SECS, ROUND_LIMIT_DEFAULT_SECS};
 use clap::{App, Arg};
 use log;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use tapyrus::{Address, PublicKey};
 
@@ -16,13 +19,20 @@ pub const OPTION_NAME_CONFIG: &str = "config";
 /// # Signer Config
 pub const OPTION_NAME_TO_ADDRESS: &str = "coinbase_pay_to_address";
 pub const OPTION_NAME_PUBLIC_KEY: &str = "publickey";
+pub const OPTION_NAME_PRIVATE_KEY_FILE: &str = "private_key_file";
 pub const OPTION_NAME_FEDERATIONS_FILE: &str = "federations-file";
+pub const OPTION_NAME_FEDERATION_CHECK: &str = "federation_check";
+pub const OPTION_NAME_FEDERATION_CHECK_CACHE: &str = "federation_check_cache";
 
 /// # RPC Config
 pub const OPTION_NAME_RPC_ENDPOINT_HOST: &str = "rpc_endpoint_host";
 pub const OPTION_NAME_RPC_ENDPOINT_PORT: &str = "rpc_endpoint_port";
 pub const OPTION_NAME_RPC_ENDPOINT_USER: &str = "rpc_endpoint_user";
 pub const OPTION_NAME_RPC_ENDPOINT_PASS: &str = "rpc_endpoint_pass";
+pub const OPTION_NAME_RPC_COOKIE_FILE: &str = "rpc_cookie_file";
+pub const OPTION_NAME_RPC_TIMEOUT: &str = "rpc_timeout";
+pub const OPTION_NAME_RPC_RETRIES: &str = "rpc_retries";
+pub const OPTION_NAME_ZMQ_PUB_HASHBLOCK: &str = "zmq_pub_hashblock";
 
 /// # Redis Config
 pub const OPTION_NAME_REDIS_HOST: &str = "redis_host";
@@ -41,6 +51,13 @@ pub const OPTION_NAME_PID: &str = "pid";
 pub const OPTION_NAME_LOG_FILE: &str = "log_file";
 /// Others
 pub const OPTION_NAME_SKIP_WAITING_IBD: &str = "skip_waiting_ibd";
+pub const OPTION_NAME_PRECOMPUTE_BLOCK_VSS: &str = "precompute_block_vss";
+pub const OPTION_NAME_RECORD_FILE: &str = "record_file";
+pub const OPTION_NAME_EVIDENCE_FILE: &str = "evidence_file";
+pub const OPTION_NAME_ADMIN_RPC_BIND: &str = "admin_rpc_bind";
+pub const OPTION_NAME_ROUND_SCHEDULE_EPOCH: &str = "round_schedule_epoch";
+pub const OPTION_NAME_BLOCK_PRODUCTION: &str = "block_production";
+pub const OPTION_NAME_MAX_BLOCK_INTERVAL: &str = "max_block_interval";
 
 /// # Default Values
 pub const DEFAULT_RPC_HOST: &str = "127.0.0.1";
@@ -50,6 +67,12 @@ pub const DEFAULT_RPC_PASSWORD: &str = "";
 pub const DEFAULT_REDIS_HOST: &str = "127.0.0.1";
 pub const DEFAULT_REDIS_PORT: &str = "6379";
 pub const DEFAULT_LOG_LEVEL: &str = "info";
+pub const DEFAULT_FEDERATION_CHECK: &str = "warn";
+pub const DEFAULT_BLOCK_PRODUCTION: &str = "always";
+pub const DEFAULT_MAX_BLOCK_INTERVAL: u64 = 3600;
+pub const DEFAULT_RATE_LIMIT: u32 = 100;
+pub const DEFAULT_METRICS_INTERVAL: u64 = 300;
+pub const DEFAULT_CANDIDATE_CHECKS: [&str; 4] = ["height", "parent", "timestamp", "size"];
 
 lazy_static! {
     pub static ref DEFAULT_PID: PathBuf = {
@@ -73,8 +96,14 @@ struct SignerToml {
     to_address: Option<String>,
     #[serde(rename = "public-key")]
     publickey: Option<String>,
+    #[serde(rename = "private-key-file")]
+    private_key_file: Option<String>,
     #[serde(rename = "federations-file")]
     federations_file: Option<String>,
+    #[serde(rename = "federation-check")]
+    federation_check: Option<String>,
+    #[serde(rename = "federation-check-cache")]
+    federation_check_cache: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -87,6 +116,18 @@ pub struct RpcToml {
     rpc_endpoint_user: Option<String>,
     #[serde(rename = "rpc-endpoint-pass")]
     rpc_endpoint_pass: Option<String>,
+    #[serde(rename = "rpc-endpoints")]
+    rpc_endpoints: Option<Vec<String>>,
+    #[serde(rename = "rpc-cookie-file")]
+    rpc_cookie_file: Option<String>,
+    #[serde(rename = "rpc-timeout")]
+    rpc_timeout: Option<u64>,
+    #[serde(rename = "rpc-retries")]
+    rpc_retries: Option<u32>,
+    #[serde(rename = "rpc-method-timeouts")]
+    rpc_method_timeouts: Option<BTreeMap<String, u64>>,
+    #[serde(rename = "zmq-pub-hashblock")]
+    zmq_pub_hashblock: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -109,10 +150,46 @@ pub struct GeneralToml {
     log_quiet: Option<bool>,
     #[serde(rename = "skip-waiting-ibd")]
     skip_waiting_ibd: Option<bool>,
+    #[serde(rename = "precompute-block-vss")]
+    precompute_block_vss: Option<bool>,
     daemon: Option<bool>,
     pid: Option<String>,
     #[serde(rename = "log-file")]
     log_file: Option<String>,
+    #[serde(rename = "record-file")]
+    record_file: Option<String>,
+    #[serde(rename = "evidence-file")]
+    evidence_file: Option<String>,
+    #[serde(rename = "admin-rpc-bind")]
+    admin_rpc_bind: Option<String>,
+    #[serde(rename = "round-schedule-epoch")]
+    round_schedule_epoch: Option<u64>,
+    #[serde(rename = "block-production")]
+    block_production: Option<String>,
+    #[serde(rename = "max-block-interval")]
+    max_block_interval: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MiddlewareToml {
+    layers: Option<Vec<String>>,
+    #[serde(rename = "rate-limit")]
+    rate_limit: Option<u32>,
+    #[serde(rename = "metrics-interval")]
+    metrics_interval: Option<u64>,
+    #[serde(rename = "drop-rate")]
+    drop_rate: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidationToml {
+    checks: Option<Vec<String>>,
+    #[serde(rename = "max-time-skew")]
+    max_time_skew: Option<u64>,
+    #[serde(rename = "max-block-size")]
+    max_block_size: Option<usize>,
+    #[serde(rename = "coinbase-addresses")]
+    coinbase_addresses: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -121,6 +198,8 @@ struct ConfigToml {
     rpc: Option<RpcToml>,
     redis: Option<RedisToml>,
     general: Option<GeneralToml>,
+    middleware: Option<MiddlewareToml>,
+    validation: Option<ValidationToml>,
 }
 
 pub struct CommandArgs<'a> {
@@ -131,7 +210,10 @@ pub struct CommandArgs<'a> {
 pub struct SignerCommandArgs<'a> {
     to_address: Option<&'a str>,
     public_key: Option<&'a str>,
+    private_key_file: Option<&'a str>,
     federations_file: Option<&'a str>,
+    federation_check: Option<&'a str>,
+    federation_check_cache: Option<&'a str>,
 }
 
 pub struct SignerConfig<'a> {
@@ -164,6 +246,19 @@ impl<'a> SignerConfig<'a> {
             .expect("public-key isn't specified or is invalid.")
     }
 
+    /// The file of the private key of the public key in WIF, which the node signs the blocks
+    /// with when it is the master of a round. Without it, the node doesn't sign blocks.
+    pub fn private_key_file(&self) -> Option<&Path> {
+        let value_within_config: Option<&str> = self
+            .toml_config
+            .and_then(|config| config.private_key_file.as_ref())
+            .map(|p| p as &str);
+        self.command_args
+            .private_key_file
+            .or(value_within_config)
+            .map(Path::new)
+    }
+
     pub fn federations_file(&self) -> &Path {
         let value_within_config: Option<&str> = self
             .toml_config
@@ -175,6 +270,32 @@ impl<'a> SignerConfig<'a> {
             .and_then(|s| Some(Path::new(s)))
             .expect("Must be specified federations-file.")
     }
+
+    /// How to treat the divergences between the federations and the chain at startup. One of
+    /// "strict", "warn" and "skip".
+    pub fn federation_check(&self) -> &str {
+        let value_within_config: Option<&str> = self
+            .toml_config
+            .and_then(|config| config.federation_check.as_ref())
+            .map(|p| p as &str);
+        self.command_args
+            .federation_check
+            .or(value_within_config)
+            .unwrap_or(DEFAULT_FEDERATION_CHECK)
+    }
+
+    /// The file to save the block headers checked at startup, so that they are not walked again
+    /// at the next startup.
+    pub fn federation_check_cache(&self) -> Option<&Path> {
+        let value_within_config: Option<&str> = self
+            .toml_config
+            .and_then(|config| config.federation_check_cache.as_ref())
+            .map(|p| p as &str);
+        self.command_args
+            .federation_check_cache
+            .or(value_within_config)
+            .map(Path::new)
+    }
 }
 
 pub struct RpcCommandArgs<'a> {
@@ -182,6 +303,10 @@ pub struct RpcCommandArgs<'a> {
     pub port: Option<&'a str>,
     pub username: Option<&'a str>,
     pub password: Option<&'a str>,
+    pub cookie_file: Option<&'a str>,
+    pub timeout: Option<&'a str>,
+    pub retries: Option<&'a str>,
+    pub zmq_pub_hashblock: Option<&'a str>,
 }
 
 pub struct RpcConfig<'a> {
@@ -208,6 +333,22 @@ impl<'a> RpcConfig<'a> {
             .or(toml_value)
             .unwrap_or(DEFAULT_RPC_PORT.parse().unwrap_or_default())
     }
+    /// Returns the URLs of the endpoints. The list of `rpc-endpoints` in the config file is used
+    /// unless the host or the port is given as command args.
+    pub fn endpoints(&'a self) -> Vec<String> {
+        let toml_value = self
+            .toml_config
+            .and_then(|config| config.rpc_endpoints.as_ref())
+            .filter(|endpoints| !endpoints.is_empty());
+        match toml_value {
+            Some(endpoints)
+                if self.command_args.host.is_none() && self.command_args.port.is_none() =>
+            {
+                endpoints.iter().map(|e| format!("http://{}", e)).collect()
+            }
+            _ => vec![format!("http://{}:{}", self.host(), self.port())],
+        }
+    }
     pub fn user_name(&'a self) -> Option<&'a str> {
         let toml_value = self
             .toml_config
@@ -222,6 +363,42 @@ impl<'a> RpcConfig<'a> {
             .map(|s| s as &str);
         self.command_args.password.or(toml_value)
     }
+    pub fn cookie_file(&'a self) -> Option<&'a Path> {
+        let toml_value = self
+            .toml_config
+            .and_then(|config| config.rpc_cookie_file.as_ref())
+            .map(|s| s as &str);
+        self.command_args.cookie_file.or(toml_value).map(Path::new)
+    }
+    pub fn timeout(&'a self) -> u64 {
+        let toml_value = self.toml_config.and_then(|config| config.rpc_timeout);
+        self.command_args
+            .timeout
+            .and_then(|s| s.parse::<u64>().ok())
+            .or(toml_value)
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+    }
+    pub fn retries(&'a self) -> u32 {
+        let toml_value = self.toml_config.and_then(|config| config.rpc_retries);
+        self.command_args
+            .retries
+            .and_then(|s| s.parse::<u32>().ok())
+            .or(toml_value)
+            .unwrap_or(DEFAULT_RETRIES)
+    }
+    pub fn zmq_pub_hashblock(&'a self) -> Option<&'a str> {
+        let toml_value = self
+            .toml_config
+            .and_then(|config| config.zmq_pub_hashblock.as_ref())
+            .map(|s| s as &str);
+        self.command_args.zmq_pub_hashblock.or(toml_value)
+    }
+    /// Timeouts in seconds for each method, which can be set only in the config file.
+    pub fn method_timeouts(&'a self) -> BTreeMap<String, u64> {
+        self.toml_config
+            .and_then(|config| config.rpc_method_timeouts.clone())
+            .unwrap_or_default()
+    }
 }
 
 pub struct RedisCommandArgs<'a> {
@@ -261,9 +438,16 @@ pub struct GeneralCommandArgs<'a> {
     log_quiet: bool,
     log_level: Option<&'a str>,
     skip_waiting_ibd: bool,
+    precompute_block_vss: bool,
     daemon: bool,
     pid: Option<&'a str>,
     log_file: Option<&'a str>,
+    record_file: Option<&'a str>,
+    evidence_file: Option<&'a str>,
+    admin_rpc_bind: Option<&'a str>,
+    round_schedule_epoch: Option<&'a str>,
+    block_production: Option<&'a str>,
+    max_block_interval: Option<&'a str>,
 }
 
 pub struct GeneralConfig<'a> {
@@ -312,6 +496,13 @@ impl<'a> GeneralConfig<'a> {
             .unwrap_or_default();
         self.command_args.skip_waiting_ibd || toml_value
     }
+    pub fn precompute_block_vss(&'a self) -> bool {
+        let toml_value = self
+            .toml_config
+            .and_then(|config| config.precompute_block_vss)
+            .unwrap_or_default();
+        self.command_args.precompute_block_vss || toml_value
+    }
     pub fn daemon(&'a self) -> bool {
         let toml_value = self
             .toml_config
@@ -341,6 +532,126 @@ impl<'a> GeneralConfig<'a> {
                 .expect("Can't cast default log file PathBuf to &str"),
         )
     }
+    pub fn record_file(&'a self) -> Option<&'a str> {
+        let toml_value = self
+            .toml_config
+            .and_then(|config| config.record_file.as_ref())
+            .map(|s| s as &str);
+        self.command_args.record_file.or(toml_value)
+    }
+    pub fn evidence_file(&'a self) -> Option<&'a str> {
+        let toml_value = self
+            .toml_config
+            .and_then(|config| config.evidence_file.as_ref())
+            .map(|s| s as &str);
+        self.command_args.evidence_file.or(toml_value)
+    }
+    pub fn admin_rpc_bind(&'a self) -> Option<&'a str> {
+        let toml_value = self
+            .toml_config
+            .and_then(|config| config.admin_rpc_bind.as_ref())
+            .map(|s| s as &str);
+        self.command_args.admin_rpc_bind.or(toml_value)
+    }
+    pub fn round_schedule_epoch(&'a self) -> Option<u64> {
+        let toml_value = self
+            .toml_config
+            .and_then(|config| config.round_schedule_epoch);
+        self.command_args
+            .round_schedule_epoch
+            .and_then(|e| e.parse().ok())
+            .or(toml_value)
+    }
+    /// When the master produces a block. One of "always", "mempool" and "interval".
+    pub fn block_production(&'a self) -> &'a str {
+        let toml_value = self
+            .toml_config
+            .and_then(|config| config.block_production.as_ref())
+            .map(|s| s as &str);
+        self.command_args
+            .block_production
+            .or(toml_value)
+            .unwrap_or(DEFAULT_BLOCK_PRODUCTION)
+    }
+    pub fn max_block_interval(&'a self) -> u64 {
+        let toml_value = self
+            .toml_config
+            .and_then(|config| config.max_block_interval);
+        self.command_args
+            .max_block_interval
+            .and_then(|i| i.parse().ok())
+            .or(toml_value)
+            .unwrap_or(DEFAULT_MAX_BLOCK_INTERVAL)
+    }
+}
+
+/// Middleware options can be set only in the config file.
+pub struct MiddlewareConfig<'a> {
+    toml_config: Option<&'a MiddlewareToml>,
+}
+
+impl<'a> MiddlewareConfig<'a> {
+    pub fn layers(&'a self) -> Vec<String> {
+        self.toml_config
+            .and_then(|config| config.layers.clone())
+            .unwrap_or_default()
+    }
+    pub fn rate_limit(&'a self) -> u32 {
+        self.toml_config
+            .and_then(|config| config.rate_limit)
+            .unwrap_or(DEFAULT_RATE_LIMIT)
+    }
+    pub fn metrics_interval(&'a self) -> u64 {
+        self.toml_config
+            .and_then(|config| config.metrics_interval)
+            .unwrap_or(DEFAULT_METRICS_INTERVAL)
+    }
+    pub fn drop_rate(&'a self) -> f64 {
+        self.toml_config
+            .and_then(|config| config.drop_rate)
+            .unwrap_or_default()
+    }
+}
+
+/// Candidate block validation options can be set only in the config file.
+pub struct ValidationConfig<'a> {
+    toml_config: Option<&'a ValidationToml>,
+}
+
+impl<'a> ValidationConfig<'a> {
+    pub fn checks(&'a self) -> Vec<String> {
+        self.toml_config
+            .and_then(|config| config.checks.clone())
+            .unwrap_or(
+                DEFAULT_CANDIDATE_CHECKS
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            )
+    }
+    pub fn max_time_skew(&'a self) -> u64 {
+        self.toml_config
+            .and_then(|config| config.max_time_skew)
+            .unwrap_or(DEFAULT_MAX_TIME_SKEW_SECS)
+    }
+    pub fn max_block_size(&'a self) -> usize {
+        self.toml_config
+            .and_then(|config| config.max_block_size)
+            .unwrap_or(DEFAULT_MAX_BLOCK_SIZE)
+    }
+    pub fn coinbase_addresses(&'a self) -> Vec<Address> {
+        self.toml_config
+            .and_then(|config| config.coinbase_addresses.as_ref())
+            .map(|addresses| {
+                addresses
+                    .iter()
+                    .map(|s| {
+                        Address::from_str(s).expect("coinbase-addresses has an invalid address.")
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
 
 impl<'a> CommandArgs<'a> {
@@ -379,7 +690,10 @@ impl<'a> CommandArgs<'a> {
             command_args: SignerCommandArgs {
                 to_address: self.matches.value_of(OPTION_NAME_TO_ADDRESS),
                 public_key: self.matches.value_of(OPTION_NAME_PUBLIC_KEY),
+                private_key_file: self.matches.value_of(OPTION_NAME_PRIVATE_KEY_FILE),
                 federations_file: self.matches.value_of(OPTION_NAME_FEDERATIONS_FILE),
+                federation_check: self.matches.value_of(OPTION_NAME_FEDERATION_CHECK),
+                federation_check_cache: self.matches.value_of(OPTION_NAME_FEDERATION_CHECK_CACHE),
             },
             toml_config: self.config.as_ref().and_then(|c| c.signer.as_ref()),
         }
@@ -392,6 +706,10 @@ impl<'a> CommandArgs<'a> {
                 port: self.matches.value_of(OPTION_NAME_RPC_ENDPOINT_PORT),
                 username: self.matches.value_of(OPTION_NAME_RPC_ENDPOINT_USER),
                 password: self.matches.value_of(OPTION_NAME_RPC_ENDPOINT_PASS),
+                cookie_file: self.matches.value_of(OPTION_NAME_RPC_COOKIE_FILE),
+                timeout: self.matches.value_of(OPTION_NAME_RPC_TIMEOUT),
+                retries: self.matches.value_of(OPTION_NAME_RPC_RETRIES),
+                zmq_pub_hashblock: self.matches.value_of(OPTION_NAME_ZMQ_PUB_HASHBLOCK),
             },
             toml_config: self.config.as_ref().and_then(|c| c.rpc.as_ref()),
         }
@@ -414,13 +732,32 @@ impl<'a> CommandArgs<'a> {
                 log_level: self.matches.value_of(OPTION_NAME_LOG_LEVEL),
                 log_quiet: self.matches.is_present(OPTION_NAME_LOG_QUIET),
                 skip_waiting_ibd: self.matches.is_present(OPTION_NAME_SKIP_WAITING_IBD),
+                precompute_block_vss: self.matches.is_present(OPTION_NAME_PRECOMPUTE_BLOCK_VSS),
                 daemon: self.matches.is_present(OPTION_NAME_DAEMON),
                 pid: self.matches.value_of(OPTION_NAME_PID),
                 log_file: self.matches.value_of(OPTION_NAME_LOG_FILE),
+                record_file: self.matches.value_of(OPTION_NAME_RECORD_FILE),
+                evidence_file: self.matches.value_of(OPTION_NAME_EVIDENCE_FILE),
+                admin_rpc_bind: self.matches.value_of(OPTION_NAME_ADMIN_RPC_BIND),
+                round_schedule_epoch: self.matches.value_of(OPTION_NAME_ROUND_SCHEDULE_EPOCH),
+                block_production: self.matches.value_of(OPTION_NAME_BLOCK_PRODUCTION),
+                max_block_interval: self.matches.value_of(OPTION_NAME_MAX_BLOCK_INTERVAL),
             },
             toml_config: self.config.as_ref().and_then(|c| c.general.as_ref()),
         }
     }
+
+    pub fn middleware_config(&self) -> MiddlewareConfig {
+        MiddlewareConfig {
+            toml_config: self.config.as_ref().and_then(|c| c.middleware.as_ref()),
+        }
+    }
+
+    pub fn validation_config(&self) -> ValidationConfig {
+        ValidationConfig {
+            toml_config: self.config.as_ref().and_then(|c| c.validation.as_ref()),
+        }
+    }
 }
 
 fn read_config(file_path: &str) -> Result<ConfigToml, crate::errors::Error> {
@@ -447,10 +784,23 @@ pub fn get_options<'a, 'b>() -> clap::App<'a, 'b> {
             .long("public-key")
             .value_name("PUBLIC_KEY")
             .help("Public key of the signer who host this tapyrus-sigenrd. example: 03831a69b8009833ab5b0326012eaf489bfea35a7321b1ca15b11d88131423fafc"))
+        .arg(Arg::with_name(OPTION_NAME_PRIVATE_KEY_FILE)
+            .long("private-key-file")
+            .value_name("FILE")
+            .help("The path to the file of the private key of the public key in WIF. The node signs the candidate blocks and the completed blocks with it when it is the master of a round. Without it, the node doesn't sign blocks."))
         .arg(Arg::with_name(OPTION_NAME_FEDERATIONS_FILE)
             .long("federations-file")
             .value_name("FILE")
             .help("The path to TOML file of the federations of the chain."))
+        .arg(Arg::with_name(OPTION_NAME_FEDERATION_CHECK)
+            .long("federation-check")
+            .takes_value(true)
+            .possible_values(&["strict", "warn", "skip"])
+            .help("Check the aggregated public keys in the federations file against the xfields of the blocks on the chain at startup. With `strict`, the node refuses to start on divergences. With `warn`, the default, it only logs them. With `skip`, it doesn't walk the block headers."))
+        .arg(Arg::with_name(OPTION_NAME_FEDERATION_CHECK_CACHE)
+            .long("federation-check-cache")
+            .value_name("FILE")
+            .help("The path to the file where the block headers checked at startup are saved, so that they are not walked again at the next startup."))
         .arg(Arg::with_name(OPTION_NAME_RPC_ENDPOINT_HOST)
             .long("rpc-host")
             .value_name("HOST_NAME or IP")
@@ -467,6 +817,22 @@ pub fn get_options<'a, 'b>() -> clap::App<'a, 'b> {
             .long("rpc-pass")
             .value_name("PASS")
             .help("TapyrusCore RPC user password."))
+        .arg(Arg::with_name(OPTION_NAME_RPC_COOKIE_FILE)
+            .long("rpc-cookie-file")
+            .value_name("PATH")
+            .help("Path to the .cookie file of TapyrusCore, which is used instead of rpc-user and rpc-pass."))
+        .arg(Arg::with_name(OPTION_NAME_RPC_TIMEOUT)
+            .long("rpc-timeout")
+            .value_name("SECs")
+            .help("Timeout of TapyrusCore RPC calls(sec)."))
+        .arg(Arg::with_name(OPTION_NAME_RPC_RETRIES)
+            .long("rpc-retries")
+            .value_name("COUNT")
+            .help("The number of retries of TapyrusCore RPC calls which can be sent again safely."))
+        .arg(Arg::with_name(OPTION_NAME_ZMQ_PUB_HASHBLOCK)
+            .long("zmq-pub-hashblock")
+            .value_name("ADDRESS")
+            .help("ZMQ address of TapyrusCore which publishes hashblock notifications. example: tcp://127.0.0.1:28332"))
         .arg(Arg::with_name(OPTION_NAME_REDIS_HOST)
             .long("redis-host")
             .value_name("HOST_NAME or IP")
@@ -497,9 +863,27 @@ pub fn get_options<'a, 'b>() -> clap::App<'a, 'b> {
             .takes_value(true)
             .value_name("SECs")
             .help("Round limit times(sec).If the communications for rounds spends time more than round limit, the round would be regarded as a failure round and the next round would be started."))
+        .arg(Arg::with_name(OPTION_NAME_ROUND_SCHEDULE_EPOCH)
+            .long("round-schedule-epoch")
+            .takes_value(true)
+            .value_name("UNIXTIME")
+            .help("Start rounds at every round duration since the unix time, and choose the master of each round from the number of the slot. All signers in the federation should use the same value and synchronize their clocks."))
+        .arg(Arg::with_name(OPTION_NAME_BLOCK_PRODUCTION)
+            .long("block-production")
+            .takes_value(true)
+            .possible_values(&["always", "mempool", "interval"])
+            .help("When the master of a round produces a block. With `always`, the default, it produces a block every round. With `mempool`, only when the mempool has transactions. With `interval`, when the mempool has transactions or the tip block is older than the max block interval."))
+        .arg(Arg::with_name(OPTION_NAME_MAX_BLOCK_INTERVAL)
+            .long("max-block-interval")
+            .takes_value(true)
+            .value_name("SECs")
+            .help("The longest interval(sec) between blocks with `--block-production=interval`. The master produces an empty block when the tip block is older than it. The default is 3600."))
         .arg(Arg::with_name(OPTION_NAME_SKIP_WAITING_IBD)
             .long("skip-waiting-ibd")
             .help("This flag make signer node don't waiting connected Tapyrus full node finishes Initial Block Download when signer node started. When block creation stopped much time, The status of Tapyrus full node changes to progressing Initial Block Download. In this case, block creation is never resume, because signer node waits the status is back to non-IBD. So you can use this flag to start signer node with ignore tapyrus full node status."))
+        .arg(Arg::with_name(OPTION_NAME_PRECOMPUTE_BLOCK_VSS)
+            .long("precompute-block-vss")
+            .help("This flag make signer node exchange Block VSSs for the next round as soon as the current round is completed. The Block VSSs are bound to a candidate block when it is arrived, so that the round after getting a new block needs only the local signature exchange. All signers in the federation should use the version which supports this flag."))
         .arg(Arg::with_name(OPTION_NAME_DAEMON)
             .long("daemon")
             .help("Daemonize the Tapyrus Signer node process."))
@@ -513,6 +897,21 @@ pub fn get_options<'a, 'b>() -> clap::App<'a, 'b> {
             .takes_value(true)
             .value_name("file")
             .help("Specify where log file export to. This option is enable when the node fot '--daemon' flag. If not, logs are put on stdout and stderr."))
+        .arg(Arg::with_name(OPTION_NAME_RECORD_FILE)
+            .long("record-file")
+            .takes_value(true)
+            .value_name("file")
+            .help("Record every received message, timer event and resulting node state to the file as JSON lines. The recording can be replayed offline with tapyrus-replay."))
+        .arg(Arg::with_name(OPTION_NAME_EVIDENCE_FILE)
+            .long("evidence-file")
+            .takes_value(true)
+            .value_name("file")
+            .help("Append evidence of misbehavior of round masters to the file as JSON lines."))
+        .arg(Arg::with_name(OPTION_NAME_ADMIN_RPC_BIND)
+            .long("admin-rpc-bind")
+            .takes_value(true)
+            .value_name("ADDRESS:PORT")
+            .help("Serve the admin JSON-RPC on the address, which has no authentication. example: 127.0.0.1:2380"))
 }
 
 #[test]
@@ -529,7 +928,10 @@ fn test_allow_no_exists_config_file() {
     let matches = get_options().get_matches_from(vec!["node", "-c=hoge.toml"]);
     let args = CommandArgs::load(matches);
     assert!(args.is_ok());
-    assert!(args.unwrap().config.is_none());
+    let args = args.unwrap();
+    assert!(args.config.is_none());
+    assert_eq!(args.signer_config().federation_check(), "warn");
+    assert_eq!(args.signer_config().private_key_file(), None);
 }
 
 #[test]
@@ -555,16 +957,43 @@ fn test_load_from_file() {
         args.signer_config().federations_file(),
         Path::new("/tmp/federations.toml")
     );
+    assert_eq!(
+        args.signer_config().private_key_file(),
+        Some(Path::new("/var/lib/tapyrus-signer/private-key.wif"))
+    );
     assert_eq!(
         args.signer_config().to_address(),
         Address::from_str("1Co1dFUNuYXY4izSNM9t71VpuUaYdMfq3S").unwrap()
     );
+    assert_eq!(args.signer_config().federation_check(), "warn");
+    assert_eq!(
+        args.signer_config().federation_check_cache(),
+        Some(Path::new("/var/lib/tapyrus-signer/federation-check.json"))
+    );
 
     // rpc parameters are loaded from toml data.
     assert_eq!(args.rpc_config().host(), "localhost");
     assert_eq!(args.rpc_config().port(), 12381);
     assert_eq!(args.rpc_config().user_name(), Some("user"));
     assert_eq!(args.rpc_config().password(), Some("pass"));
+    assert_eq!(
+        args.rpc_config().endpoints(),
+        vec!["http://localhost:12381", "http://192.168.0.64:12381"]
+    );
+    assert_eq!(
+        args.rpc_config().cookie_file(),
+        Some(Path::new("/var/lib/tapyrus/.cookie"))
+    );
+    assert_eq!(args.rpc_config().timeout(), 20);
+    assert_eq!(args.rpc_config().retries(), 3);
+    assert_eq!(
+        args.rpc_config().zmq_pub_hashblock(),
+        Some("tcp://127.0.0.1:28332")
+    );
+    assert_eq!(
+        args.rpc_config().method_timeouts(),
+        vec![("getnewblock".to_string(), 60)].into_iter().collect()
+    );
 
     // redis parameters are loaded from toml data.
     assert_eq!(args.redis_config().host(), "192.168.0.63");
@@ -582,6 +1011,49 @@ fn test_load_from_file() {
         "/var/log/tapyrus-signer.log"
     );
     assert_eq!(args.general_config().skip_waiting_ibd(), true);
+    assert_eq!(args.general_config().precompute_block_vss(), false);
+    assert_eq!(
+        args.general_config().record_file(),
+        Some("/var/log/tapyrus-signer-record.jsonl")
+    );
+    assert_eq!(
+        args.general_config().evidence_file(),
+        Some("/var/log/tapyrus-signer-evidence.jsonl")
+    );
+    assert_eq!(
+        args.general_config().admin_rpc_bind(),
+        Some("127.0.0.1:2380")
+    );
+    assert_eq!(
+        args.general_config().round_schedule_epoch(),
+        Some(1577836800)
+    );
+    assert_eq!(args.general_config().block_production(), "interval");
+    assert_eq!(args.general_config().max_block_interval(), 600);
+
+    // middleware parameters are loaded from toml data.
+    assert_eq!(
+        args.middleware_config().layers(),
+        vec!["metrics", "allowlist", "rate-limit"]
+    );
+    assert_eq!(args.middleware_config().rate_limit(), 50);
+    assert_eq!(args.middleware_config().metrics_interval(), 60);
+    assert_eq!(args.middleware_config().drop_rate(), 0.0);
+
+    // validation parameters are loaded from toml data.
+    assert_eq!(
+        args.validation_config().checks(),
+        vec!["height", "parent", "coinbase"]
+    );
+    assert_eq!(args.validation_config().max_time_skew(), 600);
+    assert_eq!(
+        args.validation_config().max_block_size(),
+        DEFAULT_MAX_BLOCK_SIZE
+    );
+    assert_eq!(
+        args.validation_config().coinbase_addresses(),
+        vec![Address::from_str("1Co1dFUNuYXY4izSNM9t71VpuUaYdMfq3S").unwrap()]
+    );
 }
 
 #[test]
@@ -592,17 +1064,31 @@ fn test_priority_commandline() {
         "--duration=999",
         "--round-limit=99",
         "-p=033cfe7fa1be58191b9108883543e921d31dc7726e051ee773e0ea54786ce438f8",
+        "--private-key-file=/tmp/private-key.wif",
         "--federations-file=/tmp/federations.toml",
+        "--federation-check=skip",
+        "--federation-check-cache=/tmp/federation-check.json",
         "--rpc-host=tapyrus.dev.chaintope.com",
         "--rpc-port=12345",
         "--rpc-user=test",
         "--rpc-pass=test",
+        "--rpc-cookie-file=/tmp/.cookie",
+        "--rpc-timeout=5",
+        "--rpc-retries=0",
+        "--zmq-pub-hashblock=tcp://192.168.0.1:28332",
         "--redis-host=redis.endpoint.dev.chaintope.com",
         "--redis-port=88888",
         "--daemon",
         "--pid=/tmp/test.pid",
         "--log-file=/tmp/tapyrus-signer.log",
         "--skip-waiting-ibd",
+        "--precompute-block-vss",
+        "--record-file=/tmp/record.jsonl",
+        "--evidence-file=/tmp/evidence.jsonl",
+        "--admin-rpc-bind=127.0.0.1:12380",
+        "--round-schedule-epoch=1600000000",
+        "--block-production=mempool",
+        "--max-block-interval=1200",
     ]);
     let args = CommandArgs::load(matches).unwrap();
 
@@ -615,12 +1101,35 @@ fn test_priority_commandline() {
         args.signer_config().federations_file(),
         Path::new("/tmp/federations.toml")
     );
+    assert_eq!(
+        args.signer_config().private_key_file(),
+        Some(Path::new("/tmp/private-key.wif"))
+    );
+    assert_eq!(args.signer_config().federation_check(), "skip");
+    assert_eq!(
+        args.signer_config().federation_check_cache(),
+        Some(Path::new("/tmp/federation-check.json"))
+    );
 
     // rpc parameters are loaded from toml data.
     assert_eq!(args.rpc_config().host(), "tapyrus.dev.chaintope.com");
     assert_eq!(args.rpc_config().port(), 12345);
     assert_eq!(args.rpc_config().user_name(), Some("test"));
     assert_eq!(args.rpc_config().password(), Some("test"));
+    assert_eq!(
+        args.rpc_config().endpoints(),
+        vec!["http://tapyrus.dev.chaintope.com:12345"]
+    );
+    assert_eq!(
+        args.rpc_config().cookie_file(),
+        Some(Path::new("/tmp/.cookie"))
+    );
+    assert_eq!(args.rpc_config().timeout(), 5);
+    assert_eq!(args.rpc_config().retries(), 0);
+    assert_eq!(
+        args.rpc_config().zmq_pub_hashblock(),
+        Some("tcp://192.168.0.1:28332")
+    );
 
     // redis parameters are loaded from toml data.
     assert_eq!(
@@ -635,6 +1144,25 @@ fn test_priority_commandline() {
     assert_eq!(args.general_config().pid(), "/tmp/test.pid");
     assert_eq!(args.general_config().log_file(), "/tmp/tapyrus-signer.log");
     assert_eq!(args.general_config().skip_waiting_ibd(), true);
+    assert_eq!(args.general_config().precompute_block_vss(), true);
+    assert_eq!(
+        args.general_config().record_file(),
+        Some("/tmp/record.jsonl")
+    );
+    assert_eq!(
+        args.general_config().evidence_file(),
+        Some("/tmp/evidence.jsonl")
+    );
+    assert_eq!(
+        args.general_config().admin_rpc_bind(),
+        Some("127.0.0.1:12380")
+    );
+    assert_eq!(
+        args.general_config().round_schedule_epoch(),
+        Some(1600000000)
+    );
+    assert_eq!(args.general_config().block_production(), "mempool");
+    assert_eq!(args.general_config().max_block_interval(), 1200);
 }
 
 #[test]
@@ -647,7 +1175,10 @@ fn test_invalid_public_key() {
             signer: Some(SignerToml {
                 to_address: None,
                 publickey: Some("aabbccdd".to_string()),
+                private_key_file: None,
                 federations_file: None,
+                federation_check: None,
+                federation_check_cache: None,
             }),
             ..ConfigToml::default()
         }),
@@ -676,7 +1207,10 @@ fn test_invalid_to_address() {
             signer: Some(SignerToml {
                 to_address: Some("aabbccdd".to_string()),
                 publickey: None,
+                private_key_file: None,
                 federations_file: None,
+                federation_check: None,
+                federation_check_cache: None,
             }),
             ..ConfigToml::default()
         }),
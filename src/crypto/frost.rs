@@ -0,0 +1,303 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Two-round threshold Schnorr signing with preprocessed nonces, following FROST
+//! (https://eprint.iacr.org/2020/852).
+//!
+//! Each signer generates pairs of nonces `(d, e)` in advance and publishes commitments
+//! `(D, E) = (d * G, e * G)`. For signing a block, the master chooses a commitment from each of the
+//! participants and the participants reply local signatures, so it doesn't need the Block VSS
+//! exchange.
+//!
+//! Tapyrus requires that the y-coordinate of `R` is a quadratic residue. If it is not, all
+//! participants negate their nonces, as the `positive`/`negative` Block VSS do in the VSS scheme.
+use crate::crypto::multi_party_schnorr::{compute_e, LocalSig, SharedKeys, Signature};
+use crate::errors::Error;
+use crate::util::{lagrange_coefficient, sum_point};
+
+use curv::arithmetic::traits::{Converter, Modulo};
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tapyrus::util::prime::jacobi;
+
+/// Commitments of nonces chosen by the master, keyed by 1-based signer index.
+pub type SigningCommitments = BTreeMap<usize, NonceCommitment>;
+
+/// Secret nonce pair of a signer. It must be used only once.
+pub struct Nonce {
+    d: FE,
+    e: FE,
+}
+
+impl Nonce {
+    pub fn new_random() -> Self {
+        Nonce {
+            d: ECScalar::new_random(),
+            e: ECScalar::new_random(),
+        }
+    }
+
+    pub fn commitment(&self) -> NonceCommitment {
+        let g: GE = ECPoint::generator();
+        NonceCommitment {
+            d: g * &self.d,
+            e: g * &self.e,
+        }
+    }
+}
+
+/// Public commitment for a nonce pair.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NonceCommitment {
+    pub d: GE,
+    pub e: GE,
+}
+
+pub struct Frost;
+
+impl Frost {
+    /// Creates a local signature of the signer at `index`.
+    ///
+    /// * `nonce` - The nonce which is committed in `commitments` at `index`.
+    /// * `key` - The node secret share and the aggregated public key.
+    pub fn sign(
+        nonce: &Nonce,
+        index: usize,
+        key: &SharedKeys,
+        message: &[u8],
+        commitments: &SigningCommitments,
+    ) -> Result<LocalSig, Error> {
+        match commitments.get(&index) {
+            Some(c) if *c == nonce.commitment() => {}
+            _ => return Err(Error::InvalidNonce),
+        }
+
+        let (is_positive, r) = group_commitment(message, commitments)?;
+        let rho = binding_factor(index, message, commitments);
+        let k = nonce.d + nonce.e * rho;
+        let k = if is_positive { k } else { negate(&k) };
+
+        let c = compute_e(&r, &key.y, message);
+        let parties: Vec<usize> = commitments.keys().cloned().collect();
+        let lambda = lagrange_coefficient(index, 0, &parties[..]);
+        Ok(LocalSig {
+            gamma_i: k + lambda * key.x_i * c,
+            e: c,
+        })
+    }
+
+    /// Verifies a local signature with the public share of the signer.
+    pub fn verify_local_sig(
+        local_sig: &LocalSig,
+        index: usize,
+        public_share: &GE,
+        public_key: &GE,
+        message: &[u8],
+        commitments: &SigningCommitments,
+    ) -> Result<(), Error> {
+        let commitment = commitments
+            .get(&index)
+            .ok_or(Error::InvalidLocalSignature)?;
+        let (is_positive, r) = group_commitment(message, commitments)?;
+        let c = compute_e(&r, public_key, message);
+        if local_sig.e != c {
+            return Err(Error::InvalidLocalSignature);
+        }
+
+        let rho = binding_factor(index, message, commitments);
+        let r_i = commitment.d + commitment.e * &rho;
+        let r_i = if is_positive {
+            r_i
+        } else {
+            r_i * &negate(&one())
+        };
+
+        let parties: Vec<usize> = commitments.keys().cloned().collect();
+        let lambda = lagrange_coefficient(index, 0, &parties[..]);
+        let g: GE = ECPoint::generator();
+        if g * &local_sig.gamma_i == r_i + public_share * &(lambda * c) {
+            Ok(())
+        } else {
+            Err(Error::InvalidLocalSignature)
+        }
+    }
+
+    /// Aggregates local signatures of all participants and verifies the final signature.
+    pub fn aggregate(
+        local_sigs: &BTreeMap<usize, LocalSig>,
+        public_key: &GE,
+        message: &[u8],
+        commitments: &SigningCommitments,
+    ) -> Result<Signature, Error> {
+        if local_sigs.len() != commitments.len()
+            || local_sigs.keys().any(|i| !commitments.contains_key(i))
+        {
+            return Err(Error::InvalidAggregatedSignature);
+        }
+
+        let (_, r) = group_commitment(message, commitments)?;
+        let sigma = local_sigs
+            .values()
+            .fold(FE::zero(), |acc, s| acc + &s.gamma_i);
+        let signature = Signature { sigma, v: r };
+        signature.verify(message, public_key)?;
+        Ok(signature)
+    }
+
+    /// Returns the public share `x_i * G` of the signer at `index`, which is computed from the
+    /// commitments in the node VSSs.
+    pub fn public_share(index: usize, node_vss: &Vec<VerifiableSS>) -> GE {
+        let q = FE::q();
+        let x = BigInt::from(index as u64);
+        let points: Vec<GE> = node_vss
+            .iter()
+            .flat_map(|vss| {
+                vss.commitments.iter().enumerate().map(|(k, c)| {
+                    let x_k: FE = ECScalar::from(&BigInt::mod_pow(&x, &BigInt::from(k as u64), &q));
+                    c * &x_k
+                })
+            })
+            .collect();
+        sum_point(&points)
+    }
+}
+
+/// Returns the group commitment `R` whose y-coordinate is a quadratic residue, and whether the
+/// nonces are used as it is(true) or negated(false).
+fn group_commitment(message: &[u8], commitments: &SigningCommitments) -> Result<(bool, GE), Error> {
+    if commitments.is_empty() {
+        return Err(Error::InvalidNonce);
+    }
+    let points: Vec<GE> = commitments
+        .iter()
+        .map(|(i, c)| c.d + c.e * &binding_factor(*i, message, commitments))
+        .collect();
+    let r = sum_point(&points);
+
+    let y = r.y_coor().ok_or(Error::InvalidNonce)?;
+    if jacobi(&Converter::to_vec(&y)) == 1 {
+        Ok((true, r))
+    } else {
+        Ok((false, r * &negate(&one())))
+    }
+}
+
+fn binding_factor(index: usize, message: &[u8], commitments: &SigningCommitments) -> FE {
+    let mut hasher = Sha256::new();
+    hasher.input(b"tapyrus-signer/frost/binding");
+    hasher.input(&(index as u64).to_be_bytes());
+    hasher.input(message);
+    for (i, c) in commitments {
+        hasher.input(&(*i as u64).to_be_bytes());
+        hasher.input(&c.d.get_element().serialize()[..]);
+        hasher.input(&c.e.get_element().serialize()[..]);
+    }
+    ECScalar::from(&BigInt::from(&hasher.result()[..]))
+}
+
+fn one() -> FE {
+    ECScalar::from(&BigInt::from(1))
+}
+
+fn negate(fe: &FE) -> FE {
+    ECScalar::from(&BigInt::mod_sub(&FE::q(), &fe.to_big_int(), &FE::q()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer_node::ToVerifiableSS;
+    use crate::tests::helper::node_vss::federation;
+
+    fn sign_by(
+        parties: &[usize],
+        message: &[u8],
+    ) -> (BTreeMap<usize, LocalSig>, SigningCommitments) {
+        let nonces: BTreeMap<usize, Nonce> =
+            parties.iter().map(|i| (*i, Nonce::new_random())).collect();
+        let commitments: SigningCommitments = nonces
+            .iter()
+            .map(|(i, n)| (*i + 1, n.commitment()))
+            .collect();
+        let local_sigs = nonces
+            .iter()
+            .map(|(i, n)| {
+                let key = federation(*i).node_secret_share();
+                let sig = Frost::sign(n, *i + 1, &key, message, &commitments).unwrap();
+                (*i + 1, sig)
+            })
+            .collect();
+        (local_sigs, commitments)
+    }
+
+    #[test]
+    fn test_sign_and_aggregate() {
+        let message = [1u8; 32];
+        let public_key = federation(0).node_secret_share().y;
+
+        // Nonces are random, so R is negated in about half of the iterations.
+        for _ in 0..4 {
+            let (local_sigs, commitments) = sign_by(&[0, 2, 4], &message);
+            for (i, sig) in &local_sigs {
+                let public_share =
+                    Frost::public_share(*i, &federation(0).node_shared_secrets().to_vss());
+                assert!(Frost::verify_local_sig(
+                    sig,
+                    *i,
+                    &public_share,
+                    &public_key,
+                    &message,
+                    &commitments
+                )
+                .is_ok());
+            }
+
+            let signature =
+                Frost::aggregate(&local_sigs, &public_key, &message, &commitments).unwrap();
+            assert!(signature.verify(&message, &public_key).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_aggregate_with_missing_local_sig() {
+        let message = [1u8; 32];
+        let public_key = federation(0).node_secret_share().y;
+        let (mut local_sigs, commitments) = sign_by(&[0, 1, 2], &message);
+        local_sigs.remove(&1);
+
+        assert!(Frost::aggregate(&local_sigs, &public_key, &message, &commitments).is_err());
+    }
+
+    #[test]
+    fn test_verify_local_sig_with_wrong_signer() {
+        let message = [1u8; 32];
+        let public_key = federation(0).node_secret_share().y;
+        let (local_sigs, commitments) = sign_by(&[0, 1, 2], &message);
+
+        let public_share = Frost::public_share(2, &federation(0).node_shared_secrets().to_vss());
+        assert!(Frost::verify_local_sig(
+            &local_sigs[&1],
+            1,
+            &public_share,
+            &public_key,
+            &message,
+            &commitments
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_sign_with_wrong_nonce() {
+        let message = [1u8; 32];
+        let key = federation(0).node_secret_share();
+        let mut commitments = SigningCommitments::new();
+        commitments.insert(1, Nonce::new_random().commitment());
+
+        let result = Frost::sign(&Nonce::new_random(), 1, &key, &message, &commitments);
+        assert!(result.is_err());
+    }
+}
@@ -1,4 +1,6 @@
+pub mod frost;
 pub mod multi_party_schnorr;
+pub mod recovery;
 pub mod vss;
 
 #[cfg(test)]
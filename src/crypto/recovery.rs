@@ -0,0 +1,342 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Recovery protocol of a node secret share.
+//!
+//! When a signer loses its node VSSs, at least `threshold` other signers (helpers) of the same
+//! federation can reconstruct them without changing the aggregated public key.
+//!
+//! Each node VSS from dealer `j` to signer `i` has the secret `f_j(i)`, where `f_j` is the
+//! polynomial of the dealer. For the lost signer `r` and the set of helpers `S`, it holds that
+//! `f_j(r) = Σ_{i ∈ S} λ_{i,r} f_j(i)` where `λ_{i,r}` is the Lagrange coefficient.
+//!
+//! Helper `i` sends `λ_{i,r} f_j(i) + Σ_{k ∈ S, k != i} ±m_{i,k,j}` for each dealer `j` to the
+//! lost signer. `m_{i,k,j}` is a mask derived from ECDH between helper `i` and helper `k`, so the
+//! masks are canceled out when the lost signer sums all contributions up, but the lost signer
+//! can not know each helper's share `f_j(i)`.
+use crate::crypto::vss::{Commitment, Vss};
+use crate::errors::Error;
+use crate::rpc::Rpc;
+use crate::sign::Sign;
+use crate::signer_node::node_parameters::NodeParameters;
+use crate::util::{lagrange_coefficient, sum_point};
+
+use curv::arithmetic::traits::Modulo;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    ShamirSecretSharing, VerifiableSS,
+};
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tapyrus::{PrivateKey, PublicKey};
+
+pub struct Recovery;
+
+impl Recovery {
+    /// Creates the recovery shares which the helper sends to the lost signer.
+    ///
+    /// * `private_key` - The private key of the helper.
+    /// * `node_vss` - The node VSSs which the helper received in the key generation.
+    /// * `helpers` - Public keys of all helpers, including the helper itself.
+    /// * `lost_public_key` - The public key of the signer who lost its node VSSs.
+    ///
+    /// Returns a VSS for each dealer whose receiver is the lost signer.
+    pub fn create_recovery_shares(
+        private_key: &PrivateKey,
+        node_vss: &Vec<Vss>,
+        helpers: &Vec<PublicKey>,
+        lost_public_key: &PublicKey,
+    ) -> Result<Vec<Vss>, Error> {
+        let secp = tapyrus::secp256k1::Secp256k1::new();
+        let public_key = PublicKey::from_private_key(&secp, private_key);
+
+        let mut signers: Vec<PublicKey> = node_vss.iter().map(|v| v.sender_public_key).collect();
+        NodeParameters::<Rpc>::sort_publickey(&mut signers);
+
+        let index_of = |pk: &PublicKey| -> Result<usize, Error> {
+            signers
+                .iter()
+                .position(|i| i == pk)
+                .map(|i| i + 1)
+                .ok_or(Error::InvalidArgs(format!(
+                    "{} is not a member of the federation",
+                    pk
+                )))
+        };
+
+        let self_index = index_of(&public_key)?;
+        let lost_index = index_of(lost_public_key)?;
+        let mut parties = helpers
+            .iter()
+            .map(|pk| index_of(pk))
+            .collect::<Result<Vec<usize>, _>>()?;
+        parties.sort();
+        parties.dedup();
+
+        if !parties.contains(&self_index) {
+            return Err(Error::InvalidArgs(
+                "helpers should include the signer itself".to_string(),
+            ));
+        }
+        if parties.contains(&lost_index) {
+            return Err(Error::InvalidArgs(
+                "helpers should not include the lost signer".to_string(),
+            ));
+        }
+
+        let threshold = node_vss
+            .first()
+            .map(|v| v.positive_commitments.len())
+            .ok_or(Error::InvalidArgs("node-vss".to_string()))?;
+        if parties.len() < threshold {
+            return Err(Error::InvalidArgs(format!(
+                "the number of helpers should be greater than or equal to threshold {}",
+                threshold
+            )));
+        }
+
+        let lambda = lagrange_coefficient(self_index, lost_index, &parties[..]);
+        let scalar: FE = ECScalar::from(
+            &Sign::private_key_to_big_int(private_key.key).ok_or(Error::InvalidKey)?,
+        );
+
+        let mut shares = Vec::new();
+        for vss in node_vss {
+            let verifiable_ss = to_verifiable_ss(&vss.positive_commitments, signers.len())?;
+            if vss.receiver_public_key != public_key
+                || verifiable_ss
+                    .validate_share(&vss.positive_secret, self_index)
+                    .is_err()
+            {
+                return Err(Error::InvalidSS);
+            }
+
+            let mut secret = vss.positive_secret * lambda;
+            for helper in helpers.iter().filter(|pk| **pk != public_key) {
+                let m = mask(&scalar, helper, lost_public_key, &vss.sender_public_key)?;
+                secret = if self_index < index_of(helper)? {
+                    secret + m
+                } else {
+                    secret + negate(&m)
+                };
+            }
+
+            shares.push(Vss::new(
+                vss.sender_public_key,
+                *lost_public_key,
+                vss.positive_commitments.clone(),
+                secret,
+                vss.positive_commitments.clone(),
+                secret,
+            ));
+        }
+        Ok(shares)
+    }
+
+    /// Reconstructs the node VSSs of the lost signer from the recovery shares of all helpers.
+    ///
+    /// Each reconstructed share is verified against the commitments of its dealer, so the
+    /// returned node VSSs have the same commitments as the ones in the original key generation.
+    /// The commitments come from the helpers, so the sum of their constant terms is also checked
+    /// against `aggregated_public_key` of the federation. Otherwise colluding helpers could
+    /// substitute the shares together with the commitments.
+    pub fn recover_node_vss(
+        lost_public_key: &PublicKey,
+        aggregated_public_key: &PublicKey,
+        recovery_shares: &Vec<Vss>,
+    ) -> Result<Vec<Vss>, Error> {
+        let mut grouped: BTreeMap<Vec<u8>, Vec<&Vss>> = BTreeMap::new();
+        for share in recovery_shares {
+            if share.receiver_public_key != *lost_public_key {
+                return Err(Error::InvalidArgs(format!(
+                    "the recovery share is not for {}",
+                    lost_public_key
+                )));
+            }
+            grouped
+                .entry(share.sender_public_key.key.serialize().to_vec())
+                .or_insert(Vec::new())
+                .push(share);
+        }
+
+        let mut signers: Vec<PublicKey> = recovery_shares
+            .iter()
+            .map(|v| v.sender_public_key)
+            .collect();
+        NodeParameters::<Rpc>::sort_publickey(&mut signers);
+        signers.dedup();
+        let lost_index = signers
+            .iter()
+            .position(|pk| pk == lost_public_key)
+            .map(|i| i + 1)
+            .ok_or(Error::InvalidArgs(
+                "the lost signer is not a member of the federation".to_string(),
+            ))?;
+
+        let helper_count = grouped.values().next().map(|v| v.len()).unwrap_or(0);
+        let mut node_vss = Vec::new();
+        for shares in grouped.values() {
+            let commitments = &shares[0].positive_commitments;
+            if shares.len() != helper_count || shares.len() < commitments.len() {
+                return Err(Error::InvalidArgs(
+                    "recovery shares from all helpers are required for each signer".to_string(),
+                ));
+            }
+            if shares
+                .iter()
+                .any(|s| s.positive_commitments != *commitments)
+            {
+                return Err(Error::InvalidSS);
+            }
+
+            let secret = shares
+                .iter()
+                .fold(FE::zero(), |acc, s| acc + s.positive_secret);
+            to_verifiable_ss(commitments, signers.len())?
+                .validate_share(&secret, lost_index)
+                .map_err(|_| Error::InvalidSS)?;
+
+            node_vss.push(Vss::new(
+                shares[0].sender_public_key,
+                *lost_public_key,
+                commitments.clone(),
+                secret,
+                commitments.clone(),
+                secret,
+            ));
+        }
+
+        let points: Vec<GE> = node_vss
+            .iter()
+            .map(|vss| vss.positive_commitments[0].to_point())
+            .collect();
+        if points.is_empty() {
+            return Err(Error::InvalidSS);
+        }
+        let sum = PublicKey::from_slice(&sum_point(&points).pk_to_key_slice())
+            .map_err(|_| Error::InvalidKey)?;
+        if sum.key != aggregated_public_key.key {
+            return Err(Error::InvalidAggregatedPublicKey);
+        }
+        Ok(node_vss)
+    }
+}
+
+fn to_verifiable_ss(
+    commitments: &Vec<Commitment>,
+    share_count: usize,
+) -> Result<VerifiableSS, Error> {
+    if commitments.is_empty() || !commitments.iter().all(|c| c.is_valid_point()) {
+        return Err(Error::InvalidSS);
+    }
+    Ok(VerifiableSS {
+        parameters: ShamirSecretSharing {
+            threshold: commitments.len() - 1,
+            share_count,
+        },
+        commitments: commitments.iter().map(|c| c.to_point()).collect(),
+    })
+}
+
+/// Mask shared between two helpers, which is derived from ECDH of their keys.
+fn mask(
+    scalar: &FE,
+    other: &PublicKey,
+    lost_public_key: &PublicKey,
+    dealer: &PublicKey,
+) -> Result<FE, Error> {
+    let bytes = other.key.serialize_uncompressed();
+    let point: GE = GE::from_bytes(&bytes[1..]).map_err(|_| Error::InvalidKey)?;
+    let shared = point * scalar;
+
+    let mut hasher = Sha256::new();
+    hasher.input(&shared.get_element().serialize()[..]);
+    hasher.input(&lost_public_key.key.serialize()[..]);
+    hasher.input(&dealer.key.serialize()[..]);
+    Ok(ECScalar::from(&BigInt::from(&hasher.result()[..])))
+}
+
+fn negate(fe: &FE) -> FE {
+    ECScalar::from(&BigInt::mod_sub(&FE::q(), &fe.to_big_int(), &FE::q()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::node_vss;
+
+    #[test]
+    fn test_recover_node_vss() {
+        let lost = TEST_KEYS.pubkeys()[4];
+        let helpers: Vec<PublicKey> = TEST_KEYS.pubkeys()[0..3].to_vec();
+
+        let shares: Vec<Vss> = (0..3)
+            .flat_map(|i| {
+                Recovery::create_recovery_shares(&TEST_KEYS.key[i], &node_vss(i), &helpers, &lost)
+                    .unwrap()
+            })
+            .collect();
+        let recovered =
+            Recovery::recover_node_vss(&lost, &TEST_KEYS.aggregated(), &shares).unwrap();
+
+        let mut expected = node_vss(4);
+        expected.sort_by_key(|v| v.sender_public_key.key.serialize().to_vec());
+        assert_eq!(recovered.len(), expected.len());
+        for (r, e) in recovered.iter().zip(expected.iter()) {
+            assert_eq!(r.sender_public_key, e.sender_public_key);
+            assert_eq!(r.positive_secret, e.positive_secret);
+            assert_eq!(r.positive_commitments, e.positive_commitments);
+        }
+    }
+
+    #[test]
+    fn test_recover_node_vss_without_enough_helpers() {
+        let lost = TEST_KEYS.pubkeys()[4];
+        let helpers: Vec<PublicKey> = TEST_KEYS.pubkeys()[0..2].to_vec();
+
+        let result =
+            Recovery::create_recovery_shares(&TEST_KEYS.key[0], &node_vss(0), &helpers, &lost);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_recover_node_vss_with_missing_helper() {
+        let lost = TEST_KEYS.pubkeys()[4];
+        let helpers: Vec<PublicKey> = TEST_KEYS.pubkeys()[0..3].to_vec();
+
+        // Shares from helper 2 are missing, so the masks are not canceled.
+        let shares: Vec<Vss> = (0..2)
+            .flat_map(|i| {
+                Recovery::create_recovery_shares(&TEST_KEYS.key[i], &node_vss(i), &helpers, &lost)
+                    .unwrap()
+            })
+            .collect();
+        assert!(Recovery::recover_node_vss(&lost, &TEST_KEYS.aggregated(), &shares).is_err());
+    }
+
+    #[test]
+    fn test_recover_node_vss_with_other_aggregated_public_key() {
+        let lost = TEST_KEYS.pubkeys()[4];
+        let helpers: Vec<PublicKey> = TEST_KEYS.pubkeys()[0..3].to_vec();
+
+        let shares: Vec<Vss> = (0..3)
+            .flat_map(|i| {
+                Recovery::create_recovery_shares(&TEST_KEYS.key[i], &node_vss(i), &helpers, &lost)
+                    .unwrap()
+            })
+            .collect();
+        let result = Recovery::recover_node_vss(&lost, &TEST_KEYS.pubkeys()[0], &shares);
+        match result {
+            Err(Error::InvalidAggregatedPublicKey) => {}
+            _ => panic!("should be InvalidAggregatedPublicKey"),
+        }
+    }
+
+    #[test]
+    fn test_to_verifiable_ss_without_commitments() {
+        assert!(to_verifiable_ss(&vec![], 5).is_err());
+    }
+}
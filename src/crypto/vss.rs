@@ -11,6 +11,7 @@ use crate::signer_node::BidirectionalSharedSecretMap;
 use crate::signer_node::SharedSecretMap;
 use crate::signer_node::ToSharedSecretMap;
 use crate::signer_node::ToVerifiableSS;
+use crate::util::is_on_curve;
 
 use curv::arithmetic::traits::Converter;
 use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
@@ -215,6 +216,12 @@ impl Commitment {
     pub fn to_point(&self) -> GE {
         ECPoint::from_coor(&self.x, &self.y)
     }
+
+    /// Returns whether the commitment is a point on secp256k1. `to_point` panics for an invalid
+    /// point, so the commitments from untrusted input should be checked with this first.
+    pub fn is_valid_point(&self) -> bool {
+        is_on_curve(&self.x, &self.y)
+    }
 }
 
 impl fmt::Debug for Commitment {
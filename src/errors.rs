@@ -34,6 +34,22 @@ pub enum Error {
     InvalidAggregatedPublicKey,
     /// xField is not supported by signer.
     UnsupportedXField,
+    /// Nonce for the FROST signing scheme is not found, already used or mismatched with its commitment.
+    InvalidNonce,
+    /// Error when the partially signed block is malformed or not ready for the operation.
+    InvalidPartiallySignedBlock(&'static str),
+    /// Error when no tapyrus-core RPC endpoint responds.
+    NoHealthyRpcEndpoint,
+    /// Error when the RPC call has no response within the timeout. This has the method name.
+    RpcTimeout(String),
+    /// Error when the signer can't connect to tapyrus-core.
+    RpcConnectionFailed(std::io::Error),
+    /// Error when tapyrus-core rejects the credential.
+    RpcAuthFailed,
+    /// Error when the cookie file of tapyrus-core can't be read.
+    RpcCookieFile(std::io::Error),
+    /// Error when the response is not a JSON-RPC response over HTTP.
+    InvalidRpcResponse(String),
 }
 
 impl std::fmt::Display for Error {
@@ -0,0 +1,476 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Failover across multiple tapyrus-core RPC endpoints.
+
+use crate::errors::Error;
+use crate::rpc::{GetBlockchainInfoResult, GetMempoolInfoResult, TapyrusApi};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tapyrus::blockdata::block::{Block, BlockHeader};
+use tapyrus::hash_types::BlockHash;
+use tapyrus::Address;
+
+/// Interval to check all endpoints again, even if the current one works.
+pub const HEALTH_CHECK_INTERVAL_SECS: u64 = 10;
+/// Timeout of getblockchaininfo for the health check. It is shorter than the timeout of the other
+/// calls, because the health check calls the endpoints one by one.
+pub const HEALTH_CHECK_TIMEOUT_SECS: u64 = 2;
+
+pub struct Endpoint<T: TapyrusApi> {
+    /// The name used in logs, like the URL.
+    pub name: String,
+    pub api: T,
+    /// The client for the health check of the same endpoint. It should have a short timeout and no
+    /// retries, so that a hung endpoint doesn't delay the check of the others.
+    pub health_check_api: T,
+}
+
+/// TapyrusApi which routes calls to one of the endpoints.
+///
+/// The endpoint is chosen from the ones which respond to getblockchaininfo and have the highest
+/// tip, so that the candidate block created by one endpoint is tested on a node having the same
+/// tip. The endpoints are checked in a background thread, so the calls don't wait for the check.
+/// When a call fails to connect or times out, it switches to another endpoint which was healthy
+/// at the last check and calls again. The other errors, including the ones returned by
+/// tapyrus-core itself, are returned as they are.
+pub struct FailoverRpc<T: TapyrusApi> {
+    shared: Arc<Shared<T>>,
+}
+
+struct Shared<T: TapyrusApi> {
+    endpoints: Vec<Endpoint<T>>,
+    selection: Mutex<Selection>,
+}
+
+struct Selection {
+    /// The tips of the endpoints at the last health check. None for the unhealthy ones.
+    heights: Vec<Option<u64>>,
+    current: Option<usize>,
+}
+
+impl Selection {
+    /// Chooses the endpoint from the healthy ones with the highest tip. The current endpoint is
+    /// kept if it is one of them.
+    fn choose(&self, current: Option<usize>) -> Option<usize> {
+        let best = self.heights.iter().filter_map(|h| *h).max()?;
+        let is_best = |index: &usize| self.heights[*index] == Some(best);
+        current
+            .filter(is_best)
+            .or_else(|| (0..self.heights.len()).find(is_best))
+    }
+}
+
+impl<T: TapyrusApi + Send + Sync + 'static> FailoverRpc<T> {
+    pub fn new(endpoints: Vec<Endpoint<T>>) -> Self {
+        FailoverRpc::with_interval(endpoints, Duration::from_secs(HEALTH_CHECK_INTERVAL_SECS))
+    }
+
+    /// Checks the endpoints once and starts the thread which checks them again every interval.
+    /// The thread stops when the FailoverRpc is dropped.
+    pub fn with_interval(endpoints: Vec<Endpoint<T>>, health_check_interval: Duration) -> Self {
+        let rpc = FailoverRpc::without_health_check_thread(endpoints);
+        let shared = Arc::downgrade(&rpc.shared);
+        thread::Builder::new()
+            .name("RpcHealthCheckThread".to_string())
+            .spawn(move || loop {
+                thread::sleep(health_check_interval);
+                match shared.upgrade() {
+                    Some(shared) => shared.health_check(),
+                    None => return,
+                }
+            })
+            .expect("Failed to spawn the RPC health check thread");
+        rpc
+    }
+}
+
+impl<T: TapyrusApi> FailoverRpc<T> {
+    fn without_health_check_thread(endpoints: Vec<Endpoint<T>>) -> Self {
+        assert!(!endpoints.is_empty(), "No RPC endpoint is given.");
+        let shared = Shared {
+            selection: Mutex::new(Selection {
+                heights: vec![None; endpoints.len()],
+                current: None,
+            }),
+            endpoints,
+        };
+        shared.health_check();
+        FailoverRpc {
+            shared: Arc::new(shared),
+        }
+    }
+
+    /// Returns the name of the endpoint which calls are routed to.
+    pub fn current(&self) -> Option<&str> {
+        self.shared
+            .current()
+            .map(|index| &self.shared.endpoints[index].name as &str)
+    }
+
+    /// Returns Ok if any endpoint was healthy at the last check.
+    pub fn test_connection(&self) -> Result<(), Error> {
+        self.shared
+            .current()
+            .map(|_| ())
+            .ok_or(Error::NoHealthyRpcEndpoint)
+    }
+
+    fn call<R>(&self, f: impl Fn(&T) -> Result<R, Error>) -> Result<R, Error> {
+        let mut last_error = Error::NoHealthyRpcEndpoint;
+        for _ in 0..self.shared.endpoints.len() {
+            let index = match self.shared.current() {
+                Some(index) => index,
+                None => break,
+            };
+            let endpoint = &self.shared.endpoints[index];
+            match f(&endpoint.api) {
+                Err(e) if is_connection_error(&e) => {
+                    log::warn!("RPC call to {} failed: {:?}", endpoint.name, e);
+                    self.shared.mark_failed(index);
+                    last_error = e;
+                }
+                result => return result,
+            }
+        }
+        Err(last_error)
+    }
+}
+
+impl<T: TapyrusApi> Shared<T> {
+    fn current(&self) -> Option<usize> {
+        self.selection.lock().unwrap().current
+    }
+
+    /// Calls getblockchaininfo of all endpoints and switches to the best one. The lock is not held
+    /// while the endpoints are called.
+    fn health_check(&self) {
+        let heights: Vec<Option<u64>> = self
+            .endpoints
+            .iter()
+            .map(
+                |endpoint| match endpoint.health_check_api.getblockchaininfo() {
+                    Ok(info) => Some(info.blocks),
+                    Err(e) => {
+                        log::warn!("RPC endpoint {} is unhealthy: {:?}", endpoint.name, e);
+                        None
+                    }
+                },
+            )
+            .collect();
+        let mut selection = self.selection.lock().unwrap();
+        selection.heights = heights;
+        let next = selection.choose(selection.current);
+        self.switch(&mut selection, next);
+    }
+
+    /// Treats the endpoint as unhealthy until the next health check, without calling any endpoint.
+    fn mark_failed(&self, index: usize) {
+        let mut selection = self.selection.lock().unwrap();
+        selection.heights[index] = None;
+        if selection.current == Some(index) {
+            let next = selection.choose(None);
+            self.switch(&mut selection, next);
+        }
+    }
+
+    fn switch(&self, selection: &mut Selection, next: Option<usize>) {
+        if next != selection.current {
+            log::warn!(
+                "Switch the RPC endpoint from {} to {}.",
+                self.name(selection.current),
+                self.name(next)
+            );
+        }
+        selection.current = next;
+    }
+
+    fn name(&self, index: Option<usize>) -> &str {
+        match index {
+            Some(index) => &self.endpoints[index].name,
+            None => "none",
+        }
+    }
+}
+
+/// Whether the call failed to reach the endpoint. Authentication errors and invalid responses
+/// come from an endpoint which is reachable, so they are returned as they are.
+fn is_connection_error(error: &Error) -> bool {
+    match error {
+        Error::RpcTimeout(_) | Error::RpcConnectionFailed(_) => true,
+        _ => false,
+    }
+}
+
+impl<T: TapyrusApi> TapyrusApi for FailoverRpc<T> {
+    fn getnewblock(&self, address: &Address) -> Result<Block, Error> {
+        self.call(|api| api.getnewblock(address))
+    }
+
+    fn testproposedblock(&self, block: &Block) -> Result<bool, Error> {
+        self.call(|api| api.testproposedblock(block))
+    }
+
+    fn submitblock(&self, block: &Block) -> Result<(), Error> {
+        self.call(|api| api.submitblock(block))
+    }
+
+    fn getblockchaininfo(&self) -> Result<GetBlockchainInfoResult, Error> {
+        self.call(|api| api.getblockchaininfo())
+    }
+
+    fn getblockhash(&self, height: u64) -> Result<BlockHash, Error> {
+        self.call(|api| api.getblockhash(height))
+    }
+
+    fn getblockheader(&self, hash: &BlockHash) -> Result<BlockHeader, Error> {
+        self.call(|api| api.getblockheader(hash))
+    }
+
+    fn getmempoolinfo(&self) -> Result<GetMempoolInfoResult, Error> {
+        self.call(|api| api.getmempoolinfo())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::blocks::get_block;
+    use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Clone)]
+    struct Node {
+        up: Arc<AtomicBool>,
+        auth_failed: Arc<AtomicBool>,
+        height: Arc<AtomicU64>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Node {
+        fn new(height: u64) -> Self {
+            Node {
+                up: Arc::new(AtomicBool::new(true)),
+                auth_failed: Arc::new(AtomicBool::new(false)),
+                height: Arc::new(AtomicU64::new(height)),
+                calls: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+
+        fn respond<R>(&self, result: R) -> Result<R, Error> {
+            if !self.up.load(Ordering::SeqCst) {
+                Err(Error::RpcConnectionFailed(std::io::Error::from(
+                    std::io::ErrorKind::ConnectionRefused,
+                )))
+            } else if self.auth_failed.load(Ordering::SeqCst) {
+                Err(Error::RpcAuthFailed)
+            } else {
+                Ok(result)
+            }
+        }
+    }
+
+    impl TapyrusApi for Node {
+        fn getnewblock(&self, _address: &Address) -> Result<Block, Error> {
+            self.respond(get_block(0))
+        }
+
+        fn testproposedblock(&self, _block: &Block) -> Result<bool, Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.respond(true)
+        }
+
+        fn submitblock(&self, _block: &Block) -> Result<(), Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.respond(())?;
+            Err(Error::InvalidRequest(jsonrpc::error::RpcError {
+                code: -25,
+                message: "rejected".to_string(),
+                data: None,
+            }))
+        }
+
+        fn getblockchaininfo(&self) -> Result<GetBlockchainInfoResult, Error> {
+            self.respond(GetBlockchainInfoResult {
+                chain: "regtest".to_string(),
+                blocks: self.height.load(Ordering::SeqCst),
+                headers: self.height.load(Ordering::SeqCst),
+                bestblockhash: String::new(),
+                mediantime: 0,
+                initialblockdownload: false,
+            })
+        }
+
+        fn getblockhash(&self, _height: u64) -> Result<BlockHash, Error> {
+            self.respond(get_block(0).block_hash())
+        }
+
+        fn getblockheader(&self, _hash: &BlockHash) -> Result<BlockHeader, Error> {
+            self.respond(get_block(0).header)
+        }
+
+        fn getmempoolinfo(&self) -> Result<GetMempoolInfoResult, Error> {
+            self.respond(GetMempoolInfoResult { size: 0, bytes: 0 })
+        }
+    }
+
+    /// The health check runs only when the test calls it.
+    fn failover(nodes: &[Node]) -> FailoverRpc<Node> {
+        let endpoints = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| Endpoint {
+                name: format!("node{}", i),
+                api: node.clone(),
+                health_check_api: node.clone(),
+            })
+            .collect();
+        FailoverRpc::without_health_check_thread(endpoints)
+    }
+
+    #[test]
+    fn test_select_highest_tip() {
+        let nodes = vec![Node::new(9), Node::new(10), Node::new(10)];
+        let rpc = failover(&nodes);
+        assert_eq!(rpc.current(), Some("node1"));
+        assert_eq!(rpc.getblockchaininfo().unwrap().blocks, 10);
+    }
+
+    #[test]
+    fn test_failover() {
+        let nodes = vec![Node::new(10), Node::new(10)];
+        let rpc = failover(&nodes);
+        assert_eq!(rpc.current(), Some("node0"));
+
+        nodes[0].up.store(false, Ordering::SeqCst);
+        assert!(rpc.testproposedblock(&get_block(0)).unwrap());
+        assert_eq!(nodes[0].calls.load(Ordering::SeqCst), 1);
+        assert_eq!(nodes[1].calls.load(Ordering::SeqCst), 1);
+        assert_eq!(rpc.current(), Some("node1"));
+    }
+
+    #[test]
+    fn test_keep_current_while_healthy() {
+        let nodes = vec![Node::new(10), Node::new(10)];
+        let rpc = failover(&nodes);
+        nodes[0].up.store(false, Ordering::SeqCst);
+        rpc.shared.health_check();
+        assert_eq!(rpc.current(), Some("node1"));
+
+        // node0 comes back with the same tip, but the calls stay on node1.
+        nodes[0].up.store(true, Ordering::SeqCst);
+        rpc.shared.health_check();
+        assert_eq!(rpc.current(), Some("node1"));
+
+        // node0 gets ahead.
+        nodes[0].height.store(11, Ordering::SeqCst);
+        rpc.shared.health_check();
+        assert_eq!(rpc.current(), Some("node0"));
+    }
+
+    #[test]
+    fn test_health_check_uses_its_own_client() {
+        let api = Node::new(10);
+        let health_check_api = Node::new(10);
+        let rpc = FailoverRpc::without_health_check_thread(vec![Endpoint {
+            name: "node0".to_string(),
+            api: api.clone(),
+            health_check_api: health_check_api.clone(),
+        }]);
+        assert_eq!(rpc.current(), Some("node0"));
+        assert_eq!(api.calls.load(Ordering::SeqCst), 0);
+
+        health_check_api.up.store(false, Ordering::SeqCst);
+        rpc.shared.health_check();
+        assert_eq!(rpc.current(), None);
+    }
+
+    #[test]
+    fn test_core_error_is_not_failed_over() {
+        let nodes = vec![Node::new(10), Node::new(10)];
+        let rpc = failover(&nodes);
+        match rpc.submitblock(&get_block(0)) {
+            Err(Error::InvalidRequest(e)) => assert_eq!(e.code, -25),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(nodes[1].calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_no_healthy_endpoint() {
+        let nodes = vec![Node::new(10), Node::new(10)];
+        let rpc = failover(&nodes);
+        for node in &nodes {
+            node.up.store(false, Ordering::SeqCst);
+        }
+        match rpc.testproposedblock(&get_block(0)) {
+            Err(Error::RpcConnectionFailed(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match rpc.test_connection() {
+            Err(Error::NoHealthyRpcEndpoint) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_auth_error_is_not_failed_over() {
+        let nodes = vec![Node::new(10), Node::new(10)];
+        let rpc = failover(&nodes);
+        nodes[0].auth_failed.store(true, Ordering::SeqCst);
+        match rpc.testproposedblock(&get_block(0)) {
+            Err(Error::RpcAuthFailed) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(nodes[1].calls.load(Ordering::SeqCst), 0);
+        assert_eq!(rpc.current(), Some("node0"));
+    }
+
+    #[test]
+    fn test_failover_without_health_check() {
+        let nodes = vec![Node::new(10), Node::new(10)];
+        let rpc = failover(&nodes);
+        nodes[1].up.store(false, Ordering::SeqCst);
+
+        // node1 looks healthy until the next health check, so the call tries it and fails.
+        nodes[0].up.store(false, Ordering::SeqCst);
+        match rpc.testproposedblock(&get_block(0)) {
+            Err(Error::RpcConnectionFailed(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(nodes[0].calls.load(Ordering::SeqCst), 1);
+        assert_eq!(nodes[1].calls.load(Ordering::SeqCst), 1);
+        assert_eq!(rpc.current(), None);
+
+        // The endpoints stay failed until the next health check.
+        nodes[0].up.store(true, Ordering::SeqCst);
+        assert!(rpc.testproposedblock(&get_block(0)).is_err());
+        rpc.shared.health_check();
+        assert!(rpc.testproposedblock(&get_block(0)).unwrap());
+    }
+
+    #[test]
+    fn test_health_check_thread() {
+        let nodes = vec![Node::new(10), Node::new(10)];
+        let endpoints = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| Endpoint {
+                name: format!("node{}", i),
+                api: node.clone(),
+                health_check_api: node.clone(),
+            })
+            .collect();
+        let rpc = FailoverRpc::with_interval(endpoints, Duration::from_millis(10));
+        assert_eq!(rpc.current(), Some("node0"));
+
+        nodes[1].height.store(11, Ordering::SeqCst);
+        let started = std::time::Instant::now();
+        while rpc.current() != Some("node1") {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
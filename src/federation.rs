@@ -4,12 +4,18 @@ use crate::errors::Error;
 use crate::net::SignerID;
 use crate::sign::Sign;
 use crate::signer_node::{SharedSecret, SharedSecretMap};
+use crate::util::sum_point;
 use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
     ShamirSecretSharing, VerifiableSS,
 };
+use curv::elliptic::curves::traits::ECPoint;
+use curv::GE;
 use std::collections::HashSet;
 use tapyrus::PublicKey;
 
+const INVALID_COMMITMENT_MESSAGE: &str =
+    "The nodevss includes a commitment which is not a point on secp256k1.";
+
 #[derive(Debug, Clone)]
 pub struct Federations {
     /// The vector of federations. This vector should be sorted by block height.
@@ -43,6 +49,10 @@ impl Federations {
         self.federations.len()
     }
 
+    pub fn iter(&self) -> std::slice::Iter<'_, Federation> {
+        self.federations.iter()
+    }
+
     pub fn validate(&self) -> Result<(), Error> {
         if self.federations.len() == 0 {
             return Err(Error::InvalidFederation(
@@ -68,10 +78,58 @@ impl Federations {
         Ok(())
     }
 
+    /// Returns all problems in the federations. While `validate` stops at the first problem, this
+    /// goes through all federations and also checks the aggregated public key of each federation.
+    pub fn problems(&self) -> Vec<Error> {
+        let mut problems = Vec::new();
+        if self.federations.len() == 0 {
+            problems.push(Error::InvalidFederation(
+                None,
+                "At least the node must have one federation",
+            ));
+        }
+
+        let mut block_heights: HashSet<u64> = HashSet::new();
+        for federation in &self.federations {
+            if !block_heights.insert(federation.block_height) {
+                problems.push(Error::InvalidFederation(
+                    Some(federation.block_height),
+                    "The federations include block height duplication. The block height in all federations should be unique.",
+                ));
+            }
+        }
+
+        for federation in &self.federations {
+            if let Err(e) = federation.validate() {
+                problems.push(e);
+            }
+            // `validate` has already reported the commitments which are not valid points.
+            if !federation.has_valid_commitments() {
+                continue;
+            }
+            if let Err(e) = federation.validate_aggregated_public_key() {
+                problems.push(e);
+            }
+        }
+        problems
+    }
+
     /// Create Federations instance from:
     ///   * `pubkey` The public key of a signer who runs this node.
     ///   * `toml` toml string for federations.
     pub fn from_pubkey_and_toml(pubkey: &PublicKey, toml: &str) -> Result<Self, Error> {
+        let r = Federations::from_pubkey_and_toml_without_validation(pubkey, toml)?;
+        r.validate()?;
+
+        Ok(r)
+    }
+
+    /// Create Federations instance like `from_pubkey_and_toml`, but doesn't validate it. This is
+    /// for tools which report the problems of the federations by themselves.
+    pub fn from_pubkey_and_toml_without_validation(
+        pubkey: &PublicKey,
+        toml: &str,
+    ) -> Result<Self, Error> {
         let ser: SerFederations = toml::from_str(toml)?;
 
         let vec: Vec<Federation> = ser
@@ -80,10 +138,7 @@ impl Federations {
             .map(|i| Federation::from(*pubkey, i))
             .collect();
 
-        let r = Federations::new(vec);
-        r.validate()?;
-
-        Ok(r)
+        Ok(Federations::new(vec))
     }
 }
 
@@ -104,6 +159,24 @@ pub struct Federation {
     nodevss: Option<Vec<Vss>>,
     /// The aggregated public key
     aggregated_public_key: PublicKey,
+    /// The scheme which the federation uses to produce block signatures.
+    signing_scheme: SigningScheme,
+}
+
+/// The protocol which signers use to produce a block signature.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningScheme {
+    /// Signers exchange Block VSSs for each block to generate a nonce.
+    Vss,
+    /// Signers exchange nonce commitments in advance and sign a block in a single round.
+    Frost,
+}
+
+impl Default for SigningScheme {
+    fn default() -> Self {
+        SigningScheme::Vss
+    }
 }
 
 impl Federation {
@@ -120,9 +193,15 @@ impl Federation {
             threshold,
             nodevss,
             aggregated_public_key,
+            signing_scheme: SigningScheme::default(),
         }
     }
 
+    pub fn with_signing_scheme(mut self, signing_scheme: SigningScheme) -> Self {
+        self.signing_scheme = signing_scheme;
+        self
+    }
+
     pub fn node_index(&self) -> usize {
         self.signers()
             .iter()
@@ -157,6 +236,9 @@ impl Federation {
     pub fn aggregated_public_key(&self) -> PublicKey {
         self.aggregated_public_key
     }
+    pub fn signing_scheme(&self) -> SigningScheme {
+        self.signing_scheme
+    }
 
     /// Returns Map collection of received shares from all each signers in Key Generation Protocol
     pub fn node_shared_secrets(&self) -> SharedSecretMap {
@@ -244,6 +326,13 @@ impl Federation {
             }
         }
 
+        if !self.has_valid_commitments() {
+            return Err(Error::InvalidFederation(
+                Some(self.block_height),
+                INVALID_COMMITMENT_MESSAGE,
+            ));
+        }
+
         // verify each vss.
         if Sign::verify_vss_and_construct_key(&self.node_shared_secrets(), &(self.node_index() + 1))
             .is_err()
@@ -257,6 +346,56 @@ impl Federation {
         Ok(())
     }
 
+    /// Checks the aggregated public key is equal to the sum of the first commitments in the nodevss,
+    /// which is the public key that all signers aggregated in the key generation.
+    /// The check is skipped if the nodevss is not available or doesn't have any commitment, because
+    /// `validate` reports such a federation.
+    pub fn validate_aggregated_public_key(&self) -> Result<(), Error> {
+        let nodevss = match &self.nodevss {
+            Some(nodevss)
+                if !nodevss.is_empty()
+                    && nodevss
+                        .iter()
+                        .all(|vss| !vss.positive_commitments.is_empty()) =>
+            {
+                nodevss
+            }
+            _ => return Ok(()),
+        };
+        if !self.has_valid_commitments() {
+            return Err(Error::InvalidFederation(
+                Some(self.block_height),
+                INVALID_COMMITMENT_MESSAGE,
+            ));
+        }
+
+        let points: Vec<GE> = nodevss
+            .iter()
+            .map(|vss| vss.positive_commitments[0].to_point())
+            .collect();
+        let sum = sum_point(&points);
+        let public_key =
+            PublicKey::from_slice(&sum.pk_to_key_slice()).map_err(|_| Error::InvalidKey)?;
+        if public_key.key != self.aggregated_public_key.key {
+            return Err(Error::InvalidFederation(
+                Some(self.block_height),
+                "The aggregated public key is not equal to the sum of the commitments in the nodevss.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns whether all commitments in the nodevss are points on secp256k1. The commitments
+    /// come from the federations file, so they should be checked before converting to points.
+    fn has_valid_commitments(&self) -> bool {
+        self.nodevss.iter().flatten().all(|vss| {
+            vss.positive_commitments
+                .iter()
+                .chain(vss.negative_commitments.iter())
+                .all(|c| c.is_valid_point())
+        })
+    }
+
     /// Returns whether the signer who hosts the node is a member of this federation.
     /// It is `true` if the signer is a member.
     pub fn is_member(&self) -> bool {
@@ -271,6 +410,20 @@ impl Federation {
             ser.nodevss,
             ser.aggregated_public_key,
         )
+        .with_signing_scheme(ser.signing_scheme.unwrap_or_default())
+    }
+
+    /// Returns the `[[federation]]` table of the federations file for this federation.
+    pub fn to_toml(&self) -> Result<String, Error> {
+        let ser = SerFederations {
+            federation: vec![self.clone().to_ser()],
+        };
+        toml::to_string_pretty(&ser).map_err(|_| {
+            Error::InvalidFederation(
+                Some(self.block_height),
+                "The federation can not be serialized as toml.",
+            )
+        })
     }
 
     pub fn to_ser(self) -> SerFederation {
@@ -279,6 +432,10 @@ impl Federation {
             threshold: self.threshold,
             nodevss: self.nodevss,
             aggregated_public_key: self.aggregated_public_key,
+            signing_scheme: match self.signing_scheme {
+                SigningScheme::Vss => None,
+                scheme => Some(scheme),
+            },
         }
     }
 }
@@ -297,12 +454,19 @@ pub struct SerFederation {
     nodevss: Option<Vec<Vss>>,
     #[serde(rename = "aggregated-public-key")]
     aggregated_public_key: PublicKey,
+    #[serde(
+        rename = "signing-scheme",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    signing_scheme: Option<SigningScheme>,
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::crypto::vss::Commitment;
     use crate::errors::Error;
-    use crate::federation::{Federation, Federations};
+    use crate::federation::{Federation, Federations, SigningScheme, INVALID_COMMITMENT_MESSAGE};
     use crate::net::SignerID;
     use crate::tests::helper::keys::TEST_KEYS;
     use crate::tests::helper::node_vss::node_vss;
@@ -466,6 +630,86 @@ mod tests {
         assert!(federation.validate().is_err());
     }
 
+    #[test]
+    fn test_validate_aggregated_public_key() {
+        let federation = valid_federation();
+        assert!(federation.validate_aggregated_public_key().is_ok());
+
+        let mut federation = valid_federation();
+        federation.aggregated_public_key = TEST_KEYS.pubkeys()[0];
+        match federation.validate_aggregated_public_key() {
+            Err(Error::InvalidFederation(Some(0), m)) => assert_eq!(
+                m,
+                "The aggregated public key is not equal to the sum of the commitments in the nodevss."
+            ),
+            _ => assert!(false, "it should error"),
+        }
+
+        // Skip the check when the federation doesn't include the signer.
+        let mut federation = valid_federation();
+        federation.aggregated_public_key = TEST_KEYS.pubkeys()[0];
+        federation.nodevss = None;
+        federation.threshold = None;
+        assert!(federation.validate_aggregated_public_key().is_ok());
+    }
+
+    #[test]
+    fn test_validate_with_invalid_commitment() {
+        let mut federation = valid_federation();
+        let mut nodevss = node_vss(0);
+        nodevss[1].positive_commitments[0] = Commitment::new(BigInt::from(1), BigInt::from(1));
+        federation.nodevss = Some(nodevss);
+
+        for result in vec![
+            federation.validate(),
+            federation.validate_aggregated_public_key(),
+        ] {
+            match result {
+                Err(Error::InvalidFederation(Some(0), m)) => {
+                    assert_eq!(m, INVALID_COMMITMENT_MESSAGE)
+                }
+                _ => assert!(false, "it should error"),
+            }
+        }
+
+        let federations = Federations::new(vec![federation]);
+        assert_eq!(federations.problems().len(), 1);
+    }
+
+    #[test]
+    fn test_federations_problems() {
+        let federations = Federations::new(vec![valid_federation()]);
+        assert!(federations.problems().is_empty());
+
+        let federations = Federations::new(vec![]);
+        assert_eq!(federations.problems().len(), 1);
+
+        // It reports all problems in the federations.
+        let mut invalid_share = valid_federation();
+        invalid_share.block_height = 100;
+        invalid_share.nodevss.as_mut().unwrap()[0].positive_secret = ECScalar::from(
+            &BigInt::from_hex("9b77b12bf0ec14c6094be7657a3a3d473077bc3c8b694ead6c1b6d8c5b4e816c"),
+        );
+        let mut invalid_key = valid_federation();
+        invalid_key.block_height = 200;
+        invalid_key.aggregated_public_key = TEST_KEYS.pubkeys()[0];
+        let federations = Federations::new(vec![
+            valid_federation(),
+            valid_federation(),
+            invalid_share,
+            invalid_key,
+        ]);
+        let heights: Vec<Option<u64>> = federations
+            .problems()
+            .iter()
+            .map(|e| match e {
+                Error::InvalidFederation(height, _) => *height,
+                _ => panic!("unexpected error"),
+            })
+            .collect();
+        assert_eq!(heights, vec![Some(0), Some(100), Some(200)]);
+    }
+
     #[test]
     fn test_serialize_deserialize() {
         let federation = valid_federation();
@@ -478,6 +722,32 @@ mod tests {
         assert_eq!(federation, deserialized);
     }
 
+    #[test]
+    fn test_to_toml() {
+        let federation = valid_federation();
+        let toml = federation.to_toml().unwrap();
+        assert!(toml.starts_with("[[federation]]"));
+
+        let federations =
+            Federations::from_pubkey_and_toml(&federation.signer_id.pubkey, &toml).unwrap();
+        assert_eq!(federations.len(), 1);
+        assert_eq!(federations.last(), &federation);
+    }
+
+    #[test]
+    fn test_serialize_deserialize_signing_scheme() {
+        let federation = valid_federation();
+        let str = toml::to_string(&federation.clone().to_ser()).unwrap();
+        assert!(!str.contains("signing-scheme"));
+
+        let federation = valid_federation().with_signing_scheme(SigningScheme::Frost);
+        let str = toml::to_string(&federation.clone().to_ser()).unwrap();
+        assert!(str.contains("signing-scheme = \"frost\""));
+        let deserialized =
+            Federation::from(federation.signer_id.pubkey, toml::from_str(&str).unwrap());
+        assert_eq!(deserialized.signing_scheme(), SigningScheme::Frost);
+    }
+
     #[test]
     fn test_from_pubkey_and_toml() {
         let pubkey = PublicKey::from_str(
@@ -0,0 +1,435 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Consistency check of the federations against the aggregated public keys on the chain.
+//!
+//! A block which has an aggregated public key in its xfield changes the key from the next block,
+//! so the block at height h carries the key of the federation which gets started at h + 1. The
+//! genesis block carries the key of the first federation, which gets started at 0.
+
+use crate::errors::Error;
+use crate::federation::Federations;
+use crate::rpc::TapyrusApi;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use tapyrus::hash_types::BlockHash;
+use tapyrus::hashes::hex::FromHex;
+use tapyrus::PublicKey;
+
+/// Log the progress of walking headers every this number of blocks.
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+/// A difference between the federations and the chain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    /// The chain changes the aggregated public key at the block height, but no federation gets
+    /// started there.
+    MissingFederation {
+        block_height: u64,
+        aggregated_public_key: PublicKey,
+    },
+    /// The federation which gets started at the block height has the different aggregated public
+    /// key from the one on the chain.
+    AggregatedPublicKeyMismatch {
+        block_height: u64,
+        federation: PublicKey,
+        chain: PublicKey,
+    },
+    /// The federation should have got started at the block height, but the chain doesn't have its
+    /// aggregated public key.
+    NotOnChain {
+        block_height: u64,
+        aggregated_public_key: PublicKey,
+    },
+}
+
+impl Divergence {
+    pub fn block_height(&self) -> u64 {
+        match self {
+            Divergence::MissingFederation { block_height, .. }
+            | Divergence::AggregatedPublicKeyMismatch { block_height, .. }
+            | Divergence::NotOnChain { block_height, .. } => *block_height,
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::MissingFederation {
+                block_height,
+                aggregated_public_key,
+            } => write!(
+                f,
+                "The chain uses the aggregated public key {} from block height {}, but no federation gets started there.",
+                aggregated_public_key, block_height
+            ),
+            Divergence::AggregatedPublicKeyMismatch {
+                block_height,
+                federation,
+                chain,
+            } => write!(
+                f,
+                "The federation at block height {} has the aggregated public key {}, but the chain has {}.",
+                block_height, federation, chain
+            ),
+            Divergence::NotOnChain {
+                block_height,
+                aggregated_public_key,
+            } => write!(
+                f,
+                "The federation at block height {} with the aggregated public key {} is not on the chain.",
+                block_height, aggregated_public_key
+            ),
+        }
+    }
+}
+
+/// The aggregated public key changes found by walking the block headers up to a block. It is
+/// saved so that the next walk continues from the block instead of walking all headers again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckedHeaders {
+    /// The block height where the walk started.
+    pub from: u64,
+    /// The height of the last checked block.
+    pub height: u64,
+    /// The hash of the last checked block, which tells whether the chain is reorganized.
+    pub hash: BlockHash,
+    pub changes: Vec<(u64, PublicKey)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CheckedHeadersJson {
+    from: u64,
+    height: u64,
+    hash: String,
+    changes: Vec<(u64, String)>,
+}
+
+impl CheckedHeaders {
+    /// Reads the checked headers saved in the file. Returns None when the file doesn't exist or
+    /// can't be read, so that the headers are walked again.
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                log::warn!("Can't read the checked headers in {:?}: {}", path, e);
+                return None;
+            }
+        };
+        let json: CheckedHeadersJson = match serde_json::from_str(&content) {
+            Ok(json) => json,
+            Err(e) => {
+                log::warn!("Invalid checked headers in {:?}: {}", path, e);
+                return None;
+            }
+        };
+        let changes: Option<Vec<(u64, PublicKey)>> = json
+            .changes
+            .iter()
+            .map(|(height, key)| PublicKey::from_str(key).ok().map(|key| (*height, key)))
+            .collect();
+        match (BlockHash::from_hex(&json.hash), changes) {
+            (Ok(hash), Some(changes)) => Some(CheckedHeaders {
+                from: json.from,
+                height: json.height,
+                hash,
+                changes,
+            }),
+            _ => {
+                log::warn!("Invalid checked headers in {:?}", path);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let json = CheckedHeadersJson {
+            from: self.from,
+            height: self.height,
+            hash: self.hash.to_string(),
+            changes: self
+                .changes
+                .iter()
+                .map(|(height, key)| (*height, key.to_string()))
+                .collect(),
+        };
+        std::fs::write(path, serde_json::to_string(&json)?)?;
+        Ok(())
+    }
+}
+
+/// Returns the block height to start walking the headers from. The block before the first
+/// federation carries its aggregated public key, and the blocks before it are not described by
+/// the federations.
+pub fn walk_start(federations: &Federations) -> u64 {
+    federations
+        .iter()
+        .map(|f| f.block_height())
+        .min()
+        .unwrap_or(0)
+        .saturating_sub(1)
+}
+
+/// Walks the block headers from the block height to the tip and returns the pairs of the block
+/// height where each aggregated public key gets started and the key, with the tip. When the
+/// headers checked before are still on the chain, the walk continues from the last of them.
+pub fn aggregated_public_key_changes<T: TapyrusApi>(
+    rpc: &T,
+    from: u64,
+    checked: Option<CheckedHeaders>,
+) -> Result<CheckedHeaders, Error> {
+    let tip = rpc.getblockchaininfo()?.blocks;
+    let (start, mut changes) = match checked {
+        Some(checked)
+            if checked.from == from
+                && checked.height <= tip
+                && rpc.getblockhash(checked.height)? == checked.hash =>
+        {
+            (std::cmp::max(from, checked.height + 1), checked.changes)
+        }
+        Some(_) => {
+            log::info!(
+                "The checked headers are not on the chain. Walk the headers from {}.",
+                from
+            );
+            (from, vec![])
+        }
+        None => (from, vec![]),
+    };
+    for height in start..=tip {
+        let header = rpc.getblockheader(&rpc.getblockhash(height)?)?;
+        if let Some(aggregated_public_key) = header.aggregated_public_key() {
+            let started_at = if height == 0 { 0 } else { height + 1 };
+            changes.push((started_at, aggregated_public_key));
+        }
+        if height % PROGRESS_INTERVAL == 0 && height > 0 {
+            log::info!("Checked block headers up to {}/{}", height, tip);
+        }
+    }
+    Ok(CheckedHeaders {
+        from,
+        height: tip,
+        hash: rpc.getblockhash(tip)?,
+        changes,
+    })
+}
+
+/// Compares the federations with the aggregated public key changes on the chain whose tip is at
+/// the height. The federations which get started after the next block of the tip are not on the
+/// chain yet, so they are not compared.
+pub fn compare(
+    federations: &Federations,
+    changes: &[(u64, PublicKey)],
+    tip: u64,
+) -> Vec<Divergence> {
+    let mut divergences = vec![];
+    for (block_height, aggregated_public_key) in changes {
+        match federations
+            .iter()
+            .find(|f| f.block_height() == *block_height)
+        {
+            Some(federation) if federation.aggregated_public_key() != *aggregated_public_key => {
+                divergences.push(Divergence::AggregatedPublicKeyMismatch {
+                    block_height: *block_height,
+                    federation: federation.aggregated_public_key(),
+                    chain: *aggregated_public_key,
+                })
+            }
+            Some(_) => {}
+            None => divergences.push(Divergence::MissingFederation {
+                block_height: *block_height,
+                aggregated_public_key: *aggregated_public_key,
+            }),
+        }
+    }
+    for federation in federations.iter() {
+        if federation.block_height() > tip + 1 {
+            continue;
+        }
+        if !changes
+            .iter()
+            .any(|(block_height, _)| *block_height == federation.block_height())
+        {
+            divergences.push(Divergence::NotOnChain {
+                block_height: federation.block_height(),
+                aggregated_public_key: federation.aggregated_public_key(),
+            });
+        }
+    }
+    divergences.sort_by_key(|d| d.block_height());
+    divergences
+}
+
+/// Returns the divergences between the federations and the chain which the RPC connects to. With
+/// the cache file, the headers checked at the last time are not walked again.
+pub fn check_federations<T: TapyrusApi>(
+    rpc: &T,
+    federations: &Federations,
+    cache: Option<&Path>,
+) -> Result<Vec<Divergence>, Error> {
+    let checked = aggregated_public_key_changes(
+        rpc,
+        walk_start(federations),
+        cache.and_then(CheckedHeaders::load),
+    )?;
+    if let Some(path) = cache {
+        if let Err(e) = checked.save(path) {
+            log::warn!("Can't save the checked headers to {:?}: {:?}", path, e);
+        }
+    }
+    Ok(compare(federations, &checked.changes, checked.height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::setup::create_genesis::create_genesis_block;
+    use crate::federation::Federation;
+    use crate::simulation::chain::MockChain;
+    use crate::tests::helper::address;
+    use crate::tests::helper::keys::TEST_KEYS;
+
+    fn federation(block_height: u64, aggregated_public_key: PublicKey) -> Federation {
+        Federation::new(
+            TEST_KEYS.pubkeys()[0],
+            block_height,
+            None,
+            None,
+            aggregated_public_key,
+        )
+    }
+
+    fn other_key() -> PublicKey {
+        PublicKey::from_str("02472012cf49fca573ca1f63deafe59df842f0bbe77e9ac7e67b211bb074b72506")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_compare_consistent() {
+        let federations = Federations::new(vec![
+            federation(0, TEST_KEYS.aggregated()),
+            federation(100, other_key()),
+            federation(200, TEST_KEYS.aggregated()),
+        ]);
+        let changes = vec![(0, TEST_KEYS.aggregated()), (100, other_key())];
+        // The federation at 200 is set to the block at 199, which is not created yet.
+        assert!(compare(&federations, &changes, 150).is_empty());
+        // The federation at 200 is set to the block which is created next.
+        assert!(compare(&federations, &changes, 198).is_empty());
+    }
+
+    #[test]
+    fn test_compare_divergences() {
+        let federations = Federations::new(vec![
+            federation(0, TEST_KEYS.aggregated()),
+            federation(100, other_key()),
+            federation(200, TEST_KEYS.aggregated()),
+        ]);
+        let changes = vec![
+            (0, TEST_KEYS.aggregated()),
+            (100, TEST_KEYS.aggregated()),
+            (150, other_key()),
+        ];
+        assert_eq!(
+            compare(&federations, &changes, 300),
+            vec![
+                Divergence::AggregatedPublicKeyMismatch {
+                    block_height: 100,
+                    federation: other_key(),
+                    chain: TEST_KEYS.aggregated(),
+                },
+                Divergence::MissingFederation {
+                    block_height: 150,
+                    aggregated_public_key: other_key(),
+                },
+                Divergence::NotOnChain {
+                    block_height: 200,
+                    aggregated_public_key: TEST_KEYS.aggregated(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_federations() {
+        let genesis = create_genesis_block(
+            &TEST_KEYS.aggregated(),
+            &address(&TEST_KEYS.key[0]),
+            1585197531,
+        );
+        let chain = MockChain::new(genesis);
+
+        let federations = Federations::new(vec![federation(0, TEST_KEYS.aggregated())]);
+        assert!(check_federations(&chain, &federations).unwrap().is_empty());
+
+        let federations = Federations::new(vec![
+            federation(0, TEST_KEYS.aggregated()),
+            federation(1, other_key()),
+        ]);
+        assert_eq!(
+            check_federations(&chain, &federations, None).unwrap(),
+            vec![Divergence::NotOnChain {
+                block_height: 1,
+                aggregated_public_key: other_key(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_walk_start() {
+        let federations = Federations::new(vec![federation(0, TEST_KEYS.aggregated())]);
+        assert_eq!(walk_start(&federations), 0);
+        let federations = Federations::new(vec![
+            federation(200, TEST_KEYS.aggregated()),
+            federation(100, other_key()),
+        ]);
+        assert_eq!(walk_start(&federations), 99);
+    }
+
+    #[test]
+    fn test_check_federations_with_cache() {
+        let genesis = create_genesis_block(
+            &TEST_KEYS.aggregated(),
+            &address(&TEST_KEYS.key[0]),
+            1585197531,
+        );
+        let chain = MockChain::new(genesis);
+        let federations = Federations::new(vec![federation(0, TEST_KEYS.aggregated())]);
+        let path = std::env::temp_dir().join(format!(
+            "federationcheck_test_check_federations_with_cache_{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        assert!(check_federations(&chain, &federations, Some(&path))
+            .unwrap()
+            .is_empty());
+        let checked = CheckedHeaders::load(&path).unwrap();
+        assert_eq!(checked.height, 0);
+        assert_eq!(checked.changes, vec![(0, TEST_KEYS.aggregated())]);
+
+        // The headers in the cache are not walked again.
+        let mut cached = checked.clone();
+        cached.changes = vec![(0, other_key())];
+        cached.save(&path).unwrap();
+        assert_eq!(
+            check_federations(&chain, &federations, Some(&path)).unwrap(),
+            vec![Divergence::AggregatedPublicKeyMismatch {
+                block_height: 0,
+                federation: TEST_KEYS.aggregated(),
+                chain: other_key(),
+            }]
+        );
+
+        // The cache of the other chain is not used.
+        cached.hash = BlockHash::from_hex(&"00".repeat(32)).unwrap();
+        cached.save(&path).unwrap();
+        let result = check_federations(&chain, &federations, Some(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.unwrap().is_empty());
+    }
+}
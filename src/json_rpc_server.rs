@@ -0,0 +1,103 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Minimal JSON-RPC over HTTP, which serves one request per connection with optional basic
+//! authentication. It is shared by the admin RPC of the signer and the mock of tapyrus-core.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+/// Reads a request from the stream and writes the response of the handler, which is called with
+/// the method and the params of the request. `authorization` is the expected value of the
+/// Authorization header. No authentication if it is None.
+pub fn serve_connection<F>(
+    stream: TcpStream,
+    authorization: &Option<String>,
+    handler: F,
+) -> std::io::Result<()>
+where
+    F: Fn(&str, &[Value]) -> Result<Value, Value>,
+{
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut content_length = 0;
+    let mut given_authorization = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(index) = line.find(':') {
+            let (name, value) = line.split_at(index);
+            let value = value[1..].trim();
+            match name.to_lowercase().as_str() {
+                "content-length" => content_length = value.parse().unwrap_or(0),
+                "authorization" => given_authorization = Some(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body)?;
+
+    if authorization.is_some() && *authorization != given_authorization {
+        return write_response(stream, "401 Unauthorized", "");
+    }
+    let (status, reply) = match serde_json::from_slice::<Value>(&body) {
+        Ok(request) => handle_request(&request, handler),
+        Err(_) => (
+            "500 Internal Server Error",
+            response(&Value::Null, Err(rpc_error(-32700, "Parse error"))),
+        ),
+    };
+    write_response(stream, status, &reply.to_string())
+}
+
+fn write_response(mut stream: TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Like tapyrus-core, it responds an error with the status 500.
+fn handle_request<F>(request: &Value, handler: F) -> (&'static str, Value)
+where
+    F: Fn(&str, &[Value]) -> Result<Value, Value>,
+{
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = match request.get("params") {
+        Some(Value::Array(params)) => params.clone(),
+        _ => vec![],
+    };
+    log::debug!("RPC request: method={}, params={:?}", method, params);
+
+    let result = handler(method, &params);
+    let status = match result {
+        Ok(_) => "200 OK",
+        Err(_) => "500 Internal Server Error",
+    };
+    (status, response(&id, result))
+}
+
+fn response(id: &Value, result: Result<Value, Value>) -> Value {
+    match result {
+        Ok(result) => json!({ "result": result, "error": null, "id": id }),
+        Err(error) => json!({ "result": null, "error": error, "id": id }),
+    }
+}
+
+/// Returns a JSON-RPC error object.
+pub fn rpc_error(code: i32, message: &str) -> Value {
+    json!({ "code": code, "message": message })
+}
@@ -18,17 +18,26 @@ extern crate lazy_static;
 extern crate derive_builder;
 extern crate sha2;
 
+pub mod admin;
 pub mod cli;
 pub mod command_args;
 pub mod crypto;
 pub mod errors;
+pub mod failover;
 pub mod federation;
+pub mod federation_check;
+pub mod json_rpc_server;
 pub mod key;
+pub mod middleware;
 pub mod net;
+pub mod notification;
+pub mod rng;
 pub mod rpc;
 pub mod serialize;
 pub mod sign;
 pub mod signer_node;
+#[cfg(any(test, feature = "simulation"))]
+pub mod simulation;
 pub mod timer;
 pub mod util;
 
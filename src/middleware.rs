@@ -0,0 +1,534 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Middleware layers around `ConnectionManager`.
+//!
+//! A `Layer` sees every message the node sends (outbound) and receives (inbound), and can inspect,
+//! transform or drop it. `Layered` wraps any `ConnectionManager` with layers, so cross-cutting
+//! concerns don't need changes in the transport like `RedisManager`.
+//!
+//! Messages are not authenticated, so `sender_id` is just what the sender claims. The layers which
+//! look at it, like `AllowlistLayer` and `RateLimitLayer`, are filters against misconfigured or
+//! noisy nodes, not authentication.
+
+use crate::errors::Error;
+use crate::federation::Federations;
+use crate::net::{ConnectionManager, ConnectionManagerError, Message, SignerID};
+use crate::rng::SimRng;
+use redis::ControlFlow;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Hooks for the messages passing through `Layered`.
+pub trait Layer: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Called for the message which the node sends. Returns None to drop it.
+    fn outbound(&self, message: Message) -> Option<Message> {
+        Some(message)
+    }
+
+    /// Called for the message which the node receives. Returns None to drop it.
+    fn inbound(&self, message: Message) -> Option<Message> {
+        Some(message)
+    }
+}
+
+/// ConnectionManager which passes messages through the layers. Outbound messages go through the
+/// layers in the order they were added, and inbound messages go in the reverse order.
+pub struct Layered<C: ConnectionManager> {
+    inner: C,
+    layers: Arc<Vec<Box<dyn Layer>>>,
+}
+
+impl<C: ConnectionManager> Layered<C> {
+    pub fn new(inner: C, layers: Vec<Box<dyn Layer>>) -> Self {
+        Layered {
+            inner,
+            layers: Arc::new(layers),
+        }
+    }
+
+    fn outbound(&self, message: Message) -> Option<Message> {
+        self.layers
+            .iter()
+            .try_fold(message, |message, layer| layer.outbound(message))
+    }
+}
+
+fn inbound(layers: &[Box<dyn Layer>], message: Message) -> Option<Message> {
+    layers
+        .iter()
+        .rev()
+        .try_fold(message, |message, layer| layer.inbound(message))
+}
+
+impl<C: ConnectionManager> ConnectionManager for Layered<C> {
+    type ERROR = C::ERROR;
+
+    fn broadcast_message(&self, message: Message) {
+        if let Some(message) = self.outbound(message) {
+            self.inner.broadcast_message(message);
+        }
+    }
+
+    fn send_message(&self, message: Message) {
+        if let Some(message) = self.outbound(message) {
+            self.inner.send_message(message);
+        }
+    }
+
+    fn start(
+        &self,
+        mut message_processor: impl FnMut(Message) -> ControlFlow<()> + Send + 'static,
+        id: SignerID,
+    ) -> JoinHandle<()> {
+        let layers = Arc::clone(&self.layers);
+        self.inner.start(
+            move |message| match inbound(&layers, message) {
+                Some(message) => message_processor(message),
+                None => ControlFlow::Continue,
+            },
+            id,
+        )
+    }
+
+    fn test_connection(&self) -> Result<(), Error> {
+        self.inner.test_connection()
+    }
+
+    fn take_error(
+        &mut self,
+    ) -> Result<ConnectionManagerError<Self::ERROR>, std::sync::mpsc::TryRecvError> {
+        self.inner.take_error()
+    }
+}
+
+/// Logs every message.
+pub struct LoggingLayer;
+
+impl Layer for LoggingLayer {
+    fn name(&self) -> &'static str {
+        "logging"
+    }
+
+    fn outbound(&self, message: Message) -> Option<Message> {
+        log::info!(
+            "Send {} message to {:?}",
+            message.message_type,
+            message.receiver_id
+        );
+        Some(message)
+    }
+
+    fn inbound(&self, message: Message) -> Option<Message> {
+        log::info!(
+            "Receive {} message from {}",
+            message.message_type,
+            message.sender_id
+        );
+        Some(message)
+    }
+}
+
+/// Counts messages for each direction and message type, and logs the counts periodically.
+pub struct MetricsLayer {
+    counts: Mutex<BTreeMap<String, u64>>,
+    report_interval: Duration,
+    last_report: Mutex<Instant>,
+}
+
+impl MetricsLayer {
+    pub fn new(report_interval: Duration) -> Self {
+        MetricsLayer {
+            counts: Mutex::new(BTreeMap::new()),
+            report_interval,
+            last_report: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Returns the count of messages for each key like "inbound.Blockvss".
+    pub fn counts(&self) -> BTreeMap<String, u64> {
+        self.counts.lock().unwrap().clone()
+    }
+
+    fn count(&self, direction: &str, message: &Message) {
+        let key = format!("{}.{}", direction, message.message_type);
+        *self.counts.lock().unwrap().entry(key).or_insert(0) += 1;
+
+        let mut last_report = self.last_report.lock().unwrap();
+        if last_report.elapsed() >= self.report_interval {
+            *last_report = Instant::now();
+            log::info!("Message counts: {:?}", self.counts());
+        }
+    }
+}
+
+impl Layer for MetricsLayer {
+    fn name(&self) -> &'static str {
+        "metrics"
+    }
+
+    fn outbound(&self, message: Message) -> Option<Message> {
+        self.count("outbound", &message);
+        Some(message)
+    }
+
+    fn inbound(&self, message: Message) -> Option<Message> {
+        self.count("inbound", &message);
+        Some(message)
+    }
+}
+
+/// Drops inbound messages whose `sender_id` is not a signer of any federation. It trusts the
+/// claimed `sender_id`, so it filters out misconfigured nodes but doesn't authenticate senders.
+pub struct AllowlistLayer {
+    signers: HashSet<SignerID>,
+}
+
+impl AllowlistLayer {
+    pub fn new(signers: HashSet<SignerID>) -> Self {
+        AllowlistLayer { signers }
+    }
+
+    /// Allows the signers of the federations which the node is a member of.
+    pub fn from_federations(federations: &Federations) -> Self {
+        let signers = federations
+            .iter()
+            .filter(|federation| federation.is_member())
+            .flat_map(|federation| federation.signers())
+            .collect();
+        AllowlistLayer::new(signers)
+    }
+}
+
+impl Layer for AllowlistLayer {
+    fn name(&self) -> &'static str {
+        "allowlist"
+    }
+
+    fn inbound(&self, message: Message) -> Option<Message> {
+        if self.signers.contains(&message.sender_id) {
+            Some(message)
+        } else {
+            log::warn!(
+                "Drop {} message from {}, which is not a signer.",
+                message.message_type,
+                message.sender_id
+            );
+            None
+        }
+    }
+}
+
+/// The max number of senders whose windows `RateLimitLayer` keeps.
+const MAX_RATE_LIMIT_WINDOWS: usize = 1024;
+
+/// Drops inbound messages from a sender who sends more than the limit per second. Senders are
+/// identified by the claimed `sender_id`, so a node can consume the limit of another signer by
+/// spoofing its ID. Put `AllowlistLayer` together so that unknown IDs don't get windows.
+pub struct RateLimitLayer {
+    limit: u32,
+    windows: Mutex<BTreeMap<SignerID, (Instant, u32)>>,
+}
+
+impl RateLimitLayer {
+    pub fn new(limit: u32) -> Self {
+        RateLimitLayer {
+            limit,
+            windows: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl Layer for RateLimitLayer {
+    fn name(&self) -> &'static str {
+        "rate-limit"
+    }
+
+    fn inbound(&self, message: Message) -> Option<Message> {
+        let mut windows = self.windows.lock().unwrap();
+        let now = Instant::now();
+        if !windows.contains_key(&message.sender_id) && windows.len() >= MAX_RATE_LIMIT_WINDOWS {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < Duration::from_secs(1));
+            if windows.len() >= MAX_RATE_LIMIT_WINDOWS {
+                log::warn!(
+                    "Drop {} message from {}, because too many senders sent messages.",
+                    message.message_type,
+                    message.sender_id
+                );
+                return None;
+            }
+        }
+        let window = windows.entry(message.sender_id).or_insert((now, 0));
+        if now.duration_since(window.0) >= Duration::from_secs(1) {
+            *window = (now, 0);
+        }
+        window.1 += 1;
+        if window.1 > self.limit {
+            log::warn!(
+                "Drop {} message from {}, which exceeds the rate limit.",
+                message.message_type,
+                message.sender_id
+            );
+            return None;
+        }
+        Some(message)
+    }
+}
+
+/// Drops messages at random in both directions. It is for testing the network in trouble.
+pub struct FaultInjectionLayer {
+    drop_rate: f64,
+    rng: Mutex<SimRng>,
+}
+
+impl FaultInjectionLayer {
+    pub fn new(drop_rate: f64, seed: u64) -> Self {
+        FaultInjectionLayer {
+            drop_rate,
+            rng: Mutex::new(SimRng::new(seed)),
+        }
+    }
+
+    fn pass(&self, message: Message) -> Option<Message> {
+        if self.rng.lock().unwrap().gen_bool(self.drop_rate) {
+            log::debug!("Drop {} message by fault injection.", message.message_type);
+            None
+        } else {
+            Some(message)
+        }
+    }
+}
+
+impl Layer for FaultInjectionLayer {
+    fn name(&self) -> &'static str {
+        "fault-injection"
+    }
+
+    fn outbound(&self, message: Message) -> Option<Message> {
+        self.pass(message)
+    }
+
+    fn inbound(&self, message: Message) -> Option<Message> {
+        self.pass(message)
+    }
+}
+
+/// Settings of the built-in layers.
+#[derive(Clone, Debug)]
+pub struct LayerSettings {
+    pub metrics_interval: Duration,
+    pub rate_limit: u32,
+    pub drop_rate: f64,
+    pub seed: u64,
+}
+
+/// Creates the built-in layers by their names in the order. Only `allowlist` is moved to the end,
+/// which is the nearest to the transport, so that it drops inbound messages from unknown senders
+/// before any other layer sees them.
+pub fn build_layers(
+    names: &[String],
+    settings: &LayerSettings,
+    federations: &Federations,
+) -> Result<Vec<Box<dyn Layer>>, Error> {
+    let mut layers = names
+        .iter()
+        .map(|name| -> Result<Box<dyn Layer>, Error> {
+            match name.as_str() {
+                "logging" => Ok(Box::new(LoggingLayer)),
+                "metrics" => Ok(Box::new(MetricsLayer::new(settings.metrics_interval))),
+                "allowlist" => Ok(Box::new(AllowlistLayer::from_federations(federations))),
+                "rate-limit" => Ok(Box::new(RateLimitLayer::new(settings.rate_limit))),
+                "fault-injection" => Ok(Box::new(FaultInjectionLayer::new(
+                    settings.drop_rate,
+                    settings.seed,
+                ))),
+                _ => Err(Error::InvalidArgs(format!(
+                    "unknown middleware layer: {}",
+                    name
+                ))),
+            }
+        })
+        .collect::<Result<Vec<Box<dyn Layer>>, Error>>()?;
+    layers.sort_by_key(|layer| layer.name() == "allowlist");
+    Ok(layers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::generate_key_pair;
+    use crate::net::MessageType;
+    use crate::tests::helper::blocks::get_block;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::net::TestConnectionManager;
+
+    fn message(sender: usize) -> Message {
+        Message {
+            message_type: MessageType::Completedblock(get_block(0)),
+            sender_id: SignerID::new(TEST_KEYS.pubkeys()[sender]),
+            receiver_id: None,
+        }
+    }
+
+    /// Drops messages whose sender is the signer.
+    struct DropSender(SignerID);
+
+    impl Layer for DropSender {
+        fn name(&self) -> &'static str {
+            "drop-sender"
+        }
+
+        fn outbound(&self, message: Message) -> Option<Message> {
+            self.inbound(message)
+        }
+
+        fn inbound(&self, message: Message) -> Option<Message> {
+            if message.sender_id == self.0 {
+                None
+            } else {
+                Some(message)
+            }
+        }
+    }
+
+    #[test]
+    fn test_outbound() {
+        let mut inner = TestConnectionManager::new();
+        inner.should_broadcast(message(1));
+        let layered = Layered::new(
+            inner,
+            vec![Box::new(DropSender(SignerID::new(TEST_KEYS.pubkeys()[0])))],
+        );
+        layered.broadcast_message(message(0));
+        layered.broadcast_message(message(1));
+        layered.inner.assert();
+    }
+
+    #[test]
+    fn test_inbound_in_reverse_order() {
+        /// Appends its name to the trace.
+        struct Trace(&'static str, Arc<Mutex<Vec<&'static str>>>);
+
+        impl Layer for Trace {
+            fn name(&self) -> &'static str {
+                self.0
+            }
+
+            fn outbound(&self, message: Message) -> Option<Message> {
+                self.1.lock().unwrap().push(self.0);
+                Some(message)
+            }
+
+            fn inbound(&self, message: Message) -> Option<Message> {
+                self.outbound(message)
+            }
+        }
+
+        let trace = Arc::new(Mutex::new(vec![]));
+        let layered = Layered::new(
+            TestConnectionManager::new(),
+            vec![
+                Box::new(Trace("first", Arc::clone(&trace))),
+                Box::new(Trace("second", Arc::clone(&trace))),
+            ],
+        );
+        layered.send_message(message(0));
+        assert_eq!(*trace.lock().unwrap(), vec!["first", "second"]);
+
+        trace.lock().unwrap().clear();
+        assert!(inbound(&layered.layers, message(0)).is_some());
+        assert_eq!(*trace.lock().unwrap(), vec!["second", "first"]);
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let layer = AllowlistLayer::new(
+            vec![SignerID::new(TEST_KEYS.pubkeys()[0])]
+                .into_iter()
+                .collect(),
+        );
+        assert!(layer.inbound(message(0)).is_some());
+        assert!(layer.inbound(message(1)).is_none());
+        // Only inbound messages are checked.
+        assert!(layer.outbound(message(1)).is_some());
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let layer = RateLimitLayer::new(2);
+        assert!(layer.inbound(message(0)).is_some());
+        assert!(layer.inbound(message(0)).is_some());
+        assert!(layer.inbound(message(0)).is_none());
+        // The limit is for each sender.
+        assert!(layer.inbound(message(1)).is_some());
+    }
+
+    #[test]
+    fn test_rate_limit_windows_are_bounded() {
+        let layer = RateLimitLayer::new(2);
+        let mut windows = layer.windows.lock().unwrap();
+        for _ in 0..MAX_RATE_LIMIT_WINDOWS {
+            let (_, public_key) = generate_key_pair();
+            windows.insert(SignerID::new(public_key), (Instant::now(), 0));
+        }
+        drop(windows);
+
+        // A new sender doesn't get a window while all windows are active.
+        assert!(layer.inbound(message(0)).is_none());
+        assert_eq!(layer.windows.lock().unwrap().len(), MAX_RATE_LIMIT_WINDOWS);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let layer = MetricsLayer::new(Duration::from_secs(60));
+        layer.inbound(message(0));
+        layer.inbound(message(1));
+        layer.outbound(message(0));
+        let counts = layer.counts();
+        assert_eq!(counts["inbound.Completedblock"], 2);
+        assert_eq!(counts["outbound.Completedblock"], 1);
+    }
+
+    #[test]
+    fn test_fault_injection() {
+        let layer = FaultInjectionLayer::new(1.0, 0);
+        assert!(layer.inbound(message(0)).is_none());
+        let layer = FaultInjectionLayer::new(0.0, 0);
+        assert!(layer.outbound(message(0)).is_some());
+    }
+
+    #[test]
+    fn test_build_layers() {
+        let settings = LayerSettings {
+            metrics_interval: Duration::from_secs(60),
+            rate_limit: 10,
+            drop_rate: 0.0,
+            seed: 0,
+        };
+        let federations = Federations::new(vec![]);
+        let names = vec!["logging".to_string(), "rate-limit".to_string()];
+        let layers = build_layers(&names, &settings, &federations).unwrap();
+        let names: Vec<&str> = layers.iter().map(|layer| layer.name()).collect();
+        assert_eq!(names, vec!["logging", "rate-limit"]);
+
+        // The allowlist checks inbound messages before the other layers.
+        let names = vec![
+            "metrics".to_string(),
+            "allowlist".to_string(),
+            "rate-limit".to_string(),
+        ];
+        let layers = build_layers(&names, &settings, &federations).unwrap();
+        let names: Vec<&str> = layers.iter().map(|layer| layer.name()).collect();
+        assert_eq!(names, vec!["metrics", "rate-limit", "allowlist"]);
+
+        match build_layers(&["unknown".to_string()], &settings, &federations) {
+            Err(Error::InvalidArgs(m)) => assert_eq!(m, "unknown middleware layer: unknown"),
+            _ => panic!("should be an error"),
+        }
+    }
+}
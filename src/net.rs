@@ -2,8 +2,11 @@
 // Distributed under the MIT software license, see the accompanying
 // file COPYING or http://www.opensource.org/licenses/mit-license.php.
 
+use crate::crypto::frost::NonceCommitment;
 use crate::errors;
 use crate::serialize::{ByteBufVisitor, HexStrVisitor};
+use crate::signer_node::equivocation::CandidateDigest;
+use crate::signer_node::master_signature::MasterSignature;
 use redis::{Client, Commands, ControlFlow, PubSubCommands, RedisError};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::cmp::Ordering;
@@ -22,7 +25,7 @@ use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
 use curv::FE;
 use serde::export::fmt::Error;
 use serde::export::Formatter;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::sync::mpsc::TryRecvError;
 use tapyrus::blockdata::block::Block;
 use tapyrus::hash_types::BlockSigHash;
@@ -105,6 +108,45 @@ pub enum MessageType {
     Blockvss(BlockSigHash, VerifiableSS, FE, VerifiableSS, FE),
     Blockparticipants(BlockSigHash, HashSet<SignerID>),
     Blocksig(BlockSigHash, FE, FE),
+    /// Block VSSs for the round of the block height and the master, which are sent before the
+    /// candidate block is proposed.
+    Preblockvss(u64, SignerID, VerifiableSS, FE, VerifiableSS, FE),
+    /// Nonce commitments which the sender prepared for the FROST signing scheme.
+    Noncecommitments(Vec<NonceCommitment>),
+    /// Candidate block and nonce commitments of the participants for the FROST signing scheme,
+    /// and the signature of the master over the candidate block if the master signs blocks.
+    Signingpackage(
+        Block,
+        BTreeMap<SignerID, NonceCommitment>,
+        Option<MasterSignature>,
+    ),
+    /// Digest of the candidate block which the sender received from the master, with the
+    /// signature of the master.
+    Candidatedigest(CandidateDigest),
+    /// Candidate block and the signature of the master over it. The master which has the private
+    /// key sends it instead of candidateblock.
+    Signedcandidateblock(Block, MasterSignature),
+    /// Completed block and the signature of the master over it. The master which has the private
+    /// key sends it instead of completedblock.
+    Signedcompletedblock(Block, MasterSignature),
+}
+
+impl MessageType {
+    /// Returns candidateblock message, or signedcandidateblock message with the signature.
+    pub fn candidateblock(block: Block, signature: Option<MasterSignature>) -> Self {
+        match signature {
+            Some(signature) => MessageType::Signedcandidateblock(block, signature),
+            None => MessageType::Candidateblock(block),
+        }
+    }
+
+    /// Returns completedblock message, or signedcompletedblock message with the signature.
+    pub fn completedblock(block: Block, signature: Option<MasterSignature>) -> Self {
+        match signature {
+            Some(signature) => MessageType::Signedcompletedblock(block, signature),
+            None => MessageType::Completedblock(block),
+        }
+    }
 }
 
 impl Display for MessageType {
@@ -115,6 +157,12 @@ impl Display for MessageType {
             MessageType::Blockvss(_, _, _, _, _) => write!(f, "Blockvss"),
             MessageType::Blockparticipants(_, _) => write!(f, "Blockparticipants"),
             MessageType::Blocksig(_, _, _) => write!(f, "Blocksig"),
+            MessageType::Preblockvss(_, _, _, _, _, _) => write!(f, "Preblockvss"),
+            MessageType::Noncecommitments(_) => write!(f, "Noncecommitments"),
+            MessageType::Signingpackage(_, _, _) => write!(f, "Signingpackage"),
+            MessageType::Candidatedigest(_) => write!(f, "Candidatedigest"),
+            MessageType::Signedcandidateblock(_, _) => write!(f, "Signedcandidateblock"),
+            MessageType::Signedcompletedblock(_, _) => write!(f, "Signedcompletedblock"),
         }
     }
 }
@@ -365,7 +413,8 @@ impl ConnectionManager for RedisManager {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::tests::helper::blocks::get_block;
+    use crate::signer_node::master_signature::SignedBlock;
+    use crate::tests::helper::blocks::{get_block, master_sign};
     use crate::tests::helper::keys::TEST_KEYS;
     use std::collections::BTreeMap;
     use std::str::FromStr;
@@ -448,6 +497,33 @@ mod test {
         assert_eq!(expected, signer_id);
     }
 
+    #[test]
+    fn test_block_messages() {
+        // A master without the private key sends the message in the format of older signers.
+        let block = get_block(0);
+        let message = MessageType::candidateblock(block.clone(), None);
+        assert_eq!(message, MessageType::Candidateblock(block.clone()));
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({ "Candidateblock": serde_json::to_value(&block).unwrap() })
+        );
+
+        let signature = master_sign(SignedBlock::Candidate, 0, &block, 4);
+        assert_eq!(
+            MessageType::candidateblock(block.clone(), Some(signature.clone())),
+            MessageType::Signedcandidateblock(block.clone(), signature)
+        );
+        let signature = master_sign(SignedBlock::Completed, 0, &block, 4);
+        assert_eq!(
+            MessageType::completedblock(block.clone(), None),
+            MessageType::Completedblock(block.clone())
+        );
+        assert_eq!(
+            MessageType::completedblock(block.clone(), Some(signature.clone())),
+            MessageType::Signedcompletedblock(block, signature)
+        );
+    }
+
     #[test]
     fn test_sort_signer_id() {
         let alice = SignerID::new(
@@ -0,0 +1,236 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Subscriber of the `hashblock` notifications which tapyrus-core publishes over ZMQ when it is
+//! started with `-zmqpubhashblock`.
+//!
+//! It speaks the subset of ZMTP 3.0 which a SUB socket needs with the NULL security mechanism,
+//! so the signer doesn't depend on libzmq.
+
+use bitcoin_hashes::Hash;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+use tapyrus::hash_types::BlockHash;
+
+pub const HASHBLOCK_TOPIC: &[u8] = b"hashblock";
+
+/// Wait before connecting again when the connection is lost.
+pub const RECONNECT_INTERVAL_SECS: u64 = 5;
+
+/// Read timeout of the connection. The subscriber connects again when nothing comes for this
+/// long, so a half-open connection doesn't stop the notifications forever.
+pub const READ_TIMEOUT_SECS: u64 = 600;
+
+/// Upper bound of the body of a frame. Notifications and READY commands are far smaller.
+const MAX_FRAME_SIZE: u64 = 1024;
+
+const FLAG_MORE: u8 = 0x01;
+const FLAG_LONG: u8 = 0x02;
+const FLAG_COMMAND: u8 = 0x04;
+
+/// A frame of ZMTP.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Frame {
+    pub more: bool,
+    pub command: bool,
+    pub body: Vec<u8>,
+}
+
+pub(crate) fn write_frame(
+    stream: &mut impl Write,
+    body: &[u8],
+    more: bool,
+    command: bool,
+) -> std::io::Result<()> {
+    let mut flags = 0;
+    if more {
+        flags |= FLAG_MORE;
+    }
+    if command {
+        flags |= FLAG_COMMAND;
+    }
+    let mut frame = vec![];
+    if body.len() > 255 {
+        frame.push(flags | FLAG_LONG);
+        frame.extend_from_slice(&(body.len() as u64).to_be_bytes());
+    } else {
+        frame.push(flags);
+        frame.push(body.len() as u8);
+    }
+    frame.extend_from_slice(body);
+    stream.write_all(&frame)
+}
+
+pub(crate) fn read_frame(stream: &mut impl Read) -> std::io::Result<Frame> {
+    let mut flags = [0u8; 1];
+    stream.read_exact(&mut flags)?;
+    let flags = flags[0];
+    let size = if flags & FLAG_LONG != 0 {
+        let mut size = [0u8; 8];
+        stream.read_exact(&mut size)?;
+        u64::from_be_bytes(size)
+    } else {
+        let mut size = [0u8; 1];
+        stream.read_exact(&mut size)?;
+        size[0] as u64
+    };
+    if size > MAX_FRAME_SIZE {
+        return Err(invalid_data("frame is too large"));
+    }
+    let mut body = vec![0u8; size as usize];
+    stream.read_exact(&mut body)?;
+    Ok(Frame {
+        more: flags & FLAG_MORE != 0,
+        command: flags & FLAG_COMMAND != 0,
+        body,
+    })
+}
+
+/// Exchanges the greetings and the READY commands with the peer.
+pub(crate) fn handshake<S: Read + Write>(stream: &mut S, socket_type: &str) -> std::io::Result<()> {
+    let mut greeting = [0u8; 64];
+    greeting[0] = 0xff;
+    greeting[9] = 0x7f;
+    greeting[10] = 3;
+    greeting[12..16].copy_from_slice(b"NULL");
+    stream.write_all(&greeting)?;
+
+    let mut peer = [0u8; 64];
+    stream.read_exact(&mut peer)?;
+    if peer[0] != 0xff || peer[9] != 0x7f || peer[10] < 3 || &peer[12..16] != b"NULL" {
+        return Err(invalid_data("unsupported ZMTP greeting"));
+    }
+
+    let mut ready = vec![5u8];
+    ready.extend_from_slice(b"READY");
+    ready.push(11);
+    ready.extend_from_slice(b"Socket-Type");
+    ready.extend_from_slice(&(socket_type.len() as u32).to_be_bytes());
+    ready.extend_from_slice(socket_type.as_bytes());
+    write_frame(stream, &ready, false, true)?;
+
+    let frame = read_frame(stream)?;
+    if !frame.command || !frame.body.starts_with(b"\x05READY") {
+        return Err(invalid_data("peer is not ready"));
+    }
+    Ok(())
+}
+
+/// Reads frames until the last part of a message. Commands between messages are ignored.
+pub(crate) fn read_message(stream: &mut impl Read) -> std::io::Result<Vec<Vec<u8>>> {
+    let mut parts = vec![];
+    loop {
+        let frame = read_frame(stream)?;
+        if frame.command {
+            continue;
+        }
+        parts.push(frame.body);
+        if !frame.more {
+            return Ok(parts);
+        }
+    }
+}
+
+fn invalid_data(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Connects to the endpoint like "tcp://127.0.0.1:28332" and subscribes `hashblock`. The hashes
+/// of new tip blocks are sent to the returned receiver. The subscriber connects again when the
+/// connection is lost, and stops when the receiver is dropped.
+pub fn subscribe_hashblock(endpoint: &str) -> Receiver<BlockHash> {
+    let address = endpoint.trim_start_matches("tcp://").to_string();
+    let (sender, receiver) = channel();
+    thread::Builder::new()
+        .name("HashblockSubscriberThread".to_string())
+        .spawn(move || loop {
+            match receive_hashblocks(&address, &sender) {
+                Ok(()) => return,
+                Err(e) => log::warn!(
+                    "Lost the ZMQ connection to {}: {}. Reconnect in {} secs.",
+                    address,
+                    e,
+                    RECONNECT_INTERVAL_SECS
+                ),
+            }
+            thread::sleep(Duration::from_secs(RECONNECT_INTERVAL_SECS));
+        })
+        .expect("Failed to spawn the hashblock subscriber thread");
+    receiver
+}
+
+/// Returns Ok when the receiver is dropped.
+fn receive_hashblocks(address: &str, sender: &Sender<BlockHash>) -> std::io::Result<()> {
+    let mut stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(Duration::from_secs(READ_TIMEOUT_SECS)))?;
+    handshake(&mut stream, "SUB")?;
+    let mut subscription = vec![1u8];
+    subscription.extend_from_slice(HASHBLOCK_TOPIC);
+    write_frame(&mut stream, &subscription, false, false)?;
+    log::info!("Subscribed hashblock notifications from {}", address);
+
+    loop {
+        let parts = read_message(&mut stream)?;
+        match parts.as_slice() {
+            [topic, hash, ..] if topic.as_slice() == HASHBLOCK_TOPIC && hash.len() == 32 => {
+                // tapyrus-core publishes the hash in the reversed byte order.
+                let mut hash = hash.clone();
+                hash.reverse();
+                let hash = BlockHash::from_slice(&hash).expect("hash should be 32 bytes");
+                log::debug!("Got a new tip notification: {}", hash);
+                if sender.send(hash).is_err() {
+                    return Ok(());
+                }
+            }
+            _ => log::debug!("Ignore an unknown ZMQ message: {:?}", parts),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::zmq_publisher::ZmqPublisher;
+    use crate::tests::helper::blocks::get_block;
+    use std::time::Instant;
+
+    #[test]
+    fn test_subscribe_hashblock() {
+        let publisher = ZmqPublisher::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("tcp://{}", publisher.local_addr().unwrap());
+        let receiver = subscribe_hashblock(&endpoint);
+
+        let started = Instant::now();
+        while publisher.subscribers() == 0 {
+            assert!(started.elapsed() < Duration::from_secs(5), "not subscribed");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let hash = get_block(0).block_hash();
+        publisher.publish_hashblock(&hash);
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_read_frame_too_large() {
+        let mut frame = vec![FLAG_LONG];
+        frame.extend_from_slice(&(MAX_FRAME_SIZE + 1).to_be_bytes());
+        let error = read_frame(&mut frame.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut frame = vec![FLAG_LONG];
+        frame.extend_from_slice(&u64::max_value().to_be_bytes());
+        assert!(read_frame(&mut frame.as_slice()).is_err());
+
+        let mut frame = vec![];
+        write_frame(&mut frame, &[1u8; 300], false, false).unwrap();
+        assert_eq!(
+            read_frame(&mut frame.as_slice()).unwrap().body,
+            vec![1u8; 300]
+        );
+    }
+}
@@ -0,0 +1,68 @@
+/// Small pseudo random number generator (SplitMix64) for simulations and fault injection. Unlike
+/// the generators in the crypto libraries, it always gives the same sequence for the same seed.
+/// It must not be used for keys or nonces.
+#[derive(Clone, Debug)]
+pub struct SimRng {
+    state: u64,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        SimRng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `[low, high)`. It returns `low` if the range is empty.
+    pub fn gen_range(&mut self, low: u64, high: u64) -> u64 {
+        if high <= low {
+            return low;
+        }
+        low + self.next_u64() % (high - low)
+    }
+
+    /// Returns true with the probability.
+    pub fn gen_bool(&mut self, probability: f64) -> bool {
+        let sample = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        sample < probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SimRng;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = SimRng::new(42);
+        let mut b = SimRng::new(42);
+        let mut c = SimRng::new(43);
+        let sequence: Vec<u64> = (0..10).map(|_| a.next_u64()).collect();
+        assert_eq!(
+            sequence,
+            (0..10).map(|_| b.next_u64()).collect::<Vec<u64>>()
+        );
+        assert_ne!(
+            sequence,
+            (0..10).map(|_| c.next_u64()).collect::<Vec<u64>>()
+        );
+    }
+
+    #[test]
+    fn test_gen_range_and_bool() {
+        let mut rng = SimRng::new(0);
+        for _ in 0..1000 {
+            let n = rng.gen_range(10, 20);
+            assert!(10 <= n && n < 20);
+        }
+        assert_eq!(rng.gen_range(5, 5), 5);
+        assert!((0..100).all(|_| !rng.gen_bool(0.0)));
+        assert!((0..100).all(|_| rng.gen_bool(1.0)));
+    }
+}
@@ -5,11 +5,20 @@
 use log::Level::Trace;
 use log::{log_enabled, trace};
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tapyrus::Address;
 
 use crate::errors::Error;
-use tapyrus::blockdata::block::Block;
+use tapyrus::blockdata::block::{Block, BlockHeader};
 use tapyrus::consensus::encode::{deserialize, serialize};
+use tapyrus::hash_types::BlockHash;
+use tapyrus::hashes::hex::FromHex;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct GetBlockchainInfoResult {
@@ -21,8 +30,48 @@ pub struct GetBlockchainInfoResult {
     pub initialblockdownload: bool,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct GetMempoolInfoResult {
+    /// The number of transactions in the mempool.
+    pub size: u64,
+    /// The total size of the transactions in the mempool.
+    pub bytes: u64,
+}
+
+/// Default timeout of each RPC call.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Default number of retries for idempotent calls.
+pub const DEFAULT_RETRIES: u32 = 2;
+/// Default wait before the first retry. It is doubled for each retry.
+pub const DEFAULT_RETRY_BACKOFF_MILLIS: u64 = 500;
+
+/// Upper bound of the size of an RPC response, which is large enough for the hex of any block.
+const MAX_RESPONSE_SIZE: usize = 32 * 1024 * 1024;
+
+/// Calls which can be sent again safely when they fail for a timeout or a connection error.
+const IDEMPOTENT_METHODS: [&str; 5] = [
+    "getblockchaininfo",
+    "testproposedblock",
+    "getblockhash",
+    "getblockheader",
+    "getmempoolinfo",
+];
+
+enum Auth {
+    UserPass(Option<String>, Option<String>),
+    /// The `.cookie` file which tapyrus-core writes on start. The content is cached and read
+    /// again when the authentication fails, because tapyrus-core renews it on restart.
+    CookieFile(PathBuf, Mutex<Option<String>>),
+}
+
 pub struct Rpc {
-    client: jsonrpc::client::Client,
+    url: String,
+    auth: Auth,
+    timeout: Duration,
+    method_timeouts: HashMap<String, Duration>,
+    retries: u32,
+    retry_backoff: Duration,
+    nonce: AtomicUsize,
 }
 
 pub trait TapyrusApi {
@@ -34,47 +83,282 @@ pub trait TapyrusApi {
     fn submitblock(&self, block: &Block) -> Result<(), Error>;
     /// Get block chain info
     fn getblockchaininfo(&self) -> Result<GetBlockchainInfoResult, Error>;
+    /// Get the hash of the block at the height in the best chain.
+    fn getblockhash(&self, height: u64) -> Result<BlockHash, Error>;
+    /// Get the header of the block.
+    fn getblockheader(&self, hash: &BlockHash) -> Result<BlockHeader, Error>;
+    /// Get the state of the mempool.
+    fn getmempoolinfo(&self) -> Result<GetMempoolInfoResult, Error>;
 }
 
 impl Rpc {
     pub fn new(url: String, user: Option<String>, pass: Option<String>) -> Self {
         // Check that if we have a password, we have a username; other way around is ok
         debug_assert!(pass.is_none() || user.is_some());
+        Rpc::with_auth(url, Auth::UserPass(user, pass))
+    }
+
+    /// Creates the client authenticated with the cookie file of tapyrus-core.
+    pub fn with_cookie_file(url: String, path: PathBuf) -> Self {
+        Rpc::with_auth(url, Auth::CookieFile(path, Mutex::new(None)))
+    }
+
+    fn with_auth(url: String, auth: Auth) -> Self {
         Rpc {
-            client: jsonrpc::client::Client::new(url, user, pass),
+            url,
+            auth,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            method_timeouts: HashMap::new(),
+            retries: DEFAULT_RETRIES,
+            retry_backoff: Duration::from_millis(DEFAULT_RETRY_BACKOFF_MILLIS),
+            nonce: AtomicUsize::new(0),
         }
     }
 
+    /// Sets the timeout of the calls which don't have their own timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout of the calls of the method.
+    pub fn method_timeout(mut self, method: &str, timeout: Duration) -> Self {
+        self.method_timeouts.insert(method.to_string(), timeout);
+        self
+    }
+
+    /// Sets how many times idempotent calls are retried, and the wait before the first retry.
+    pub fn retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
     fn call<T>(&self, name: &str, params: &[serde_json::Value]) -> Result<T, Error>
     where
         T: serde::de::DeserializeOwned,
     {
-        let req = self.client.build_request(name, params);
-
-        trace!("JSON-RPC request: {}", serde_json::to_string(&req).unwrap());
-
-        match self.client.send_request(&req) {
-            Ok(resp) => {
-                if log_enabled!(Trace) {
-                    trace!(
-                        "JSON-RPC response: {}: {}",
-                        name,
-                        serde_json::to_string(&resp).unwrap()
-                    );
+        let retries = if IDEMPOTENT_METHODS.contains(&name) {
+            self.retries
+        } else {
+            0
+        };
+        let mut attempt = 0;
+        loop {
+            match self.call_once(name, params) {
+                Err(e) if attempt < retries && is_retryable(&e) => {
+                    let wait = self.retry_backoff * 2u32.pow(attempt);
+                    warn!("RPC {} failed: {:?}. Retry after {:?}.", name, e, wait);
+                    std::thread::sleep(wait);
+                    attempt += 1;
                 }
+                result => return result,
+            }
+        }
+    }
 
-                if let Err(jsonrpc::Error::Rpc(e)) = resp.clone().check_error() {
-                    warn!("RPC Error: {:?}", e);
-                    return Err(Error::InvalidRequest(e));
+    fn call_once<T>(&self, name: &str, params: &[serde_json::Value]) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let req = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": self.nonce.fetch_add(1, Ordering::Relaxed),
+            "method": name,
+            "params": params,
+        });
+
+        trace!("JSON-RPC request: {}", req);
+
+        let timeout = self
+            .method_timeouts
+            .get(name)
+            .cloned()
+            .unwrap_or(self.timeout);
+        let body = match self.post(name, &req.to_string(), timeout, false) {
+            Err(Error::RpcAuthFailed) if self.is_cookie_auth() => {
+                // tapyrus-core may have been restarted with a new cookie.
+                self.post(name, &req.to_string(), timeout, true)?
+            }
+            result => result?,
+        };
+        let resp: jsonrpc::Response = serde_json::from_slice(&body)?;
+
+        if log_enabled!(Trace) {
+            trace!(
+                "JSON-RPC response: {}: {}",
+                name,
+                serde_json::to_string(&resp).unwrap()
+            );
+        }
+
+        if let Err(jsonrpc::Error::Rpc(e)) = resp.clone().check_error() {
+            warn!("RPC Error: {:?}", e);
+            return Err(Error::InvalidRequest(e));
+        }
+
+        match resp.result::<T>() {
+            Ok(result) => Ok(result),
+            Err(e) => Err(Error::JsonRpc(e)),
+        }
+    }
+
+    fn is_cookie_auth(&self) -> bool {
+        match self.auth {
+            Auth::CookieFile(..) => true,
+            Auth::UserPass(..) => false,
+        }
+    }
+
+    /// Returns the value of the Authorization header.
+    fn authorization(&self, reload_cookie: bool) -> Result<Option<String>, Error> {
+        let credential = match &self.auth {
+            Auth::UserPass(None, _) => return Ok(None),
+            Auth::UserPass(Some(user), pass) => {
+                format!("{}:{}", user, pass.as_ref().map_or("", |p| p as &str))
+            }
+            Auth::CookieFile(path, cookie) => {
+                let mut cookie = cookie.lock().unwrap();
+                if cookie.is_none() || reload_cookie {
+                    let content = std::fs::read_to_string(path).map_err(Error::RpcCookieFile)?;
+                    *cookie = Some(content.trim().to_string());
                 }
+                cookie.clone().unwrap()
+            }
+        };
+        Ok(Some(format!("Basic {}", base64::encode(&credential))))
+    }
 
-                match resp.result::<T>() {
-                    Ok(result) => Ok(result),
-                    Err(e) => Err(Error::JsonRpc(e)),
+    /// Sends the request over HTTP/1.1 and returns the body of the response. The timeout bounds
+    /// the whole call from connecting to reading the last byte. Only plain `http://` urls and
+    /// responses with `Content-Length` or closed connection are supported.
+    fn post(
+        &self,
+        name: &str,
+        body: &str,
+        timeout: Duration,
+        reload_cookie: bool,
+    ) -> Result<Vec<u8>, Error> {
+        let deadline = Instant::now() + timeout;
+        let authorization = self.authorization(reload_cookie)?;
+        if !self.url.starts_with("http://") && self.url.contains("://") {
+            return Err(Error::InvalidArgs(format!(
+                "unsupported url, only http is supported: {}",
+                self.url
+            )));
+        }
+        let url = self.url.trim_start_matches("http://");
+        let (host, path) = match url.find('/') {
+            Some(index) => url.split_at(index),
+            None => (url, "/"),
+        };
+        let to_error = |e: std::io::Error| match e.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => Error::RpcTimeout(name.to_string()),
+            _ => Error::RpcConnectionFailed(e),
+        };
+        let remaining = || {
+            deadline
+                .checked_duration_since(Instant::now())
+                .filter(|d| *d > Duration::from_millis(0))
+                .ok_or_else(|| Error::RpcTimeout(name.to_string()))
+        };
+
+        let addrs = host.to_socket_addrs().map_err(Error::RpcConnectionFailed)?;
+        let mut stream = None;
+        let mut last_error = None;
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, remaining()?) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        let mut stream = match (stream, last_error) {
+            (Some(stream), _) => stream,
+            (None, Some(e)) => return Err(to_error(e)),
+            (None, None) => return Err(Error::InvalidArgs(format!("invalid url: {}", self.url))),
+        };
+        stream
+            .set_write_timeout(Some(remaining()?))
+            .map_err(to_error)?;
+
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            path,
+            host,
+            body.len()
+        );
+        if let Some(authorization) = authorization {
+            request.push_str(&format!("Authorization: {}\r\n", authorization));
+        }
+        request.push_str("\r\n");
+        request.push_str(body);
+        stream.write_all(request.as_bytes()).map_err(to_error)?;
+
+        let mut response = vec![];
+        let mut buf = [0u8; 8192];
+        loop {
+            stream
+                .set_read_timeout(Some(remaining()?))
+                .map_err(to_error)?;
+            let read = match stream.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(to_error(e)),
+            };
+            if response.len() + read > MAX_RESPONSE_SIZE {
+                return Err(Error::InvalidRpcResponse(format!(
+                    "response exceeds {} bytes",
+                    MAX_RESPONSE_SIZE
+                )));
+            }
+            response.extend_from_slice(&buf[..read]);
+        }
+        let header_end = response
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or_else(|| Error::InvalidRpcResponse("no HTTP header".to_string()))?;
+        let header = String::from_utf8_lossy(&response[..header_end]).to_string();
+        let status = header
+            .split_whitespace()
+            .nth(1)
+            .and_then(|s| s.parse::<u16>().ok())
+            .ok_or_else(|| Error::InvalidRpcResponse(format!("invalid status: {}", header)))?;
+        if status == 401 {
+            return Err(Error::RpcAuthFailed);
+        }
+        let header_value = |key: &str| {
+            header.lines().find_map(|line| {
+                let mut parts = line.splitn(2, ':');
+                match (parts.next(), parts.next()) {
+                    (Some(name), Some(value)) if name.trim().eq_ignore_ascii_case(key) => {
+                        Some(value.trim().to_string())
+                    }
+                    _ => None,
                 }
+            })
+        };
+        if let Some(encoding) = header_value("transfer-encoding") {
+            if encoding.to_ascii_lowercase().contains("chunked") {
+                return Err(Error::InvalidRpcResponse(
+                    "chunked transfer encoding is not supported".to_string(),
+                ));
             }
-            Err(e) => Err(Error::from(e)),
         }
+        let mut body = response.split_off(header_end + 4);
+        let content_length =
+            header_value("content-length").and_then(|value| value.parse::<usize>().ok());
+        if let Some(length) = content_length {
+            body.truncate(length);
+        }
+        // tapyrus-core responds errors of calls with the status 500 and the JSON body.
+        if status != 200 && status != 500 {
+            return Err(Error::InvalidRpcResponse(format!("HTTP status {}", status)));
+        }
+        Ok(body)
     }
 
     pub fn test_connection(&self) -> Result<(), Error> {
@@ -85,6 +369,13 @@ impl Rpc {
     }
 }
 
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::RpcTimeout(_) | Error::RpcConnectionFailed(_) => true,
+        _ => false,
+    }
+}
+
 impl TapyrusApi for Rpc {
     /// Call getnewblock rpc
     fn getnewblock(&self, address: &Address) -> Result<Block, Error> {
@@ -113,20 +404,48 @@ impl TapyrusApi for Rpc {
     fn getblockchaininfo(&self) -> Result<GetBlockchainInfoResult, Error> {
         self.call::<GetBlockchainInfoResult>("getblockchaininfo", &[])
     }
+
+    fn getblockhash(&self, height: u64) -> Result<BlockHash, Error> {
+        let hex = self.call::<String>("getblockhash", &[height.into()])?;
+        BlockHash::from_hex(&hex).map_err(|_| Error::InvalidRpcResponse(hex))
+    }
+
+    fn getblockheader(&self, hash: &BlockHash) -> Result<BlockHeader, Error> {
+        let args = [hash.to_string().into(), false.into()];
+        let hex = self.call::<String>("getblockheader", &args)?;
+        let raw_header = hex::decode(&hex).map_err(|_| Error::InvalidRpcResponse(hex))?;
+        deserialize(&raw_header).map_err(Error::BitcoinConsensusEncodeError)
+    }
+
+    fn getmempoolinfo(&self) -> Result<GetMempoolInfoResult, Error> {
+        self.call::<GetMempoolInfoResult>("getmempoolinfo", &[])
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::cli::setup::create_genesis::create_genesis_block;
+    use crate::simulation::chain::MockChain;
+    use crate::simulation::rpc_server::RpcServer;
     use crate::tests::helper::keys::TEST_KEYS;
+    use std::str::FromStr;
     use tapyrus::secp256k1::Secp256k1;
 
+    /// Starts the stand-in of tapyrus-core, and returns the client connecting to it.
     pub fn get_rpc_client() -> Rpc {
-        Rpc::new(
-            "http://127.0.0.1:12381".to_string(),
+        let address = Address::from_str("1FiFhpYZHCZt33L4hh7C4jM2uKKueAxcH7").unwrap();
+        let genesis = create_genesis_block(&TEST_KEYS.aggregated(), &address, 1585197531);
+        let server = RpcServer::bind(
+            "127.0.0.1:0",
+            MockChain::new(genesis),
             Some("user".to_string()),
             Some("pass".to_string()),
         )
+        .unwrap();
+        let url = format!("http://{}", server.local_addr().unwrap());
+        server.spawn();
+        Rpc::new(url, Some("user".to_string()), Some("pass".to_string()))
     }
 
     pub fn call_getnewblock() -> Result<Block, Error> {
@@ -207,11 +526,21 @@ pub mod tests {
                 initialblockdownload: false,
             })
         }
+
+        fn getblockhash(&self, _height: u64) -> Result<BlockHash, Error> {
+            unimplemented!()
+        }
+
+        fn getblockheader(&self, _hash: &BlockHash) -> Result<BlockHeader, Error> {
+            unimplemented!()
+        }
+
+        fn getmempoolinfo(&self) -> Result<GetMempoolInfoResult, Error> {
+            unimplemented!()
+        }
     }
 
-    /// TODO: use rpc mock. Now this test needs tapyrus node process.
     #[test]
-    #[ignore]
     fn test_getnewblock() {
         let result = call_getnewblock();
         assert!(result.is_ok());
@@ -220,7 +549,6 @@ pub mod tests {
     }
 
     #[test]
-    #[ignore]
     fn test_testproposedblock() {
         let block = call_getnewblock().unwrap();
         let rpc = get_rpc_client();
@@ -229,4 +557,161 @@ pub mod tests {
 
         assert!(result.is_ok());
     }
+    #[test]
+    fn test_getmempoolinfo() {
+        let rpc = get_rpc_client();
+
+        let info = rpc.getmempoolinfo().unwrap();
+
+        assert_eq!(info.size, 0);
+        assert_eq!(info.bytes, 0);
+    }
+
+    /// Starts the server which accepts connections and never responds. Returns the url and the
+    /// number of accepted connections.
+    fn start_hung_server() -> (String, Arc<Mutex<usize>>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let accepted = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&accepted);
+        std::thread::spawn(move || {
+            let mut streams = vec![];
+            for stream in listener.incoming() {
+                *counter.lock().unwrap() += 1;
+                streams.push(stream);
+            }
+        });
+        (url, accepted)
+    }
+
+    /// Starts the server which reads a request and passes the connection to `respond`.
+    fn start_server(respond: fn(std::net::TcpStream)) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                std::thread::spawn(move || respond(stream));
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_timeout_bounds_whole_response() {
+        // The server sends a byte at intervals shorter than the timeout and never finishes.
+        let url = start_server(|mut stream| {
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\n");
+            while stream.write_all(b"X").is_ok() {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        });
+        let rpc = Rpc::new(url, None, None)
+            .timeout(Duration::from_millis(200))
+            .retries(0, Duration::from_millis(10));
+        match rpc.getblockchaininfo() {
+            Err(Error::RpcTimeout(method)) => assert_eq!(method, "getblockchaininfo"),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_chunked_response_is_rejected() {
+        let url = start_server(|mut stream| {
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n{}\r\n0\r\n\r\n",
+            );
+        });
+        let rpc = Rpc::new(url, None, None).retries(0, Duration::from_millis(10));
+        match rpc.getblockchaininfo() {
+            Err(Error::InvalidRpcResponse(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_https_is_rejected() {
+        let rpc = Rpc::new("https://127.0.0.1:2377".to_string(), None, None);
+        match rpc.getblockchaininfo() {
+            Err(Error::InvalidArgs(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_timeout_and_retries() {
+        let (url, accepted) = start_hung_server();
+        let rpc = Rpc::new(url, None, None)
+            .timeout(Duration::from_millis(100))
+            .retries(2, Duration::from_millis(10));
+
+        match rpc.getblockchaininfo() {
+            Err(Error::RpcTimeout(method)) => assert_eq!(method, "getblockchaininfo"),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(*accepted.lock().unwrap(), 3);
+
+        // submitblock is not retried.
+        let block = crate::tests::helper::blocks::get_block(0);
+        assert!(rpc.submitblock(&block).is_err());
+        assert_eq!(*accepted.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_method_timeout() {
+        let (url, _accepted) = start_hung_server();
+        let rpc = Rpc::new(url, None, None)
+            .timeout(Duration::from_secs(60))
+            .method_timeout("getblockchaininfo", Duration::from_millis(100))
+            .retries(0, Duration::from_millis(10));
+        match rpc.getblockchaininfo() {
+            Err(Error::RpcTimeout(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_cookie_file() {
+        let address = Address::from_str("1FiFhpYZHCZt33L4hh7C4jM2uKKueAxcH7").unwrap();
+        let genesis = create_genesis_block(&TEST_KEYS.aggregated(), &address, 1585197531);
+        let server = RpcServer::bind(
+            "127.0.0.1:0",
+            MockChain::new(genesis),
+            Some("__cookie__".to_string()),
+            Some("secret".to_string()),
+        )
+        .unwrap();
+        let url = format!("http://{}", server.local_addr().unwrap());
+        server.spawn();
+
+        // The process id keeps the path apart from test runs in parallel.
+        let path = std::env::temp_dir().join(format!(
+            "rpc_test_cookie_file_{}.cookie",
+            std::process::id()
+        ));
+        std::fs::write(&path, "__cookie__:old").unwrap();
+        let rpc = Rpc::with_cookie_file(url, path.clone());
+        match rpc.getblockchaininfo() {
+            Err(Error::RpcAuthFailed) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        // The cookie is read again after tapyrus-core is restarted.
+        std::fs::write(&path, "__cookie__:secret\n").unwrap();
+        let result = rpc.getblockchaininfo();
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_connection_failed() {
+        let rpc = Rpc::new("http://127.0.0.1:1".to_string(), None, None)
+            .retries(0, Duration::from_millis(10));
+        match rpc.getblockchaininfo() {
+            Err(Error::RpcConnectionFailed(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
 }
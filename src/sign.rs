@@ -14,7 +14,10 @@ use crate::signer_node::ToShares;
 use crate::signer_node::ToVerifiableSS;
 use crate::util::*;
 use secp256k1::rand::thread_rng;
+use tapyrus::blockdata::block::Block;
+use tapyrus::consensus::encode::serialize;
 use tapyrus::hash_types::BlockSigHash;
+use tapyrus::PublicKey;
 
 pub struct Sign;
 
@@ -96,6 +99,22 @@ impl Sign {
         let s_as_str = s_as_int.to_str_radix(16);
         format!("{:0>64}{:0>64}", v_as_str, s_as_str)
     }
+
+    /// Verifies the proof of the block with the aggregated public key which signed the block.
+    pub fn verify_proof(block: &Block, aggregated_public_key: &PublicKey) -> Result<(), Error> {
+        let proof = block.header.proof.as_ref().ok_or(Error::IncompleteBlock)?;
+        let bytes = serialize(proof);
+        if bytes.len() != 64 {
+            return Err(Error::InvalidSig);
+        }
+        let v = lift_x(&BigInt::from(&bytes[..32])).ok_or(Error::InvalidSig)?;
+        let sigma: FE = ECScalar::from(&BigInt::from(&bytes[32..]));
+        let public_key = aggregated_public_key.key.serialize_uncompressed();
+        let y = GE::from_bytes(&public_key[1..]).map_err(|_| Error::InvalidKey)?;
+
+        let hash = block.header.signature_hash();
+        Signature { sigma, v }.verify(&hash[..], &y)
+    }
 }
 
 #[test]
@@ -171,3 +190,42 @@ fn test_format_signature() {
     };
     assert_eq!(Sign::format_signature(&sig_0), "c726149bfb2d4ab64823e0cfd8245645a7950e605ef9222735d821ae570b1e910000000000000000000000000000000000000000000000000000000000000001");
 }
+
+#[test]
+fn test_verify_proof() {
+    use crate::tests::helper::blocks::get_block;
+    use tapyrus::consensus::encode::deserialize;
+
+    let g: GE = ECPoint::generator();
+    let x: FE = ECScalar::new_random();
+    let y = g * &x;
+    let r: FE = ECScalar::new_random();
+    let v = g * &r;
+    // The y coordinate of R in the proof should be a quadratic residue.
+    let (r, v) = if tapyrus::util::prime::jacobi(&Converter::to_vec(&v.y_coor().unwrap())) == 1 {
+        (r, v)
+    } else {
+        let r: FE = ECScalar::from(&(FE::q() - r.to_big_int()));
+        (r, g * &r)
+    };
+
+    let mut block = get_block(0);
+    let hash = block.header.signature_hash();
+    let e = compute_e(&v, &y, &hash[..]);
+    let sigma = r + &(e * &x);
+    let proof = Sign::format_signature(&Signature { sigma, v });
+    block.header.proof = Some(deserialize(&hex::decode(proof).unwrap()).unwrap());
+
+    let public_key = PublicKey::from_slice(&y.get_element().serialize()[..]).unwrap();
+    assert!(Sign::verify_proof(&block, &public_key).is_ok());
+
+    let other: FE = ECScalar::new_random();
+    let other = PublicKey::from_slice(&(g * &other).get_element().serialize()[..]).unwrap();
+    assert!(Sign::verify_proof(&block, &other).is_err());
+
+    block.header.proof = None;
+    match Sign::verify_proof(&block, &public_key) {
+        Err(Error::IncompleteBlock) => {}
+        _ => panic!("it should be an incomplete block"),
+    }
+}
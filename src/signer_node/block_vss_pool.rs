@@ -0,0 +1,185 @@
+use crate::net::SignerID;
+use crate::signer_node::{BidirectionalSharedSecretMap, SharedSecret};
+use curv::FE;
+use std::collections::BTreeMap;
+
+/// Own block key and the VSSs for itself(for positive and negative).
+pub type OwnBlockVss = (FE, SharedSecret, SharedSecret);
+
+/// Block VSSs which are exchanged before the candidate block of the round is proposed.
+///
+/// The VSSs are keyed by the target block height and the master of the round, and bound to a
+/// candidate block when it arrives. Each entry is removed when it is bound, so the same ephemeral
+/// key is never used for two candidate blocks.
+pub struct BlockVssPool {
+    /// Own block keys and VSSs which are not bound to any candidate block yet.
+    own: BTreeMap<(u64, SignerID), OwnBlockVss>,
+    /// VSSs which the other signers sent for future rounds.
+    received: BTreeMap<(u64, SignerID), BidirectionalSharedSecretMap>,
+}
+
+impl BlockVssPool {
+    pub fn new() -> Self {
+        BlockVssPool {
+            own: BTreeMap::new(),
+            received: BTreeMap::new(),
+        }
+    }
+
+    pub fn contains_own(&self, block_height: u64, master_id: &SignerID) -> bool {
+        self.own.contains_key(&(block_height, *master_id))
+    }
+
+    pub fn insert_own(&mut self, block_height: u64, master_id: &SignerID, own: OwnBlockVss) {
+        self.own.insert((block_height, *master_id), own);
+    }
+
+    /// Stores VSSs which `sender_id` sent for the round. Each sender can have only one entry for
+    /// each block height, so returns false without storing if the sender already has one.
+    pub fn insert_received(
+        &mut self,
+        block_height: u64,
+        master_id: &SignerID,
+        sender_id: &SignerID,
+        shared_secret_for_positive: SharedSecret,
+        shared_secret_for_negative: SharedSecret,
+    ) -> bool {
+        let exists = self.received.iter().any(|((height, _), secrets)| {
+            *height == block_height && secrets.contains_key(sender_id)
+        });
+        if exists {
+            return false;
+        }
+        self.received
+            .entry((block_height, *master_id))
+            .or_insert(BidirectionalSharedSecretMap::new())
+            .insert(
+                *sender_id,
+                (shared_secret_for_positive, shared_secret_for_negative),
+            );
+        true
+    }
+
+    /// Removes all VSSs for the round and returns them to bind to the candidate block.
+    /// Own block key is None if this node didn't prepare it.
+    pub fn take(
+        &mut self,
+        block_height: u64,
+        master_id: &SignerID,
+    ) -> (Option<OwnBlockVss>, BidirectionalSharedSecretMap) {
+        let key = (block_height, *master_id);
+        let own = self.own.remove(&key);
+        let received = self.received.remove(&key).unwrap_or_default();
+        (own, received)
+    }
+
+    /// Removes VSSs for the rounds whose target block height is lower than `block_height`.
+    pub fn prune(&mut self, block_height: u64) {
+        self.own.retain(|(height, _), _| *height >= block_height);
+        self.received
+            .retain(|(height, _), _| *height >= block_height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::vss::Vss;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use curv::elliptic::curves::traits::ECScalar;
+
+    fn shared_secret() -> SharedSecret {
+        let (_, vss, secret_shares, _, _) = Vss::create_block_shares(1, 3, 5);
+        SharedSecret {
+            vss,
+            secret_share: secret_shares[0],
+        }
+    }
+
+    #[test]
+    fn test_take() {
+        let master_id = TEST_KEYS.signer_ids()[0];
+        let sender_id = TEST_KEYS.signer_ids()[1];
+        let mut pool = BlockVssPool::new();
+        let own: OwnBlockVss = (FE::new_random(), shared_secret(), shared_secret());
+        pool.insert_own(10, &master_id, own.clone());
+        assert!(pool.insert_received(10, &master_id, &sender_id, shared_secret(), shared_secret()));
+        assert!(pool.contains_own(10, &master_id));
+
+        // VSSs for the other master are not returned.
+        let (other, received) = pool.take(10, &sender_id);
+        assert!(other.is_none());
+        assert!(received.is_empty());
+
+        let (taken, received) = pool.take(10, &master_id);
+        assert_eq!(taken.unwrap().0, own.0);
+        assert!(received.contains_key(&sender_id));
+
+        // The VSSs can not be bound twice.
+        let (taken, received) = pool.take(10, &master_id);
+        assert!(taken.is_none());
+        assert!(received.is_empty());
+    }
+
+    #[test]
+    fn test_prune() {
+        let master_id = TEST_KEYS.signer_ids()[0];
+        let sender_id = TEST_KEYS.signer_ids()[1];
+        let mut pool = BlockVssPool::new();
+        pool.insert_own(
+            9,
+            &master_id,
+            (FE::new_random(), shared_secret(), shared_secret()),
+        );
+        assert!(pool.insert_received(9, &master_id, &sender_id, shared_secret(), shared_secret()));
+        pool.insert_own(
+            10,
+            &master_id,
+            (FE::new_random(), shared_secret(), shared_secret()),
+        );
+
+        pool.prune(10);
+        assert!(!pool.contains_own(9, &master_id));
+        assert!(pool.take(9, &master_id).1.is_empty());
+        assert!(pool.contains_own(10, &master_id));
+    }
+
+    #[test]
+    fn test_insert_received_once_per_sender_and_height() {
+        let signer_ids = TEST_KEYS.signer_ids();
+        let sender_id = signer_ids[1];
+        let mut pool = BlockVssPool::new();
+        assert!(pool.insert_received(
+            10,
+            &signer_ids[0],
+            &sender_id,
+            shared_secret(),
+            shared_secret()
+        ));
+
+        // The sender can't send VSSs twice for the same height, even for another master.
+        assert!(!pool.insert_received(
+            10,
+            &signer_ids[0],
+            &sender_id,
+            shared_secret(),
+            shared_secret()
+        ));
+        assert!(!pool.insert_received(
+            10,
+            &signer_ids[2],
+            &sender_id,
+            shared_secret(),
+            shared_secret()
+        ));
+        assert!(pool.take(10, &signer_ids[2]).1.is_empty());
+
+        assert!(pool.insert_received(
+            11,
+            &signer_ids[2],
+            &sender_id,
+            shared_secret(),
+            shared_secret()
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Detection of masters which send different candidate blocks to different members.
+//!
+//! A member which accepts a candidate block broadcasts a digest of it to all signers. The digest
+//! includes the signature of the master over the header of the candidate block, so any signer can
+//! verify it with the public key of the master, and a member can't fabricate it. Two valid digests
+//! of different candidate blocks for the same block height and master in a round are the evidence
+//! of equivocation of the master.
+
+use crate::errors::Error;
+use crate::net::SignerID;
+use crate::signer_node::master_signature::{MasterSignature, SignedBlock};
+use std::collections::{BTreeMap, HashSet};
+use tapyrus::blockdata::block::BlockHeader;
+
+/// Header of the candidate block which the sender received from the master of the round.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CandidateDigest {
+    pub block_height: u64,
+    pub master: SignerID,
+    pub header: BlockHeader,
+    pub sender: SignerID,
+    /// Signature of the master over the header, which the sender received with the block.
+    pub signature: MasterSignature,
+}
+
+impl CandidateDigest {
+    pub fn new(
+        block_height: u64,
+        master: SignerID,
+        header: BlockHeader,
+        sender: SignerID,
+        signature: MasterSignature,
+    ) -> Self {
+        CandidateDigest {
+            block_height,
+            master,
+            header,
+            sender,
+            signature,
+        }
+    }
+
+    /// Verifies the signature of the master over the header.
+    pub fn verify(&self) -> Result<(), Error> {
+        self.signature.verify(
+            SignedBlock::Candidate,
+            self.block_height,
+            &self.header,
+            &self.master,
+        )
+    }
+
+    /// Returns true if both digests are for the same round but for different candidate blocks.
+    pub fn conflicts_with(&self, other: &CandidateDigest) -> bool {
+        self.block_height == other.block_height
+            && self.master == other.master
+            && self.header.signature_hash() != other.header.signature_hash()
+    }
+}
+
+/// Keeps the first digest for each pair of the block height and the master in a round, and finds
+/// the digests which conflict with it. A failed round is followed by another round at the same
+/// block height, where the master may propose a new candidate block legitimately, so the detector
+/// is replaced at the start of each round.
+#[derive(Default)]
+pub struct EquivocationDetector {
+    digests: BTreeMap<(u64, SignerID), CandidateDigest>,
+    reported: HashSet<(u64, SignerID)>,
+}
+
+impl EquivocationDetector {
+    /// Returns the digest which was observed before and conflicts with the given one. A conflict
+    /// is returned only once for each round.
+    pub fn observe(&mut self, digest: CandidateDigest) -> Option<CandidateDigest> {
+        let key = (digest.block_height, digest.master);
+        match self.digests.get(&key) {
+            Some(first) if first.conflicts_with(&digest) => {
+                if self.reported.insert(key) {
+                    Some(first.clone())
+                } else {
+                    None
+                }
+            }
+            Some(_) => None,
+            None => {
+                self.digests.insert(key, digest);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::blocks::{get_block, master_sign};
+    use crate::tests::helper::keys::TEST_KEYS;
+
+    fn digest(sender: usize, block: u8) -> CandidateDigest {
+        CandidateDigest::new(
+            1,
+            SignerID::new(TEST_KEYS.pubkeys()[0]),
+            get_block(block).header,
+            SignerID::new(TEST_KEYS.pubkeys()[sender]),
+            master_sign(SignedBlock::Candidate, 1, &get_block(block), 0),
+        )
+    }
+
+    #[test]
+    fn test_verify() {
+        let digest = digest(1, 0);
+        assert!(digest.verify().is_ok());
+
+        let mut forged = digest.clone();
+        forged.header = get_block(1).header;
+        assert!(forged.verify().is_err());
+
+        let mut forged = digest.clone();
+        forged.master = SignerID::new(TEST_KEYS.pubkeys()[1]);
+        assert!(forged.verify().is_err());
+
+        // A member can't sign the digest instead of the master.
+        let mut forged = digest.clone();
+        forged.header = get_block(1).header;
+        forged.signature = master_sign(SignedBlock::Candidate, 1, &get_block(1), 1);
+        assert!(forged.verify().is_err());
+    }
+
+    #[test]
+    fn test_observe() {
+        let mut detector = EquivocationDetector::default();
+        let first = digest(1, 0);
+        assert_eq!(detector.observe(first.clone()), None);
+        assert_eq!(detector.observe(digest(2, 0)), None);
+        assert_eq!(detector.observe(digest(3, 1)), Some(first));
+        // It is reported only once.
+        assert_eq!(detector.observe(digest(4, 1)), None);
+    }
+}
@@ -0,0 +1,400 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Evidence of misbehavior of round masters which members observed.
+//!
+//! Evidence is kept in memory and, if a file is given, appended to it as JSON lines, so that it
+//! survives restarts and can be examined after an incident. `Evidence::verify` checks it again
+//! with the federations, which `tapyrus-setup verifyevidence` uses. Evidence of an invalid
+//! completed block includes the signatures of the master, so that only the master can be its
+//! cause.
+
+use crate::errors::Error;
+use crate::federation::Federations;
+use crate::net::SignerID;
+use crate::sign::Sign;
+use crate::signer_node::equivocation::CandidateDigest;
+use crate::signer_node::master_signature::{MasterSignature, SignedBlock};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tapyrus::blockdata::block::Block;
+use tapyrus::PublicKey;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Evidence {
+    /// The master sent a completed block which is not the candidate block signed in the round or
+    /// whose proof doesn't verify under the aggregated public key of the federation. Both blocks
+    /// are signed by the master.
+    InvalidCompletedBlock {
+        block_height: u64,
+        master: SignerID,
+        /// The candidate block which the member got in the round.
+        candidate: Block,
+        candidate_signature: MasterSignature,
+        completed: Block,
+        completed_signature: MasterSignature,
+        reason: String,
+    },
+    /// The master sent different candidate blocks to different members in the round. Each digest
+    /// has the signature of the master over the candidate block which a member received.
+    Equivocation {
+        block_height: u64,
+        master: SignerID,
+        digests: (CandidateDigest, CandidateDigest),
+    },
+}
+
+impl Evidence {
+    pub fn block_height(&self) -> u64 {
+        match self {
+            Evidence::InvalidCompletedBlock { block_height, .. }
+            | Evidence::Equivocation { block_height, .. } => *block_height,
+        }
+    }
+
+    pub fn master(&self) -> SignerID {
+        match self {
+            Evidence::InvalidCompletedBlock { master, .. }
+            | Evidence::Equivocation { master, .. } => *master,
+        }
+    }
+
+    /// Short name of the kind of the evidence.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Evidence::InvalidCompletedBlock { .. } => "invalid-completed-block",
+            Evidence::Equivocation { .. } => "equivocation",
+        }
+    }
+
+    /// Checks the evidence again with the federations. Both blocks of an invalid completed block
+    /// are checked to be signed by the master and to match their transactions, and then the
+    /// completed block is checked against the candidate block and the aggregated public key. The
+    /// digests of the equivocation are checked to be for the same round, to be different and to
+    /// be signed by the master.
+    pub fn verify(&self, federations: &Federations) -> Result<(), String> {
+        match self {
+            Evidence::InvalidCompletedBlock {
+                block_height,
+                master,
+                candidate,
+                candidate_signature,
+                completed,
+                completed_signature,
+                ..
+            } => {
+                let federation = federations.get_by_block_height(*block_height);
+                if federation.is_member() && !federation.signers().contains(master) {
+                    return Err("the master is not a signer of the federation".to_string());
+                }
+                candidate_signature
+                    .verify(
+                        SignedBlock::Candidate,
+                        *block_height,
+                        &candidate.header,
+                        master,
+                    )
+                    .map_err(|e| {
+                        format!("the signature of the candidate block is invalid: {:?}", e)
+                    })?;
+                completed_signature
+                    .verify(
+                        SignedBlock::Completed,
+                        *block_height,
+                        &completed.header,
+                        master,
+                    )
+                    .map_err(|e| {
+                        format!("the signature of the completed block is invalid: {:?}", e)
+                    })?;
+                // The signatures cover only the headers, so the transactions must be the ones in
+                // the headers.
+                for (name, block) in &[("candidate", candidate), ("completed", completed)] {
+                    if block.header.merkle_root != block.merkle_root() {
+                        return Err(format!(
+                            "the transactions of the {} block don't match the header",
+                            name
+                        ));
+                    }
+                }
+                match verify_completed_block(
+                    completed,
+                    candidate,
+                    &federation.aggregated_public_key(),
+                ) {
+                    Ok(()) => Err("the completed block is valid".to_string()),
+                    Err(_) => Ok(()),
+                }
+            }
+            Evidence::Equivocation {
+                block_height,
+                master,
+                digests: (first, second),
+            } => {
+                if first.block_height != *block_height || first.master != *master {
+                    return Err("the digest is not for the round".to_string());
+                }
+                if !first.conflicts_with(second) {
+                    return Err("the digests don't conflict".to_string());
+                }
+                let federation = federations.get_by_block_height(*block_height);
+                if federation.is_member() && !federation.signers().contains(master) {
+                    return Err("the master is not a signer of the federation".to_string());
+                }
+                for digest in &[first, second] {
+                    digest.verify().map_err(|e| {
+                        format!(
+                            "the signature of the master over the digest from {} is invalid: {:?}",
+                            digest.sender, e
+                        )
+                    })?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Checks that the completed block is the candidate block which was signed in the round and that
+/// its proof verifies under the aggregated public key of the federation.
+pub fn verify_completed_block(
+    block: &Block,
+    candidate: &Block,
+    aggregated_public_key: &PublicKey,
+) -> Result<(), String> {
+    if block.header.signature_hash() != candidate.header.signature_hash() {
+        return Err("the block is different from the candidate block".to_string());
+    }
+    if block.header.merkle_root != block.merkle_root() {
+        return Err("the transactions don't match the header".to_string());
+    }
+    verify_proof(block, aggregated_public_key)
+}
+
+/// Checks that the proof of the block verifies under the aggregated public key of the federation.
+pub fn verify_proof(block: &Block, aggregated_public_key: &PublicKey) -> Result<(), String> {
+    Sign::verify_proof(block, aggregated_public_key)
+        .map_err(|e| format!("the proof is invalid: {:?}", e))
+}
+
+/// A line of the evidence file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceRecord {
+    /// Unix time in milliseconds when the evidence was recorded.
+    pub time: u64,
+    pub evidence: Evidence,
+}
+
+#[derive(Default)]
+pub struct EvidenceStore {
+    records: Mutex<Vec<EvidenceRecord>>,
+    file: Option<Mutex<LineWriter<File>>>,
+}
+
+impl EvidenceStore {
+    /// Opens the file to append evidence.
+    pub fn create(path: &str) -> Result<Self, Error> {
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        Ok(EvidenceStore {
+            records: Mutex::new(vec![]),
+            file: Some(Mutex::new(LineWriter::new(file))),
+        })
+    }
+
+    pub fn record(&self, evidence: Evidence) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let record = EvidenceRecord { time, evidence };
+        if let Some(file) = &self.file {
+            let result = serde_json::to_string(&record)
+                .map_err(Error::from)
+                .and_then(|line| {
+                    let mut file = file.lock().unwrap();
+                    writeln!(file, "{}", line).map_err(Error::from)
+                });
+            if let Err(e) = result {
+                log::warn!("Failed to record the evidence: {:?}", e);
+            }
+        }
+        self.records.lock().unwrap().push(record);
+    }
+
+    /// Returns the evidence recorded since the node got started.
+    pub fn records(&self) -> Vec<EvidenceRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+/// Reads all evidence in the file.
+pub fn read_evidence(path: &str) -> Result<Vec<EvidenceRecord>, Error> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str(&line)?);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::federation::Federation;
+    use crate::tests::helper::blocks::{get_block, master_sign, sign};
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::federation;
+
+    fn digest(sender: usize, block: u8) -> CandidateDigest {
+        CandidateDigest::new(
+            1,
+            SignerID::new(TEST_KEYS.pubkeys()[0]),
+            get_block(block).header,
+            SignerID::new(TEST_KEYS.pubkeys()[sender]),
+            master_sign(SignedBlock::Candidate, 1, &get_block(block), 0),
+        )
+    }
+
+    fn invalid_completed_block(candidate: Block, completed: Block) -> Evidence {
+        Evidence::InvalidCompletedBlock {
+            block_height: 1,
+            master: SignerID::new(TEST_KEYS.pubkeys()[0]),
+            candidate_signature: master_sign(SignedBlock::Candidate, 1, &candidate, 0),
+            completed_signature: master_sign(SignedBlock::Completed, 1, &completed, 0),
+            candidate,
+            completed,
+            reason: "the block is different from the candidate block".to_string(),
+        }
+    }
+
+    fn evidence() -> Evidence {
+        invalid_completed_block(get_block(0), get_block(1))
+    }
+
+    #[test]
+    fn test_record_in_memory() {
+        let store = EvidenceStore::default();
+        store.record(evidence());
+        let records = store.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].evidence, evidence());
+    }
+
+    #[test]
+    fn test_record_to_file() {
+        let path = std::env::temp_dir().join("evidence_test_record_to_file.jsonl");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let store = EvidenceStore::create(path).unwrap();
+        store.record(evidence());
+        store.record(evidence());
+
+        let records = read_evidence(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].evidence, evidence());
+    }
+
+    #[test]
+    fn test_verify_invalid_completed_block() {
+        let federations = Federations::new(vec![federation(1)]);
+        assert!(evidence().verify(&federations).is_ok());
+        assert!(invalid_completed_block(get_block(0), get_block(0))
+            .verify(&federations)
+            .is_ok());
+
+        let valid = invalid_completed_block(get_block(0), sign(get_block(0)));
+        assert_eq!(
+            valid.verify(&federations),
+            Err("the completed block is valid".to_string())
+        );
+
+        // The blocks which the master didn't sign are not the evidence.
+        let mut forged = evidence();
+        if let Evidence::InvalidCompletedBlock {
+            candidate_signature,
+            ..
+        } = &mut forged
+        {
+            *candidate_signature = master_sign(SignedBlock::Candidate, 1, &get_block(0), 1);
+        }
+        assert!(forged
+            .verify(&federations)
+            .unwrap_err()
+            .starts_with("the signature of the candidate block is invalid"));
+
+        let mut forged = evidence();
+        if let Evidence::InvalidCompletedBlock { completed, .. } = &mut forged {
+            *completed = get_block(2);
+        }
+        assert!(forged
+            .verify(&federations)
+            .unwrap_err()
+            .starts_with("the signature of the completed block is invalid"));
+
+        // The transactions are not covered by the signatures, so they must match the header.
+        let mut forged = valid.clone();
+        if let Evidence::InvalidCompletedBlock { completed, .. } = &mut forged {
+            completed.txdata = get_block(1).txdata;
+        }
+        assert_eq!(
+            forged.verify(&federations),
+            Err("the transactions of the completed block don't match the header".to_string())
+        );
+    }
+
+    #[test]
+    fn test_verify_equivocation() {
+        let federations = Federations::new(vec![federation(1)]);
+        let master = SignerID::new(TEST_KEYS.pubkeys()[0]);
+        let evidence = Evidence::Equivocation {
+            block_height: 1,
+            master,
+            digests: (digest(1, 0), digest(2, 1)),
+        };
+        assert!(evidence.verify(&federations).is_ok());
+
+        let evidence = Evidence::Equivocation {
+            block_height: 1,
+            master,
+            digests: (digest(1, 0), digest(2, 0)),
+        };
+        assert_eq!(
+            evidence.verify(&federations),
+            Err("the digests don't conflict".to_string())
+        );
+
+        // A member signs another candidate block instead of the master.
+        let mut forged = digest(2, 1);
+        forged.signature = master_sign(SignedBlock::Candidate, 1, &get_block(1), 2);
+        let evidence = Evidence::Equivocation {
+            block_height: 1,
+            master,
+            digests: (digest(1, 0), forged),
+        };
+        assert!(evidence.verify(&federations).is_err());
+
+        // Any signer can verify it without the node VSSs.
+        let federations = Federations::new(vec![Federation::new(
+            TEST_KEYS.pubkeys()[1],
+            0,
+            None,
+            None,
+            TEST_KEYS.aggregated(),
+        )]);
+        let evidence = Evidence::Equivocation {
+            block_height: 1,
+            master,
+            digests: (digest(1, 0), digest(2, 1)),
+        };
+        assert!(evidence.verify(&federations).is_ok());
+    }
+}
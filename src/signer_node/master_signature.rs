@@ -0,0 +1,158 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Signatures of round masters over the blocks which they send.
+//!
+//! The master signs the header of each candidate block and completed block with the private key
+//! of its signer ID, so anyone who knows the signer ID can verify that the master sent the block.
+//! Evidence of misbehavior of a master includes these signatures, so that other signers can't
+//! fabricate it.
+
+use crate::crypto::multi_party_schnorr::{compute_e, Signature};
+use crate::errors::Error;
+use crate::net::SignerID;
+use crate::sign::Sign;
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use sha2::{Digest, Sha256};
+use tapyrus::blockdata::block::BlockHeader;
+use tapyrus::consensus::encode::serialize;
+use tapyrus::secp256k1::Secp256k1;
+use tapyrus::{PrivateKey, PublicKey};
+
+/// The blocks which the master signs in a round.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignedBlock {
+    Candidate,
+    Completed,
+}
+
+impl SignedBlock {
+    fn tag(&self) -> &'static [u8] {
+        match self {
+            SignedBlock::Candidate => b"tapyrus-signer/candidateblock",
+            SignedBlock::Completed => b"tapyrus-signer/completedblock",
+        }
+    }
+}
+
+/// Schnorr signature of the master with the private key of its signer ID over the header of the
+/// block which it sent in the round at the block height.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MasterSignature {
+    pub sigma: FE,
+    pub v: GE,
+}
+
+impl MasterSignature {
+    /// Signs the header. The nonce is derived from the private key and the message, so the same
+    /// header always gets the same signature.
+    pub fn sign(
+        kind: SignedBlock,
+        block_height: u64,
+        header: &BlockHeader,
+        private_key: &PrivateKey,
+    ) -> Self {
+        let secp = Secp256k1::new();
+        let master = SignerID::new(PublicKey::from_private_key(&secp, private_key));
+        let message = signed_message(kind, block_height, &master, header);
+        let x: FE = ECScalar::from(
+            &Sign::private_key_to_big_int(private_key.key).expect("failed to parse private_key"),
+        );
+
+        let mut hasher = Sha256::new();
+        hasher.input(b"tapyrus-signer/master-signature-nonce");
+        hasher.input(&private_key.key[..]);
+        hasher.input(&message);
+        let r: FE = ECScalar::from(&BigInt::from(&hasher.result()[..]));
+
+        let g: GE = ECPoint::generator();
+        let v = g * &r;
+        let e = compute_e(&v, &(g * &x), &message);
+        let sigma = r + &(e * &x);
+        MasterSignature { sigma, v }
+    }
+
+    /// Verifies the signature with the public key of the master.
+    pub fn verify(
+        &self,
+        kind: SignedBlock,
+        block_height: u64,
+        header: &BlockHeader,
+        master: &SignerID,
+    ) -> Result<(), Error> {
+        let public_key = master.pubkey.key.serialize_uncompressed();
+        let y = GE::from_bytes(&public_key[1..]).map_err(|_| Error::InvalidKey)?;
+        let message = signed_message(kind, block_height, master, header);
+        Signature {
+            sigma: self.sigma,
+            v: self.v,
+        }
+        .verify(&message, &y)
+    }
+}
+
+fn signed_message(
+    kind: SignedBlock,
+    block_height: u64,
+    master: &SignerID,
+    header: &BlockHeader,
+) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.input(kind.tag());
+    hasher.input(&block_height.to_be_bytes());
+    hasher.input(&master.pubkey.key.serialize()[..]);
+    hasher.input(&serialize(header));
+    hasher.result().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::blocks::{get_block, sign};
+    use crate::tests::helper::keys::TEST_KEYS;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let master = TEST_KEYS.signer_ids()[0];
+        let header = get_block(0).header;
+        let signature =
+            MasterSignature::sign(SignedBlock::Candidate, 1, &header, &TEST_KEYS.key[0]);
+        assert!(signature
+            .verify(SignedBlock::Candidate, 1, &header, &master)
+            .is_ok());
+        // The signature is deterministic.
+        assert_eq!(
+            signature,
+            MasterSignature::sign(SignedBlock::Candidate, 1, &header, &TEST_KEYS.key[0])
+        );
+
+        // It is only for the kind, the block height, the master and the header.
+        assert!(signature
+            .verify(SignedBlock::Completed, 1, &header, &master)
+            .is_err());
+        assert!(signature
+            .verify(SignedBlock::Candidate, 2, &header, &master)
+            .is_err());
+        assert!(signature
+            .verify(
+                SignedBlock::Candidate,
+                1,
+                &header,
+                &TEST_KEYS.signer_ids()[1]
+            )
+            .is_err());
+        assert!(signature
+            .verify(SignedBlock::Candidate, 1, &get_block(1).header, &master)
+            .is_err());
+        assert!(signature
+            .verify(
+                SignedBlock::Candidate,
+                1,
+                &sign(get_block(0)).header,
+                &master
+            )
+            .is_err());
+    }
+}
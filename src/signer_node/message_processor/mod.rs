@@ -1,14 +1,20 @@
 mod process_blockparticipants;
 mod process_blocksig;
+mod process_blocksig_frost;
 mod process_blockvss;
 mod process_candidateblock;
 mod process_completedblock;
+mod process_signingpackage;
 pub use process_blockparticipants::process_blockparticipants;
 pub use process_blocksig::process_blocksig;
+pub use process_blocksig_frost::process_blocksig_frost;
 pub use process_blockvss::process_blockvss;
+pub use process_blockvss::start_signing_if_threshold_met;
 pub use process_candidateblock::process_candidateblock;
 pub use process_completedblock::process_completedblock;
+pub use process_signingpackage::process_signingpackage;
 
+use crate::crypto::frost::{Frost, NonceCommitment};
 use crate::crypto::multi_party_schnorr::Keys;
 use crate::crypto::multi_party_schnorr::{LocalSig, SharedKeys};
 use crate::crypto::vss::Vss;
@@ -18,8 +24,13 @@ use crate::net::Message;
 use crate::net::MessageType;
 use crate::net::SignerID;
 use crate::rpc::TapyrusApi;
+use crate::signer_node::block_vss_pool::BlockVssPool;
+use crate::signer_node::nonce_store::{to_signing_commitments, NonceStore};
 use crate::signer_node::SharedSecret;
 use crate::signer_node::{BidirectionalSharedSecretMap, NodeParameters, NodeState};
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::FE;
+use std::collections::BTreeMap;
 use tapyrus::blockdata::block::Block;
 use tapyrus::hash_types::BlockSigHash;
 
@@ -63,6 +74,133 @@ pub fn create_block_vss<T, C>(
 where
     T: TapyrusApi,
     C: ConnectionManager,
+{
+    let blockhash = block.header.signature_hash();
+    send_block_vss(params, conman, block_height, |vss_p, s_p, vss_n, s_n| {
+        MessageType::Blockvss(blockhash, vss_p, s_p, vss_n, s_n)
+    })
+}
+
+/// Create own VSSs for the round, which is identified by the block height and the master, and
+/// send to each other signers before the candidate block of the round is proposed.
+/// The own block key and the VSS for itself are stored in the pool.
+pub fn precompute_block_vss<T, C>(
+    block_height: u64,
+    master_id: &SignerID,
+    params: &NodeParameters<T>,
+    conman: &C,
+    pool: &mut BlockVssPool,
+) where
+    T: TapyrusApi,
+    C: ConnectionManager,
+{
+    let (key, shared_secret_for_positive, shared_secret_for_negative) =
+        send_block_vss(params, conman, block_height, |vss_p, s_p, vss_n, s_n| {
+            MessageType::Preblockvss(block_height, *master_id, vss_p, s_p, vss_n, s_n)
+        });
+    pool.insert_own(
+        block_height,
+        master_id,
+        (
+            key.u_i,
+            shared_secret_for_positive,
+            shared_secret_for_negative,
+        ),
+    );
+}
+
+/// Verify the block VSSs which `sender_id` pre-shared for the round of `block_height` and
+/// `master_id`. Both signers should be members of the federation at the height, and the secret
+/// shares for this node should be consistent with their commitments.
+pub fn verify_pre_block_vss<T>(
+    block_height: u64,
+    master_id: &SignerID,
+    sender_id: &SignerID,
+    shared_secret_for_positive: &SharedSecret,
+    shared_secret_for_negative: &SharedSecret,
+    params: &NodeParameters<T>,
+) -> Result<(), Error>
+where
+    T: TapyrusApi,
+{
+    let federation = params.get_federation_by_block_height(block_height);
+    if !federation.is_member()
+        || !federation.signers().contains(sender_id)
+        || !federation.signers().contains(master_id)
+        || *sender_id == params.signer_id
+    {
+        return Err(Error::InvalidSS);
+    }
+
+    let sharing_params = params.sharing_params(block_height);
+    let index = params.self_node_index(block_height) + 1;
+    for shared_secret in &[shared_secret_for_positive, shared_secret_for_negative] {
+        let vss = &shared_secret.vss;
+        if vss.parameters.threshold != sharing_params.threshold
+            || vss.parameters.share_count != sharing_params.share_count
+            || vss.commitments.len() != sharing_params.threshold + 1
+            || vss
+                .validate_share(&shared_secret.secret_share, index)
+                .is_err()
+        {
+            return Err(Error::InvalidSS);
+        }
+    }
+    Ok(())
+}
+
+/// Bind Block VSSs in the pool to the candidate block of the round. If the node doesn't have own
+/// VSS in the pool, it creates and sends VSSs for the candidate block.
+/// Returns own block key and all VSSs which the node has for the candidate block.
+pub fn bind_block_vss<T, C>(
+    block: &Block,
+    master_id: &SignerID,
+    params: &NodeParameters<T>,
+    conman: &C,
+    block_height: u64,
+    pool: &mut BlockVssPool,
+) -> (FE, BidirectionalSharedSecretMap)
+where
+    T: TapyrusApi,
+    C: ConnectionManager,
+{
+    let (own, mut shared_block_secrets) = pool.take(block_height, master_id);
+    let (block_key, shared_secret_for_positive, shared_secret_for_negative) = match own {
+        Some(own) => {
+            log::debug!(
+                "Bind pre-shared block VSSs to the candidate block. number of VSSs: {}",
+                shared_block_secrets.len() + 1
+            );
+            own
+        }
+        None => {
+            let (key, shared_secret_for_positive, shared_secret_for_negative) =
+                create_block_vss(block.clone(), params, conman, block_height);
+            (
+                key.u_i,
+                shared_secret_for_positive,
+                shared_secret_for_negative,
+            )
+        }
+    };
+    shared_block_secrets.insert(
+        params.signer_id,
+        (shared_secret_for_positive, shared_secret_for_negative),
+    );
+    (block_key, shared_block_secrets)
+}
+
+/// Create own VSSs and send them to each other signers with the message which `message` builds.
+fn send_block_vss<T, C, F>(
+    params: &NodeParameters<T>,
+    conman: &C,
+    block_height: u64,
+    message: F,
+) -> (Keys, SharedSecret, SharedSecret)
+where
+    T: TapyrusApi,
+    C: ConnectionManager,
+    F: Fn(VerifiableSS, FE, VerifiableSS, FE) -> MessageType,
 {
     let sharing_params = params.sharing_params(block_height);
 
@@ -87,8 +225,7 @@ where
         }
 
         conman.send_message(Message {
-            message_type: MessageType::Blockvss(
-                block.header.signature_hash(),
+            message_type: message(
                 vss_scheme_for_positive.clone(),
                 secret_shares_for_positive[i],
                 vss_scheme_for_negative.clone(),
@@ -139,6 +276,37 @@ where
     )
 }
 
+/// Create own local signature for the FROST signing scheme with the nonce which is committed in
+/// the signing package. The nonce is removed from the nonce store, so it is never used again.
+pub fn create_frost_local_sig<T>(
+    block: &Block,
+    signing_commitments: &BTreeMap<SignerID, NonceCommitment>,
+    nonce_store: &mut NonceStore,
+    block_height: u64,
+    params: &NodeParameters<T>,
+) -> Result<LocalSig, Error>
+where
+    T: TapyrusApi,
+{
+    let commitment = signing_commitments
+        .get(&params.signer_id)
+        .ok_or(Error::InvalidNonce)?;
+    let nonce = nonce_store
+        .take_nonce(commitment)
+        .ok_or(Error::InvalidNonce)?;
+    let federation = params.get_federation_by_block_height(block_height);
+    let commitments =
+        to_signing_commitments(signing_commitments, &params.pubkey_list(block_height));
+
+    Frost::sign(
+        &nonce,
+        params.self_node_index(block_height) + 1,
+        &federation.node_secret_share(),
+        &block.header.signature_hash()[..],
+        &commitments,
+    )
+}
+
 fn broadcast_localsig<C: ConnectionManager>(
     sighash: BlockSigHash,
     local_sig: &LocalSig,
@@ -159,8 +327,12 @@ fn broadcast_localsig<C: ConnectionManager>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::federation::Federations;
     use crate::signer_node::node_state::builder::{Builder, Master, Member};
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_parameters_builder::NodeParametersBuilder;
     use crate::tests::helper::node_state_builder::BuilderForTest;
+    use crate::tests::helper::node_vss::federation;
     use tapyrus::consensus::encode::deserialize;
     use tapyrus::hashes::hex::FromHex;
 
@@ -206,4 +378,36 @@ mod tests {
         let blockhash = BlockSigHash::from_hex(INVALID_HASH).unwrap();
         assert!(get_valid_block(&state, blockhash).is_err());
     }
+
+    #[test]
+    fn test_verify_pre_block_vss() {
+        let params = NodeParametersBuilder::new()
+            .public_key(TEST_KEYS.pubkeys()[1])
+            .federations(Federations::new(vec![federation(1)]))
+            .build();
+        let signer_ids = TEST_KEYS.signer_ids();
+        let (_, vss_p, shares_p, vss_n, shares_n) = Vss::create_block_shares(1, 3, 5);
+        let positive = SharedSecret {
+            vss: vss_p,
+            secret_share: shares_p[1],
+        };
+        let negative = SharedSecret {
+            vss: vss_n,
+            secret_share: shares_n[1],
+        };
+        let verify = |sender_id: &SignerID, positive: &SharedSecret| {
+            verify_pre_block_vss(10, &signer_ids[0], sender_id, positive, &negative, &params)
+        };
+        assert!(verify(&signer_ids[0], &positive).is_ok());
+
+        // The secret share for another signer doesn't match with the commitments.
+        let other_share = SharedSecret {
+            vss: positive.vss.clone(),
+            secret_share: shares_p[2],
+        };
+        assert!(verify(&signer_ids[0], &other_share).is_err());
+
+        // The VSS from the node itself is not accepted.
+        assert!(verify(&signer_ids[1], &positive).is_err());
+    }
 }
@@ -4,6 +4,7 @@ use crate::errors::Error;
 use crate::net::{ConnectionManager, Message, MessageType, SignerID};
 use crate::rpc::TapyrusApi;
 use crate::sign::Sign;
+use crate::signer_node::master_signature::SignedBlock;
 use crate::signer_node::message_processor::get_valid_block;
 use crate::signer_node::node_state::builder::{Builder, Master};
 use crate::signer_node::NodeParameters;
@@ -173,7 +174,7 @@ where
         .log();
 
     // send completeblock message
-    broadcast_completedblock(completed_block, &params.signer_id, conman);
+    broadcast_completedblock(completed_block, block_height, params, conman);
 
     return state_builder.round_is_done(true).build();
 }
@@ -189,7 +190,11 @@ fn store_received_local_sig(
     result
 }
 
-fn submitblock<T>(block: &Block, sig: &Signature, rpc: &std::sync::Arc<T>) -> Result<Block, Error>
+pub(super) fn submitblock<T>(
+    block: &Block,
+    sig: &Signature,
+    rpc: &std::sync::Arc<T>,
+) -> Result<Block, Error>
 where
     T: TapyrusApi,
 {
@@ -204,14 +209,22 @@ where
     }
 }
 
-fn broadcast_completedblock<C>(block: Block, own_id: &SignerID, conman: &C)
-where
+/// Broadcasts the completed block, with the signature of the node as the master of the round if
+/// the node has the private key.
+pub(super) fn broadcast_completedblock<T, C>(
+    block: Block,
+    block_height: u64,
+    params: &NodeParameters<T>,
+    conman: &C,
+) where
+    T: TapyrusApi,
     C: ConnectionManager,
 {
+    let signature = params.sign_block(SignedBlock::Completed, block_height, &block.header);
     log::info!("Broadcast CompletedBlock message.");
     let message = Message {
-        message_type: MessageType::Completedblock(block),
-        sender_id: own_id.clone(),
+        message_type: MessageType::completedblock(block, signature),
+        sender_id: params.signer_id,
         receiver_id: None,
     };
     conman.broadcast_message(message);
@@ -250,8 +263,10 @@ mod tests {
     use super::process_blocksig;
     use crate::federation::{Federation, Federations};
     use crate::net::Message;
+    use crate::signer_node::master_signature::{MasterSignature, SignedBlock};
     use crate::signer_node::message_processor::process_blocksig::Dump;
     use crate::signer_node::*;
+    use crate::tests::helper::keys::TEST_KEYS;
     use crate::tests::helper::net::TestConnectionManager;
     use crate::tests::helper::node_parameters_builder::NodeParametersBuilder;
     use crate::tests::helper::rpc::MockRpc;
@@ -515,8 +530,15 @@ mod tests {
             .build();
 
         let mut conman = TestConnectionManager::new();
+        let completed_block = dump.completed_block.unwrap();
+        let signature = MasterSignature::sign(
+            SignedBlock::Completed,
+            dump.prev_state.block_height(),
+            &completed_block.header,
+            &TEST_KEYS.key[1],
+        );
         conman.should_broadcast(Message {
-            message_type: MessageType::Completedblock(dump.completed_block.unwrap().clone()),
+            message_type: MessageType::Signedcompletedblock(completed_block, signature),
             sender_id: params.signer_id,
             receiver_id: None,
         });
@@ -0,0 +1,276 @@
+use crate::crypto::frost::Frost;
+use crate::crypto::multi_party_schnorr::LocalSig;
+use crate::net::{ConnectionManager, SignerID};
+use crate::rpc::TapyrusApi;
+use crate::signer_node::message_processor::get_valid_block;
+use crate::signer_node::message_processor::process_blocksig::{
+    broadcast_completedblock, submitblock,
+};
+use crate::signer_node::node_state::builder::{Builder, Master};
+use crate::signer_node::nonce_store::{to_signing_commitments, NonceStore};
+use crate::signer_node::utils::sender_index;
+use crate::signer_node::{NodeParameters, NodeState, ToVerifiableSS};
+use curv::FE;
+use std::collections::BTreeMap;
+use tapyrus::hash_types::BlockSigHash;
+
+/// Processes a local signature of the FROST signing scheme. Each local signature is verified with
+/// the public share of the sender, and the master aggregates them when all participants in the
+/// signing package sent them.
+pub fn process_blocksig_frost<T, C>(
+    sender_id: &SignerID,
+    blockhash: BlockSigHash,
+    gamma_i: FE,
+    e: FE,
+    prev_state: &NodeState,
+    conman: &C,
+    params: &NodeParameters<T>,
+    nonce_store: &NonceStore,
+) -> NodeState
+where
+    T: TapyrusApi,
+    C: ConnectionManager,
+{
+    // Ignore blocksig message except Master state which is not done.
+    let (signatures, participants) = match prev_state {
+        NodeState::Master {
+            signatures,
+            participants,
+            round_is_done: false,
+            ..
+        } => (signatures, participants),
+        _ => return prev_state.clone(),
+    };
+
+    // Ignore the message if the sender is not contained in the participants.
+    if !participants.contains(sender_id) {
+        return prev_state.clone();
+    }
+
+    let candidate_block = match get_valid_block(prev_state, blockhash) {
+        Ok(block) => block,
+        Err(_) => return prev_state.clone(),
+    };
+
+    let signing_commitments = match nonce_store.signing_commitments(&blockhash) {
+        Some(commitments) => commitments,
+        None => {
+            log::error!("Signing package is not found. blockhash: {:?}", blockhash);
+            return prev_state.clone();
+        }
+    };
+
+    let block_height = prev_state.block_height();
+    let federation = params.get_federation_by_block_height(block_height);
+    let pubkey_list = params.pubkey_list(block_height);
+    let commitments = to_signing_commitments(signing_commitments, &pubkey_list);
+    let public_key = federation.node_secret_share().y;
+    let message = &blockhash[..];
+
+    let index = sender_index(sender_id, &pubkey_list) + 1;
+    let public_share = Frost::public_share(index, &federation.node_shared_secrets().to_vss());
+    let local_sig = LocalSig { gamma_i, e };
+    if let Err(e) = Frost::verify_local_sig(
+        &local_sig,
+        index,
+        &public_share,
+        &public_key,
+        message,
+        &commitments,
+    ) {
+        log::error!(
+            "Received invalid local signature. sender: {}, error: {:?}",
+            sender_id,
+            e
+        );
+        return prev_state.clone();
+    }
+
+    log::debug!(
+        "Store local sig, sender: {:?}, gamma_i: {:?}, e: {:?}",
+        sender_id,
+        gamma_i,
+        e
+    );
+    let mut state_builder = Master::from_node_state(prev_state.clone());
+    state_builder.insert_signature(*sender_id, local_sig);
+
+    let mut new_signatures = signatures.clone();
+    new_signatures.insert(*sender_id, (gamma_i, e));
+
+    // Check whether all participants sent their local signatures.
+    if new_signatures.len() < participants.len() {
+        return state_builder.build();
+    }
+
+    let local_sigs: BTreeMap<usize, LocalSig> = new_signatures
+        .iter()
+        .map(|(signer_id, (gamma_i, e))| {
+            (
+                sender_index(signer_id, &pubkey_list) + 1,
+                LocalSig {
+                    gamma_i: *gamma_i,
+                    e: *e,
+                },
+            )
+        })
+        .collect();
+    let signature = match Frost::aggregate(&local_sigs, &public_key, message, &commitments) {
+        Ok(sig) => sig,
+        Err(e) => {
+            log::error!("aggregated signature is invalid. e: {:?}", e);
+            return prev_state.clone();
+        }
+    };
+
+    let completed_block = match submitblock(candidate_block, &signature, &params.rpc) {
+        Ok(block) => block,
+        Err(e) => {
+            log::error!("block rejected by Tapyrus Core: {:?}", e);
+            return state_builder.build();
+        }
+    };
+
+    log::info!(
+        "Round Success. candidateblock(block hash for sign)={:?}",
+        candidate_block.header.signature_hash(),
+    );
+
+    // send completeblock message
+    broadcast_completedblock(completed_block, block_height, params, conman);
+
+    state_builder.round_is_done(true).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::frost::{Nonce, NonceCommitment};
+    use crate::federation::{Federations, SigningScheme};
+    use crate::net::MessageType;
+    use crate::tests::helper::blocks::get_block;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::net::TestConnectionManager;
+    use crate::tests::helper::node_parameters_builder::NodeParametersBuilder;
+    use crate::tests::helper::node_vss::federation;
+    use crate::tests::helper::rpc::MockRpc;
+    use std::collections::HashSet;
+
+    /// Master is signer 0, and signer 0, 1 and 2 participate in the round.
+    fn setup(
+        rpc: MockRpc,
+    ) -> (
+        NodeParameters<MockRpc>,
+        NonceStore,
+        BTreeMap<SignerID, LocalSig>,
+    ) {
+        let block = get_block(0);
+        let message = block.header.signature_hash();
+        let signer_ids = TEST_KEYS.signer_ids();
+        let nonces: Vec<Nonce> = (0..3).map(|_| Nonce::new_random()).collect();
+        let signing_commitments: BTreeMap<SignerID, NonceCommitment> = (0..3)
+            .map(|i| (signer_ids[i], nonces[i].commitment()))
+            .collect();
+        let commitments = to_signing_commitments(&signing_commitments, &TEST_KEYS.pubkeys());
+
+        let local_sigs = (0..3)
+            .map(|i| {
+                let key = federation(i).node_secret_share();
+                let sig = Frost::sign(&nonces[i], i + 1, &key, &message[..], &commitments);
+                (signer_ids[i], sig.unwrap())
+            })
+            .collect();
+
+        let mut nonce_store = NonceStore::new();
+        nonce_store.set_signing_commitments(message, signing_commitments);
+
+        let params = NodeParametersBuilder::new()
+            .rpc(rpc)
+            .public_key(TEST_KEYS.pubkeys()[0])
+            .federations(Federations::new(vec![
+                federation(0).with_signing_scheme(SigningScheme::Frost)
+            ]))
+            .build();
+        (params, nonce_store, local_sigs)
+    }
+
+    fn prev_state(local_sigs: &BTreeMap<SignerID, LocalSig>) -> NodeState {
+        let signer_ids = TEST_KEYS.signer_ids();
+        let participants: HashSet<SignerID> = signer_ids[0..3].iter().cloned().collect();
+        let own_sig = local_sigs.get(&signer_ids[0]).unwrap();
+        Master::default()
+            .candidate_block(Some(get_block(0)))
+            .participants(participants)
+            .insert_signature(
+                signer_ids[0],
+                LocalSig {
+                    gamma_i: own_sig.gamma_i,
+                    e: own_sig.e,
+                },
+            )
+            .block_height(0)
+            .build()
+    }
+
+    #[test]
+    fn test_process_blocksig_frost() {
+        let mut rpc = MockRpc::new();
+        rpc.should_call_submitblock(Ok(()));
+        let (params, nonce_store, local_sigs) = setup(rpc);
+        let conman = TestConnectionManager::new();
+        let blockhash = get_block(0).header.signature_hash();
+        let signer_ids = TEST_KEYS.signer_ids();
+
+        let mut state = prev_state(&local_sigs);
+        for i in 1..3 {
+            let sig = &local_sigs[&signer_ids[i]];
+            state = process_blocksig_frost(
+                &signer_ids[i],
+                blockhash,
+                sig.gamma_i,
+                sig.e,
+                &state,
+                &conman,
+                &params,
+                &nonce_store,
+            );
+        }
+
+        match state {
+            NodeState::Master { round_is_done, .. } => assert!(round_is_done),
+            _ => panic!("NodeState should be Master"),
+        }
+        let broadcasted = conman.broadcasted.borrow();
+        assert_eq!(broadcasted.len(), 1);
+        match &broadcasted[0].message_type {
+            MessageType::Signedcompletedblock(..) => {}
+            _ => panic!("Signedcompletedblock should be broadcasted"),
+        }
+        params.rpc.assert();
+    }
+
+    #[test]
+    fn test_process_blocksig_frost_with_invalid_local_sig() {
+        let (params, nonce_store, local_sigs) = setup(MockRpc::new());
+        let conman = TestConnectionManager::new();
+        let blockhash = get_block(0).header.signature_hash();
+        let signer_ids = TEST_KEYS.signer_ids();
+
+        // The local signature of signer 2 is sent by signer 1.
+        let sig = &local_sigs[&signer_ids[2]];
+        let prev_state = prev_state(&local_sigs);
+        let next_state = process_blocksig_frost(
+            &signer_ids[1],
+            blockhash,
+            sig.gamma_i,
+            sig.e,
+            &prev_state,
+            &conman,
+            &params,
+            &nonce_store,
+        );
+
+        assert_eq!(next_state, prev_state);
+        assert!(conman.broadcasted.borrow().is_empty());
+    }
+}
@@ -52,64 +52,17 @@ where
     };
 
     match prev_state {
-        NodeState::Master {
-            participants,
-            block_height,
-            ..
-        } => {
-            let mut state_builder = Master::from_node_state(prev_state.clone());
-
-            // Broadcast blockparticipants message when the master haven't broadcast yet and met
-            // the threshold.
-            let threshold = params.threshold(*block_height);
-            if participants.len() == 0 && new_shared_block_secrets.len() >= threshold as usize {
-                let participants =
-                    select_participants_for_signing(&new_shared_block_secrets, threshold as usize);
-
-                let shared_block_secrets_by_participants = new_shared_block_secrets
-                    .clone()
-                    .into_iter()
-                    .filter(|(i, ..)| participants.contains(i))
-                    .collect();
-
-                let (block_shared_keys, local_sig) = match generate_local_sig(
-                    candidate_block.header.signature_hash(),
-                    &shared_block_secrets_by_participants,
-                    prev_state,
-                    params,
-                ) {
-                    Ok((is_positive, shared_keys, local_sig)) => {
-                        ((is_positive, shared_keys.x_i, shared_keys.y), local_sig)
-                    }
-                    Err(e) => {
-                        error!("Error: {:?}, state: {:?}", e, prev_state);
-                        return prev_state.clone();
-                    }
-                };
-
-                broadcast_blockparticipants(
-                    &participants,
-                    candidate_block,
-                    conman,
-                    &params.signer_id,
-                );
-
-                broadcast_localsig(
-                    candidate_block.header.signature_hash(),
-                    &local_sig,
-                    conman,
-                    &params.signer_id,
-                );
-
-                state_builder
-                    .participants(participants)
-                    .block_shared_keys(Some(block_shared_keys))
-                    .insert_signature(params.signer_id.clone(), local_sig);
-            }
-
-            state_builder
+        NodeState::Master { .. } => {
+            let state = Master::from_node_state(prev_state.clone())
                 .shared_block_secrets(new_shared_block_secrets)
-                .build()
+                .build();
+            match start_signing_if_threshold_met(&state, candidate_block, conman, params) {
+                Ok(state) => state,
+                Err(e) => {
+                    error!("Error: {:?}, state: {:?}", e, prev_state);
+                    prev_state.clone()
+                }
+            }
         }
         NodeState::Member { participants, .. } => {
             let mut state_builder = Member::from_node_state(prev_state.clone());
@@ -151,6 +104,65 @@ where
     }
 }
 
+/// Broadcast blockparticipants message and own local signature when the master haven't broadcast
+/// yet and the number of collected VSSs met the threshold. Returns the next state of the master.
+pub fn start_signing_if_threshold_met<T, C>(
+    state: &NodeState,
+    candidate_block: &Block,
+    conman: &C,
+    params: &NodeParameters<T>,
+) -> Result<NodeState, Error>
+where
+    T: TapyrusApi,
+    C: ConnectionManager,
+{
+    let (participants, shared_block_secrets, block_height) = match state {
+        NodeState::Master {
+            participants,
+            shared_block_secrets,
+            block_height,
+            ..
+        } => (participants, shared_block_secrets, block_height),
+        _ => return Ok(state.clone()),
+    };
+
+    let threshold = params.threshold(*block_height);
+    if participants.len() != 0 || shared_block_secrets.len() < threshold as usize {
+        return Ok(state.clone());
+    }
+
+    let participants = select_participants_for_signing(shared_block_secrets, threshold as usize);
+
+    let shared_block_secrets_by_participants = shared_block_secrets
+        .clone()
+        .into_iter()
+        .filter(|(i, ..)| participants.contains(i))
+        .collect();
+
+    let (is_positive, shared_keys, local_sig) = generate_local_sig(
+        candidate_block.header.signature_hash(),
+        &shared_block_secrets_by_participants,
+        state,
+        params,
+    )?;
+    let block_shared_keys = (is_positive, shared_keys.x_i, shared_keys.y);
+
+    broadcast_blockparticipants(&participants, candidate_block, conman, &params.signer_id);
+
+    broadcast_localsig(
+        candidate_block.header.signature_hash(),
+        &local_sig,
+        conman,
+        &params.signer_id,
+    );
+
+    Ok(Master::from_node_state(state.clone())
+        .participants(participants)
+        .block_shared_keys(Some(block_shared_keys))
+        .insert_signature(params.signer_id.clone(), local_sig)
+        .build())
+}
+
 fn broadcast_blockparticipants<C: ConnectionManager>(
     participants: &HashSet<SignerID>,
     block: &Block,
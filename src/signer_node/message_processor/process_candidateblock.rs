@@ -1,11 +1,11 @@
-use crate::errors::Error;
 use crate::net::{ConnectionManager, SignerID};
 use crate::rpc::TapyrusApi;
-use crate::signer_node::message_processor::create_block_vss;
+use crate::signer_node::block_vss_pool::BlockVssPool;
+use crate::signer_node::message_processor::bind_block_vss;
 use crate::signer_node::node_state::builder::{Builder, Member};
 use crate::signer_node::utils::sender_index;
 use crate::signer_node::{NodeParameters, NodeState};
-use tapyrus::blockdata::block::{Block, XField};
+use tapyrus::blockdata::block::Block;
 
 pub fn process_candidateblock<T, C>(
     sender_id: &SignerID,
@@ -13,6 +13,7 @@ pub fn process_candidateblock<T, C>(
     prev_state: &NodeState,
     conman: &C,
     params: &NodeParameters<T>,
+    block_vss_pool: &mut BlockVssPool,
 ) -> NodeState
 where
     T: TapyrusApi,
@@ -27,10 +28,11 @@ where
     }
 
     let block_height = prev_state.block_height();
-    if let Err(_) = verify_block(block, block_height, params) {
-        log::error!(
-            "Block is invalid. sender: {}, block: {:?}",
+    if let Err(rejection) = params.validate_candidate_block(block, block_height) {
+        log::warn!(
+            "Reject the candidate block from {}: {}. block: {:?}",
             sender_id,
+            rejection,
             block,
         );
         return prev_state.clone();
@@ -41,90 +43,40 @@ where
         block.header.signature_hash()
     );
 
-    if let Err(e) = params.rpc.testproposedblock(&block) {
-        log::warn!(
-            "Received Invalid candidate block sender: {}, {:?}",
-            sender_id,
-            e
-        );
-        return prev_state.clone();
-    }
-
-    let (key, shared_secret_for_positive, shared_secret_for_negative) =
-        create_block_vss(block.clone(), params, conman, block_height);
+    let (block_key, shared_block_secrets) = bind_block_vss(
+        block,
+        sender_id,
+        params,
+        conman,
+        block_height,
+        block_vss_pool,
+    );
 
     Member::default()
         .block_height(block_height)
-        .block_key(Some(key.u_i))
+        .block_key(Some(block_key))
         .candidate_block(Some(block.clone()))
         .master_index(sender_index(sender_id, &params.pubkey_list(block_height)))
-        .insert_shared_block_secrets(
-            params.signer_id.clone(),
-            shared_secret_for_positive,
-            shared_secret_for_negative,
-        )
+        .shared_block_secrets(shared_block_secrets)
         .build()
 }
 
-fn verify_block<T>(
-    block: &Block,
-    block_height: u64,
-    params: &NodeParameters<T>,
-) -> Result<(), Error>
-where
-    T: TapyrusApi,
-{
-    match block.header.xfield {
-        XField::Unknown(_, _) => return Err(Error::UnsupportedXField),
-        _ => {}
-    }
-    verify_aggregated_public_key(block, block_height, params)
-}
-
-fn verify_aggregated_public_key<T>(
-    block: &Block,
-    block_height: u64,
-    params: &NodeParameters<T>,
-) -> Result<(), Error>
-where
-    T: TapyrusApi,
-{
-    let next_block_height = block_height + 1;
-    let federation = params.get_federation_by_block_height(next_block_height);
-    if let Some(public_key) = block.header.aggregated_public_key() {
-        if public_key == federation.aggregated_public_key()
-            && next_block_height == federation.block_height()
-        {
-            Ok(())
-        } else {
-            Err(Error::InvalidAggregatedPublicKey)
-        }
-    } else {
-        if next_block_height == federation.block_height() {
-            Err(Error::InvalidAggregatedPublicKey)
-        } else {
-            Ok(())
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::federation::{Federation, Federations};
+    use crate::crypto::vss::Vss;
     use crate::net::{Message, MessageType, SignerID};
     use crate::signer_node::node_state::builder::{Builder, Master, Member};
-    use crate::signer_node::{master_index, NodeState};
+    use crate::signer_node::{master_index, NodeState, SharedSecret};
     use crate::tests::helper::blocks::get_block;
     use crate::tests::helper::keys::TEST_KEYS;
     use crate::tests::helper::net::TestConnectionManager;
     use crate::tests::helper::node_parameters_builder::NodeParametersBuilder;
     use crate::tests::helper::node_state_builder::BuilderForTest;
-    use crate::tests::helper::node_vss::node_vss;
     use crate::tests::helper::rpc::MockRpc;
-    use std::str::FromStr;
+    use curv::elliptic::curves::traits::ECScalar;
+    use curv::FE;
     use tapyrus::consensus::encode::deserialize;
-    use tapyrus::PublicKey;
 
     fn sender_id() -> SignerID {
         TEST_KEYS.signer_ids()[2]
@@ -142,8 +94,14 @@ mod tests {
         rpc.should_call_testproposedblock(Ok(true));
         let params = NodeParametersBuilder::new().rpc(rpc).build();
 
-        let next_state =
-            process_candidateblock(&sender_id, &candidate_block, &prev_state, &conman, &params);
+        let next_state = process_candidateblock(
+            &sender_id,
+            &candidate_block,
+            &prev_state,
+            &conman,
+            &params,
+            &mut BlockVssPool::new(),
+        );
 
         // It should set candidate_block into return state.
         match next_state {
@@ -181,8 +139,14 @@ mod tests {
         rpc.should_call_testproposedblock_and_returns_invalid_block_error();
         let params = NodeParametersBuilder::new().rpc(rpc).build();
 
-        let next_state =
-            process_candidateblock(&sender_id, &candidate_block, &prev_state, &conman, &params);
+        let next_state = process_candidateblock(
+            &sender_id,
+            &candidate_block,
+            &prev_state,
+            &conman,
+            &params,
+            &mut BlockVssPool::new(),
+        );
 
         // It should not be changed any thing.
         assert_eq!(next_state, prev_state);
@@ -194,6 +158,98 @@ mod tests {
         params.rpc.assert();
     }
 
+    #[test]
+    fn test_as_member_with_block_rejected_by_core() {
+        let sender_id = sender_id();
+        let candidate_block = get_block(0);
+        let prev_state = Member::for_test().build();
+        let conman = TestConnectionManager::new();
+        let mut rpc = MockRpc::new();
+        // testproposedblock returns false without an error.
+        rpc.should_call_testproposedblock(Ok(false));
+        let params = NodeParametersBuilder::new().rpc(rpc).build();
+
+        let next_state = process_candidateblock(
+            &sender_id,
+            &candidate_block,
+            &prev_state,
+            &conman,
+            &params,
+            &mut BlockVssPool::new(),
+        );
+
+        // It should not set candidate_block into return state.
+        assert_eq!(prev_state, next_state);
+        assert_eq!(conman.sent.borrow().len(), 0);
+        assert_eq!(
+            params
+                .candidate_validator
+                .rejections()
+                .get("rejected-by-core"),
+            Some(&1)
+        );
+
+        params.rpc.assert();
+    }
+
+    #[test]
+    fn test_as_member_with_pre_shared_block_vss() {
+        let sender_id = sender_id();
+        let candidate_block = get_block(0);
+        let prev_state = Member::for_test().build();
+        let conman = TestConnectionManager::new();
+        let mut rpc = MockRpc::new();
+        rpc.should_call_testproposedblock(Ok(true));
+        let params = NodeParametersBuilder::new().rpc(rpc).build();
+
+        let shared_secret = || {
+            let (_, vss, secret_shares, _, _) = Vss::create_block_shares(1, 3, 5);
+            SharedSecret {
+                vss,
+                secret_share: secret_shares[0],
+            }
+        };
+        let block_key: FE = ECScalar::new_random();
+        let mut pool = BlockVssPool::new();
+        pool.insert_own(0, &sender_id, (block_key, shared_secret(), shared_secret()));
+        pool.insert_received(
+            0,
+            &sender_id,
+            &TEST_KEYS.signer_ids()[0],
+            shared_secret(),
+            shared_secret(),
+        );
+
+        let next_state = process_candidateblock(
+            &sender_id,
+            &candidate_block,
+            &prev_state,
+            &conman,
+            &params,
+            &mut pool,
+        );
+
+        // It should bind the pre-shared block VSSs to the candidate block.
+        match next_state {
+            NodeState::Member {
+                block_key: Some(key),
+                shared_block_secrets,
+                ..
+            } => {
+                assert_eq!(key, block_key);
+                assert_eq!(shared_block_secrets.len(), 2);
+                assert!(shared_block_secrets.contains_key(&params.signer_id));
+            }
+            _ => assert!(false),
+        }
+
+        // It should not send blockvss messages.
+        assert_eq!(conman.sent.borrow().len(), 0);
+        assert!(!pool.contains_own(0, &sender_id));
+
+        params.rpc.assert();
+    }
+
     #[test]
     fn test_as_member_with_invalid_block() {
         let sender_id = sender_id();
@@ -207,8 +263,14 @@ mod tests {
         rpc.should_call_testproposedblock_and_returns_invalid_block_error();
         let params = NodeParametersBuilder::new().rpc(rpc).build();
 
-        let next_state =
-            process_candidateblock(&sender_id, &candidate_block, &prev_state, &conman, &params);
+        let next_state = process_candidateblock(
+            &sender_id,
+            &candidate_block,
+            &prev_state,
+            &conman,
+            &params,
+            &mut BlockVssPool::new(),
+        );
 
         // It should not set candidate_block into return state.
         assert_eq!(prev_state, next_state);
@@ -249,14 +311,26 @@ mod tests {
 
         // Step 1.
         let sender_id = SignerID::new(TEST_KEYS.pubkeys()[0]);
-        let state =
-            process_candidateblock(&sender_id, &candidate_block, &prev_state, &conman, &params);
+        let state = process_candidateblock(
+            &sender_id,
+            &candidate_block,
+            &prev_state,
+            &conman,
+            &params,
+            &mut BlockVssPool::new(),
+        );
         assert_eq!(master_index(&state, &params).unwrap(), 0);
 
         // Step 2.
         let sender_id = SignerID::new(TEST_KEYS.pubkeys()[4]);
-        let state =
-            process_candidateblock(&sender_id, &candidate_block, &prev_state, &conman, &params);
+        let state = process_candidateblock(
+            &sender_id,
+            &candidate_block,
+            &prev_state,
+            &conman,
+            &params,
+            &mut BlockVssPool::new(),
+        );
         assert_eq!(master_index(&state, &params).unwrap(), 4);
 
         params.rpc.assert();
@@ -279,8 +353,14 @@ mod tests {
             .build();
 
         let sender_id = SignerID::new(TEST_KEYS.pubkeys()[0]);
-        let state =
-            process_candidateblock(&sender_id, &candidate_block, &prev_state, &conman, &params);
+        let state = process_candidateblock(
+            &sender_id,
+            &candidate_block,
+            &prev_state,
+            &conman,
+            &params,
+            &mut BlockVssPool::new(),
+        );
 
         // The state should be Member and master_index should be 0.
         match state {
@@ -290,70 +370,4 @@ mod tests {
 
         params.rpc.assert();
     }
-
-    const TEST_BLOCK_WITH_PUBKEY: &str = "010000000000000000000000000000000000000000000000000000000000000000000000e7c526d0125538b13a50b06465fb8b72120be13fb1142e93aba2aabb2a4f369826c18219f76e4d0ebddbaa9b744837c2ac65b347673695a23c3cc1a2be4141e1427d735e0121030d856ac9f5871c3785a2d76e3a5d9eca6fcce70f4de63339671dfb9d1f33edb0000101000000010000000000000000000000000000000000000000000000000000000000000000000000002221025700236c2890233592fcef262f4520d22af9160e3d9705855140eb2aa06c35d3ffffffff0100f2052a010000001976a914834e0737cdb9008db614cd95ec98824e952e3dc588ac00000000";
-    const TEST_BLOCK_WITHOUT_PUBKEY: &str = "010000000000000000000000000000000000000000000000000000000000000000000000e7c526d0125538b13a50b06465fb8b72120be13fb1142e93aba2aabb2a4f369826c18219f76e4d0ebddbaa9b744837c2ac65b347673695a23c3cc1a2be4141e1427d735e00000101000000010000000000000000000000000000000000000000000000000000000000000000000000002221025700236c2890233592fcef262f4520d22af9160e3d9705855140eb2aa06c35d3ffffffff0100f2052a010000001976a914834e0737cdb9008db614cd95ec98824e952e3dc588ac00000000";
-
-    fn test_block_with_public_key() -> Block {
-        let raw_block = hex::decode(TEST_BLOCK_WITH_PUBKEY).unwrap();
-        deserialize(&raw_block).unwrap()
-    }
-
-    fn test_block_without_public_key() -> Block {
-        let raw_block = hex::decode(TEST_BLOCK_WITHOUT_PUBKEY).unwrap();
-        deserialize(&raw_block).unwrap()
-    }
-    #[test]
-    fn test_verify_aggregated_public_key() {
-        let federation0 = Federation::new(
-            TEST_KEYS.pubkeys()[4],
-            0,
-            Some(3),
-            Some(node_vss(0)),
-            TEST_KEYS.aggregated(),
-        );
-        let federation100 = Federation::new(
-            TEST_KEYS.pubkeys()[4],
-            100,
-            Some(3),
-            Some(node_vss(1)),
-            TEST_KEYS.aggregated(),
-        );
-        let another_key = PublicKey::from_str(
-            "030acd6af981c498ebf2ffd9a341d2a96bde5832c150e7d300fa3583eee0f964fe",
-        )
-        .unwrap();
-        let federation200 = Federation::new(
-            TEST_KEYS.pubkeys()[4],
-            200,
-            Some(4),
-            Some(node_vss(2)),
-            another_key,
-        );
-        let federations = Federations::new(vec![
-            federation0.clone(),
-            federation100.clone(),
-            federation200.clone(),
-        ]);
-        let params = NodeParametersBuilder::new()
-            .public_key(TEST_KEYS.pubkeys()[2])
-            .rpc(MockRpc::new())
-            .federations(federations)
-            .build();
-
-        let block = test_block_with_public_key();
-        assert!(verify_aggregated_public_key(&block, 99, &params).is_ok());
-
-        let block = test_block_with_public_key();
-        assert!(verify_aggregated_public_key(&block, 100, &params).is_err());
-
-        let block = test_block_with_public_key();
-        assert!(verify_aggregated_public_key(&block, 199, &params).is_err());
-
-        let block = test_block_without_public_key();
-        assert!(verify_aggregated_public_key(&block, 99, &params).is_err());
-
-        let block = test_block_without_public_key();
-        assert!(verify_aggregated_public_key(&block, 100, &params).is_ok());
-    }
 }
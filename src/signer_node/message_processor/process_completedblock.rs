@@ -1,11 +1,15 @@
 use crate::net::SignerID;
 use crate::rpc::TapyrusApi;
+use crate::signer_node::evidence::{verify_completed_block, verify_proof, Evidence};
+use crate::signer_node::master_signature::{MasterSignature, SignedBlock};
 use crate::signer_node::{is_master, master_index, NodeParameters, NodeState};
 use tapyrus::blockdata::block::Block;
 
 pub fn process_completedblock<T>(
     sender_id: &SignerID,
     block: &Block,
+    signature: Option<&MasterSignature>,
+    candidate_signature: Option<&MasterSignature>,
     prev_state: &NodeState,
     params: &NodeParameters<T>,
 ) -> NodeState
@@ -17,7 +21,60 @@ where
         return prev_state.clone(); // Ignore message
     }
 
-    if let Err(e) = params.rpc.submitblock(block) {
+    let block_height = prev_state.block_height();
+    if let Some(signature) = signature {
+        if let Err(e) = signature.verify(
+            SignedBlock::Completed,
+            block_height,
+            &block.header,
+            sender_id,
+        ) {
+            log::warn!(
+                "Ignore the completed block from {}, because the signature is invalid: {:?}",
+                sender_id,
+                e
+            );
+            return prev_state.clone();
+        }
+    }
+
+    let candidate = match prev_state {
+        NodeState::Member {
+            candidate_block, ..
+        } => candidate_block.as_ref(),
+        _ => None,
+    };
+    let aggregated_public_key = params.aggregated_public_key(block_height);
+    let result = match candidate {
+        Some(candidate) => verify_completed_block(block, candidate, &aggregated_public_key),
+        // The node missed the candidate block, so it can only check the proof.
+        None => verify_proof(block, &aggregated_public_key),
+    };
+    if let Err(reason) = result {
+        log::error!(
+            "Refuse to submit the completed block from the master {}: {}. block: {:?}",
+            sender_id,
+            reason,
+            block
+        );
+        // Other signers can verify the evidence only if the master signed both blocks.
+        if let (Some(candidate), Some(candidate_signature), Some(signature)) =
+            (candidate, candidate_signature, signature)
+        {
+            record_evidence(
+                Evidence::InvalidCompletedBlock {
+                    block_height,
+                    master: *sender_id,
+                    candidate: candidate.clone(),
+                    candidate_signature: candidate_signature.clone(),
+                    completed: block.clone(),
+                    completed_signature: signature.clone(),
+                    reason,
+                },
+                params,
+            );
+        }
+    } else if let Err(e) = params.rpc.submitblock(block) {
         log::warn!(
             "The node got invalid completed block or it was already relayed via Tapyrus network. from-peer: {}, block: {:?}, rpc error: {:?}",
             sender_id,
@@ -29,7 +86,23 @@ where
     NodeState::RoundComplete {
         master_index: master_index(prev_state, params)
             .expect("Previous state getting round complete should have round master"),
-        block_height: prev_state.block_height(),
+        block_height,
+    }
+}
+
+/// Records the evidence only if other signers can verify it, so that the node never accuses the
+/// master of what the master didn't sign.
+fn record_evidence<T>(evidence: Evidence, params: &NodeParameters<T>)
+where
+    T: TapyrusApi,
+{
+    match evidence.verify(params.federations()) {
+        Ok(()) => params.evidence.record(evidence),
+        Err(e) => log::warn!(
+            "Don't record the evidence against the master {}, because it can't be verified: {}",
+            evidence.master(),
+            e
+        ),
     }
 }
 
@@ -38,34 +111,67 @@ mod tests {
     use super::process_completedblock;
     use crate::errors::Error;
     use crate::net::SignerID;
+    use crate::signer_node::evidence::Evidence;
+    use crate::signer_node::master_signature::{MasterSignature, SignedBlock};
     use crate::signer_node::node_state::builder::{Builder, Master, Member};
-    use crate::signer_node::{master_index, NodeState};
-    use crate::tests::helper::blocks::get_block;
+    use crate::signer_node::{master_index, NodeParameters, NodeState};
+    use crate::tests::helper::blocks::{get_block, master_sign, sign};
     use crate::tests::helper::keys::TEST_KEYS;
     use crate::tests::helper::node_parameters_builder::NodeParametersBuilder;
     use crate::tests::helper::node_state_builder::BuilderForTest;
     use crate::tests::helper::rpc::MockRpc;
+    use tapyrus::blockdata::block::Block;
 
-    #[test]
-    fn test_process_completedblock() {
-        let block = get_block(0);
-        let mut rpc = MockRpc::new();
-        rpc.should_call_submitblock(Ok(()));
+    /// The master 0 sends the completed block to the member which got the candidate block.
+    fn process(
+        rpc: MockRpc,
+        candidate_block: Option<Block>,
+        block: &Block,
+        candidate_signature: Option<MasterSignature>,
+    ) -> (NodeState, NodeParameters<MockRpc>) {
         let params = NodeParametersBuilder::new().rpc(rpc).build();
-
-        // check 1, node state should be RoundComplete after process completeblock message.
-        let prev_state = Member::for_test().master_index(0).build();
+        let prev_state = Member::for_test()
+            .master_index(0)
+            .candidate_block(candidate_block)
+            .build();
         let sender_id = SignerID::new(TEST_KEYS.pubkeys()[0]);
-        let state = process_completedblock(&sender_id, &block, &prev_state, &params);
-
+        let signature = master_sign(SignedBlock::Completed, 0, block, 0);
+        let state = process_completedblock(
+            &sender_id,
+            block,
+            Some(&signature),
+            candidate_signature.as_ref(),
+            &prev_state,
+            &params,
+        );
         params.rpc.assert();
+        (state, params)
+    }
 
-        match &state {
+    fn assert_round_complete(state: &NodeState) {
+        match state {
             NodeState::RoundComplete { master_index, .. } => assert_eq!(*master_index, 0),
-            n => assert!(false, "Should be RoundComplete, but the state is {:?}", n),
+            n => panic!("Should be RoundComplete, but the state is {:?}", n),
         }
     }
 
+    #[test]
+    fn test_process_completedblock() {
+        // check 1, node state should be RoundComplete after process completeblock message.
+        let mut rpc = MockRpc::new();
+        rpc.should_call_submitblock(Ok(()));
+        let candidate_signature = master_sign(SignedBlock::Candidate, 0, &get_block(0), 0);
+        let (state, params) = process(
+            rpc,
+            Some(get_block(0)),
+            &sign(get_block(0)),
+            Some(candidate_signature),
+        );
+
+        assert_round_complete(&state);
+        assert!(params.evidence.records().is_empty());
+    }
+
     #[test]
     fn test_process_master_received_completedblock() {
         let block = get_block(0);
@@ -73,8 +179,16 @@ mod tests {
 
         let prev_state = Master::for_test().build();
         let sender_id = SignerID::new(TEST_KEYS.pubkeys()[0]);
+        let signature = master_sign(SignedBlock::Completed, 0, &block, 0);
 
-        let state = process_completedblock(&sender_id, &block, &prev_state, &params);
+        let state = process_completedblock(
+            &sender_id,
+            &block,
+            Some(&signature),
+            None,
+            &prev_state,
+            &params,
+        );
 
         // if master receives completedblock message, it does not change state
         assert_eq!(prev_state, state);
@@ -82,7 +196,6 @@ mod tests {
 
     #[test]
     fn test_process_completedblock_with_submit_block_failure() {
-        let block = get_block(0);
         let mut rpc = MockRpc::new();
         rpc.should_call_submitblock(Err(Error::JsonRpc(jsonrpc::error::Error::Rpc(
             jsonrpc::error::RpcError {
@@ -91,18 +204,15 @@ mod tests {
                 data: None,
             },
         ))));
-        let params = NodeParametersBuilder::new().rpc(rpc).build();
-
-        let prev_state = Member::for_test().master_index(0).build();
-        let sender_id = SignerID::new(TEST_KEYS.pubkeys()[0]);
-        let state = process_completedblock(&sender_id, &block, &prev_state, &params);
-
-        params.rpc.assert();
+        let candidate_signature = master_sign(SignedBlock::Candidate, 0, &get_block(0), 0);
+        let (state, _) = process(
+            rpc,
+            Some(get_block(0)),
+            &sign(get_block(0)),
+            Some(candidate_signature),
+        );
 
-        match &state {
-            NodeState::RoundComplete { master_index, .. } => assert_eq!(*master_index, 0),
-            n => assert!(false, "Should be RoundComplete, but the state is {:?}", n),
-        }
+        assert_round_complete(&state);
     }
 
     #[test]
@@ -113,7 +223,15 @@ mod tests {
 
         let prev_state = Member::for_test().master_index(0).build();
         let sender_id = SignerID::new(TEST_KEYS.pubkeys()[4]);
-        let state = process_completedblock(&sender_id, &block, &prev_state, &params);
+        let signature = master_sign(SignedBlock::Completed, 0, &block, 4);
+        let state = process_completedblock(
+            &sender_id,
+            &block,
+            Some(&signature),
+            None,
+            &prev_state,
+            &params,
+        );
 
         params.rpc.assert();
 
@@ -124,4 +242,140 @@ mod tests {
             n => panic!("Should be Member, but state:{:?}", n),
         }
     }
+
+    #[test]
+    fn test_process_completedblock_with_invalid_signature() {
+        let block = sign(get_block(0));
+        // MockRpc panics if submitblock is called.
+        let params = NodeParametersBuilder::new().rpc(MockRpc::new()).build();
+
+        let prev_state = Member::for_test()
+            .master_index(0)
+            .candidate_block(Some(get_block(0)))
+            .build();
+        let sender_id = SignerID::new(TEST_KEYS.pubkeys()[0]);
+        let candidate_signature = master_sign(SignedBlock::Candidate, 0, &get_block(0), 0);
+        for signature in vec![
+            master_sign(SignedBlock::Completed, 0, &block, 1),
+            master_sign(SignedBlock::Candidate, 0, &block, 0),
+            master_sign(SignedBlock::Completed, 0, &get_block(0), 0),
+        ] {
+            let state = process_completedblock(
+                &sender_id,
+                &block,
+                Some(&signature),
+                Some(&candidate_signature),
+                &prev_state,
+                &params,
+            );
+            assert_eq!(state, prev_state);
+        }
+
+        params.rpc.assert();
+        assert!(params.evidence.records().is_empty());
+    }
+
+    #[test]
+    fn test_process_completedblock_without_candidate() {
+        // The node which missed the candidate block submits the completed block if its proof is
+        // valid.
+        let mut rpc = MockRpc::new();
+        rpc.should_call_submitblock(Ok(()));
+        let (state, params) = process(rpc, None, &sign(get_block(0)), None);
+        assert_round_complete(&state);
+        assert!(params.evidence.records().is_empty());
+
+        // It refuses the block with an invalid proof, but it has no evidence against the master.
+        let (state, params) = process(MockRpc::new(), None, &get_block(0), None);
+        assert_round_complete(&state);
+        assert!(params.evidence.records().is_empty());
+    }
+
+    #[test]
+    fn test_process_completedblock_without_signature() {
+        // The master which doesn't sign blocks sends the completed block without the signature.
+        let sender_id = SignerID::new(TEST_KEYS.pubkeys()[0]);
+        let prev_state = Member::for_test()
+            .master_index(0)
+            .candidate_block(Some(get_block(0)))
+            .build();
+        let mut rpc = MockRpc::new();
+        rpc.should_call_submitblock(Ok(()));
+        let params = NodeParametersBuilder::new().rpc(rpc).build();
+        let state = process_completedblock(
+            &sender_id,
+            &sign(get_block(0)),
+            None,
+            None,
+            &prev_state,
+            &params,
+        );
+        params.rpc.assert();
+        assert_round_complete(&state);
+
+        // It is still checked against the candidate block, but there is no evidence.
+        let params = NodeParametersBuilder::new().rpc(MockRpc::new()).build();
+        let state = process_completedblock(
+            &sender_id,
+            &sign(get_block(1)),
+            None,
+            None,
+            &prev_state,
+            &params,
+        );
+        params.rpc.assert();
+        assert_round_complete(&state);
+        assert!(params.evidence.records().is_empty());
+    }
+
+    fn assert_refused(candidate_block: Block, block: Block, reason: &str) {
+        // MockRpc panics if submitblock is called.
+        // Without the signature of the candidate block, the node refuses the block but records
+        // no evidence.
+        let (state, params) = process(MockRpc::new(), Some(candidate_block.clone()), &block, None);
+        assert_round_complete(&state);
+        assert!(params.evidence.records().is_empty());
+
+        let candidate_signature = master_sign(SignedBlock::Candidate, 0, &candidate_block, 0);
+        let (state, params) = process(
+            MockRpc::new(),
+            Some(candidate_block.clone()),
+            &block,
+            Some(candidate_signature.clone()),
+        );
+        assert_round_complete(&state);
+
+        let records = params.evidence.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].evidence,
+            Evidence::InvalidCompletedBlock {
+                block_height: 0,
+                master: SignerID::new(TEST_KEYS.pubkeys()[0]),
+                candidate: candidate_block,
+                candidate_signature,
+                completed_signature: master_sign(SignedBlock::Completed, 0, &block, 0),
+                completed: block,
+                reason: reason.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_process_completedblock_different_from_candidate() {
+        assert_refused(
+            get_block(1),
+            sign(get_block(0)),
+            "the block is different from the candidate block",
+        );
+    }
+
+    #[test]
+    fn test_process_completedblock_with_invalid_proof() {
+        assert_refused(
+            get_block(0),
+            get_block(0),
+            "the proof is invalid: IncompleteBlock",
+        );
+    }
 }
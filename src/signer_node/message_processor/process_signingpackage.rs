@@ -0,0 +1,206 @@
+use crate::crypto::frost::NonceCommitment;
+use crate::net::{ConnectionManager, SignerID};
+use crate::rpc::TapyrusApi;
+use crate::signer_node::message_processor::{broadcast_localsig, create_frost_local_sig};
+use crate::signer_node::node_state::builder::{Builder, Member};
+use crate::signer_node::nonce_store::NonceStore;
+use crate::signer_node::utils::sender_index;
+use crate::signer_node::{is_master, NodeParameters, NodeState};
+use std::collections::{BTreeMap, HashSet};
+use tapyrus::blockdata::block::Block;
+
+/// Processes a signing package of the FROST signing scheme, which the master sends instead of
+/// candidateblock. If the node is one of the participants, it sends own local signature to the
+/// master immediately, so there is no Block VSS exchange.
+pub fn process_signingpackage<T, C>(
+    sender_id: &SignerID,
+    block: &Block,
+    signing_commitments: &BTreeMap<SignerID, NonceCommitment>,
+    prev_state: &NodeState,
+    conman: &C,
+    params: &NodeParameters<T>,
+    nonce_store: &mut NonceStore,
+) -> NodeState
+where
+    T: TapyrusApi,
+    C: ConnectionManager,
+{
+    // Guard the prev_state is Member and the sender is the master of the round.
+    match &prev_state {
+        NodeState::Member { .. } if is_master(sender_id, prev_state, params) => {}
+        _ => return prev_state.clone(),
+    }
+
+    let block_height = prev_state.block_height();
+    let federation = params.get_federation_by_block_height(block_height);
+    if signing_commitments.len() < params.threshold(block_height) as usize
+        || signing_commitments
+            .keys()
+            .any(|i| !federation.signers().contains(i))
+    {
+        log::error!(
+            "Invalid signing package. sender: {}, participants: {:?}",
+            sender_id,
+            signing_commitments.keys(),
+        );
+        return prev_state.clone();
+    }
+
+    if let Err(rejection) = params.validate_candidate_block(block, block_height) {
+        log::warn!(
+            "Reject the candidate block from {}: {}. block: {:?}",
+            sender_id,
+            rejection,
+            block,
+        );
+        return prev_state.clone();
+    }
+
+    log::info!(
+        "signingpackage received. block hash for signing: {:?}",
+        block.header.signature_hash()
+    );
+
+    if signing_commitments.contains_key(&params.signer_id) {
+        match create_frost_local_sig(
+            block,
+            signing_commitments,
+            nonce_store,
+            block_height,
+            params,
+        ) {
+            Ok(local_sig) => broadcast_localsig(
+                block.header.signature_hash(),
+                &local_sig,
+                conman,
+                &params.signer_id,
+            ),
+            Err(e) => log::error!("Can not create local signature: {:?}", e),
+        }
+    }
+
+    Member::default()
+        .block_height(block_height)
+        .candidate_block(Some(block.clone()))
+        .participants(
+            signing_commitments
+                .keys()
+                .cloned()
+                .collect::<HashSet<SignerID>>(),
+        )
+        .master_index(sender_index(sender_id, &params.pubkey_list(block_height)))
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::federation::{Federations, SigningScheme};
+    use crate::net::MessageType;
+    use crate::tests::helper::blocks::get_block;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::net::TestConnectionManager;
+    use crate::tests::helper::node_parameters_builder::NodeParametersBuilder;
+    use crate::tests::helper::node_vss::federation;
+    use crate::tests::helper::rpc::MockRpc;
+
+    fn params(rpc: MockRpc) -> NodeParameters<MockRpc> {
+        let federation = federation(1).with_signing_scheme(SigningScheme::Frost);
+        NodeParametersBuilder::new()
+            .rpc(rpc)
+            .public_key(TEST_KEYS.pubkeys()[1])
+            .federations(Federations::new(vec![federation]))
+            .build()
+    }
+
+    fn signing_commitments(nonce_store: &mut NonceStore) -> BTreeMap<SignerID, NonceCommitment> {
+        let signer_ids = TEST_KEYS.signer_ids();
+        let mut commitments = BTreeMap::new();
+        commitments.insert(
+            signer_ids[0],
+            NonceStore::new().fill(signer_ids[0], 1)[0].clone(),
+        );
+        commitments.insert(signer_ids[1], nonce_store.fill(signer_ids[1], 1)[0].clone());
+        commitments.insert(
+            signer_ids[2],
+            NonceStore::new().fill(signer_ids[2], 1)[0].clone(),
+        );
+        commitments
+    }
+
+    #[test]
+    fn test_process_signingpackage() {
+        let sender_id = TEST_KEYS.signer_ids()[0];
+        let block = get_block(0);
+        let prev_state = Member::default().master_index(0).block_height(0).build();
+        let conman = TestConnectionManager::new();
+        let mut rpc = MockRpc::new();
+        rpc.should_call_testproposedblock(Ok(true));
+        let params = params(rpc);
+        let mut nonce_store = NonceStore::new();
+        let commitments = signing_commitments(&mut nonce_store);
+
+        let next_state = process_signingpackage(
+            &sender_id,
+            &block,
+            &commitments,
+            &prev_state,
+            &conman,
+            &params,
+            &mut nonce_store,
+        );
+
+        match next_state {
+            NodeState::Member {
+                candidate_block,
+                participants,
+                master_index,
+                ..
+            } => {
+                assert_eq!(candidate_block, Some(block.clone()));
+                assert_eq!(participants.len(), 3);
+                assert_eq!(master_index, 0);
+            }
+            _ => panic!("NodeState should be Member"),
+        }
+
+        // It should broadcast own local signature.
+        let broadcasted = conman.broadcasted.borrow();
+        assert_eq!(broadcasted.len(), 1);
+        match &broadcasted[0].message_type {
+            MessageType::Blocksig(blockhash, _, _) => {
+                assert_eq!(*blockhash, block.header.signature_hash())
+            }
+            _ => panic!("Blocksig should be broadcasted"),
+        }
+
+        // The nonce should not be used again.
+        let commitment = &commitments[&params.signer_id];
+        assert!(nonce_store.take_nonce(commitment).is_none());
+        params.rpc.assert();
+    }
+
+    #[test]
+    fn test_process_signingpackage_from_non_master() {
+        let sender_id = TEST_KEYS.signer_ids()[2];
+        let block = get_block(0);
+        let prev_state = Member::default().master_index(0).block_height(0).build();
+        let conman = TestConnectionManager::new();
+        let params = params(MockRpc::new());
+        let mut nonce_store = NonceStore::new();
+        let commitments = signing_commitments(&mut nonce_store);
+
+        let next_state = process_signingpackage(
+            &sender_id,
+            &block,
+            &commitments,
+            &prev_state,
+            &conman,
+            &params,
+            &mut nonce_store,
+        );
+
+        assert_eq!(next_state, prev_state);
+        assert!(conman.broadcasted.borrow().is_empty());
+    }
+}
@@ -2,35 +2,61 @@
 // Distributed under the MIT software license, see the accompanying
 // file COPYING or http://www.opensource.org/licenses/mit-license.php.
 
+pub mod block_vss_pool;
+pub mod equivocation;
+pub mod evidence;
+pub mod master_signature;
 mod message_processor;
 pub mod node_parameters;
 pub mod node_state;
+pub mod nonce_store;
+pub mod production;
+pub mod recorder;
+pub mod replay;
+pub mod schedule;
 pub mod utils;
+pub mod validation;
 
 pub use crate::signer_node::node_parameters::NodeParameters;
 pub use crate::signer_node::node_state::NodeState;
 
 use crate::errors::Error;
+use crate::federation::SigningScheme;
 use crate::net::{ConnectionManager, ConnectionManagerError, Message, MessageType, SignerID};
 use crate::rpc::{GetBlockchainInfoResult, TapyrusApi};
-use crate::signer_node::message_processor::create_block_vss;
+use crate::signer_node::block_vss_pool::BlockVssPool;
+use crate::signer_node::equivocation::{CandidateDigest, EquivocationDetector};
+use crate::signer_node::evidence::Evidence;
+use crate::signer_node::master_signature::{MasterSignature, SignedBlock};
+use crate::signer_node::message_processor::bind_block_vss;
+use crate::signer_node::message_processor::create_frost_local_sig;
+use crate::signer_node::message_processor::precompute_block_vss;
 use crate::signer_node::message_processor::process_blockparticipants;
 use crate::signer_node::message_processor::process_blocksig;
+use crate::signer_node::message_processor::process_blocksig_frost;
 use crate::signer_node::message_processor::process_blockvss;
 use crate::signer_node::message_processor::process_candidateblock;
 use crate::signer_node::message_processor::process_completedblock;
+use crate::signer_node::message_processor::process_signingpackage;
+use crate::signer_node::message_processor::start_signing_if_threshold_met;
+use crate::signer_node::message_processor::verify_pre_block_vss;
 use crate::signer_node::node_state::builder::{Builder, Master, Member};
-use crate::timer::RoundTimeOutObserver;
+use crate::signer_node::nonce_store::{NonceStore, NONCE_POOL_SIZE};
+use crate::signer_node::production::Decision;
+use crate::signer_node::recorder::{copy_message, Event, Recorder};
+use crate::signer_node::schedule::SlotPosition;
+use crate::timer::{RoundTimeOutObserver, VirtualClock};
 
 use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
 use curv::FE;
 use redis::ControlFlow;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tapyrus::blockdata::block::Block;
 use tapyrus::blockdata::block::XField;
+use tapyrus::hash_types::BlockHash;
 
 /// Round interval.
 pub static ROUND_INTERVAL_DEFAULT_SECS: u64 = 60;
@@ -57,6 +83,42 @@ pub struct SignerNode<T: TapyrusApi, C: ConnectionManager> {
     /// If the timer was up, the node starts round communication with getting a block and sending
     /// candidateblock message.
     round_interval_timer: RoundTimeOutObserver,
+
+    /// ## Nonce Store
+    /// Own nonces and nonce commitments of all signers, which are used when the federation uses
+    /// the FROST signing scheme.
+    nonce_store: NonceStore,
+
+    /// ## Block VSS Pool
+    /// Block VSSs which are exchanged before the candidate block of the round is proposed.
+    block_vss_pool: BlockVssPool,
+
+    /// ## Equivocation Detector
+    /// Digests of candidate blocks which members received, to find masters sending different
+    /// candidate blocks.
+    equivocation_detector: EquivocationDetector,
+
+    /// ## Candidate Signature
+    /// The signature of the master over the candidate block which the node accepted in the
+    /// current round. It is included in the evidence if the master sends an invalid completed
+    /// block.
+    candidate_signature: Option<MasterSignature>,
+
+    /// ## Recorder
+    /// Records received messages, timer events and resulting states if it is set.
+    recorder: Option<Recorder>,
+
+    /// ## Tip Notifications
+    /// Hashes of new tip blocks which tapyrus-core notifies, if it is set.
+    tip_notifications: Option<Receiver<BlockHash>>,
+
+    /// ## Clock
+    /// The virtual clock which drives the timers, if it is set. Otherwise the system clock is used.
+    clock: Option<VirtualClock>,
+
+    /// ## Current Slot
+    /// The slot of the current round when the round schedule is set.
+    current_slot: Option<u64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -126,13 +188,50 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
             stop_signal: None,
             round_limit_timer: RoundTimeOutObserver::new("round_limit_timer", timer_limit),
             round_interval_timer: RoundTimeOutObserver::new("round_interval_timer", round_interval),
+            nonce_store: NonceStore::new(),
+            block_vss_pool: BlockVssPool::new(),
+            equivocation_detector: EquivocationDetector::default(),
+            candidate_signature: None,
+            recorder: None,
+            tip_notifications: None,
+            clock: None,
+            current_slot: None,
         }
     }
 
+    /// Creates the node whose timers are driven by the virtual clock instead of threads. The
+    /// timers are fired by `poll_timers`.
+    pub fn with_clock(
+        connection_manager: C,
+        params: NodeParameters<T>,
+        clock: &VirtualClock,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        let timer_limit = params.round_duration + params.round_limit;
+        let round_interval = params.round_duration;
+        let mut node = SignerNode::new(connection_manager, params);
+        node.round_limit_timer =
+            RoundTimeOutObserver::with_clock("round_limit_timer", timer_limit, clock);
+        node.round_interval_timer =
+            RoundTimeOutObserver::with_clock("round_interval_timer", round_interval, clock);
+        node.clock = Some(clock.clone());
+        node
+    }
+
     pub fn stop_handler(&mut self, receiver: Receiver<u32>) {
         self.stop_signal = Some(receiver);
     }
 
+    pub fn set_recorder(&mut self, recorder: Recorder) {
+        self.recorder = Some(recorder);
+    }
+
+    pub fn set_tip_notifications(&mut self, receiver: Receiver<BlockHash>) {
+        self.tip_notifications = Some(receiver);
+    }
+
     pub fn start(&mut self) {
         if !self.params.skip_waiting_ibd {
             self.wait_for_ibd_finish(std::time::Duration::from_secs(10));
@@ -182,6 +281,8 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
 
                 self.handle_timer();
 
+                self.handle_tip_notifications();
+
                 match self.handle_connection_error() {
                     Some(_) => break,
                     None => {}
@@ -225,27 +326,7 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
     fn handle_message(&mut self, receiver: &Receiver<Message>) {
         // Receiving message.
         match receiver.try_recv() {
-            Ok(Message {
-                message_type,
-                sender_id,
-                ..
-            }) => {
-                log::debug!(
-                    "Got {} message from {:?}. MessageType: {:?}",
-                    message_type,
-                    sender_id,
-                    message_type
-                );
-
-                let next = self.process_round_message(&sender_id, message_type);
-                self.current_state = next;
-
-                if let NodeState::RoundComplete { .. } = &self.current_state {
-                    self.start_next_round()
-                }
-
-                log::debug!("Current state updated as {:?}", self.current_state);
-            }
+            Ok(message) => self.receive_message(message),
             Err(TryRecvError::Empty) => {
                 // No new messages. Do nothing.
             }
@@ -255,16 +336,37 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
         }
     }
 
+    /// Check if tapyrus-core notified new tips.
+    /// if notified, the node finishes the round for the block which is already in the chain.
+    fn handle_tip_notifications(&mut self) {
+        let mut notified = false;
+        if let Some(receiver) = &self.tip_notifications {
+            loop {
+                match receiver.try_recv() {
+                    Ok(hash) => {
+                        log::debug!("New tip: {}", hash);
+                        notified = true;
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        log::warn!("Tip notifications are disconnected.");
+                        self.tip_notifications = None;
+                        break;
+                    }
+                }
+            }
+        }
+        if notified {
+            self.on_new_tip();
+        }
+    }
+
     /// Check if round timer elapsed
     /// if elapsed, the node start new round.
     fn handle_timer(&mut self) {
         // Checking whether the time limit of a round exceeds.
         match self.round_limit_timer.receiver.try_recv() {
-            Ok(_) => {
-                // Round duration is timeout. Starting next round.
-                self.start_next_round();
-                log::debug!("Current state updated as {:?}", self.current_state);
-            }
+            Ok(_) => self.on_round_limit_timeout(),
             Err(TryRecvError::Empty) => {
                 // Still waiting round duration interval. Do nothing.
             }
@@ -277,13 +379,7 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
     fn handle_round_interval_timer(&mut self) {
         // Checking whether the time limit of a round exceeds.
         match self.round_interval_timer.receiver.try_recv() {
-            Ok(_) => {
-                // Round interval is timeout.
-                if let NodeState::Master { block_height, .. } = self.current_state {
-                    self.current_state = self.start_round_communication(block_height);
-                    log::debug!("Current state updated as {:?}", self.current_state);
-                }
-            }
+            Ok(_) => self.on_round_interval_timeout(),
             Err(TryRecvError::Empty) => {
                 // Still waiting round interval. Do nothing.
             }
@@ -291,6 +387,228 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
         }
     }
 
+    /// Processes the message received from other nodes. If the round is completed by the
+    /// message, the next round is started.
+    pub fn receive_message(&mut self, message: Message) {
+        self.check_clock_skew(&message);
+
+        let block = match &message.message_type {
+            MessageType::Candidateblock(block) => Some((block, false)),
+            MessageType::Signedcandidateblock(block, _) => Some((block, false)),
+            MessageType::Signingpackage(block, _, _) => Some((block, false)),
+            MessageType::Completedblock(block) => Some((block, true)),
+            MessageType::Signedcompletedblock(block, _) => Some((block, true)),
+            _ => None,
+        };
+        if let Some((block, completed)) = block {
+            if !self.check_tip_for(block, completed) {
+                log::warn!(
+                    "Drop {} message from {}, because the block is not on the chain tip. block hash: {}",
+                    message.message_type,
+                    message.sender_id,
+                    block.block_hash()
+                );
+                return;
+            }
+        }
+
+        let recorded = match self.recorder {
+            Some(_) => Some((copy_message(&message), self.current_state.clone())),
+            None => None,
+        };
+        let Message {
+            message_type,
+            sender_id,
+            ..
+        } = message;
+        log::debug!(
+            "Got {} message from {:?}. MessageType: {:?}",
+            message_type,
+            sender_id,
+            message_type
+        );
+
+        let next = self.process_round_message(&sender_id, message_type);
+        if let (Some(recorder), Some((message, prev_state))) = (&mut self.recorder, recorded) {
+            recorder.record(Event::Message(message), &prev_state, &next);
+        }
+        self.current_state = next;
+
+        if let NodeState::RoundComplete { .. } = &self.current_state {
+            self.start_next_round()
+        }
+
+        log::debug!("Current state updated as {:?}", self.current_state);
+    }
+
+    /// Warns if the message which starts the round communication arrives outside the slot of the
+    /// current round, because the clocks of the node and the master may be skewed.
+    fn check_clock_skew(&self, message: &Message) {
+        let (schedule, slot) = match (&self.params.round_schedule, self.current_slot) {
+            (Some(schedule), Some(slot)) => (schedule, slot),
+            _ => return,
+        };
+        match &message.message_type {
+            MessageType::Candidateblock(..)
+            | MessageType::Signedcandidateblock(..)
+            | MessageType::Signingpackage(..) => {}
+            _ => return,
+        }
+        match schedule.position(slot, self.now()) {
+            SlotPosition::Early(d) => log::warn!(
+                "{} message from {} arrived {:?} before the slot {} starts. The clock of this node or the sender may be skewed.",
+                message.message_type,
+                message.sender_id,
+                d,
+                slot
+            ),
+            SlotPosition::Late(d) => log::warn!(
+                "{} message from {} arrived {:?} after the slot {} ends. The clock of this node or the sender may be skewed.",
+                message.message_type,
+                message.sender_id,
+                d,
+                slot
+            ),
+            SlotPosition::Within => {}
+        }
+    }
+
+    /// Returns the time since the Unix epoch, or the time on the virtual clock if it is set.
+    fn now(&self) -> Duration {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Called when the round limit timer is up. The node gives up the current round and starts
+    /// the next one.
+    pub fn on_round_limit_timeout(&mut self) {
+        let prev_state = self.current_state.clone();
+        // Round duration is timeout. Starting next round.
+        self.start_next_round();
+        log::debug!("Current state updated as {:?}", self.current_state);
+        self.record(Event::RoundLimitTimeout, &prev_state);
+    }
+
+    /// Called when the tip of the chain changes. If the tip is no longer the parent of the block
+    /// of the current round, for example the block was relayed from another path, the node
+    /// finishes the round and starts the next one without waiting for the round limit timer.
+    pub fn on_new_tip(&mut self) {
+        match self.params.rpc.getblockchaininfo() {
+            Ok(tip) => {
+                self.restart_round_if_stale(&tip);
+            }
+            Err(e) => log::warn!("Couldn't get the tip of the chain: {:?}", e),
+        }
+    }
+
+    /// Whether the chain tip is no longer the parent of the block of the current round, because
+    /// the block was added to the chain from another path or the chain was reorganized.
+    fn is_round_stale(&self, tip: &GetBlockchainInfoResult) -> bool {
+        let candidate_block = match &self.current_state {
+            NodeState::Joining => return true,
+            NodeState::Master {
+                candidate_block, ..
+            } => candidate_block.as_ref(),
+            NodeState::Member {
+                candidate_block, ..
+            } => candidate_block.as_ref(),
+            _ => None,
+        };
+        if self.current_state.block_height() != tip.blocks + 1 {
+            return true;
+        }
+        match candidate_block {
+            Some(block) => block.header.prev_blockhash.to_string() != tip.bestblockhash,
+            None => false,
+        }
+    }
+
+    /// Starts the next round if the current round is stale. Returns true if it is restarted.
+    fn restart_round_if_stale(&mut self, tip: &GetBlockchainInfoResult) -> bool {
+        if !self.is_round_stale(tip) {
+            return false;
+        }
+        let prev_state = self.current_state.clone();
+        log::info!(
+            "The chain tip changed to {} at height {}. Start next round.",
+            tip.bestblockhash,
+            tip.blocks
+        );
+        self.start_next_round();
+        log::debug!("Current state updated as {:?}", self.current_state);
+        self.record(Event::NewTip, &prev_state);
+        true
+    }
+
+    /// Checks the block in a message against the chain tip. If the tip changed underneath the
+    /// current round, the next round is started first. Returns false if the parent of the block
+    /// is not the tip, so that the node doesn't sign or submit a stale block. A completed block
+    /// which is already the tip is accepted, because the master submits it before sending.
+    fn check_tip_for(&mut self, block: &Block, completed: bool) -> bool {
+        let tip = match self.params.rpc.getblockchaininfo() {
+            Ok(tip) => tip,
+            Err(e) => {
+                // Leave the check to tapyrus-core.
+                log::warn!("Couldn't get the tip of the chain: {:?}", e);
+                return true;
+            }
+        };
+        if completed && block.block_hash().to_string() == tip.bestblockhash {
+            return true;
+        }
+        self.restart_round_if_stale(&tip);
+        block.header.prev_blockhash.to_string() == tip.bestblockhash
+    }
+
+    /// Called when the round interval timer is up. If the node is the master of the round, it
+    /// starts the round communication.
+    pub fn on_round_interval_timeout(&mut self) {
+        if let NodeState::Master { block_height, .. } = self.current_state {
+            let prev_state = self.current_state.clone();
+            self.current_state = self.start_round_communication(block_height);
+            log::debug!("Current state updated as {:?}", self.current_state);
+            self.record(Event::RoundIntervalTimeout, &prev_state);
+        }
+    }
+
+    fn record(&mut self, event: Event, prev_state: &NodeState) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(event, prev_state, &self.current_state);
+        }
+    }
+
+    /// Returns the earliest time on the virtual clock when a timer of the node gets expired.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        match (
+            self.round_limit_timer.deadline(),
+            self.round_interval_timer.deadline(),
+        ) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Fires the timers driven by the virtual clock which are expired, and handles them in the
+    /// same order as the loop in `start`.
+    pub fn poll_timers(&mut self) {
+        self.round_interval_timer.poll();
+        self.round_limit_timer.poll();
+        self.handle_round_interval_timer();
+        self.handle_timer();
+    }
+
+    pub fn current_state(&self) -> &NodeState {
+        &self.current_state
+    }
+
+    pub fn params(&self) -> &NodeParameters<T> {
+        &self.params
+    }
+
     /// Check connection to redis server.
     fn handle_connection_error(&mut self) -> Option<ConnectionManagerError<C::ERROR>> {
         // Checking network connection error
@@ -347,6 +665,35 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
 
     /// A master node of the round starts a round communication with sending candidateblock message.
     pub fn start_round_communication(&mut self, block_height: u64) -> NodeState {
+        match self
+            .params
+            .block_production
+            .should_produce(&*self.params.rpc, self.now().as_secs())
+        {
+            Ok(Decision::Produce) => {}
+            Ok(Decision::Skip { tip_age }) => {
+                match tip_age {
+                    Some(tip_age) => log::info!(
+                        "Skip producing a block. policy={}, tip_age={}s",
+                        self.params.block_production,
+                        tip_age
+                    ),
+                    None => log::info!(
+                        "Skip producing a block. policy={}",
+                        self.params.block_production
+                    ),
+                }
+                //Behave as master without block.
+                return Master::default().block_height(block_height).build();
+            }
+            Err(e) => {
+                log::warn!(
+                    "Couldn't check the mempool, so produce a block anyway. reason={:?}",
+                    e
+                );
+            }
+        }
+
         let block = match self.params.rpc.getnewblock(&self.params.address) {
             Ok(block) => block,
             Err(e) => {
@@ -363,31 +710,126 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
         }
 
         let block = self.add_aggregated_public_key_if_needed(block_height, block);
+        let signature = self
+            .params
+            .sign_block(SignedBlock::Candidate, block_height, &block.header);
+        let federation = self.params.get_federation_by_block_height(block_height);
+        if federation.signing_scheme() == SigningScheme::Frost {
+            return self.start_frost_signing(block_height, block, signature);
+        }
+
         log::info!(
             "Broadcast candidate block. block hash for signing: {:?}",
             block.header.signature_hash()
         );
         self.connection_manager.broadcast_message(Message {
-            message_type: MessageType::Candidateblock(block.clone()),
+            message_type: MessageType::candidateblock(block.clone(), signature),
             sender_id: self.params.signer_id,
             receiver_id: None,
         });
 
-        let (keys, shared_secret_for_positive, shared_secret_for_negative) = create_block_vss(
-            block.clone(),
+        let (block_key, shared_block_secrets) = bind_block_vss(
+            &block,
+            &self.params.signer_id,
             &self.params,
             &self.connection_manager,
             block_height,
+            &mut self.block_vss_pool,
         );
 
+        let state = Master::default()
+            .candidate_block(Some(block.clone()))
+            .block_key(Some(block_key))
+            .shared_block_secrets(shared_block_secrets)
+            .block_height(block_height)
+            .build();
+
+        // When the block VSSs were pre-shared, the master can start signing right now.
+        match start_signing_if_threshold_met(&state, &block, &self.connection_manager, &self.params)
+        {
+            Ok(state) => state,
+            Err(e) => {
+                log::error!("Can not start signing with pre-shared block VSSs: {:?}", e);
+                state
+            }
+        }
+    }
+
+    /// A master node of the round chooses nonce commitments of `threshold` signers including
+    /// itself and sends signingpackage message, instead of candidateblock message.
+    fn start_frost_signing(
+        &mut self,
+        block_height: u64,
+        block: Block,
+        signature: Option<MasterSignature>,
+    ) -> NodeState {
+        let threshold = self.params.threshold(block_height) as usize;
+        let mut signer_ids: Vec<SignerID> = self
+            .params
+            .get_federation_by_block_height(block_height)
+            .signers()
+            .iter()
+            .filter(|i| **i != self.params.signer_id)
+            .cloned()
+            .collect();
+        signer_ids.insert(0, self.params.signer_id);
+
+        let mut signing_commitments = BTreeMap::new();
+        for signer_id in signer_ids {
+            if signing_commitments.len() == threshold {
+                break;
+            }
+            if let Some(commitment) = self.nonce_store.pop_commitment(&signer_id) {
+                signing_commitments.insert(signer_id, commitment);
+            }
+        }
+        if signing_commitments.len() < threshold {
+            log::error!(
+                "Not enough nonce commitments. required: {}, available: {}",
+                threshold,
+                signing_commitments.len()
+            );
+            //Behave as master without block.
+            return Master::default().block_height(block_height).build();
+        }
+
+        let local_sig = match create_frost_local_sig(
+            &block,
+            &signing_commitments,
+            &mut self.nonce_store,
+            block_height,
+            &self.params,
+        ) {
+            Ok(local_sig) => local_sig,
+            Err(e) => {
+                log::error!("Can not create local signature: {:?}", e);
+                //Behave as master without block.
+                return Master::default().block_height(block_height).build();
+            }
+        };
+
+        log::info!(
+            "Broadcast signing package. block hash for signing: {:?}",
+            block.header.signature_hash()
+        );
+        self.connection_manager.broadcast_message(Message {
+            message_type: MessageType::Signingpackage(
+                block.clone(),
+                signing_commitments.clone(),
+                signature,
+            ),
+            sender_id: self.params.signer_id,
+            receiver_id: None,
+        });
+
+        let participants: HashSet<SignerID> = signing_commitments.keys().cloned().collect();
+        self.nonce_store
+            .set_signing_commitments(block.header.signature_hash(), signing_commitments);
+
         Master::default()
             .candidate_block(Some(block))
-            .block_key(Some(keys.u_i))
-            .insert_shared_block_secrets(
-                self.params.signer_id.clone(),
-                shared_secret_for_positive,
-                shared_secret_for_negative,
-            )
+            .participants(participants)
+            .insert_signature(self.params.signer_id, local_sig)
             .block_height(block_height)
             .build()
     }
@@ -429,16 +871,28 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
         }
 
         match message {
-            MessageType::Candidateblock(block) => process_candidateblock(
+            MessageType::Candidateblock(block) => {
+                self.receive_candidateblock(sender_id, &block, None)
+            }
+            MessageType::Signedcandidateblock(block, signature) => {
+                self.receive_candidateblock(sender_id, &block, Some(signature))
+            }
+            MessageType::Completedblock(block) => process_completedblock(
                 &sender_id,
                 &block,
+                None,
+                self.candidate_signature.as_ref(),
+                &self.current_state,
+                &self.params,
+            ),
+            MessageType::Signedcompletedblock(block, signature) => process_completedblock(
+                &sender_id,
+                &block,
+                Some(&signature),
+                self.candidate_signature.as_ref(),
                 &self.current_state,
-                &self.connection_manager,
                 &self.params,
             ),
-            MessageType::Completedblock(block) => {
-                process_completedblock(&sender_id, &block, &self.current_state, &self.params)
-            }
             MessageType::Blockvss(
                 blockhash,
                 vss_for_positive,
@@ -464,21 +918,305 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
                 &self.connection_manager,
                 &self.params,
             ),
-            MessageType::Blocksig(blockhash, gamma_i, e) => process_blocksig(
-                &sender_id,
-                blockhash,
-                gamma_i,
-                e,
-                &self.current_state,
-                &self.connection_manager,
-                &self.params,
-            ),
+            MessageType::Blocksig(blockhash, gamma_i, e) => {
+                let block_height = self.current_state.block_height();
+                let federation = self.params.get_federation_by_block_height(block_height);
+                match federation.signing_scheme() {
+                    SigningScheme::Vss => process_blocksig(
+                        &sender_id,
+                        blockhash,
+                        gamma_i,
+                        e,
+                        &self.current_state,
+                        &self.connection_manager,
+                        &self.params,
+                    ),
+                    SigningScheme::Frost => process_blocksig_frost(
+                        &sender_id,
+                        blockhash,
+                        gamma_i,
+                        e,
+                        &self.current_state,
+                        &self.connection_manager,
+                        &self.params,
+                        &self.nonce_store,
+                    ),
+                }
+            }
+            MessageType::Preblockvss(
+                block_height,
+                master_id,
+                vss_for_positive,
+                secret_share_for_positive,
+                vss_for_negative,
+                secret_share_for_negative,
+            ) => {
+                // Accept VSSs only for the current and the next round, so that the pool can't
+                // grow by VSSs for far-future rounds.
+                let current_height = self.current_state.block_height();
+                if block_height < current_height || block_height > current_height + 1 {
+                    return self.current_state.clone();
+                }
+
+                let shared_secret_for_positive = SharedSecret {
+                    vss: vss_for_positive,
+                    secret_share: secret_share_for_positive,
+                };
+                let shared_secret_for_negative = SharedSecret {
+                    vss: vss_for_negative,
+                    secret_share: secret_share_for_negative,
+                };
+                if let Err(e) = verify_pre_block_vss(
+                    block_height,
+                    &master_id,
+                    sender_id,
+                    &shared_secret_for_positive,
+                    &shared_secret_for_negative,
+                    &self.params,
+                ) {
+                    log::warn!(
+                        "Invalid Preblockvss message from {}: block_height={}, error={:?}",
+                        sender_id,
+                        block_height,
+                        e
+                    );
+                    return self.current_state.clone();
+                }
+
+                if !self.block_vss_pool.insert_received(
+                    block_height,
+                    &master_id,
+                    sender_id,
+                    shared_secret_for_positive,
+                    shared_secret_for_negative,
+                ) {
+                    log::warn!(
+                        "Ignore Preblockvss message from {}, which already sent VSSs for block_height={}",
+                        sender_id,
+                        block_height
+                    );
+                }
+                self.current_state.clone()
+            }
+            MessageType::Noncecommitments(commitments) => {
+                // Only the signers of the federation keep nonce commitments in the store, and no
+                // signer publishes more than its nonce pool.
+                if !self.is_federation_member(sender_id) || commitments.len() > NONCE_POOL_SIZE {
+                    log::warn!(
+                        "Ignore {} nonce commitments from {}. It may be malicious node.",
+                        commitments.len(),
+                        sender_id
+                    );
+                } else {
+                    self.nonce_store.update_commitments(*sender_id, commitments);
+                }
+                self.current_state.clone()
+            }
+            MessageType::Signingpackage(block, signing_commitments, signature) => {
+                if !self.verify_candidate_signature(sender_id, &block, signature.as_ref()) {
+                    return self.current_state.clone();
+                }
+                let next = process_signingpackage(
+                    &sender_id,
+                    &block,
+                    &signing_commitments,
+                    &self.current_state,
+                    &self.connection_manager,
+                    &self.params,
+                    &mut self.nonce_store,
+                );
+                self.accept_candidate_block(sender_id, signature, &next);
+                next
+            }
+            MessageType::Candidatedigest(digest) => {
+                self.process_candidate_digest(sender_id, digest);
+                self.current_state.clone()
+            }
+        }
+    }
+
+    fn receive_candidateblock(
+        &mut self,
+        sender_id: &SignerID,
+        block: &Block,
+        signature: Option<MasterSignature>,
+    ) -> NodeState {
+        if !self.verify_candidate_signature(sender_id, block, signature.as_ref()) {
+            return self.current_state.clone();
+        }
+        let next = process_candidateblock(
+            &sender_id,
+            block,
+            &self.current_state,
+            &self.connection_manager,
+            &self.params,
+            &mut self.block_vss_pool,
+        );
+        self.accept_candidate_block(sender_id, signature, &next);
+        next
+    }
+
+    /// Returns true if the candidate block is signed by the sender for the current round, or the
+    /// sender doesn't sign blocks.
+    fn verify_candidate_signature(
+        &self,
+        sender_id: &SignerID,
+        block: &Block,
+        signature: Option<&MasterSignature>,
+    ) -> bool {
+        let signature = match signature {
+            Some(signature) => signature,
+            None => return true,
+        };
+        let block_height = self.current_state.block_height();
+        match signature.verify(
+            SignedBlock::Candidate,
+            block_height,
+            &block.header,
+            sender_id,
+        ) {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!(
+                    "Ignore the candidate block from {}, because the signature is invalid: {:?}",
+                    sender_id,
+                    e
+                );
+                false
+            }
+        }
+    }
+
+    /// Keeps the signature of the master over the candidate block which the node newly accepted
+    /// in the next state, and broadcasts the digest of the block with the signature, so that other
+    /// members can find the master sending different candidate blocks. Without the signature, the
+    /// digest is not broadcast, because other members can't verify it.
+    fn accept_candidate_block(
+        &mut self,
+        master: &SignerID,
+        signature: Option<MasterSignature>,
+        next: &NodeState,
+    ) {
+        let (block, block_height) = match next {
+            NodeState::Member {
+                candidate_block: Some(block),
+                block_height,
+                ..
+            } => (block, *block_height),
+            _ => return,
+        };
+        if let NodeState::Member {
+            candidate_block: Some(current),
+            ..
+        } = &self.current_state
+        {
+            if current == block {
+                return;
+            }
+        }
+        self.candidate_signature = signature.clone();
+        let signature = match signature {
+            Some(signature) => signature,
+            None => return,
+        };
+        let digest = CandidateDigest::new(
+            block_height,
+            *master,
+            block.header.clone(),
+            self.params.signer_id,
+            signature,
+        );
+        self.observe_candidate_digest(digest.clone());
+        self.connection_manager.broadcast_message(Message {
+            message_type: MessageType::Candidatedigest(digest),
+            sender_id: self.params.signer_id,
+            receiver_id: None,
+        });
+    }
+
+    /// Verifies the digest of the candidate block which another member received in the current
+    /// round. A master may propose another candidate block at the same block height in a later
+    /// round, so the digests for other rounds are not compared.
+    fn process_candidate_digest(&mut self, sender_id: &SignerID, digest: CandidateDigest) {
+        if digest.block_height != self.current_state.block_height()
+            || !is_master(&digest.master, &self.current_state, &self.params)
+        {
+            log::debug!(
+                "Ignore the candidate digest for the block height {} and the master {} from {}, which is not for the current round.",
+                digest.block_height,
+                digest.master,
+                sender_id
+            );
+            return;
+        }
+        if digest.sender != *sender_id {
+            log::warn!(
+                "Peer {} sent the candidate digest of {}. It may be malicious node.",
+                sender_id,
+                digest.sender
+            );
+            return;
+        }
+        let federation = self
+            .params
+            .get_federation_by_block_height(digest.block_height);
+        if !federation.is_member() {
+            return;
+        }
+        if !federation.signers().contains(&digest.master) {
+            log::warn!(
+                "Peer {} sent the candidate digest of {}, which is not a signer.",
+                sender_id,
+                digest.master
+            );
+            return;
+        }
+        if let Err(e) = digest.verify() {
+            log::warn!(
+                "Received invalid candidate digest. sender: {}, error: {:?}",
+                sender_id,
+                e
+            );
+            return;
+        }
+        self.observe_candidate_digest(digest);
+    }
+
+    /// Records the evidence if the digest conflicts with the one observed before.
+    fn observe_candidate_digest(&mut self, digest: CandidateDigest) {
+        if let Some(first) = self.equivocation_detector.observe(digest.clone()) {
+            log::error!(
+                "The master {} sent different candidate blocks at block height {}. {} got {}, and {} got {}.",
+                digest.master,
+                digest.block_height,
+                first.sender,
+                first.header.signature_hash(),
+                digest.sender,
+                digest.header.signature_hash()
+            );
+            self.params.evidence.record(Evidence::Equivocation {
+                block_height: digest.block_height,
+                master: digest.master,
+                digests: (first, digest),
+            });
         }
     }
 
     /// Start next round.
     /// decide master of next round according to Round-robin.
     fn start_next_round(&mut self) {
+        // With the round schedule, the round is for the next slot. The master starts the round
+        // communication at the start of the slot and the round limit counts from it.
+        self.current_slot = None;
+        if let Some(schedule) = self.params.round_schedule {
+            let now = self.now();
+            let slot = schedule.next_slot(now);
+            let wait = schedule.slot_start(slot) - now;
+            self.round_interval_timer.set_timelimit(wait);
+            self.round_limit_timer
+                .set_timelimit(wait + Duration::from_secs(self.params.round_limit));
+            self.current_slot = Some(slot);
+        }
         self.round_limit_timer.restart().unwrap();
 
         // Get a block height at next of the tip block.
@@ -495,8 +1233,11 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
                 // height is not incremented here.
                 NodeState::Member { block_height, .. } => block_height,
                 NodeState::Master { block_height, .. } => block_height,
+                // The node doesn't know the block height yet. It tries again when the round
+                // limit timer is up.
                 NodeState::Joining => {
-                    panic!("Couldn't start the node because of an RPC connection error.")
+                    log::error!("Couldn't start the round because of an RPC connection error.");
+                    return;
                 }
             },
         };
@@ -511,13 +1252,40 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
             return;
         }
 
-        let next_master_index = next_master_index(&self.current_state, &self.params, block_height);
+        let signing_scheme = federation.signing_scheme();
+        if signing_scheme == SigningScheme::Frost {
+            self.broadcast_nonce_commitments();
+        }
+
+        let next_master_index = match (&self.params.round_schedule, self.current_slot) {
+            (Some(schedule), Some(slot)) => {
+                schedule.master_index(slot, self.params.pubkey_list(block_height).len())
+            }
+            _ => next_master_index(&self.current_state, &self.params, block_height),
+        };
+
+        self.block_vss_pool.prune(block_height);
+        self.candidate_signature = None;
+        self.equivocation_detector = EquivocationDetector::default();
+        if self.params.precompute_block_vss && signing_scheme == SigningScheme::Vss {
+            let master_id = self
+                .params
+                .get_signer_id_by_index(block_height, next_master_index);
+            precompute_block_vss(
+                block_height,
+                &master_id,
+                &self.params,
+                &self.connection_manager,
+                &mut self.block_vss_pool,
+            );
+        }
 
         log::info!(
-            "Start next round: target_block_height={}, self_index={}, master_index={}",
+            "Start next round: target_block_height={}, self_index={}, master_index={}, slot={:?}",
             block_height,
             self.params.self_node_index(block_height),
             next_master_index,
+            self.current_slot,
         );
 
         if self.params.self_node_index(block_height) == next_master_index {
@@ -530,6 +1298,19 @@ impl<T: TapyrusApi, C: ConnectionManager> SignerNode<T, C> {
         }
     }
 
+    /// Prepares own nonces for the FROST signing scheme and sends their commitments to all signers.
+    /// Receivers replace the commitments of this node, so the used ones are discarded.
+    fn broadcast_nonce_commitments(&mut self) {
+        let commitments = self
+            .nonce_store
+            .fill(self.params.signer_id, NONCE_POOL_SIZE);
+        self.connection_manager.broadcast_message(Message {
+            message_type: MessageType::Noncecommitments(commitments),
+            sender_id: self.params.signer_id,
+            receiver_id: None,
+        });
+    }
+
     fn verify_block(&self, block: &Block) -> Result<(), Error> {
         // master node accepts the block that has None xfield type.
         match block.header.xfield {
@@ -599,20 +1380,29 @@ where
 
 #[cfg(test)]
 mod tests {
+    use crate::crypto::frost::{Nonce, NonceCommitment};
     use crate::errors;
     use crate::federation::{Federation, Federations};
-    use crate::net::{ConnectionManager, ConnectionManagerError, Message, SignerID};
+    use crate::key::generate_key_pair;
+    use crate::net::{ConnectionManager, ConnectionManagerError, Message, MessageType, SignerID};
     use crate::rpc::tests::{safety, MockRpc};
-    use crate::rpc::TapyrusApi;
+    use crate::rpc::{GetMempoolInfoResult, TapyrusApi};
+    use crate::signer_node::equivocation::CandidateDigest;
+    use crate::signer_node::evidence::Evidence;
+    use crate::signer_node::master_signature::SignedBlock;
+    use crate::signer_node::nonce_store::NONCE_POOL_SIZE;
+    use crate::signer_node::production::BlockProductionPolicy;
+    use crate::signer_node::schedule::RoundSchedule;
     use crate::signer_node::{
         master_index, BidirectionalSharedSecretMap, NodeParameters, NodeState, SignerNode,
     };
-    use crate::tests::helper::blocks::get_block;
+    use crate::tests::helper::blocks::{get_block, master_sign};
     use crate::tests::helper::keys::TEST_KEYS;
     use crate::tests::helper::node_vss::node_vss;
     use crate::tests::helper::{address, enable_log};
+    use crate::timer::{RoundTimeOutObserver, VirtualClock};
     use redis::ControlFlow;
-    use std::collections::HashSet;
+    use std::collections::{BTreeMap, HashSet};
     use std::str::FromStr;
     use std::sync::mpsc::{channel, Receiver, Sender};
     use std::sync::Arc;
@@ -727,7 +1517,9 @@ mod tests {
             aggregated_public_key,
         )]));
 
-        let mut params = NodeParameters::new(to_address, public_key, rpc, 0, 10, true, federations);
+        let mut params =
+            NodeParameters::new(to_address, public_key, rpc, 0, 10, true, false, federations);
+        params.private_key = Some(private_key);
         params.round_duration = 0;
         let con = TestConnectionManager::new(publish_count, spy);
         let broadcaster = con.sender.clone();
@@ -831,6 +1623,327 @@ mod tests {
         assert_eq!(master_index(&node.current_state, &node.params).unwrap(), 0);
     }
 
+    #[test]
+    fn test_start_next_round_with_schedule() {
+        let rpc = MockRpc {
+            return_block: safety(get_block(0)),
+        };
+        let mut node = create_node(NodeState::Joining, rpc, None);
+        let clock = VirtualClock::new();
+        node.clock = Some(clock.clone());
+        node.round_limit_timer = RoundTimeOutObserver::with_clock("round_limit_timer", 0, &clock);
+        node.round_interval_timer =
+            RoundTimeOutObserver::with_clock("round_interval_timer", 0, &clock);
+        node.params.round_schedule = Some(RoundSchedule::new(100, 60));
+
+        // The next slot is 1, which starts at 160.
+        clock.advance_to(Duration::from_secs(130));
+        node.start_next_round();
+        assert_eq!(node.current_slot, Some(1));
+        assert_eq!(master_index(&node.current_state, &node.params).unwrap(), 1);
+        assert_eq!(
+            node.round_limit_timer.deadline(),
+            Some(Duration::from_secs(170))
+        );
+
+        // The master is derived from the slot, not from the previous round.
+        clock.advance_to(Duration::from_secs(250));
+        node.start_next_round();
+        assert_eq!(node.current_slot, Some(3));
+        assert_eq!(master_index(&node.current_state, &node.params).unwrap(), 3);
+
+        // The master starts the round communication at the start of the slot.
+        let slot = node.params.self_node_index(1) as u64 + 5;
+        clock.advance_to(Duration::from_secs(100 + 60 * slot - 30));
+        node.start_next_round();
+        match node.current_state {
+            NodeState::Master { .. } => {}
+            _ => panic!("NodeState should be Master"),
+        }
+        assert_eq!(
+            node.round_interval_timer.deadline(),
+            Some(Duration::from_secs(100 + 60 * slot))
+        );
+    }
+
+    #[test]
+    fn test_skip_block_production() {
+        let mut rpc = crate::tests::helper::rpc::MockRpc::new();
+        rpc.should_call_getmempoolinfo(Ok(GetMempoolInfoResult { size: 0, bytes: 0 }));
+        let mut node = create_node(NodeState::Joining, rpc, None);
+        node.params.block_production = BlockProductionPolicy::Mempool;
+
+        // The master doesn't call getnewblock, and behaves as master without block.
+        let state = node.start_round_communication(1);
+        match state {
+            NodeState::Master {
+                candidate_block: None,
+                block_height: 1,
+                ..
+            } => {}
+            _ => panic!("NodeState should be Master without block"),
+        }
+        node.params.rpc.assert();
+    }
+
+    #[test]
+    fn test_on_new_tip() {
+        let member = |block_height| NodeState::Member {
+            block_key: None,
+            block_shared_keys: None,
+            shared_block_secrets: BidirectionalSharedSecretMap::new(),
+            candidate_block: None,
+            participants: HashSet::new(),
+            master_index: 0,
+            block_height,
+        };
+        let rpc = MockRpc {
+            return_block: safety(get_block(0)),
+        };
+
+        // The tip is still before the block of the round.
+        let mut node = create_node(member(1), rpc, None);
+        node.on_new_tip();
+        assert_eq!(node.current_state, member(1));
+
+        // The block of the round is the tip, so the next round is started.
+        node.current_state = member(0);
+        node.on_new_tip();
+        assert_eq!(node.current_state.block_height(), 1);
+        assert_eq!(master_index(&node.current_state, &node.params).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_drop_block_not_on_tip() {
+        let state = NodeState::Member {
+            block_key: None,
+            block_shared_keys: None,
+            shared_block_secrets: BidirectionalSharedSecretMap::new(),
+            candidate_block: None,
+            participants: HashSet::new(),
+            master_index: 0,
+            block_height: 1,
+        };
+        let rpc = MockRpc {
+            return_block: safety(get_block(0)),
+        };
+        let mut node = create_node(state.clone(), rpc, None);
+
+        // The parent of the block is not the tip which MockRpc returns.
+        let sender_id = SignerID::new(TEST_KEYS.pubkeys()[0]);
+        for message_type in vec![
+            MessageType::Candidateblock(get_block(0)),
+            MessageType::Signedcandidateblock(
+                get_block(0),
+                master_sign(SignedBlock::Candidate, 1, &get_block(0), 0),
+            ),
+            MessageType::Signingpackage(
+                get_block(0),
+                TEST_KEYS.signer_ids()[..3]
+                    .iter()
+                    .map(|i| (*i, Nonce::new_random().commitment()))
+                    .collect::<BTreeMap<_, _>>(),
+                Some(master_sign(SignedBlock::Candidate, 1, &get_block(0), 0)),
+            ),
+            MessageType::Completedblock(get_block(0)),
+            MessageType::Signedcompletedblock(
+                get_block(0),
+                master_sign(SignedBlock::Completed, 1, &get_block(0), 0),
+            ),
+        ] {
+            node.receive_message(Message {
+                message_type,
+                sender_id,
+                receiver_id: None,
+            });
+            assert_eq!(node.current_state, state);
+        }
+    }
+
+    #[test]
+    fn test_verify_candidate_signature() {
+        let state = NodeState::Member {
+            block_key: None,
+            block_shared_keys: None,
+            shared_block_secrets: BidirectionalSharedSecretMap::new(),
+            candidate_block: None,
+            participants: HashSet::new(),
+            master_index: 0,
+            block_height: 1,
+        };
+        let rpc = MockRpc {
+            return_block: safety(get_block(0)),
+        };
+        let node = create_node(state, rpc, None);
+
+        let master = SignerID::new(TEST_KEYS.pubkeys()[0]);
+        let block = get_block(0);
+        let signature = master_sign(SignedBlock::Candidate, 1, &block, 0);
+        assert!(node.verify_candidate_signature(&master, &block, Some(&signature)));
+        // The master which doesn't sign blocks is trusted as before.
+        assert!(node.verify_candidate_signature(&master, &block, None));
+
+        // The signature of another signer, for another round or for the completed block is not
+        // accepted.
+        for signature in vec![
+            master_sign(SignedBlock::Candidate, 1, &block, 1),
+            master_sign(SignedBlock::Candidate, 2, &block, 0),
+            master_sign(SignedBlock::Completed, 1, &block, 0),
+        ] {
+            assert!(!node.verify_candidate_signature(&master, &block, Some(&signature)));
+        }
+        assert!(!node.verify_candidate_signature(&master, &get_block(1), Some(&signature)));
+    }
+
+    #[test]
+    fn test_bound_nonce_commitments() {
+        let state = NodeState::Member {
+            block_key: None,
+            block_shared_keys: None,
+            shared_block_secrets: BidirectionalSharedSecretMap::new(),
+            candidate_block: None,
+            participants: HashSet::new(),
+            master_index: 0,
+            block_height: 1,
+        };
+        let rpc = MockRpc {
+            return_block: safety(get_block(0)),
+        };
+        let mut node = create_node(state, rpc, None);
+        let commitments = |n: usize| -> Vec<NonceCommitment> {
+            (0..n).map(|_| Nonce::new_random().commitment()).collect()
+        };
+
+        // The list longer than the nonce pool is dropped.
+        let signer = SignerID::new(TEST_KEYS.pubkeys()[1]);
+        node.process_round_message(
+            &signer,
+            MessageType::Noncecommitments(commitments(NONCE_POOL_SIZE + 1)),
+        );
+        assert_eq!(node.nonce_store.pop_commitment(&signer), None);
+
+        // The commitments of a node which is not a signer are not stored.
+        let (_, public_key) = generate_key_pair();
+        let stranger = SignerID::new(public_key);
+        node.process_round_message(
+            &stranger,
+            MessageType::Noncecommitments(commitments(NONCE_POOL_SIZE)),
+        );
+        assert_eq!(node.nonce_store.pop_commitment(&stranger), None);
+
+        let valid = commitments(NONCE_POOL_SIZE);
+        node.process_round_message(&signer, MessageType::Noncecommitments(valid.clone()));
+        assert_eq!(
+            node.nonce_store.pop_commitment(&signer),
+            Some(valid[0].clone())
+        );
+    }
+
+    #[test]
+    fn test_record_equivocation() {
+        let state = NodeState::Member {
+            block_key: None,
+            block_shared_keys: None,
+            shared_block_secrets: BidirectionalSharedSecretMap::new(),
+            candidate_block: None,
+            participants: HashSet::new(),
+            master_index: 0,
+            block_height: 1,
+        };
+        let rpc = MockRpc {
+            return_block: safety(get_block(0)),
+        };
+        let mut node = create_node(state, rpc, None);
+
+        // The members 1 and 2 got the same candidate block, but the member 3 got another one.
+        let master = SignerID::new(TEST_KEYS.pubkeys()[0]);
+        for (i, block) in vec![(1, 0), (2, 0), (3, 1)] {
+            let sender_id = SignerID::new(TEST_KEYS.pubkeys()[i]);
+            let digest = CandidateDigest::new(
+                1,
+                master,
+                get_block(block).header,
+                sender_id,
+                master_sign(SignedBlock::Candidate, 1, &get_block(block), 0),
+            );
+            node.process_round_message(&sender_id, MessageType::Candidatedigest(digest));
+        }
+
+        let records = node.params.evidence.records();
+        assert_eq!(records.len(), 1);
+        match &records[0].evidence {
+            Evidence::Equivocation {
+                block_height,
+                master: m,
+                digests: (first, second),
+            } => {
+                assert_eq!(*block_height, 1);
+                assert_eq!(*m, master);
+                assert_eq!(first.sender, SignerID::new(TEST_KEYS.pubkeys()[1]));
+                assert_eq!(second.sender, SignerID::new(TEST_KEYS.pubkeys()[3]));
+            }
+            e => panic!("Should be Equivocation, but the evidence is {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_no_equivocation_in_different_rounds() {
+        let state = NodeState::Member {
+            block_key: None,
+            block_shared_keys: None,
+            shared_block_secrets: BidirectionalSharedSecretMap::new(),
+            candidate_block: None,
+            participants: HashSet::new(),
+            master_index: 0,
+            block_height: 1,
+        };
+        let rpc = MockRpc {
+            return_block: safety(get_block(0)),
+        };
+        let mut node = create_node(state.clone(), rpc, None);
+        let master = SignerID::new(TEST_KEYS.pubkeys()[0]);
+        let digest = |sender: usize, block: u8| {
+            CandidateDigest::new(
+                1,
+                master,
+                get_block(block).header,
+                SignerID::new(TEST_KEYS.pubkeys()[sender]),
+                master_sign(SignedBlock::Candidate, 1, &get_block(block), 0),
+            )
+        };
+
+        node.process_round_message(
+            &digest(1, 0).sender,
+            MessageType::Candidatedigest(digest(1, 0)),
+        );
+
+        // The round fails and the master proposes another candidate block at the same block
+        // height in a later round.
+        node.start_next_round();
+        node.current_state = state;
+        node.process_round_message(
+            &digest(2, 1).sender,
+            MessageType::Candidatedigest(digest(2, 1)),
+        );
+        assert!(node.params.evidence.records().is_empty());
+
+        // The digest for the master of another round is ignored.
+        node.current_state = NodeState::Member {
+            block_key: None,
+            block_shared_keys: None,
+            shared_block_secrets: BidirectionalSharedSecretMap::new(),
+            candidate_block: None,
+            participants: HashSet::new(),
+            master_index: 1,
+            block_height: 1,
+        };
+        node.process_round_message(
+            &digest(3, 0).sender,
+            MessageType::Candidatedigest(digest(3, 0)),
+        );
+        assert!(node.params.evidence.records().is_empty());
+    }
+
     #[test]
     fn test_verify_block() {
         let arc_block = safety(get_block(0));
@@ -857,12 +1970,13 @@ mod tests {
 
     mod test_for_waiting_ibd_finish {
         use crate::errors::Error;
-        use crate::rpc::{GetBlockchainInfoResult, TapyrusApi};
+        use crate::rpc::{GetBlockchainInfoResult, GetMempoolInfoResult, TapyrusApi};
         use crate::signer_node::tests::create_node;
         use crate::signer_node::{BidirectionalSharedSecretMap, NodeState};
         use std::cell::Cell;
         use std::collections::HashSet;
-        use tapyrus::blockdata::block::Block;
+        use tapyrus::blockdata::block::{Block, BlockHeader};
+        use tapyrus::hash_types::BlockHash;
         use tapyrus::Address;
 
         struct MockRpc {
@@ -889,6 +2003,18 @@ mod tests {
 
                 Ok(result)
             }
+
+            fn getblockhash(&self, _height: u64) -> Result<BlockHash, Error> {
+                unimplemented!()
+            }
+
+            fn getblockheader(&self, _hash: &BlockHash) -> Result<BlockHeader, Error> {
+                unimplemented!()
+            }
+
+            fn getmempoolinfo(&self) -> Result<GetMempoolInfoResult, Error> {
+                unimplemented!()
+            }
         }
 
         #[test]
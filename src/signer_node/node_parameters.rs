@@ -2,18 +2,36 @@ use crate::crypto::multi_party_schnorr::Parameters;
 use crate::federation::{Federation, Federations};
 use crate::net::SignerID;
 use crate::rpc::TapyrusApi;
+use crate::signer_node::evidence::EvidenceStore;
+use crate::signer_node::master_signature::{MasterSignature, SignedBlock};
+use crate::signer_node::production::BlockProductionPolicy;
+use crate::signer_node::schedule::RoundSchedule;
+use crate::signer_node::validation::{CandidateContext, CandidateValidator, Rejection};
 use std::convert::TryInto;
 use std::sync::Arc;
-use tapyrus::{Address, PublicKey};
+use tapyrus::blockdata::block::{Block, BlockHeader};
+use tapyrus::{Address, PrivateKey, PublicKey};
 
 pub struct NodeParameters<T: TapyrusApi> {
     pub rpc: std::sync::Arc<T>,
     pub address: Address,
     /// Own Signer ID. Actually it is signer own public key.
     pub signer_id: SignerID,
+    /// Own private key, which the node signs the blocks with when it is the master of a round.
+    pub private_key: Option<PrivateKey>,
     pub round_duration: u64,
     pub round_limit: u64,
     pub skip_waiting_ibd: bool,
+    /// Exchange Block VSSs for the next round in advance.
+    pub precompute_block_vss: bool,
+    /// Checks for candidate blocks on the member side.
+    pub candidate_validator: Arc<CandidateValidator>,
+    /// Evidence of misbehavior of round masters.
+    pub evidence: Arc<EvidenceStore>,
+    /// Starts rounds at the slots aligned to the wall clock if it is set.
+    pub round_schedule: Option<RoundSchedule>,
+    /// Decides whether the node produces a block when it is the master of the round.
+    pub block_production: BlockProductionPolicy,
     federations: Federations,
 }
 
@@ -25,6 +43,7 @@ impl<T: TapyrusApi> NodeParameters<T> {
         round_duration: u64,
         round_limit: u64,
         skip_waiting_ibd: bool,
+        precompute_block_vss: bool,
         federations: Federations,
     ) -> NodeParameters<T> {
         let signer_id = SignerID { pubkey: public_key };
@@ -33,13 +52,50 @@ impl<T: TapyrusApi> NodeParameters<T> {
             rpc: Arc::new(rpc),
             address: to_address,
             signer_id,
+            private_key: None,
             round_duration,
             round_limit,
             skip_waiting_ibd,
+            precompute_block_vss,
+            candidate_validator: Arc::new(CandidateValidator::default()),
+            evidence: Arc::new(EvidenceStore::default()),
+            round_schedule: None,
+            block_production: BlockProductionPolicy::default(),
             federations,
         }
     }
 
+    /// Validates the candidate block for the round at the block height.
+    pub fn validate_candidate_block(
+        &self,
+        block: &Block,
+        block_height: u64,
+    ) -> Result<(), Rejection> {
+        let context = CandidateContext {
+            block_height,
+            federations: &self.federations,
+            rpc: &*self.rpc,
+        };
+        self.candidate_validator.validate(block, &context)
+    }
+
+    /// Signs the header of the block which the node sends as the master of the round at the block
+    /// height. Returns None if the node has no private key.
+    pub fn sign_block(
+        &self,
+        kind: SignedBlock,
+        block_height: u64,
+        header: &BlockHeader,
+    ) -> Option<MasterSignature> {
+        self.private_key
+            .as_ref()
+            .map(|key| MasterSignature::sign(kind, block_height, header, key))
+    }
+
+    pub fn federations(&self) -> &Federations {
+        &self.federations
+    }
+
     pub fn get_federation_by_block_height(&self, block_height: u64) -> &Federation {
         self.federations.get_by_block_height(block_height)
     }
@@ -0,0 +1,144 @@
+use crate::crypto::frost::{Nonce, NonceCommitment, SigningCommitments};
+use crate::net::SignerID;
+use crate::signer_node::utils::sender_index;
+use std::collections::BTreeMap;
+use tapyrus::hash_types::BlockSigHash;
+use tapyrus::PublicKey;
+
+/// The number of nonces which each signer keeps in advance for the FROST signing scheme.
+pub static NONCE_POOL_SIZE: usize = 10;
+
+/// Nonces and nonce commitments for the FROST signing scheme.
+///
+/// Each signer keeps own secret nonces and the nonce commitments which are published by all signers
+/// in the federation. A nonce is removed from the store when it is used, so it is never used twice.
+pub struct NonceStore {
+    /// Own secret nonces which are not used yet.
+    nonces: Vec<Nonce>,
+    /// Nonce commitments which each signer published and are not used yet.
+    commitments: BTreeMap<SignerID, Vec<NonceCommitment>>,
+    /// Nonce commitments chosen for the candidate block of the current round.
+    signing_commitments: Option<(BlockSigHash, BTreeMap<SignerID, NonceCommitment>)>,
+}
+
+impl NonceStore {
+    pub fn new() -> Self {
+        NonceStore {
+            nonces: Vec::new(),
+            commitments: BTreeMap::new(),
+            signing_commitments: None,
+        }
+    }
+
+    /// Generates new nonces until the store has `size` own nonces.
+    /// Returns commitments of all own nonces which are not used yet.
+    pub fn fill(&mut self, signer_id: SignerID, size: usize) -> Vec<NonceCommitment> {
+        while self.nonces.len() < size {
+            self.nonces.push(Nonce::new_random());
+        }
+        let commitments: Vec<NonceCommitment> =
+            self.nonces.iter().map(|n| n.commitment()).collect();
+        self.commitments.insert(signer_id, commitments.clone());
+        commitments
+    }
+
+    /// Replaces nonce commitments of the signer with the latest ones.
+    pub fn update_commitments(&mut self, signer_id: SignerID, commitments: Vec<NonceCommitment>) {
+        self.commitments.insert(signer_id, commitments);
+    }
+
+    /// Removes the oldest nonce commitment of the signer and returns it.
+    pub fn pop_commitment(&mut self, signer_id: &SignerID) -> Option<NonceCommitment> {
+        match self.commitments.get_mut(signer_id) {
+            Some(commitments) if !commitments.is_empty() => Some(commitments.remove(0)),
+            _ => None,
+        }
+    }
+
+    /// Removes own nonce for the commitment and returns it.
+    pub fn take_nonce(&mut self, commitment: &NonceCommitment) -> Option<Nonce> {
+        let position = self
+            .nonces
+            .iter()
+            .position(|n| n.commitment() == *commitment)?;
+        Some(self.nonces.remove(position))
+    }
+
+    pub fn set_signing_commitments(
+        &mut self,
+        blockhash: BlockSigHash,
+        commitments: BTreeMap<SignerID, NonceCommitment>,
+    ) {
+        self.signing_commitments = Some((blockhash, commitments));
+    }
+
+    /// Returns nonce commitments chosen for the block.
+    pub fn signing_commitments(
+        &self,
+        blockhash: &BlockSigHash,
+    ) -> Option<&BTreeMap<SignerID, NonceCommitment>> {
+        match &self.signing_commitments {
+            Some((hash, commitments)) if hash == blockhash => Some(commitments),
+            _ => None,
+        }
+    }
+}
+
+/// Converts the keys of nonce commitments from SignerID to 1-based signer index.
+pub fn to_signing_commitments(
+    commitments: &BTreeMap<SignerID, NonceCommitment>,
+    pubkey_list: &[PublicKey],
+) -> SigningCommitments {
+    commitments
+        .iter()
+        .map(|(signer_id, c)| (sender_index(signer_id, pubkey_list) + 1, c.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::helper::keys::TEST_KEYS;
+
+    #[test]
+    fn test_fill() {
+        let signer_id = TEST_KEYS.signer_id();
+        let mut store = NonceStore::new();
+        let commitments = store.fill(signer_id, 3);
+        assert_eq!(commitments.len(), 3);
+
+        // Existing nonces are kept.
+        let commitment = store.pop_commitment(&signer_id).unwrap();
+        assert_eq!(commitment, commitments[0]);
+        assert_eq!(store.fill(signer_id, 3), commitments);
+    }
+
+    #[test]
+    fn test_take_nonce() {
+        let signer_id = TEST_KEYS.signer_id();
+        let mut store = NonceStore::new();
+        let commitments = store.fill(signer_id, 3);
+
+        let nonce = store.take_nonce(&commitments[1]).unwrap();
+        assert_eq!(nonce.commitment(), commitments[1]);
+
+        // The nonce can not be used twice.
+        assert!(store.take_nonce(&commitments[1]).is_none());
+        assert_eq!(store.fill(signer_id, 2).len(), 2);
+    }
+
+    #[test]
+    fn test_pop_commitment() {
+        let signer_id = TEST_KEYS.signer_ids()[0];
+        let commitments = vec![Nonce::new_random().commitment()];
+        let mut store = NonceStore::new();
+        assert!(store.pop_commitment(&signer_id).is_none());
+
+        store.update_commitments(signer_id, commitments.clone());
+        assert_eq!(
+            store.pop_commitment(&signer_id),
+            Some(commitments[0].clone())
+        );
+        assert!(store.pop_commitment(&signer_id).is_none());
+    }
+}
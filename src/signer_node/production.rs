@@ -0,0 +1,184 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Policy of the master to decide whether it produces a block in the round.
+//!
+//! On private networks with sparse traffic, producing a block every round only adds empty blocks
+//! to the chain. When the master skips a round, it behaves as a master without a block, so the
+//! members give up the round at the round limit and the next master decides again.
+
+use crate::errors::Error;
+use crate::rpc::TapyrusApi;
+use tapyrus::hash_types::BlockHash;
+use tapyrus::hashes::hex::FromHex;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockProductionPolicy {
+    /// Produces a block every round.
+    Always,
+    /// Produces a block only when the mempool has transactions.
+    Mempool,
+    /// Produces a block when the mempool has transactions, or when the tip block is older than
+    /// the interval in seconds even if the mempool is empty.
+    Interval(u64),
+}
+
+/// Whether the master produces a block in the round.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    Produce,
+    /// Skips the round. The age of the tip block in seconds is known only when the policy looked
+    /// at the tip block.
+    Skip {
+        tip_age: Option<u64>,
+    },
+}
+
+impl Default for BlockProductionPolicy {
+    fn default() -> Self {
+        BlockProductionPolicy::Always
+    }
+}
+
+impl BlockProductionPolicy {
+    /// Creates the policy from the name in the config. The interval is used only by "interval".
+    pub fn from_name(name: &str, max_block_interval: u64) -> Option<Self> {
+        match name {
+            "always" => Some(BlockProductionPolicy::Always),
+            "mempool" => Some(BlockProductionPolicy::Mempool),
+            "interval" => Some(BlockProductionPolicy::Interval(max_block_interval)),
+            _ => None,
+        }
+    }
+
+    /// Decides whether the master should produce a block. `now` is the Unix time in seconds,
+    /// which is compared with the time of the tip block.
+    pub fn should_produce<T: TapyrusApi>(&self, rpc: &T, now: u64) -> Result<Decision, Error> {
+        let interval = match self {
+            BlockProductionPolicy::Always => return Ok(Decision::Produce),
+            BlockProductionPolicy::Mempool => None,
+            BlockProductionPolicy::Interval(interval) => Some(*interval),
+        };
+        if rpc.getmempoolinfo()?.size > 0 {
+            return Ok(Decision::Produce);
+        }
+        match interval {
+            Some(interval) => {
+                let tip = rpc.getblockchaininfo()?;
+                let hash = BlockHash::from_hex(&tip.bestblockhash)
+                    .map_err(|_| Error::InvalidRpcResponse(tip.bestblockhash.clone()))?;
+                let header = rpc.getblockheader(&hash)?;
+                let tip_age = now.saturating_sub(header.time as u64);
+                if tip_age >= interval {
+                    Ok(Decision::Produce)
+                } else {
+                    Ok(Decision::Skip {
+                        tip_age: Some(tip_age),
+                    })
+                }
+            }
+            None => Ok(Decision::Skip { tip_age: None }),
+        }
+    }
+}
+
+impl std::fmt::Display for BlockProductionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockProductionPolicy::Always => write!(f, "always"),
+            BlockProductionPolicy::Mempool => write!(f, "mempool"),
+            BlockProductionPolicy::Interval(interval) => write!(f, "interval({}s)", interval),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::setup::create_genesis::create_genesis_block;
+    use crate::rpc::GetMempoolInfoResult;
+    use crate::simulation::chain::MockChain;
+    use crate::tests::helper::address;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::rpc::MockRpc;
+
+    fn mempool(size: u64) -> GetMempoolInfoResult {
+        GetMempoolInfoResult {
+            size,
+            bytes: size * 250,
+        }
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(
+            BlockProductionPolicy::from_name("always", 600),
+            Some(BlockProductionPolicy::Always)
+        );
+        assert_eq!(
+            BlockProductionPolicy::from_name("interval", 600),
+            Some(BlockProductionPolicy::Interval(600))
+        );
+        assert_eq!(BlockProductionPolicy::from_name("never", 600), None);
+    }
+
+    #[test]
+    fn test_always() {
+        // It doesn't call any RPC.
+        let rpc = MockRpc::new();
+        assert_eq!(
+            BlockProductionPolicy::Always
+                .should_produce(&rpc, 0)
+                .unwrap(),
+            Decision::Produce
+        );
+        rpc.assert();
+    }
+
+    #[test]
+    fn test_mempool() {
+        let mut rpc = MockRpc::new();
+        rpc.should_call_getmempoolinfo(Ok(mempool(0)));
+        rpc.should_call_getmempoolinfo(Ok(mempool(2)));
+        assert_eq!(
+            BlockProductionPolicy::Mempool
+                .should_produce(&rpc, 0)
+                .unwrap(),
+            Decision::Skip { tip_age: None }
+        );
+        assert_eq!(
+            BlockProductionPolicy::Mempool
+                .should_produce(&rpc, 0)
+                .unwrap(),
+            Decision::Produce
+        );
+        rpc.assert();
+    }
+
+    #[test]
+    fn test_interval() {
+        let genesis = create_genesis_block(
+            &TEST_KEYS.aggregated(),
+            &address(&TEST_KEYS.key[0]),
+            1585197531,
+        );
+        let chain = MockChain::new(genesis);
+        let policy = BlockProductionPolicy::Interval(600);
+        // The mock chain has no mempool, so it depends on the time of the tip block.
+        assert_eq!(
+            policy.should_produce(&chain, 1585197531 + 599).unwrap(),
+            Decision::Skip { tip_age: Some(599) }
+        );
+        assert_eq!(
+            policy.should_produce(&chain, 1585197531 + 600).unwrap(),
+            Decision::Produce
+        );
+
+        // It doesn't look at the tip block when the mempool has transactions.
+        let mut rpc = MockRpc::new();
+        rpc.should_call_getmempoolinfo(Ok(mempool(1)));
+        assert_eq!(policy.should_produce(&rpc, 0).unwrap(), Decision::Produce);
+        rpc.assert();
+    }
+}
@@ -0,0 +1,121 @@
+use crate::errors::Error;
+use crate::net::Message;
+use crate::signer_node::NodeState;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Event which changes the state of a node.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Event {
+    /// The message received from another node.
+    Message(Message),
+    RoundLimitTimeout,
+    RoundIntervalTimeout,
+    /// tapyrus-core notified the new tip block.
+    NewTip,
+}
+
+/// A line of the recording.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Record {
+    /// Unix time in milliseconds when the event happened.
+    pub time: u64,
+    pub event: Event,
+    pub prev_state: NodeState,
+    /// The state which the event led to. For a message, it is the result of
+    /// `SignerNode::process_round_message`, which is before the next round is started.
+    pub next_state: NodeState,
+}
+
+/// Writes the events and the states of a node to a JSONL file, so that they can be replayed
+/// offline.
+pub struct Recorder {
+    file: LineWriter<File>,
+}
+
+impl Recorder {
+    /// Opens the file to append records. The records include block keys and secret shares, so the
+    /// file is created readable only by the owner, and an existing file which the group or the
+    /// others can read is refused.
+    pub fn create(path: &str) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .mode(0o600)
+            .open(path)?;
+        if file.metadata()?.permissions().mode() & 0o077 != 0 {
+            return Err(Error::from(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!(
+                    "{} is accessible by other users. It should be readable only by the owner.",
+                    path
+                ),
+            )));
+        }
+        Ok(Recorder {
+            file: LineWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, event: Event, prev_state: &NodeState, next_state: &NodeState) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let record = Record {
+            time,
+            event,
+            prev_state: prev_state.clone(),
+            next_state: next_state.clone(),
+        };
+        let result = serde_json::to_string(&record)
+            .map_err(Error::from)
+            .and_then(|line| writeln!(self.file, "{}", line).map_err(Error::from));
+        if let Err(e) = result {
+            log::warn!("Failed to record the event: {:?}", e);
+        }
+    }
+}
+
+/// Reads all records in the recording.
+pub fn read_records(path: &str) -> Result<Vec<Record>, Error> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str(&line)?);
+    }
+    Ok(records)
+}
+
+/// Copies the message through JSON, because some contents of the message are not Clone.
+pub(crate) fn copy_message(message: &Message) -> Message {
+    let json = serde_json::to_string(message).expect("message should be serialized");
+    serde_json::from_str(&json).expect("message should be deserialized")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let path = std::env::temp_dir().join("recorder_test_create.jsonl");
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        Recorder::create(path).unwrap();
+        let mode = std::fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // The recording which other users can read is not appended.
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(Recorder::create(path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
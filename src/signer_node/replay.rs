@@ -0,0 +1,331 @@
+use crate::errors::Error;
+use crate::net::{ConnectionManager, ConnectionManagerError, Message, MessageType, SignerID};
+use crate::rpc::{GetBlockchainInfoResult, GetMempoolInfoResult, TapyrusApi};
+use crate::signer_node::recorder::{Event, Record};
+use crate::signer_node::{NodeState, SignerNode};
+use redis::ControlFlow;
+use std::sync::mpsc::channel;
+use std::thread::JoinHandle;
+use tapyrus::blockdata::block::{Block, BlockHeader};
+use tapyrus::hash_types::{BlockHash, BlockSigHash};
+use tapyrus::Address;
+
+/// RPC for replays. The blocks in a recording were already checked by tapyrus-core when it was
+/// made, so it accepts all blocks. It can't create new blocks.
+pub struct ReplayRpc;
+
+impl TapyrusApi for ReplayRpc {
+    fn getnewblock(&self, _address: &Address) -> Result<Block, Error> {
+        Err(Error::InvalidRequest(jsonrpc::error::RpcError {
+            code: -1,
+            message: "getnewblock is not available in replays".to_string(),
+            data: None,
+        }))
+    }
+
+    fn testproposedblock(&self, _block: &Block) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn submitblock(&self, _block: &Block) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn getblockchaininfo(&self) -> Result<GetBlockchainInfoResult, Error> {
+        Err(Error::InvalidRequest(jsonrpc::error::RpcError {
+            code: -1,
+            message: "getblockchaininfo is not available in replays".to_string(),
+            data: None,
+        }))
+    }
+
+    fn getblockhash(&self, _height: u64) -> Result<BlockHash, Error> {
+        Err(Error::InvalidRequest(jsonrpc::error::RpcError {
+            code: -1,
+            message: "getblockhash is not available in replays".to_string(),
+            data: None,
+        }))
+    }
+
+    fn getblockheader(&self, _hash: &BlockHash) -> Result<BlockHeader, Error> {
+        Err(Error::InvalidRequest(jsonrpc::error::RpcError {
+            code: -1,
+            message: "getblockheader is not available in replays".to_string(),
+            data: None,
+        }))
+    }
+
+    fn getmempoolinfo(&self) -> Result<GetMempoolInfoResult, Error> {
+        Err(Error::InvalidRequest(jsonrpc::error::RpcError {
+            code: -1,
+            message: "getmempoolinfo is not available in replays".to_string(),
+            data: None,
+        }))
+    }
+}
+
+/// ConnectionManager for replays, which discards the messages sent by the node.
+pub struct ReplayConnectionManager;
+
+impl ConnectionManager for ReplayConnectionManager {
+    type ERROR = Error;
+
+    fn broadcast_message(&self, _message: Message) {}
+
+    fn send_message(&self, _message: Message) {}
+
+    fn start(
+        &self,
+        _message_processor: impl FnMut(Message) -> ControlFlow<()> + Send + 'static,
+        _id: SignerID,
+    ) -> JoinHandle<()> {
+        std::thread::Builder::new().spawn(|| {}).unwrap()
+    }
+
+    fn test_connection(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn take_error(
+        &mut self,
+    ) -> Result<ConnectionManagerError<Self::ERROR>, std::sync::mpsc::TryRecvError> {
+        let (_s, r) = channel();
+        r.try_recv()
+    }
+}
+
+/// The first record whose state was not reproduced.
+#[derive(Debug)]
+pub struct Divergence {
+    /// Index of the record in the recording, starting from 0.
+    pub index: usize,
+    pub expected: NodeState,
+    pub actual: NodeState,
+}
+
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    /// The number of replayed messages.
+    pub replayed: usize,
+    /// The number of timer events and new tip notifications, which are not replayed.
+    pub skipped: usize,
+    pub divergence: Option<Divergence>,
+}
+
+/// Feeds the messages in the recording into `SignerNode::process_round_message` from the state
+/// recorded before each of them, and stops at the first message which leads to a different state.
+///
+/// Timer events and new tip notifications are skipped, because they need tapyrus-core to start
+/// rounds. The node must have
+/// the same public key and federations as the recorded node.
+pub fn replay<C>(node: &mut SignerNode<ReplayRpc, C>, records: Vec<Record>) -> ReplayReport
+where
+    C: ConnectionManager,
+{
+    let mut report = ReplayReport::default();
+    for (index, record) in records.into_iter().enumerate() {
+        let message = match record.event {
+            Event::Message(message) => message,
+            Event::RoundLimitTimeout | Event::RoundIntervalTimeout | Event::NewTip => {
+                report.skipped += 1;
+                continue;
+            }
+        };
+        // A candidate block makes the node draw its block key at random.
+        let exact = match message.message_type {
+            MessageType::Candidateblock(..) | MessageType::Signedcandidateblock(..) => false,
+            _ => true,
+        };
+
+        node.current_state = record.prev_state;
+        let actual = node.process_round_message(&message.sender_id, message.message_type);
+        report.replayed += 1;
+
+        let reproduced = if exact {
+            actual == record.next_state
+        } else {
+            Outline::from(&actual) == Outline::from(&record.next_state)
+        };
+        if !reproduced {
+            report.divergence = Some(Divergence {
+                index,
+                expected: record.next_state,
+                actual,
+            });
+            break;
+        }
+    }
+    report
+}
+
+/// Parts of the state which don't depend on the random values drawn by the node.
+#[derive(Debug, PartialEq)]
+struct Outline {
+    kind: &'static str,
+    block_height: Option<u64>,
+    master_index: Option<usize>,
+    candidate_block: Option<BlockSigHash>,
+    has_block_key: bool,
+    /// Signers whose block VSSs the node has.
+    block_vss_senders: Vec<SignerID>,
+    participants: Vec<SignerID>,
+    signers: Vec<SignerID>,
+    round_is_done: Option<bool>,
+}
+
+fn sorted<'a>(ids: impl Iterator<Item = &'a SignerID>) -> Vec<SignerID> {
+    let mut ids: Vec<SignerID> = ids.cloned().collect();
+    ids.sort();
+    ids
+}
+
+impl Outline {
+    fn from(state: &NodeState) -> Self {
+        let mut outline = Outline {
+            kind: "",
+            block_height: None,
+            master_index: None,
+            candidate_block: None,
+            has_block_key: false,
+            block_vss_senders: vec![],
+            participants: vec![],
+            signers: vec![],
+            round_is_done: None,
+        };
+        match state {
+            NodeState::Joining => outline.kind = "Joining",
+            NodeState::Idling { block_height } => {
+                outline.kind = "Idling";
+                outline.block_height = Some(*block_height);
+            }
+            NodeState::Master {
+                block_key,
+                shared_block_secrets,
+                candidate_block,
+                signatures,
+                participants,
+                round_is_done,
+                block_height,
+                ..
+            } => {
+                outline.kind = "Master";
+                outline.block_height = Some(*block_height);
+                outline.candidate_block =
+                    candidate_block.as_ref().map(|b| b.header.signature_hash());
+                outline.has_block_key = block_key.is_some();
+                outline.block_vss_senders = sorted(shared_block_secrets.keys());
+                outline.participants = sorted(participants.iter());
+                outline.signers = sorted(signatures.keys());
+                outline.round_is_done = Some(*round_is_done);
+            }
+            NodeState::Member {
+                block_key,
+                shared_block_secrets,
+                candidate_block,
+                participants,
+                master_index,
+                block_height,
+                ..
+            } => {
+                outline.kind = "Member";
+                outline.block_height = Some(*block_height);
+                outline.master_index = Some(*master_index);
+                outline.candidate_block =
+                    candidate_block.as_ref().map(|b| b.header.signature_hash());
+                outline.has_block_key = block_key.is_some();
+                outline.block_vss_senders = sorted(shared_block_secrets.keys());
+                outline.participants = sorted(participants.iter());
+            }
+            NodeState::RoundComplete {
+                master_index,
+                block_height,
+            } => {
+                outline.kind = "RoundComplete";
+                outline.block_height = Some(*block_height);
+                outline.master_index = Some(*master_index);
+            }
+        }
+        outline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer_node::recorder::{read_records, Recorder};
+    use crate::signer_node::{
+        NodeParameters, ROUND_INTERVAL_DEFAULT_SECS, ROUND_LIMIT_DEFAULT_SECS,
+    };
+    use crate::simulation::{FederationSpec, Simulation};
+
+    fn record_rounds(path: &str) -> Simulation {
+        let _ = std::fs::remove_file(path);
+        let mut simulation = Simulation::new(
+            0,
+            4,
+            vec![FederationSpec {
+                block_height: 0,
+                signers: vec![0, 1, 2, 3],
+                threshold: 3,
+            }],
+        );
+        simulation
+            .node_mut(1)
+            .set_recorder(Recorder::create(path).unwrap());
+        assert!(simulation.run_until(2, 0));
+        simulation
+    }
+
+    fn replay_node(simulation: &Simulation) -> SignerNode<ReplayRpc, ReplayConnectionManager> {
+        let recorded = simulation.node(1).params();
+        let params = NodeParameters::new(
+            recorded.address.clone(),
+            recorded.signer_id.pubkey,
+            ReplayRpc,
+            ROUND_INTERVAL_DEFAULT_SECS,
+            ROUND_LIMIT_DEFAULT_SECS,
+            true,
+            false,
+            recorded.federations().clone(),
+        );
+        SignerNode::new(ReplayConnectionManager, params)
+    }
+
+    #[test]
+    fn test_replay() {
+        let path = std::env::temp_dir().join("replay_test_replay.jsonl");
+        let path = path.to_str().unwrap();
+        let simulation = record_rounds(path);
+
+        let records = read_records(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let report = replay(&mut replay_node(&simulation), records);
+        assert!(report.divergence.is_none(), "{:?}", report.divergence);
+        assert!(report.replayed > 0);
+        assert!(report.skipped > 0);
+    }
+
+    #[test]
+    fn test_replay_reports_first_divergence() {
+        let path = std::env::temp_dir().join("replay_test_replay_reports_first_divergence.jsonl");
+        let path = path.to_str().unwrap();
+        let simulation = record_rounds(path);
+
+        let mut records = read_records(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        // Tamper the result of the last message.
+        let index = records
+            .iter()
+            .rposition(|record| match record.event {
+                Event::Message(_) => true,
+                _ => false,
+            })
+            .unwrap();
+        records[index].next_state = NodeState::Joining;
+
+        let report = replay(&mut replay_node(&simulation), records);
+        let divergence = report.divergence.unwrap();
+        assert_eq!(divergence.index, index);
+        assert_eq!(divergence.expected, NodeState::Joining);
+    }
+}
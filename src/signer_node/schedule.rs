@@ -0,0 +1,121 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Round schedule aligned to the wall clock.
+//!
+//! Without the schedule, a round starts when the previous one ends and the master waits for the
+//! round duration, so block intervals drift with the processing latency. With the schedule, time
+//! is divided into slots of the round duration since the epoch. The round of the slot `k` starts at
+//! `epoch + k * round_duration` and its master is the signer at the index `k % n` in the
+//! federation, so all signers whose clocks agree choose the same master without knowing the
+//! previous round.
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundSchedule {
+    /// Unix time in seconds when the slot 0 starts.
+    pub epoch: u64,
+    /// Length of a slot in seconds. It is the round duration.
+    pub slot_duration: u64,
+}
+
+/// Where a time is relative to a slot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotPosition {
+    /// The time is before the start of the slot by the duration.
+    Early(Duration),
+    Within,
+    /// The time is after the end of the slot by the duration.
+    Late(Duration),
+}
+
+impl RoundSchedule {
+    pub fn new(epoch: u64, slot_duration: u64) -> Self {
+        assert!(slot_duration > 0, "slot duration should be positive");
+        RoundSchedule {
+            epoch,
+            slot_duration,
+        }
+    }
+
+    /// Returns the time since the Unix epoch when the slot starts.
+    pub fn slot_start(&self, slot: u64) -> Duration {
+        Duration::from_secs(self.epoch + slot * self.slot_duration)
+    }
+
+    /// Returns the first slot which starts after the time.
+    pub fn next_slot(&self, now: Duration) -> u64 {
+        let epoch = Duration::from_secs(self.epoch);
+        if now < epoch {
+            return 0;
+        }
+        (now - epoch).as_secs() / self.slot_duration + 1
+    }
+
+    /// Returns the index of the master of the slot in the federation of `signers_count` signers.
+    pub fn master_index(&self, slot: u64, signers_count: usize) -> usize {
+        (slot % signers_count as u64) as usize
+    }
+
+    /// Returns where the time is relative to the slot.
+    pub fn position(&self, slot: u64, now: Duration) -> SlotPosition {
+        let start = self.slot_start(slot);
+        let end = self.slot_start(slot + 1);
+        if now < start {
+            SlotPosition::Early(start - now)
+        } else if now >= end {
+            SlotPosition::Late(now - end)
+        } else {
+            SlotPosition::Within
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_slot() {
+        let schedule = RoundSchedule::new(1000, 60);
+        assert_eq!(schedule.next_slot(Duration::from_secs(0)), 0);
+        assert_eq!(schedule.next_slot(Duration::from_secs(999)), 0);
+        assert_eq!(schedule.next_slot(Duration::from_secs(1000)), 1);
+        assert_eq!(schedule.next_slot(Duration::from_millis(1059_999)), 1);
+        assert_eq!(schedule.next_slot(Duration::from_secs(1060)), 2);
+
+        assert_eq!(schedule.slot_start(0), Duration::from_secs(1000));
+        assert_eq!(schedule.slot_start(2), Duration::from_secs(1120));
+    }
+
+    #[test]
+    fn test_master_index() {
+        let schedule = RoundSchedule::new(0, 60);
+        assert_eq!(schedule.master_index(0, 5), 0);
+        assert_eq!(schedule.master_index(4, 5), 4);
+        assert_eq!(schedule.master_index(7, 5), 2);
+    }
+
+    #[test]
+    fn test_position() {
+        let schedule = RoundSchedule::new(1000, 60);
+        assert_eq!(
+            schedule.position(1, Duration::from_secs(1055)),
+            SlotPosition::Early(Duration::from_secs(5))
+        );
+        assert_eq!(
+            schedule.position(1, Duration::from_secs(1060)),
+            SlotPosition::Within
+        );
+        assert_eq!(
+            schedule.position(1, Duration::from_millis(1119_999)),
+            SlotPosition::Within
+        );
+        assert_eq!(
+            schedule.position(1, Duration::from_secs(1122)),
+            SlotPosition::Late(Duration::from_secs(2))
+        );
+    }
+}
@@ -0,0 +1,636 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! Validation of candidate blocks on the member side.
+//!
+//! A candidate block goes through the checks in order and is rejected at the first check which
+//! fails. The xfield and the aggregated public key are always checked first, and
+//! `testproposedblock` of tapyrus-core is always called last, so the other checks can be chosen
+//! in the config file without calling RPC for blocks which are rejected locally.
+
+use crate::errors::Error;
+use crate::federation::Federations;
+use crate::rpc::TapyrusApi;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tapyrus::blockdata::block::{Block, XField};
+use tapyrus::blockdata::script::Script;
+use tapyrus::consensus::encode::serialize;
+use tapyrus::hash_types::BlockHash;
+use tapyrus::Address;
+
+/// Default of the allowed difference between the block time and the local clock. This is the same
+/// as the limit of the future block time in tapyrus-core.
+pub const DEFAULT_MAX_TIME_SKEW_SECS: u64 = 2 * 60 * 60;
+/// Default limit of the serialized block size.
+pub const DEFAULT_MAX_BLOCK_SIZE: usize = 4_000_000;
+
+/// The reason why a candidate block is rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejection {
+    UnsupportedXField,
+    /// The aggregated public key in the xfield doesn't match the federation which gets started at
+    /// the next block, or the block doesn't have the key of it.
+    InvalidAggregatedPublicKey,
+    /// The block height in the coinbase is not the height of the round.
+    HeightMismatch {
+        expected: u64,
+        actual: Option<u64>,
+    },
+    /// The parent of the block is not the tip of the chain.
+    NotOnTip {
+        parent: BlockHash,
+        tip: String,
+    },
+    /// The block time is too far from the local clock.
+    TimestampOutOfRange {
+        time: u32,
+        now: u64,
+    },
+    /// The coinbase pays to the script which is not approved.
+    UnapprovedCoinbase(Script),
+    TooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// testproposedblock returned false.
+    RejectedByCore,
+    /// testproposedblock or other RPC calls for the checks failed.
+    RpcFailed(String),
+}
+
+impl Rejection {
+    /// Short name of the reason, which is used as the key of the counts.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Rejection::UnsupportedXField => "unsupported-xfield",
+            Rejection::InvalidAggregatedPublicKey => "invalid-aggregated-public-key",
+            Rejection::HeightMismatch { .. } => "height-mismatch",
+            Rejection::NotOnTip { .. } => "not-on-tip",
+            Rejection::TimestampOutOfRange { .. } => "timestamp-out-of-range",
+            Rejection::UnapprovedCoinbase(_) => "unapproved-coinbase",
+            Rejection::TooLarge { .. } => "too-large",
+            Rejection::RejectedByCore => "rejected-by-core",
+            Rejection::RpcFailed(_) => "rpc-failed",
+        }
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::UnsupportedXField => write!(f, "the xfield type is not supported"),
+            Rejection::InvalidAggregatedPublicKey => {
+                write!(f, "the aggregated public key in the xfield is invalid")
+            }
+            Rejection::HeightMismatch {
+                expected,
+                actual: Some(actual),
+            } => write!(
+                f,
+                "the block height is {}, but the round is for {}",
+                actual, expected
+            ),
+            Rejection::HeightMismatch {
+                expected,
+                actual: None,
+            } => write!(
+                f,
+                "the block has no coinbase, but the round is for {}",
+                expected
+            ),
+            Rejection::NotOnTip { parent, tip } => {
+                write!(f, "the parent {} is not the tip {}", parent, tip)
+            }
+            Rejection::TimestampOutOfRange { time, now } => write!(
+                f,
+                "the block time {} is too far from the local clock {}",
+                time, now
+            ),
+            Rejection::UnapprovedCoinbase(script) => {
+                write!(f, "the coinbase pays to the unapproved script {}", script)
+            }
+            Rejection::TooLarge { size, limit } => {
+                write!(f, "the block size {} exceeds the limit {}", size, limit)
+            }
+            Rejection::RejectedByCore => write!(f, "testproposedblock returned false"),
+            Rejection::RpcFailed(e) => write!(f, "RPC failed: {}", e),
+        }
+    }
+}
+
+/// What the checks know about the round.
+pub struct CandidateContext<'a> {
+    /// The height of the round, which is the height of the candidate block.
+    pub block_height: u64,
+    pub federations: &'a Federations,
+    pub rpc: &'a dyn TapyrusApi,
+}
+
+pub trait CandidateCheck: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn check(&self, block: &Block, context: &CandidateContext) -> Result<(), Rejection>;
+}
+
+/// Rejects unknown xfield types.
+pub struct XFieldCheck;
+
+impl CandidateCheck for XFieldCheck {
+    fn name(&self) -> &'static str {
+        "xfield"
+    }
+
+    fn check(&self, block: &Block, _context: &CandidateContext) -> Result<(), Rejection> {
+        match block.header.xfield {
+            XField::Unknown(_, _) => Err(Rejection::UnsupportedXField),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Checks the block has the aggregated public key of the next federation if and only if the
+/// federation gets started at the next block.
+pub struct AggregatedPublicKeyCheck;
+
+impl CandidateCheck for AggregatedPublicKeyCheck {
+    fn name(&self) -> &'static str {
+        "aggregated-public-key"
+    }
+
+    fn check(&self, block: &Block, context: &CandidateContext) -> Result<(), Rejection> {
+        let next_block_height = context.block_height + 1;
+        let federation = context.federations.get_by_block_height(next_block_height);
+        let valid = match block.header.aggregated_public_key() {
+            Some(public_key) => {
+                public_key == federation.aggregated_public_key()
+                    && next_block_height == federation.block_height()
+            }
+            None => next_block_height != federation.block_height(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Rejection::InvalidAggregatedPublicKey)
+        }
+    }
+}
+
+/// Checks the block height in the coinbase, which is the index of its prevout in Tapyrus.
+pub struct HeightCheck;
+
+impl CandidateCheck for HeightCheck {
+    fn name(&self) -> &'static str {
+        "height"
+    }
+
+    fn check(&self, block: &Block, context: &CandidateContext) -> Result<(), Rejection> {
+        let actual = block
+            .txdata
+            .first()
+            .and_then(|coinbase| coinbase.input.first())
+            .map(|input| input.previous_output.vout as u64);
+        if actual == Some(context.block_height) {
+            Ok(())
+        } else {
+            Err(Rejection::HeightMismatch {
+                expected: context.block_height,
+                actual,
+            })
+        }
+    }
+}
+
+/// Checks the parent of the block is the tip of tapyrus-core.
+pub struct ParentCheck;
+
+impl CandidateCheck for ParentCheck {
+    fn name(&self) -> &'static str {
+        "parent"
+    }
+
+    fn check(&self, block: &Block, context: &CandidateContext) -> Result<(), Rejection> {
+        let tip = context
+            .rpc
+            .getblockchaininfo()
+            .map_err(|e| Rejection::RpcFailed(format!("{:?}", e)))?
+            .bestblockhash;
+        if block.header.prev_blockhash.to_string() == tip {
+            Ok(())
+        } else {
+            Err(Rejection::NotOnTip {
+                parent: block.header.prev_blockhash,
+                tip,
+            })
+        }
+    }
+}
+
+/// Checks the block time is within the skew from the local clock in both directions.
+pub struct TimestampCheck {
+    max_skew: u64,
+}
+
+impl TimestampCheck {
+    pub fn new(max_skew: u64) -> Self {
+        TimestampCheck { max_skew }
+    }
+}
+
+impl CandidateCheck for TimestampCheck {
+    fn name(&self) -> &'static str {
+        "timestamp"
+    }
+
+    fn check(&self, block: &Block, _context: &CandidateContext) -> Result<(), Rejection> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let time = block.header.time;
+        let skew = if time as u64 > now {
+            time as u64 - now
+        } else {
+            now - time as u64
+        };
+        if skew <= self.max_skew {
+            Ok(())
+        } else {
+            Err(Rejection::TimestampOutOfRange { time, now })
+        }
+    }
+}
+
+/// Checks all coinbase outputs except OP_RETURN pay to the approved addresses.
+pub struct CoinbaseCheck {
+    scripts: Vec<Script>,
+}
+
+impl CoinbaseCheck {
+    pub fn new(addresses: &[Address]) -> Self {
+        CoinbaseCheck {
+            scripts: addresses.iter().map(Address::script_pubkey).collect(),
+        }
+    }
+}
+
+impl CandidateCheck for CoinbaseCheck {
+    fn name(&self) -> &'static str {
+        "coinbase"
+    }
+
+    fn check(&self, block: &Block, _context: &CandidateContext) -> Result<(), Rejection> {
+        let outputs = match block.txdata.first() {
+            Some(coinbase) => &coinbase.output[..],
+            None => &[],
+        };
+        match outputs
+            .iter()
+            .find(|o| !o.script_pubkey.is_op_return() && !self.scripts.contains(&o.script_pubkey))
+        {
+            Some(output) => Err(Rejection::UnapprovedCoinbase(output.script_pubkey.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Limits the serialized block size.
+pub struct SizeCheck {
+    limit: usize,
+}
+
+impl SizeCheck {
+    pub fn new(limit: usize) -> Self {
+        SizeCheck { limit }
+    }
+}
+
+impl CandidateCheck for SizeCheck {
+    fn name(&self) -> &'static str {
+        "size"
+    }
+
+    fn check(&self, block: &Block, _context: &CandidateContext) -> Result<(), Rejection> {
+        let size = serialize(block).len();
+        if size <= self.limit {
+            Ok(())
+        } else {
+            Err(Rejection::TooLarge {
+                size,
+                limit: self.limit,
+            })
+        }
+    }
+}
+
+/// Asks tapyrus-core whether the block can be on the tip.
+pub struct TestProposedBlockCheck;
+
+impl CandidateCheck for TestProposedBlockCheck {
+    fn name(&self) -> &'static str {
+        "testproposedblock"
+    }
+
+    fn check(&self, block: &Block, context: &CandidateContext) -> Result<(), Rejection> {
+        match context.rpc.testproposedblock(block) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(Rejection::RejectedByCore),
+            Err(e) => Err(Rejection::RpcFailed(format!("{:?}", e))),
+        }
+    }
+}
+
+/// Runs the checks for candidate blocks and counts the rejections by reason.
+pub struct CandidateValidator {
+    checks: Vec<Box<dyn CandidateCheck>>,
+    rejections: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl CandidateValidator {
+    /// The checks are run after the xfield and the aggregated public key checks and before
+    /// testproposedblock.
+    pub fn new(checks: Vec<Box<dyn CandidateCheck>>) -> Self {
+        let mut all: Vec<Box<dyn CandidateCheck>> =
+            vec![Box::new(XFieldCheck), Box::new(AggregatedPublicKeyCheck)];
+        all.extend(checks);
+        all.push(Box::new(TestProposedBlockCheck));
+        CandidateValidator {
+            checks: all,
+            rejections: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn validate(&self, block: &Block, context: &CandidateContext) -> Result<(), Rejection> {
+        let result = self
+            .checks
+            .iter()
+            .try_for_each(|check| check.check(block, context));
+        if let Err(rejection) = &result {
+            *self
+                .rejections
+                .lock()
+                .unwrap()
+                .entry(rejection.reason())
+                .or_insert(0) += 1;
+        }
+        result
+    }
+
+    /// Returns the names of the checks in the order they run.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.checks.iter().map(|check| check.name()).collect()
+    }
+
+    /// Returns the number of rejected blocks for each reason.
+    pub fn rejections(&self) -> BTreeMap<&'static str, u64> {
+        self.rejections.lock().unwrap().clone()
+    }
+}
+
+impl Default for CandidateValidator {
+    fn default() -> Self {
+        CandidateValidator::new(vec![])
+    }
+}
+
+pub struct ValidationSettings {
+    pub max_time_skew: u64,
+    pub max_block_size: usize,
+    pub coinbase_addresses: Vec<Address>,
+}
+
+/// Creates the checks from their names.
+pub fn build_checks(
+    names: &[String],
+    settings: &ValidationSettings,
+) -> Result<Vec<Box<dyn CandidateCheck>>, Error> {
+    names
+        .iter()
+        .map(|name| -> Result<Box<dyn CandidateCheck>, Error> {
+            match name.as_str() {
+                "height" => Ok(Box::new(HeightCheck)),
+                "parent" => Ok(Box::new(ParentCheck)),
+                "timestamp" => Ok(Box::new(TimestampCheck::new(settings.max_time_skew))),
+                "coinbase" if settings.coinbase_addresses.is_empty() => Err(Error::InvalidArgs(
+                    "coinbase check needs coinbase-addresses".to_string(),
+                )),
+                "coinbase" => Ok(Box::new(CoinbaseCheck::new(&settings.coinbase_addresses))),
+                "size" => Ok(Box::new(SizeCheck::new(settings.max_block_size))),
+                _ => Err(Error::InvalidArgs(format!(
+                    "unknown candidate block check: {}",
+                    name
+                ))),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::federation::Federation;
+    use crate::rpc::tests::{safety, MockRpc};
+    use crate::tests::helper::address;
+    use crate::tests::helper::blocks::get_block;
+    use crate::tests::helper::keys::TEST_KEYS;
+    use crate::tests::helper::node_vss::node_vss;
+    use std::str::FromStr;
+    use tapyrus::consensus::encode::deserialize;
+    use tapyrus::network::constants::Network;
+    use tapyrus::PublicKey;
+
+    const TEST_BLOCK_WITH_PUBKEY: &str = "010000000000000000000000000000000000000000000000000000000000000000000000e7c526d0125538b13a50b06465fb8b72120be13fb1142e93aba2aabb2a4f369826c18219f76e4d0ebddbaa9b744837c2ac65b347673695a23c3cc1a2be4141e1427d735e0121030d856ac9f5871c3785a2d76e3a5d9eca6fcce70f4de63339671dfb9d1f33edb0000101000000010000000000000000000000000000000000000000000000000000000000000000000000002221025700236c2890233592fcef262f4520d22af9160e3d9705855140eb2aa06c35d3ffffffff0100f2052a010000001976a914834e0737cdb9008db614cd95ec98824e952e3dc588ac00000000";
+    const TEST_BLOCK_WITHOUT_PUBKEY: &str = "010000000000000000000000000000000000000000000000000000000000000000000000e7c526d0125538b13a50b06465fb8b72120be13fb1142e93aba2aabb2a4f369826c18219f76e4d0ebddbaa9b744837c2ac65b347673695a23c3cc1a2be4141e1427d735e00000101000000010000000000000000000000000000000000000000000000000000000000000000000000002221025700236c2890233592fcef262f4520d22af9160e3d9705855140eb2aa06c35d3ffffffff0100f2052a010000001976a914834e0737cdb9008db614cd95ec98824e952e3dc588ac00000000";
+
+    fn test_block_with_public_key() -> Block {
+        let raw_block = hex::decode(TEST_BLOCK_WITH_PUBKEY).unwrap();
+        deserialize(&raw_block).unwrap()
+    }
+
+    fn test_block_without_public_key() -> Block {
+        let raw_block = hex::decode(TEST_BLOCK_WITHOUT_PUBKEY).unwrap();
+        deserialize(&raw_block).unwrap()
+    }
+
+    fn federations() -> Federations {
+        Federations::new(vec![Federation::new(
+            TEST_KEYS.pubkeys()[4],
+            0,
+            Some(3),
+            Some(node_vss(0)),
+            TEST_KEYS.aggregated(),
+        )])
+    }
+
+    fn check(
+        check: &dyn CandidateCheck,
+        block: &Block,
+        block_height: u64,
+        federations: &Federations,
+    ) -> Result<(), Rejection> {
+        let rpc = MockRpc {
+            return_block: safety(get_block(0)),
+        };
+        let context = CandidateContext {
+            block_height,
+            federations,
+            rpc: &rpc,
+        };
+        check.check(block, &context)
+    }
+
+    #[test]
+    fn test_aggregated_public_key_check() {
+        let federation0 = Federation::new(
+            TEST_KEYS.pubkeys()[4],
+            0,
+            Some(3),
+            Some(node_vss(0)),
+            TEST_KEYS.aggregated(),
+        );
+        let federation100 = Federation::new(
+            TEST_KEYS.pubkeys()[4],
+            100,
+            Some(3),
+            Some(node_vss(1)),
+            TEST_KEYS.aggregated(),
+        );
+        let another_key = PublicKey::from_str(
+            "030acd6af981c498ebf2ffd9a341d2a96bde5832c150e7d300fa3583eee0f964fe",
+        )
+        .unwrap();
+        let federation200 = Federation::new(
+            TEST_KEYS.pubkeys()[4],
+            200,
+            Some(4),
+            Some(node_vss(2)),
+            another_key,
+        );
+        let federations = Federations::new(vec![federation0, federation100, federation200]);
+
+        let block = test_block_with_public_key();
+        assert!(check(&AggregatedPublicKeyCheck, &block, 99, &federations).is_ok());
+        assert!(check(&AggregatedPublicKeyCheck, &block, 100, &federations).is_err());
+        assert!(check(&AggregatedPublicKeyCheck, &block, 199, &federations).is_err());
+
+        let block = test_block_without_public_key();
+        assert!(check(&AggregatedPublicKeyCheck, &block, 99, &federations).is_err());
+        assert!(check(&AggregatedPublicKeyCheck, &block, 100, &federations).is_ok());
+    }
+
+    #[test]
+    fn test_height_check() {
+        // The coinbase of the block spends the prevout whose index is 1.
+        let block = get_block(0);
+        assert!(check(&HeightCheck, &block, 1, &federations()).is_ok());
+        assert_eq!(
+            check(&HeightCheck, &block, 2, &federations()),
+            Err(Rejection::HeightMismatch {
+                expected: 2,
+                actual: Some(1),
+            })
+        );
+    }
+
+    #[test]
+    fn test_timestamp_check() {
+        let mut block = get_block(0);
+        block.header.time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u32;
+        assert!(check(&TimestampCheck::new(60), &block, 0, &federations()).is_ok());
+
+        block.header.time -= 120;
+        match check(&TimestampCheck::new(60), &block, 0, &federations()) {
+            Err(Rejection::TimestampOutOfRange { .. }) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_coinbase_check() {
+        let block = get_block(0);
+        let paid =
+            Address::from_script(&block.txdata[0].output[0].script_pubkey, Network::Prod).unwrap();
+        // The other output of the coinbase is OP_RETURN.
+        assert!(check(&CoinbaseCheck::new(&[paid]), &block, 0, &federations()).is_ok());
+
+        let other = address(&TEST_KEYS.key[0]);
+        assert_eq!(
+            check(&CoinbaseCheck::new(&[other]), &block, 0, &federations()),
+            Err(Rejection::UnapprovedCoinbase(
+                block.txdata[0].output[0].script_pubkey.clone()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_size_check() {
+        let block = get_block(0);
+        let size = serialize(&block).len();
+        assert!(check(&SizeCheck::new(size), &block, 0, &federations()).is_ok());
+        assert_eq!(
+            check(&SizeCheck::new(size - 1), &block, 0, &federations()),
+            Err(Rejection::TooLarge {
+                size,
+                limit: size - 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_validator_counts_rejections() {
+        let validator = CandidateValidator::new(vec![Box::new(HeightCheck)]);
+        assert_eq!(
+            validator.names(),
+            vec![
+                "xfield",
+                "aggregated-public-key",
+                "height",
+                "testproposedblock"
+            ]
+        );
+
+        let rpc = MockRpc {
+            return_block: safety(get_block(0)),
+        };
+        let federations = federations();
+        let context = CandidateContext {
+            block_height: 1,
+            federations: &federations,
+            rpc: &rpc,
+        };
+        assert!(validator.validate(&get_block(0), &context).is_ok());
+
+        let context = CandidateContext {
+            block_height: 2,
+            federations: &federations,
+            rpc: &rpc,
+        };
+        assert!(validator.validate(&get_block(0), &context).is_err());
+        assert!(validator.validate(&get_block(0), &context).is_err());
+        assert_eq!(
+            validator.rejections(),
+            vec![("height-mismatch", 2)].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn test_build_checks() {
+        let settings = ValidationSettings {
+            max_time_skew: DEFAULT_MAX_TIME_SKEW_SECS,
+            max_block_size: DEFAULT_MAX_BLOCK_SIZE,
+            coinbase_addresses: vec![],
+        };
+        let names = vec!["height".to_string(), "size".to_string()];
+        let checks = build_checks(&names, &settings).unwrap();
+        assert_eq!(checks.len(), 2);
+
+        assert!(build_checks(&["coinbase".to_string()], &settings).is_err());
+        assert!(build_checks(&["unknown".to_string()], &settings).is_err());
+    }
+}
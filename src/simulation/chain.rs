@@ -0,0 +1,253 @@
+use crate::errors::Error;
+use crate::rpc::{GetBlockchainInfoResult, GetMempoolInfoResult, TapyrusApi};
+use crate::sign::Sign;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tapyrus::blockdata::block::{Block, BlockHeader, XField};
+use tapyrus::blockdata::script::Builder;
+use tapyrus::blockdata::transaction::{OutPoint, Transaction, TxIn, TxOut};
+use tapyrus::hash_types::{BlockHash, TxMerkleNode};
+use tapyrus::{Address, PublicKey};
+
+/// In-memory chain which stands in for tapyrus-core. It is shared by all nodes in a simulation,
+/// and the clones of it refer to the same chain.
+///
+/// Like tapyrus-core, it accepts only blocks whose proof is valid for the current aggregated
+/// public key, and switches the key when a block has a new one in its xfield. It also records all
+/// properly signed blocks submitted to it, even if they are not on the tip, to find conflicts.
+#[derive(Clone)]
+pub struct MockChain {
+    state: Arc<Mutex<ChainState>>,
+}
+
+struct ChainState {
+    blocks: Vec<Block>,
+    /// The aggregated public key which the block next to each block must be signed with.
+    aggregated_public_keys: Vec<PublicKey>,
+    /// Hashes of the properly signed blocks at each block height.
+    signed_blocks: BTreeMap<u64, Vec<BlockHash>>,
+}
+
+impl MockChain {
+    /// Creates the chain which has only the genesis block. The genesis block must have the
+    /// aggregated public key of the first federation in its xfield, but its proof is not verified.
+    pub fn new(genesis: Block) -> Self {
+        let aggregated_public_key = genesis
+            .header
+            .aggregated_public_key()
+            .expect("genesis block should have the aggregated public key");
+        MockChain {
+            state: Arc::new(Mutex::new(ChainState {
+                blocks: vec![genesis],
+                aggregated_public_keys: vec![aggregated_public_key],
+                signed_blocks: BTreeMap::new(),
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, ChainState> {
+        self.state.lock().expect("chain state is poisoned")
+    }
+
+    /// Returns the height of the tip block. The height of the genesis block is 0.
+    pub fn height(&self) -> u64 {
+        (self.state().blocks.len() - 1) as u64
+    }
+
+    pub fn block(&self, height: u64) -> Option<Block> {
+        self.state().blocks.get(height as usize).cloned()
+    }
+
+    pub fn tip(&self) -> Block {
+        self.state()
+            .blocks
+            .last()
+            .cloned()
+            .expect("chain should have the genesis block")
+    }
+
+    /// Returns the aggregated public key which the next block must be signed with.
+    pub fn aggregated_public_key(&self) -> PublicKey {
+        *self
+            .state()
+            .aggregated_public_keys
+            .last()
+            .expect("chain should have the genesis block")
+    }
+
+    /// Returns the block heights where two or more different blocks were signed. Federations
+    /// must never sign them.
+    pub fn conflicting_heights(&self) -> Vec<u64> {
+        self.state()
+            .signed_blocks
+            .iter()
+            .filter(|(_, hashes)| hashes.len() > 1)
+            .map(|(height, _)| *height)
+            .collect()
+    }
+}
+
+fn rpc_error(code: i32, message: &str) -> Error {
+    Error::InvalidRequest(jsonrpc::error::RpcError {
+        code: code,
+        message: message.to_string(),
+        data: None,
+    })
+}
+
+/// Creates the block without proof on the tip, which has only the coinbase transaction.
+fn create_block(tip: &Block, height: u64, address: &Address) -> Block {
+    // The prevout index of the coinbase is the block height in Tapyrus.
+    let mut previous_output = OutPoint::null();
+    previous_output.vout = height as u32;
+    let coinbase = Transaction {
+        version: 1,
+        lock_time: 0,
+        input: vec![TxIn {
+            previous_output: previous_output,
+            script_sig: Builder::new().push_int(height as i64).into_script(),
+            sequence: 0xffffffff,
+            witness: vec![],
+        }],
+        output: vec![TxOut {
+            value: 0,
+            script_pubkey: address.script_pubkey(),
+        }],
+    };
+
+    let mut block = Block {
+        header: BlockHeader {
+            version: 1,
+            prev_blockhash: tip.header.block_hash(),
+            merkle_root: TxMerkleNode::default(),
+            im_merkle_root: TxMerkleNode::default(),
+            time: tip.header.time + 1,
+            xfield: XField::None,
+            proof: None,
+        },
+        txdata: vec![coinbase],
+    };
+    block.header.merkle_root = block.merkle_root();
+    block.header.im_merkle_root = block.immutable_merkle_root();
+    block
+}
+
+/// Checks the block can be on the tip, except for its proof.
+fn check_block(state: &ChainState, block: &Block) -> Result<(), Error> {
+    let tip = state
+        .blocks
+        .last()
+        .expect("chain should have the genesis block");
+    if block.header.prev_blockhash != tip.header.block_hash() {
+        return Err(rpc_error(-25, "proposal was not based on our best chain"));
+    }
+    if block.header.merkle_root != block.merkle_root()
+        || block.header.im_merkle_root != block.immutable_merkle_root()
+    {
+        return Err(rpc_error(-25, "bad-txnmrklroot"));
+    }
+    if let XField::Unknown(_, _) = block.header.xfield {
+        return Err(rpc_error(-25, "bad-xfield"));
+    }
+    Ok(())
+}
+
+/// Records the block if it is properly signed for its parent, wherever the parent is.
+fn record_signed_block(state: &mut ChainState, block: &Block) {
+    let parent = match state
+        .blocks
+        .iter()
+        .position(|b| b.header.block_hash() == block.header.prev_blockhash)
+    {
+        Some(parent) => parent,
+        None => return,
+    };
+    if Sign::verify_proof(block, &state.aggregated_public_keys[parent]).is_err() {
+        return;
+    }
+    let hash = block.header.block_hash();
+    let hashes = state
+        .signed_blocks
+        .entry(parent as u64 + 1)
+        .or_insert_with(Vec::new);
+    if !hashes.contains(&hash) {
+        hashes.push(hash);
+    }
+}
+
+impl TapyrusApi for MockChain {
+    fn getnewblock(&self, address: &Address) -> Result<Block, Error> {
+        let state = self.state();
+        let tip = state
+            .blocks
+            .last()
+            .expect("chain should have the genesis block");
+        Ok(create_block(tip, state.blocks.len() as u64, address))
+    }
+
+    fn testproposedblock(&self, block: &Block) -> Result<bool, Error> {
+        check_block(&self.state(), block)?;
+        Ok(true)
+    }
+
+    fn submitblock(&self, block: &Block) -> Result<(), Error> {
+        let mut state = self.state();
+        let hash = block.header.block_hash();
+        record_signed_block(&mut state, block);
+        if state.blocks.iter().any(|b| b.header.block_hash() == hash) {
+            return Err(rpc_error(-27, "duplicate"));
+        }
+        check_block(&state, block)?;
+        let current = *state
+            .aggregated_public_keys
+            .last()
+            .expect("chain should have the genesis block");
+        if Sign::verify_proof(block, &current).is_err() {
+            return Err(rpc_error(-25, "bad-proof"));
+        }
+
+        let next = block.header.aggregated_public_key().unwrap_or(current);
+        state.aggregated_public_keys.push(next);
+        state.blocks.push(block.clone());
+        Ok(())
+    }
+
+    fn getblockchaininfo(&self) -> Result<GetBlockchainInfoResult, Error> {
+        let state = self.state();
+        let height = (state.blocks.len() - 1) as u64;
+        let tip = state
+            .blocks
+            .last()
+            .expect("chain should have the genesis block");
+        Ok(GetBlockchainInfoResult {
+            chain: "simulation".to_string(),
+            blocks: height,
+            headers: height,
+            bestblockhash: tip.header.block_hash().to_string(),
+            mediantime: tip.header.time as u64,
+            initialblockdownload: false,
+        })
+    }
+
+    fn getblockhash(&self, height: u64) -> Result<BlockHash, Error> {
+        self.state()
+            .blocks
+            .get(height as usize)
+            .map(|block| block.header.block_hash())
+            .ok_or_else(|| rpc_error(-8, "Block height out of range"))
+    }
+
+    fn getblockheader(&self, hash: &BlockHash) -> Result<BlockHeader, Error> {
+        self.state()
+            .blocks
+            .iter()
+            .find(|block| block.header.block_hash() == *hash)
+            .map(|block| block.header.clone())
+            .ok_or_else(|| rpc_error(-5, "Block not found"))
+    }
+
+    /// The chain has no mempool, so it is always empty.
+    fn getmempoolinfo(&self) -> Result<GetMempoolInfoResult, Error> {
+        Ok(GetMempoolInfoResult { size: 0, bytes: 0 })
+    }
+}
@@ -0,0 +1,276 @@
+use crate::net::SignerID;
+use crate::rng::SimRng;
+use crate::signer_node::SignerNode;
+use crate::simulation::chain::MockChain;
+use crate::simulation::network::{Envelope, InMemoryConnectionManager, InMemoryNetwork};
+use crate::simulation::{setup, FederationSpec, Setup};
+use crate::timer::VirtualClock;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Faults which the network injects into the simulation.
+#[derive(Clone, Debug)]
+pub struct FaultConfig {
+    /// Probability that a message is lost.
+    pub drop_rate: f64,
+    /// Probability that a message is delivered twice.
+    pub duplicate_rate: f64,
+    /// Each delivery is delayed by a random time in `[min_delay, max_delay)`, so messages are
+    /// reordered when the range is wider than the interval between them.
+    pub min_delay: Duration,
+    pub max_delay: Duration,
+    pub partitions: Vec<Partition>,
+    /// Indices of the nodes which never run.
+    pub crashed: Vec<usize>,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        FaultConfig {
+            drop_rate: 0.0,
+            duplicate_rate: 0.0,
+            min_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(100),
+            partitions: vec![],
+            crashed: vec![],
+        }
+    }
+}
+
+/// Partition of the network during `[from, until)`. Messages between the nodes in different
+/// groups are lost, and the nodes which are not in any group are isolated.
+#[derive(Clone, Debug)]
+pub struct Partition {
+    pub from: Duration,
+    pub until: Duration,
+    pub groups: Vec<Vec<usize>>,
+}
+
+impl Partition {
+    fn separates(&self, time: Duration, a: usize, b: usize) -> bool {
+        if time < self.from || self.until <= time {
+            return false;
+        }
+        !self
+            .groups
+            .iter()
+            .any(|group| group.contains(&a) && group.contains(&b))
+    }
+}
+
+/// Counts of what happened to the messages.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub sent: usize,
+    pub delivered: usize,
+    pub dropped: usize,
+    pub duplicated: usize,
+    pub timer_events: usize,
+}
+
+pub type DeterministicNode = SignerNode<MockChain, InMemoryConnectionManager>;
+
+/// Simulation which runs the nodes on a virtual clock with a network injecting faults.
+///
+/// All random choices of the network are drawn from the seed, and events are processed in the
+/// order of their time on the clock, so the same seed always gives the same schedule. Signatures
+/// still use fresh randomness, but it doesn't change the schedule.
+pub struct DeterministicSimulation {
+    pub chain: MockChain,
+    pub network: InMemoryNetwork,
+    pub clock: VirtualClock,
+    faults: FaultConfig,
+    rng: SimRng,
+    nodes: Vec<DeterministicNode>,
+    /// Messages in flight, in the order of their delivery time and the order they were sent.
+    in_flight: BTreeMap<(Duration, u64), Envelope>,
+    sequence: u64,
+    started: bool,
+    stats: Stats,
+}
+
+impl DeterministicSimulation {
+    /// Creates `node_count` nodes and the keys, and the network faults both drawn from `seed`.
+    pub fn new(
+        seed: u64,
+        node_count: usize,
+        federations: Vec<FederationSpec>,
+        faults: FaultConfig,
+    ) -> Self {
+        let Setup {
+            chain,
+            network,
+            params,
+            ..
+        } = setup(seed, node_count, &federations);
+        let clock = VirtualClock::new();
+        let nodes = params
+            .into_iter()
+            .map(|params| {
+                let connection_manager = network.connection_manager(params.signer_id);
+                SignerNode::with_clock(connection_manager, params, &clock)
+            })
+            .collect();
+
+        DeterministicSimulation {
+            chain: chain,
+            network: network,
+            clock: clock,
+            faults: faults,
+            rng: SimRng::new(seed),
+            nodes: nodes,
+            in_flight: BTreeMap::new(),
+            sequence: 0,
+            started: false,
+            stats: Stats::default(),
+        }
+    }
+
+    pub fn node(&self, index: usize) -> &DeterministicNode {
+        &self.nodes[index]
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// Returns the block heights where two or more different blocks were signed, which must be
+    /// empty.
+    pub fn conflicting_heights(&self) -> Vec<u64> {
+        self.chain.conflicting_heights()
+    }
+
+    /// Runs the simulation until the chain reaches the block height. Returns false if it doesn't
+    /// reach the height until `time_limit` on the virtual clock.
+    pub fn run_until(&mut self, block_height: u64, time_limit: Duration) -> bool {
+        self.start();
+        while self.chain.height() < block_height {
+            if !self.step(time_limit) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Runs the simulation until `time` on the virtual clock.
+    pub fn run_for(&mut self, time: Duration) {
+        self.start();
+        while self.step(time) {}
+        self.clock.advance_to(time);
+    }
+
+    /// Starts the first round of the live nodes at time zero, as `SignerNode::start` does.
+    fn start(&mut self) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        for i in self.live_nodes() {
+            self.nodes[i].on_round_limit_timeout();
+            self.schedule_sent_messages();
+        }
+    }
+
+    /// Processes the next event if it happens until `time_limit`. Messages are delivered before
+    /// timers which are up at the same time.
+    fn step(&mut self, time_limit: Duration) -> bool {
+        let next_message = self.in_flight.keys().next().map(|(time, _)| *time);
+        let next_timer = self
+            .live_nodes()
+            .into_iter()
+            .filter_map(|i| self.nodes[i].next_deadline())
+            .min();
+        let next = match (next_message, next_timer) {
+            (Some(m), Some(t)) => m.min(t),
+            (m, t) => match m.or(t) {
+                Some(next) => next,
+                None => return false,
+            },
+        };
+        if next > time_limit {
+            return false;
+        }
+        self.clock.advance_to(next);
+
+        if next_message == Some(next) {
+            let key = *self.in_flight.keys().next().unwrap();
+            let envelope = self.in_flight.remove(&key).unwrap();
+            self.deliver(envelope);
+        } else {
+            for i in self.live_nodes() {
+                match self.nodes[i].next_deadline() {
+                    Some(deadline) if deadline <= next => {
+                        self.stats.timer_events += 1;
+                        self.nodes[i].poll_timers();
+                        self.schedule_sent_messages();
+                    }
+                    _ => {}
+                }
+            }
+        }
+        true
+    }
+
+    fn deliver(&mut self, envelope: Envelope) {
+        let sender = self.index_of(&envelope.sender_id);
+        let receiver = self.index_of(&envelope.receiver_id);
+        let now = self.clock.now();
+        if self.faults.crashed.contains(&receiver)
+            || self
+                .faults
+                .partitions
+                .iter()
+                .any(|p| p.separates(now, sender, receiver))
+        {
+            self.stats.dropped += 1;
+            return;
+        }
+        self.stats.delivered += 1;
+        self.nodes[receiver].receive_message(envelope.message());
+        self.schedule_sent_messages();
+    }
+
+    /// Takes the messages which the nodes sent out of the network, and decides when they are
+    /// delivered.
+    fn schedule_sent_messages(&mut self) {
+        for envelope in self.network.drain() {
+            self.stats.sent += 1;
+            if self.rng.gen_bool(self.faults.drop_rate) {
+                self.stats.dropped += 1;
+                continue;
+            }
+            if self.rng.gen_bool(self.faults.duplicate_rate) {
+                self.stats.duplicated += 1;
+                self.schedule(envelope.clone());
+            }
+            self.schedule(envelope);
+        }
+    }
+
+    fn schedule(&mut self, envelope: Envelope) {
+        let delay = self.rng.gen_range(
+            self.faults.min_delay.as_millis() as u64,
+            self.faults.max_delay.as_millis() as u64,
+        );
+        let time = self.clock.now() + Duration::from_millis(delay);
+        self.sequence += 1;
+        self.in_flight.insert((time, self.sequence), envelope);
+    }
+
+    fn live_nodes(&self) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|i| !self.faults.crashed.contains(i))
+            .collect()
+    }
+
+    fn index_of(&self, signer_id: &SignerID) -> usize {
+        self.nodes
+            .iter()
+            .position(|node| node.params().signer_id == *signer_id)
+            .expect("signer should be in the simulation")
+    }
+}
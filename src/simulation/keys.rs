@@ -0,0 +1,72 @@
+use crate::crypto::vss::{Commitment, Vss};
+use crate::rpc::Rpc;
+use crate::signer_node::NodeParameters;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tapyrus::network::constants::Network;
+use tapyrus::secp256k1::{Secp256k1, SecretKey};
+use tapyrus::{PrivateKey, PublicKey};
+
+/// Returns the private key of the signer at `index`. The same seed and index always give the same
+/// key, so a simulation can be reproduced.
+pub fn private_key(seed: u64, index: usize) -> PrivateKey {
+    let mut hasher = Sha256::new();
+    hasher.input(b"tapyrus-signer simulation");
+    hasher.input(&seed.to_be_bytes());
+    hasher.input(&(index as u64).to_be_bytes());
+    let hash = hasher.result();
+    PrivateKey {
+        compressed: true,
+        network: Network::Dev,
+        key: SecretKey::from_slice(&hash[..]).expect("hash should be a valid secret key"),
+    }
+}
+
+pub fn public_key(private_key: &PrivateKey) -> PublicKey {
+    let secp = Secp256k1::new();
+    PublicKey::from_private_key(&secp, private_key)
+}
+
+/// Returns the aggregated public key of the signers, which is the sum of their public keys.
+pub fn aggregated_public_key(public_keys: &[PublicKey]) -> PublicKey {
+    let mut iter = public_keys.iter();
+    let head = iter.next().expect("public keys should not be empty").key;
+    let key = iter.fold(head, |acc, pk| {
+        acc.combine(&pk.key)
+            .expect("sum of public keys should be valid")
+    });
+    PublicKey {
+        compressed: true,
+        key: key,
+    }
+}
+
+/// Runs the key generation of `tapyrus-setup createnodevss` for all signers, and returns the
+/// node VSSs which each signer receives.
+pub fn node_vss(keys: &[PrivateKey], threshold: u8) -> BTreeMap<PublicKey, Vec<Vss>> {
+    let mut public_keys: Vec<PublicKey> = keys.iter().map(public_key).collect();
+    NodeParameters::<Rpc>::sort_publickey(&mut public_keys);
+
+    let mut received: BTreeMap<PublicKey, Vec<Vss>> = BTreeMap::new();
+    for key in keys {
+        let (vss_scheme, secret_shares) =
+            Vss::create_node_shares(key, threshold as usize, public_keys.len());
+        let commitments: Vec<Commitment> = vss_scheme
+            .commitments
+            .iter()
+            .map(|c| Commitment::from(c))
+            .collect();
+        for (j, receiver) in public_keys.iter().enumerate() {
+            let vss = Vss::new(
+                public_key(key),
+                *receiver,
+                commitments.clone(),
+                secret_shares[j],
+                commitments.clone(),
+                secret_shares[j],
+            );
+            received.entry(*receiver).or_insert_with(Vec::new).push(vss);
+        }
+    }
+    received
+}
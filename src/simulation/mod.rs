@@ -0,0 +1,295 @@
+// Copyright (c) 2020 Chaintope Inc.
+// Distributed under the MIT software license, see the accompanying
+// file COPYING or http://www.opensource.org/licenses/mit-license.php.
+
+//! In-process simulation of a signer network for end-to-end tests.
+//!
+//! `Simulation` runs `SignerNode`s which send messages through `InMemoryNetwork`, and share
+//! `MockChain` as their tapyrus-core. It drives the nodes by itself instead of their timers and
+//! message threads, so rounds run as fast as the nodes process messages.
+//!
+//! `DeterministicSimulation` runs the same nodes with their timers on a virtual clock, and
+//! injects network faults drawn from a seed, so a failing run can be reproduced from its seed.
+//!
+//! This module is only for tests, so it is built only with the `simulation` feature.
+
+pub mod chain;
+pub mod deterministic;
+pub mod keys;
+pub mod network;
+pub mod rpc_server;
+pub mod zmq_publisher;
+
+use crate::cli::setup::create_genesis::create_genesis_block;
+use crate::federation::{Federation, Federations};
+use crate::net::SignerID;
+use crate::signer_node::{
+    NodeParameters, NodeState, SignerNode, ROUND_INTERVAL_DEFAULT_SECS, ROUND_LIMIT_DEFAULT_SECS,
+};
+use crate::simulation::chain::MockChain;
+use crate::simulation::network::{Envelope, InMemoryConnectionManager, InMemoryNetwork};
+use tapyrus::{Address, PrivateKey, PublicKey};
+
+/// Block time of the genesis block in simulations.
+const GENESIS_TIME: u32 = 1585197531;
+
+pub type SimulatedNode = SignerNode<MockChain, InMemoryConnectionManager>;
+
+/// A federation in the simulation.
+#[derive(Clone, Debug)]
+pub struct FederationSpec {
+    /// The block height where the federation gets started.
+    pub block_height: u64,
+    /// Indices of the nodes which are the signers of the federation.
+    pub signers: Vec<usize>,
+    pub threshold: u8,
+}
+
+pub struct Simulation {
+    pub chain: MockChain,
+    pub network: InMemoryNetwork,
+    keys: Vec<PrivateKey>,
+    nodes: Vec<SimulatedNode>,
+    /// The block height of the round where the round interval timer of each node was fired.
+    interval_fired: Vec<Option<u64>>,
+    round_timeouts: usize,
+}
+
+impl Simulation {
+    /// Creates `node_count` nodes with the keys derived from `seed`, and the chain whose genesis
+    /// block has the aggregated public key of the federation at block height 0.
+    pub fn new(seed: u64, node_count: usize, federations: Vec<FederationSpec>) -> Self {
+        let Setup {
+            keys,
+            chain,
+            network,
+            params,
+        } = setup(seed, node_count, &federations);
+        let nodes = params
+            .into_iter()
+            .map(|params| {
+                let connection_manager = network.connection_manager(params.signer_id);
+                SignerNode::new(connection_manager, params)
+            })
+            .collect();
+
+        Simulation {
+            chain: chain,
+            network: network,
+            keys: keys,
+            interval_fired: vec![None; node_count],
+            nodes: nodes,
+            round_timeouts: 0,
+        }
+    }
+
+    pub fn node(&self, index: usize) -> &SimulatedNode {
+        &self.nodes[index]
+    }
+
+    pub fn node_mut(&mut self, index: usize) -> &mut SimulatedNode {
+        &mut self.nodes[index]
+    }
+
+    pub fn nodes(&self) -> &Vec<SimulatedNode> {
+        &self.nodes
+    }
+
+    pub fn public_key(&self, index: usize) -> PublicKey {
+        keys::public_key(&self.keys[index])
+    }
+
+    pub fn private_key(&self, index: usize) -> PrivateKey {
+        self.keys[index]
+    }
+
+    /// Returns how many times all rounds were timed out because they got stuck.
+    pub fn round_timeouts(&self) -> usize {
+        self.round_timeouts
+    }
+
+    /// Runs the simulation until the chain reaches the block height. When no node can go on, the
+    /// rounds of all nodes are timed out as their round limit timers would do. Returns false if
+    /// it needs more than `max_round_timeouts` timeouts.
+    pub fn run_until(&mut self, block_height: u64, max_round_timeouts: usize) -> bool {
+        let mut timeouts = 0;
+        while self.chain.height() < block_height {
+            if self.step() {
+                continue;
+            }
+            if timeouts >= max_round_timeouts {
+                return false;
+            }
+            timeouts += 1;
+            self.time_out_rounds();
+        }
+        true
+    }
+
+    /// Processes one event. Returns false if there is nothing to do, which means that the round
+    /// got stuck.
+    ///
+    /// The events are processed in this order:
+    /// 1. Deliver the oldest message in the network.
+    /// 2. Time out the round of the node which is behind the chain, like its round limit timer
+    ///    would do. It includes the nodes which haven't started yet.
+    /// 3. Fire the round interval timer of the master which waits for it.
+    pub fn step(&mut self) -> bool {
+        if let Some(envelope) = self.network.pop() {
+            self.deliver(envelope);
+            return true;
+        }
+
+        let height = self.chain.height();
+        if let Some(i) = (0..self.nodes.len()).find(|i| self.is_behind(*i, height)) {
+            self.interval_fired[i] = None;
+            self.nodes[i].on_round_limit_timeout();
+            return true;
+        }
+
+        if let Some(i) = (0..self.nodes.len()).find(|i| self.is_waiting_round_interval(*i)) {
+            self.interval_fired[i] = Some(self.nodes[i].current_state().block_height());
+            self.nodes[i].on_round_interval_timeout();
+            return true;
+        }
+        false
+    }
+
+    /// Times out the rounds of all nodes, and they start the next round.
+    pub fn time_out_rounds(&mut self) {
+        self.round_timeouts += 1;
+        for i in 0..self.nodes.len() {
+            self.interval_fired[i] = None;
+            self.nodes[i].on_round_limit_timeout();
+        }
+    }
+
+    /// Delivers the message to the receiver.
+    pub fn deliver(&mut self, envelope: Envelope) {
+        let index = self
+            .index_of(&envelope.receiver_id)
+            .expect("receiver should be in the simulation");
+        self.nodes[index].receive_message(envelope.message());
+    }
+
+    pub fn index_of(&self, signer_id: &SignerID) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|node| node.params().signer_id == *signer_id)
+    }
+
+    fn is_behind(&self, index: usize, chain_height: u64) -> bool {
+        match self.nodes[index].current_state() {
+            NodeState::Joining => true,
+            state => state.block_height() <= chain_height,
+        }
+    }
+
+    fn is_waiting_round_interval(&self, index: usize) -> bool {
+        match self.nodes[index].current_state() {
+            NodeState::Master {
+                candidate_block: None,
+                block_height,
+                ..
+            } => self.interval_fired[index] != Some(*block_height),
+            _ => false,
+        }
+    }
+}
+
+/// Keys, chain, network and node parameters which both kinds of simulation are made of.
+struct Setup {
+    keys: Vec<PrivateKey>,
+    chain: MockChain,
+    network: InMemoryNetwork,
+    params: Vec<NodeParameters<MockChain>>,
+}
+
+fn setup(seed: u64, node_count: usize, federations: &[FederationSpec]) -> Setup {
+    let keys: Vec<PrivateKey> = (0..node_count)
+        .map(|i| keys::private_key(seed, i))
+        .collect();
+    let public_keys: Vec<PublicKey> = keys.iter().map(keys::public_key).collect();
+
+    // Generate node VSSs and the aggregated public key of each federation.
+    let generated: Vec<_> = federations
+        .iter()
+        .map(|spec| {
+            let signer_keys: Vec<PrivateKey> = spec.signers.iter().map(|i| keys[*i]).collect();
+            let signer_public_keys: Vec<PublicKey> =
+                spec.signers.iter().map(|i| public_keys[*i]).collect();
+            (
+                keys::node_vss(&signer_keys, spec.threshold),
+                keys::aggregated_public_key(&signer_public_keys),
+            )
+        })
+        .collect();
+
+    let first = federations
+        .iter()
+        .position(|spec| spec.block_height == 0)
+        .expect("simulation should have the federation at block height 0");
+    let genesis = create_genesis_block(&generated[first].1, &address(&keys[0]), GENESIS_TIME);
+    let chain = MockChain::new(genesis);
+
+    let network = InMemoryNetwork::new(
+        public_keys
+            .iter()
+            .map(|public_key| SignerID::new(*public_key))
+            .collect(),
+    );
+
+    let params = keys
+        .iter()
+        .zip(public_keys.iter())
+        .map(|(private_key, public_key)| {
+            let node_federations = federations
+                .iter()
+                .zip(generated.iter())
+                .map(
+                    |(spec, (node_vss, aggregated_public_key))| match node_vss.get(public_key) {
+                        Some(node_vss) => Federation::new(
+                            *public_key,
+                            spec.block_height,
+                            Some(spec.threshold),
+                            Some(node_vss.clone()),
+                            *aggregated_public_key,
+                        ),
+                        None => Federation::new(
+                            *public_key,
+                            spec.block_height,
+                            None,
+                            None,
+                            *aggregated_public_key,
+                        ),
+                    },
+                )
+                .collect();
+            // The timers have production durations. `Simulation` never waits for them, and
+            // `DeterministicSimulation` runs them on its virtual clock.
+            let mut params = NodeParameters::new(
+                address(private_key),
+                *public_key,
+                chain.clone(),
+                ROUND_INTERVAL_DEFAULT_SECS,
+                ROUND_LIMIT_DEFAULT_SECS,
+                true,
+                false,
+                Federations::new(node_federations),
+            );
+            params.private_key = Some(*private_key);
+            params
+        })
+        .collect();
+
+    Setup {
+        keys,
+        chain,
+        network,
+        params,
+    }
+}
+
+fn address(private_key: &PrivateKey) -> Address {
+    Address::p2pkh(&keys::public_key(private_key), private_key.network)
+}
@@ -0,0 +1,127 @@
+use crate::errors;
+use crate::net::{ConnectionManager, ConnectionManagerError, Message, SignerID};
+use redis::ControlFlow;
+use std::collections::VecDeque;
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::thread::JoinHandle;
+
+/// A message on the way to a node. The message is kept as JSON like the one which is published to
+/// Redis, so each receiver decodes its own copy.
+#[derive(Clone, Debug)]
+pub struct Envelope {
+    pub sender_id: SignerID,
+    pub receiver_id: SignerID,
+    pub payload: String,
+}
+
+impl Envelope {
+    pub fn message(&self) -> Message {
+        serde_json::from_str(&self.payload).expect("payload should be a message")
+    }
+}
+
+/// In-memory network between the nodes in a simulation. Messages which nodes sent are queued in
+/// the order they were sent, and the simulation takes them out to deliver.
+#[derive(Clone)]
+pub struct InMemoryNetwork {
+    state: Arc<Mutex<NetworkState>>,
+}
+
+struct NetworkState {
+    nodes: Vec<SignerID>,
+    queue: VecDeque<Envelope>,
+}
+
+impl InMemoryNetwork {
+    pub fn new(nodes: Vec<SignerID>) -> Self {
+        InMemoryNetwork {
+            state: Arc::new(Mutex::new(NetworkState {
+                nodes: nodes,
+                queue: VecDeque::new(),
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, NetworkState> {
+        self.state.lock().expect("network state is poisoned")
+    }
+
+    /// Returns the connection manager which the node uses to send messages.
+    pub fn connection_manager(&self, id: SignerID) -> InMemoryConnectionManager {
+        InMemoryConnectionManager {
+            id: id,
+            network: self.clone(),
+        }
+    }
+
+    /// Takes out the oldest message in the queue.
+    pub fn pop(&self) -> Option<Envelope> {
+        self.state().queue.pop_front()
+    }
+
+    /// Takes out all messages in the queue.
+    pub fn drain(&self) -> Vec<Envelope> {
+        self.state().queue.drain(..).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state().queue.is_empty()
+    }
+
+    fn push(&self, sender_id: SignerID, receiver_id: SignerID, message: &Message) {
+        let payload = serde_json::to_string(message).expect("message should be serialized");
+        self.state().queue.push_back(Envelope {
+            sender_id: sender_id,
+            receiver_id: receiver_id,
+            payload: payload,
+        });
+    }
+}
+
+/// ConnectionManager which sends messages through the InMemoryNetwork.
+pub struct InMemoryConnectionManager {
+    id: SignerID,
+    network: InMemoryNetwork,
+}
+
+impl ConnectionManager for InMemoryConnectionManager {
+    type ERROR = errors::Error;
+
+    fn broadcast_message(&self, message: Message) {
+        assert!(message.receiver_id.is_none());
+        let nodes = self.network.state().nodes.clone();
+        // Like RedisManager, the sender doesn't receive its own message.
+        for receiver_id in nodes.into_iter().filter(|id| *id != self.id) {
+            self.network.push(self.id, receiver_id, &message);
+        }
+    }
+
+    fn send_message(&self, message: Message) {
+        let receiver_id = message.receiver_id.expect("receiver should be specified");
+        if receiver_id != self.id {
+            self.network.push(self.id, receiver_id, &message);
+        }
+    }
+
+    fn start(
+        &self,
+        _message_processor: impl FnMut(Message) -> ControlFlow<()> + Send + 'static,
+        _id: SignerID,
+    ) -> JoinHandle<()> {
+        // Messages are delivered by the simulation, not by the thread.
+        thread::Builder::new().spawn(|| {}).unwrap()
+    }
+
+    fn test_connection(&self) -> Result<(), errors::Error> {
+        Ok(())
+    }
+
+    fn take_error(
+        &mut self,
+    ) -> Result<ConnectionManagerError<Self::ERROR>, std::sync::mpsc::TryRecvError> {
+        let (_s, r) = channel();
+        r.try_recv()
+    }
+}
@@ -0,0 +1,242 @@
+use crate::errors::Error;
+use crate::json_rpc_server::{rpc_error, serve_connection};
+use crate::rpc::TapyrusApi;
+use crate::simulation::chain::MockChain;
+use serde_json::{json, Value};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::thread::JoinHandle;
+use tapyrus::blockdata::block::Block;
+use tapyrus::consensus::encode::{deserialize, serialize};
+use tapyrus::hash_types::BlockHash;
+use tapyrus::hashes::hex::FromHex;
+use tapyrus::Address;
+
+/// JSON-RPC server which stands in for tapyrus-core, so `rpc::Rpc` can be tested without a real
+/// node. It serves `getnewblock`, `testproposedblock`, `submitblock`, `getblockchaininfo`,
+/// `getblockhash` and `getblockheader` on `MockChain` over HTTP with basic authentication.
+pub struct RpcServer {
+    listener: TcpListener,
+    chain: MockChain,
+    /// Expected value of the Authorization header. No authentication if it is None.
+    authorization: Option<String>,
+}
+
+impl RpcServer {
+    pub fn bind(
+        address: &str,
+        chain: MockChain,
+        user: Option<String>,
+        pass: Option<String>,
+    ) -> std::io::Result<Self> {
+        let authorization = user.map(|user| {
+            format!(
+                "Basic {}",
+                base64::encode(&format!("{}:{}", user, pass.unwrap_or_default()))
+            )
+        });
+        Ok(RpcServer {
+            listener: TcpListener::bind(address)?,
+            chain: chain,
+            authorization: authorization,
+        })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serves requests one by one until the process exits.
+    pub fn run(self) {
+        for stream in self.listener.incoming() {
+            let result = stream.and_then(|stream| self.handle_connection(stream));
+            if let Err(e) = result {
+                log::warn!("Failed to handle RPC connection: {:?}", e);
+            }
+        }
+    }
+
+    /// Serves requests on a new thread.
+    pub fn spawn(self) -> JoinHandle<()> {
+        std::thread::Builder::new()
+            .name("RpcServerThread".to_string())
+            .spawn(move || self.run())
+            .unwrap()
+    }
+
+    fn handle_connection(&self, stream: TcpStream) -> std::io::Result<()> {
+        serve_connection(stream, &self.authorization, |method, params| {
+            dispatch(&self.chain, method, params)
+        })
+    }
+}
+
+fn chain_error(error: Error) -> Value {
+    match error {
+        Error::InvalidRequest(e) => rpc_error(e.code, &e.message),
+        e => rpc_error(-1, &format!("{:?}", e)),
+    }
+}
+
+fn block_param(params: &[Value]) -> Result<Block, Value> {
+    params
+        .get(0)
+        .and_then(Value::as_str)
+        .and_then(|hex| hex::decode(hex).ok())
+        .and_then(|raw| deserialize::<Block>(&raw).ok())
+        .ok_or_else(|| rpc_error(-22, "Block decode failed"))
+}
+
+/// Calls the method of the chain. Errors are JSON-RPC error objects.
+pub fn dispatch(chain: &MockChain, method: &str, params: &[Value]) -> Result<Value, Value> {
+    match method {
+        "getnewblock" => {
+            let address = params
+                .get(0)
+                .and_then(Value::as_str)
+                .and_then(|s| Address::from_str(s).ok())
+                .ok_or_else(|| rpc_error(-5, "Invalid address"))?;
+            let block = chain.getnewblock(&address).map_err(chain_error)?;
+            Ok(Value::from(hex::encode(serialize(&block))))
+        }
+        "testproposedblock" => {
+            let block = block_param(params)?;
+            let result = chain.testproposedblock(&block).map_err(chain_error)?;
+            Ok(Value::from(result))
+        }
+        "submitblock" => {
+            let block = block_param(params)?;
+            // Like tapyrus-core, the reason of rejection is returned as the result.
+            match chain.submitblock(&block) {
+                Ok(()) => Ok(Value::Null),
+                Err(Error::InvalidRequest(e)) => Ok(Value::from(e.message)),
+                Err(e) => Err(chain_error(e)),
+            }
+        }
+        "getblockchaininfo" => {
+            let info = chain.getblockchaininfo().map_err(chain_error)?;
+            Ok(json!({
+                "chain": info.chain,
+                "blocks": info.blocks,
+                "headers": info.headers,
+                "bestblockhash": info.bestblockhash,
+                "mediantime": info.mediantime,
+                "initialblockdownload": info.initialblockdownload,
+            }))
+        }
+        "getblockhash" => {
+            let height = params
+                .get(0)
+                .and_then(Value::as_u64)
+                .ok_or_else(|| rpc_error(-1, "Invalid height"))?;
+            let hash = chain.getblockhash(height).map_err(chain_error)?;
+            Ok(Value::from(hash.to_string()))
+        }
+        "getblockheader" => {
+            // Only the serialized header is served, like `getblockheader <hash> false`.
+            let hash = params
+                .get(0)
+                .and_then(Value::as_str)
+                .and_then(|hex| BlockHash::from_hex(hex).ok())
+                .ok_or_else(|| rpc_error(-8, "Invalid block hash"))?;
+            let header = chain.getblockheader(&hash).map_err(chain_error)?;
+            Ok(Value::from(hex::encode(serialize(&header))))
+        }
+        "getmempoolinfo" => {
+            let info = chain.getmempoolinfo().map_err(chain_error)?;
+            Ok(json!({ "size": info.size, "bytes": info.bytes }))
+        }
+        _ => Err(rpc_error(-32601, "Method not found")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::setup::create_genesis::create_genesis_block;
+    use crate::rpc::Rpc;
+    use crate::tests::helper::blocks::sign;
+    use crate::tests::helper::keys::TEST_KEYS;
+
+    fn address() -> Address {
+        Address::from_str("1FiFhpYZHCZt33L4hh7C4jM2uKKueAxcH7").unwrap()
+    }
+
+    fn start_server(user: Option<&str>, pass: Option<&str>) -> (MockChain, String) {
+        let genesis = create_genesis_block(&TEST_KEYS.aggregated(), &address(), 1585197531);
+        let chain = MockChain::new(genesis);
+        let server = RpcServer::bind(
+            "127.0.0.1:0",
+            chain.clone(),
+            user.map(str::to_string),
+            pass.map(str::to_string),
+        )
+        .unwrap();
+        let url = format!("http://{}", server.local_addr().unwrap());
+        server.spawn();
+        (chain, url)
+    }
+
+    #[test]
+    fn test_submit_signed_block() {
+        let (chain, url) = start_server(Some("user"), Some("pass"));
+        let rpc = Rpc::new(url, Some("user".to_string()), Some("pass".to_string()));
+
+        let block = rpc.getnewblock(&address()).unwrap();
+        assert!(rpc.testproposedblock(&block).unwrap());
+
+        // A block without proof is rejected.
+        assert!(rpc.submitblock(&block).is_err());
+        assert_eq!(chain.height(), 0);
+
+        rpc.submitblock(&sign(block)).unwrap();
+        let info = rpc.getblockchaininfo().unwrap();
+        assert_eq!(info.blocks, 1);
+        assert_eq!(
+            info.bestblockhash,
+            chain.tip().header.block_hash().to_string()
+        );
+    }
+
+    #[test]
+    fn test_getblockheader() {
+        let (chain, url) = start_server(None, None);
+        let rpc = Rpc::new(url, None, None);
+
+        let block = sign(rpc.getnewblock(&address()).unwrap());
+        rpc.submitblock(&block).unwrap();
+        let hash = rpc.getblockhash(1).unwrap();
+        assert_eq!(hash, block.header.block_hash());
+        assert_eq!(rpc.getblockheader(&hash).unwrap(), block.header);
+        assert_eq!(
+            rpc.getblockheader(&rpc.getblockhash(0).unwrap())
+                .unwrap()
+                .aggregated_public_key(),
+            chain.block(0).unwrap().header.aggregated_public_key()
+        );
+        match rpc.getblockhash(2) {
+            Err(Error::InvalidRequest(e)) => assert_eq!(e.code, -8),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_testproposedblock_on_stale_tip() {
+        let (_chain, url) = start_server(None, None);
+        let rpc = Rpc::new(url, None, None);
+
+        let block = rpc.getnewblock(&address()).unwrap();
+        rpc.submitblock(&sign(block.clone())).unwrap();
+        match rpc.testproposedblock(&block) {
+            Err(Error::InvalidRequest(e)) => assert_eq!(e.code, -25),
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_unauthorized() {
+        let (_chain, url) = start_server(Some("user"), Some("pass"));
+        let rpc = Rpc::new(url, Some("user".to_string()), Some("wrong".to_string()));
+        assert!(rpc.getblockchaininfo().is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::notification::{handshake, read_message, write_frame, HASHBLOCK_TOPIC};
+use bitcoin_hashes::Hash;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tapyrus::hash_types::BlockHash;
+
+/// ZMQ publisher which stands in for `-zmqpubhashblock` of tapyrus-core, so the subscriber can
+/// be tested without a real node. It publishes to all subscribers regardless of their topics.
+pub struct ZmqPublisher {
+    local_addr: SocketAddr,
+    subscribers: Arc<Mutex<Vec<TcpStream>>>,
+    sequence: Mutex<u32>,
+}
+
+impl ZmqPublisher {
+    /// Listens on the address and accepts subscribers in a thread.
+    pub fn bind(addr: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let subscribers = Arc::new(Mutex::new(vec![]));
+        let accepted = Arc::clone(&subscribers);
+        thread::Builder::new()
+            .name("ZmqPublisherThread".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream.and_then(accept) {
+                        Ok(stream) => accepted.lock().unwrap().push(stream),
+                        Err(e) => log::warn!("Failed to accept a subscriber: {}", e),
+                    }
+                }
+            })?;
+        Ok(ZmqPublisher {
+            local_addr,
+            subscribers,
+            sequence: Mutex::new(0),
+        })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+
+    /// Returns the number of the subscribers which have sent their subscriptions.
+    pub fn subscribers(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+
+    /// Publishes the hash like tapyrus-core, which is in the reversed byte order and followed by
+    /// the sequence number. The subscribers which are disconnected are removed.
+    pub fn publish_hashblock(&self, hash: &BlockHash) {
+        let mut body = hash.into_inner().to_vec();
+        body.reverse();
+        let mut sequence = self.sequence.lock().unwrap();
+        let sequence_bytes = sequence.to_le_bytes();
+        *sequence += 1;
+
+        self.subscribers.lock().unwrap().retain(|stream| {
+            let mut stream = stream;
+            write_frame(&mut stream, HASHBLOCK_TOPIC, true, false)
+                .and_then(|_| write_frame(&mut stream, &body, true, false))
+                .and_then(|_| write_frame(&mut stream, &sequence_bytes, false, false))
+                .is_ok()
+        });
+    }
+}
+
+/// Handshakes with the subscriber and waits for its subscription.
+fn accept(mut stream: TcpStream) -> std::io::Result<TcpStream> {
+    handshake(&mut stream, "PUB")?;
+    read_message(&mut stream)?;
+    Ok(stream)
+}
@@ -1,3 +1,8 @@
+use crate::cli::setup::psb::PartiallySignedBlock;
+use crate::federation::Federation;
+use crate::signer_node::master_signature::{MasterSignature, SignedBlock};
+use crate::tests::helper::keys::TEST_KEYS;
+use crate::tests::helper::node_vss::node_vss;
 use tapyrus::blockdata::block::Block;
 use tapyrus::consensus::encode::deserialize;
 
@@ -83,3 +88,36 @@ pub fn get_block(index: u8) -> Block {
     };
     deserialize(&bytes).unwrap()
 }
+
+/// Signs the block with the test keys, so that the proof verifies under `TEST_KEYS.aggregated()`.
+pub fn sign(block: Block) -> Block {
+    let mut psb =
+        PartiallySignedBlock::new(block, 3, TEST_KEYS.aggregated(), TEST_KEYS.pubkeys()).unwrap();
+    for i in 0..5 {
+        psb.contribute_block_vss(&TEST_KEYS.key[i]).unwrap();
+    }
+    for i in 0..3 {
+        let node_secret_share = Federation::new(
+            TEST_KEYS.pubkeys()[i],
+            0,
+            Some(3),
+            Some(node_vss(i)),
+            TEST_KEYS.aggregated(),
+        )
+        .node_secret_share()
+        .x_i;
+        psb.contribute_local_sig(&TEST_KEYS.key[i], &node_secret_share)
+            .unwrap();
+    }
+    psb.finalize(&node_vss(0)).unwrap()
+}
+
+/// Signature of the master with the test key at the index over the header of the block.
+pub fn master_sign(
+    kind: SignedBlock,
+    block_height: u64,
+    block: &Block,
+    master: usize,
+) -> MasterSignature {
+    MasterSignature::sign(kind, block_height, &block.header, &TEST_KEYS.key[master])
+}
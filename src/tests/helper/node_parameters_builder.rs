@@ -12,6 +12,7 @@ pub struct NodeParametersBuilder {
     round_duration: u64,
     round_limit: u64,
     skip_waiting_ibd: bool,
+    precompute_block_vss: bool,
     public_key: PublicKey,
     federations: Federations,
 }
@@ -25,6 +26,7 @@ impl NodeParametersBuilder {
             round_duration: 0,
             round_limit: 15,
             skip_waiting_ibd: true,
+            precompute_block_vss: false,
             public_key: TEST_KEYS.pubkeys()[4],
             federations: Federations::new(vec![Federation::new(
                 TEST_KEYS.pubkeys()[1],
@@ -36,16 +38,24 @@ impl NodeParametersBuilder {
         }
     }
 
+    /// The private key is the test key of the public key, if any.
     pub fn build(&mut self) -> NodeParameters<MockRpc> {
-        NodeParameters::new(
+        let mut params = NodeParameters::new(
             self.address.clone(),
             self.public_key,
             self.rpc.take().unwrap_or(MockRpc::new()),
             self.round_duration,
             self.round_limit,
             self.skip_waiting_ibd,
+            self.precompute_block_vss,
             self.federations.clone(),
-        )
+        );
+        params.private_key = TEST_KEYS
+            .pubkeys()
+            .iter()
+            .position(|pubkey| *pubkey == self.public_key)
+            .map(|i| TEST_KEYS.key[i]);
+        params
     }
 
     pub fn public_key(&mut self, public_key: PublicKey) -> &mut Self {
@@ -78,6 +88,11 @@ impl NodeParametersBuilder {
         self
     }
 
+    pub fn precompute_block_vss(&mut self, precompute_block_vss: bool) -> &mut Self {
+        self.precompute_block_vss = precompute_block_vss;
+        self
+    }
+
     pub fn federations(&mut self, federations: Federations) -> &mut Self {
         self.federations = federations;
         self
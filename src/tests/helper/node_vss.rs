@@ -1,4 +1,6 @@
 use crate::crypto::vss::Vss;
+use crate::federation::Federation;
+use crate::tests::helper::keys::TEST_KEYS;
 use std::str::FromStr;
 
 /// Test data for node vss.
@@ -49,3 +51,15 @@ pub fn node_vss(i: usize) -> Vec<Vss> {
         .map(|vss| Vss::from_str(vss).unwrap())
         .collect()
 }
+
+/// Returns the federation at block height 0 seen from the signer of index `i`.
+/// It has the node VSSs of the signer and threshold 3.
+pub fn federation(i: usize) -> Federation {
+    Federation::new(
+        TEST_KEYS.pubkeys()[i],
+        0,
+        Some(3),
+        Some(node_vss(i)),
+        TEST_KEYS.aggregated(),
+    )
+}
@@ -1,8 +1,9 @@
 use crate::errors::Error;
-use crate::rpc::{GetBlockchainInfoResult, TapyrusApi};
+use crate::rpc::{GetBlockchainInfoResult, GetMempoolInfoResult, TapyrusApi};
 use std::cell::RefCell;
 use std::collections::VecDeque;
-use tapyrus::blockdata::block::Block;
+use tapyrus::blockdata::block::{Block, BlockHeader};
+use tapyrus::hash_types::BlockHash;
 use tapyrus::Address;
 
 /// Mock for Rpc
@@ -38,6 +39,7 @@ pub struct MockRpc {
     getblockchaininfo_results: RefCell<VecDeque<GetBlockchainInfoResult>>,
     testproposedblock_results: RefCell<VecDeque<Result<bool, Error>>>,
     submitblock_results: RefCell<VecDeque<Result<(), Error>>>,
+    getmempoolinfo_results: RefCell<VecDeque<GetMempoolInfoResult>>,
 }
 
 impl MockRpc {
@@ -47,6 +49,7 @@ impl MockRpc {
             getblockchaininfo_results: RefCell::new(VecDeque::new()),
             testproposedblock_results: RefCell::new(VecDeque::new()),
             submitblock_results: RefCell::new(VecDeque::new()),
+            getmempoolinfo_results: RefCell::new(VecDeque::new()),
         }
     }
 
@@ -67,6 +70,10 @@ impl MockRpc {
             self.submitblock_results.borrow().is_empty(),
             "submitblock RPC should be called once or more, but not."
         );
+        assert!(
+            self.getmempoolinfo_results.borrow().is_empty(),
+            "getmempoolinfo RPC should be called once or more, but not."
+        );
     }
 
     pub fn should_call_getnewblock(&mut self, result: Result<Block, Error>) {
@@ -98,6 +105,14 @@ impl MockRpc {
         list.push_front(result);
     }
 
+    pub fn should_call_getmempoolinfo(&mut self, result: Result<GetMempoolInfoResult, Error>) {
+        let mut list = self.getmempoolinfo_results.borrow_mut();
+        match result {
+            Ok(r) => list.push_front(r),
+            Err(_) => unimplemented!("MockRpc not support testing Error result yet."),
+        }
+    }
+
     pub fn should_call_testproposedblock_and_returns_invalid_block_error(&mut self) {
         let err = Error::JsonRpc(jsonrpc::error::Error::Rpc(jsonrpc::error::RpcError {
             code: -25,
@@ -141,4 +156,26 @@ impl TapyrusApi for MockRpc {
             .expect("Unexpected RPC call method=getblockchaininfo");
         Ok(result)
     }
+
+    fn getblockhash(&self, height: u64) -> Result<BlockHash, Error> {
+        panic!(
+            "Unexpected RPC call method=getblockhash, args(height={})",
+            height
+        )
+    }
+
+    fn getblockheader(&self, hash: &BlockHash) -> Result<BlockHeader, Error> {
+        panic!(
+            "Unexpected RPC call method=getblockheader, args(hash={})",
+            hash
+        )
+    }
+
+    fn getmempoolinfo(&self) -> Result<GetMempoolInfoResult, Error> {
+        let mut list = self.getmempoolinfo_results.borrow_mut();
+        let result = list
+            .pop_back()
+            .expect("Unexpected RPC call method=getmempoolinfo");
+        Ok(result)
+    }
 }
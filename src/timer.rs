@@ -15,6 +15,34 @@ fn to_thread_safe<T>(r: Receiver<T>) -> ThreadSafeReceiver<T> {
     Arc::new(Mutex::new(r))
 }
 
+/// Clock which goes forward only when it is advanced explicitly. The clones of it refer to the same
+/// time, so a simulation can drive all timers of the nodes at once.
+#[derive(Clone, Debug, Default)]
+pub struct VirtualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        VirtualClock {
+            now: Arc::new(Mutex::new(Duration::from_secs(0))),
+        }
+    }
+
+    /// Returns the elapsed time since the clock was created.
+    pub fn now(&self) -> Duration {
+        *self.now.lock().expect("virtual clock is poisoned")
+    }
+
+    /// Advances the clock to the time. It never goes back.
+    pub fn advance_to(&self, time: Duration) {
+        let mut now = self.now.lock().expect("virtual clock is poisoned");
+        if time > *now {
+            *now = time;
+        }
+    }
+}
+
 pub struct RoundTimeOutObserver {
     name: String,
     timelimit: Duration,
@@ -24,6 +52,10 @@ pub struct RoundTimeOutObserver {
     command_receiver: ThreadSafeReceiver<Command>,
     thread: Option<JoinHandle<()>>,
     state: Arc<RwLock<State>>,
+    /// When the clock is set, the timer doesn't run a thread but gets expired at the deadline on
+    /// the clock.
+    clock: Option<VirtualClock>,
+    deadline: Option<Duration>,
 }
 
 pub enum Command {
@@ -49,9 +81,19 @@ impl RoundTimeOutObserver {
             command_sender,
             command_receiver: to_thread_safe(command_receiver),
             state: Arc::new(RwLock::new(State { started: false })),
+            clock: None,
+            deadline: None,
         }
     }
 
+    /// Creates the timer driven by the virtual clock. It sends the timeout signal when `poll` is
+    /// called after the time limit on the clock.
+    pub fn with_clock(name: &str, timelimit_secs: u64, clock: &VirtualClock) -> Self {
+        let mut observer = RoundTimeOutObserver::new(name, timelimit_secs);
+        observer.clock = Some(clock.clone());
+        observer
+    }
+
     pub fn is_started(&self) -> bool {
         let guard = self
             .state
@@ -73,6 +115,16 @@ impl RoundTimeOutObserver {
         if self.is_started() {
             return Err(Error::TimerAlreadyStarted);
         }
+        if let Some(clock) = &self.clock {
+            log::trace!(
+                "Start Timer name={} timelimit={:?}",
+                self.name,
+                self.timelimit
+            );
+            self.deadline = Some(clock.now() + self.timelimit);
+            self.set_started_state(true);
+            return Ok(());
+        }
         let sender = self.sender.clone();
         let command_receiver = self.command_receiver.clone();
         let timelimit = self.timelimit;
@@ -120,6 +172,11 @@ impl RoundTimeOutObserver {
     }
 
     pub fn stop(&mut self) {
+        if self.clock.is_some() {
+            self.deadline = None;
+            self.set_started_state(false);
+            return;
+        }
         if self.is_started() {
             match self.command_sender.try_send(Command::Stop) {
                 Ok(_) => {
@@ -145,11 +202,36 @@ impl RoundTimeOutObserver {
         self.start()?;
         Ok(())
     }
+
+    /// Changes the time limit. It takes effect when the timer is started next time.
+    pub fn set_timelimit(&mut self, timelimit: Duration) {
+        self.timelimit = timelimit;
+    }
+
+    /// Returns the time on the virtual clock when the timer gets expired.
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    /// Sends the timeout signal if the timer driven by the virtual clock is expired. Returns true
+    /// if the signal is sent.
+    pub fn poll(&mut self) -> bool {
+        match (&self.clock, self.deadline) {
+            (Some(clock), Some(deadline)) if clock.now() >= deadline => {
+                log::trace!("Stop Timer by time out name={}", self.name);
+                self.deadline = None;
+                self.set_started_state(false);
+                self.sender.send(()).is_ok()
+            }
+            _ => false,
+        }
+    }
 }
 
 impl Drop for RoundTimeOutObserver {
     fn drop(&mut self) {
-        // wait thread finished.
+        // Stop the timer not to wait for the time limit, then wait thread finished.
+        self.stop();
         if let Some(handler) = self.thread.take() {
             handler.join().unwrap();
         }
@@ -158,7 +240,7 @@ impl Drop for RoundTimeOutObserver {
 
 #[cfg(test)]
 mod tests {
-    use super::RoundTimeOutObserver;
+    use super::{RoundTimeOutObserver, VirtualClock};
     use std::time::Duration;
 
     #[test]
@@ -211,4 +293,35 @@ mod tests {
             Err(e) => panic!("Timeout signal not received. {:?}", e),
         }
     }
+
+    #[test]
+    fn test_virtual_clock() {
+        let clock = VirtualClock::new();
+        let mut observer = RoundTimeOutObserver::with_clock("test timer", 10, &clock);
+        observer.start().unwrap();
+        assert_eq!(observer.deadline(), Some(Duration::from_secs(10)));
+
+        clock.advance_to(Duration::from_secs(9));
+        assert!(!observer.poll());
+        assert!(observer.receiver.try_recv().is_err());
+
+        clock.advance_to(Duration::from_secs(10));
+        assert!(observer.poll());
+        assert!(observer.receiver.try_recv().is_ok());
+        assert_eq!(observer.is_started(), false);
+        assert_eq!(observer.deadline(), None);
+
+        // The clock never goes back.
+        clock.advance_to(Duration::from_secs(5));
+        assert_eq!(clock.now(), Duration::from_secs(10));
+
+        observer.restart().unwrap();
+        assert_eq!(observer.deadline(), Some(Duration::from_secs(20)));
+        observer.set_timelimit(Duration::from_secs(3));
+        observer.restart().unwrap();
+        assert_eq!(observer.deadline(), Some(Duration::from_secs(13)));
+        observer.stop();
+        clock.advance_to(Duration::from_secs(20));
+        assert!(!observer.poll());
+    }
 }
@@ -1,4 +1,6 @@
-use curv::GE;
+use curv::arithmetic::traits::{Converter, Modulo};
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
 use std::convert::TryFrom;
 use std::os::raw::c_int;
 use std::sync::atomic::AtomicUsize;
@@ -11,6 +13,70 @@ pub fn sum_point(points: &Vec<GE>) -> GE {
     tail.fold(head.clone(), |acc, x| acc + x)
 }
 
+/// Returns the Lagrange coefficient which moves the share at `index` to `target` over `parties`.
+/// All indices are 1-based signer indices. Set `target` to 0 to interpolate the secret itself.
+pub fn lagrange_coefficient(index: usize, target: usize, parties: &[usize]) -> FE {
+    let q = FE::q();
+    let xi = BigInt::from(index as u64);
+    let xr = BigInt::from(target as u64);
+    let (num, denom) = parties.iter().filter(|&&j| j != index).fold(
+        (BigInt::from(1), BigInt::from(1)),
+        |(num, denom), &j| {
+            let xj = BigInt::from(j as u64);
+            (
+                BigInt::mod_mul(&num, &BigInt::mod_sub(&xr, &xj, &q), &q),
+                BigInt::mod_mul(&denom, &BigInt::mod_sub(&xi, &xj, &q), &q),
+            )
+        },
+    );
+    let lambda = BigInt::mod_mul(&num, &BigInt::mod_inv(&denom, &q), &q);
+    ECScalar::from(&lambda)
+}
+
+/// The order of the base field of secp256k1.
+const FIELD_PRIME: &str = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+
+/// Returns whether (x, y) is a point on secp256k1. Unlike `ECPoint::from_coor`, this doesn't panic
+/// for an invalid point, so it can check points from untrusted input.
+pub fn is_on_curve(x: &BigInt, y: &BigInt) -> bool {
+    let p = BigInt::from_str_radix(FIELD_PRIME, 16).expect("invalid field prime");
+    if x >= &p || y >= &p {
+        return false;
+    }
+    let lhs = BigInt::mod_mul(y, y, &p);
+    let rhs = BigInt::mod_add(
+        &BigInt::mod_pow(x, &BigInt::from(3), &p),
+        &BigInt::from(7),
+        &p,
+    );
+    lhs == rhs
+}
+
+/// Returns the point whose x coordinate is `x` and whose y coordinate is a quadratic residue,
+/// which is the point R that a block proof encodes with its x coordinate.
+pub fn lift_x(x: &BigInt) -> Option<GE> {
+    let p = BigInt::from_str_radix(FIELD_PRIME, 16).expect("invalid field prime");
+    if x >= &p {
+        return None;
+    }
+    let c = BigInt::mod_add(
+        &BigInt::mod_pow(x, &BigInt::from(3), &p),
+        &BigInt::from(7),
+        &p,
+    );
+    let y = BigInt::mod_pow(&c, &((p.clone() + BigInt::from(1)) / BigInt::from(4)), &p);
+    if BigInt::mod_mul(&y, &y, &p) != c {
+        return None;
+    }
+    let y = if tapyrus::util::prime::jacobi(&Converter::to_vec(&y)) == 1 {
+        y
+    } else {
+        p - y
+    };
+    let bytes = hex::decode(format!("{:0>64}{:0>64}", x.to_hex(), y.to_hex())).ok()?;
+    GE::from_bytes(&bytes[..]).ok()
+}
+
 const STOP_SIGNALS: [usize; 6] = [
     signal_hook::SIGABRT as usize,
     signal_hook::SIGHUP as usize,
@@ -75,6 +141,46 @@ mod tests {
         assert_eq!(sum, p6);
     }
 
+    #[test]
+    fn test_is_on_curve() {
+        let g: GE = ECPoint::generator();
+        let x = g.x_coor().unwrap();
+        let y = g.y_coor().unwrap();
+        assert!(is_on_curve(&x, &y));
+        assert!(!is_on_curve(&x, &(y + BigInt::from(1))));
+    }
+
+    #[test]
+    fn test_lift_x() {
+        let g: GE = ECPoint::generator();
+        let p = lift_x(&g.x_coor().unwrap()).unwrap();
+        assert_eq!(p.x_coor(), g.x_coor());
+        assert_eq!(
+            tapyrus::util::prime::jacobi(&Converter::to_vec(&p.y_coor().unwrap())),
+            1
+        );
+
+        // There is no point whose x coordinate is 5 on secp256k1.
+        assert!(lift_x(&BigInt::from(5)).is_none());
+    }
+
+    #[test]
+    fn test_lagrange_coefficient() {
+        // f(x) = 3 + 2x, so f(1) = 5, f(2) = 7 and f(3) = 9.
+        let f1: FE = ECScalar::from(&BigInt::from(5));
+        let f2: FE = ECScalar::from(&BigInt::from(7));
+        let f3: FE = ECScalar::from(&BigInt::from(9));
+
+        let l1 = lagrange_coefficient(1, 3, &[1, 2]);
+        let l2 = lagrange_coefficient(2, 3, &[1, 2]);
+        assert_eq!(f1 * l1 + f2 * l2, f3);
+
+        let secret: FE = ECScalar::from(&BigInt::from(3));
+        let l1 = lagrange_coefficient(1, 0, &[1, 3]);
+        let l3 = lagrange_coefficient(3, 0, &[1, 3]);
+        assert_eq!(f1 * l1 + f3 * l3, secret);
+    }
+
     #[test]
     fn test_signals() {
         let handler = set_stop_signal_handler().unwrap();
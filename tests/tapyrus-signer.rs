@@ -4,9 +4,161 @@
 
 #![deny(warnings, missing_docs)]
 
+extern crate tapyrus;
 extern crate tapyrus_signer;
 
+use std::time::Duration;
+use tapyrus::PublicKey;
+use tapyrus_signer::sign::Sign;
+use tapyrus_signer::signer_node::NodeState;
+use tapyrus_signer::simulation::deterministic::{DeterministicSimulation, FaultConfig, Partition};
+use tapyrus_signer::simulation::keys::aggregated_public_key;
+use tapyrus_signer::simulation::{FederationSpec, Simulation};
+
+fn federation(block_height: u64, signers: Vec<usize>, threshold: u8) -> FederationSpec {
+    FederationSpec {
+        block_height,
+        signers,
+        threshold,
+    }
+}
+
+fn aggregated(simulation: &Simulation, signers: &[usize]) -> PublicKey {
+    let public_keys: Vec<PublicKey> = signers.iter().map(|i| simulation.public_key(*i)).collect();
+    aggregated_public_key(&public_keys)
+}
+
+#[test]
+fn test_rounds_complete() {
+    let mut simulation = Simulation::new(0, 5, vec![federation(0, vec![0, 1, 2, 3, 4], 3)]);
+    assert!(simulation.run_until(5, 0));
+    assert_eq!(simulation.round_timeouts(), 0);
+
+    let key = aggregated(&simulation, &[0, 1, 2, 3, 4]);
+    for height in 1..=5 {
+        let block = simulation.chain.block(height).unwrap();
+        assert!(Sign::verify_proof(&block, &key).is_ok());
+    }
+}
+
+#[test]
+fn test_federation_transitions() {
+    let mut simulation = Simulation::new(
+        1,
+        5,
+        vec![
+            federation(0, vec![0, 1, 2, 3, 4], 3),
+            // The node 4 leaves the federation.
+            federation(3, vec![0, 1, 2, 3], 3),
+            // The node 4 joins again and the node 0 leaves.
+            federation(6, vec![1, 2, 3, 4], 2),
+        ],
+    );
+    assert!(simulation.run_until(8, 0));
+
+    let first = aggregated(&simulation, &[0, 1, 2, 3, 4]);
+    let second = aggregated(&simulation, &[0, 1, 2, 3]);
+    let third = aggregated(&simulation, &[1, 2, 3, 4]);
+
+    // The last block of each federation has the aggregated public key of the next one.
+    let block = simulation.chain.block(2).unwrap();
+    assert_eq!(block.header.aggregated_public_key(), Some(second));
+    assert!(Sign::verify_proof(&block, &first).is_ok());
+    let block = simulation.chain.block(5).unwrap();
+    assert_eq!(block.header.aggregated_public_key(), Some(third));
+    assert!(Sign::verify_proof(&block, &second).is_ok());
+    for height in 6..=8 {
+        let block = simulation.chain.block(height).unwrap();
+        assert!(Sign::verify_proof(&block, &third).is_ok());
+    }
+    assert_eq!(simulation.chain.aggregated_public_key(), third);
+
+    match simulation.node(0).current_state() {
+        NodeState::Idling { .. } => {}
+        state => panic!("the node 0 should be idling, but the state is {:?}", state),
+    }
+}
+
+/// The number of randomized runs. It can be raised with `SIMULATION_RUNS` to search more schedules.
+fn simulation_runs() -> u64 {
+    std::env::var("SIMULATION_RUNS")
+        .ok()
+        .and_then(|runs| runs.parse().ok())
+        .unwrap_or(10)
+}
+
+fn faults(seed: u64) -> FaultConfig {
+    FaultConfig {
+        drop_rate: 0.05,
+        duplicate_rate: 0.05,
+        min_delay: Duration::from_millis(0),
+        max_delay: Duration::from_secs(2),
+        // One of the nodes is isolated for the first 10 minutes.
+        partitions: vec![Partition {
+            from: Duration::from_secs(0),
+            until: Duration::from_secs(600),
+            groups: vec![(0..5).filter(|i| *i != (seed % 5) as usize).collect()],
+        }],
+        // Another one is down through the run.
+        crashed: vec![((seed + 1) % 5) as usize],
+    }
+}
+
 #[test]
-fn sample() {
-    assert!(true);
+fn test_randomized_runs_are_safe_and_live() {
+    for seed in 0..simulation_runs() {
+        let mut simulation = DeterministicSimulation::new(
+            seed,
+            5,
+            vec![federation(0, vec![0, 1, 2, 3, 4], 3)],
+            faults(seed),
+        );
+        let live = simulation.run_until(3, Duration::from_secs(24 * 60 * 60));
+        assert!(live, "seed {} got stuck: {:?}", seed, simulation.stats());
+        assert!(
+            simulation.conflicting_heights().is_empty(),
+            "seed {} signed conflicting blocks at {:?}",
+            seed,
+            simulation.conflicting_heights()
+        );
+    }
+}
+
+#[test]
+fn test_same_seed_same_schedule() {
+    let run = |seed| {
+        let mut simulation = DeterministicSimulation::new(
+            seed,
+            5,
+            vec![federation(0, vec![0, 1, 2, 3, 4], 3)],
+            faults(seed),
+        );
+        assert!(simulation.run_until(2, Duration::from_secs(24 * 60 * 60)));
+        (simulation.now(), simulation.stats().clone())
+    };
+    assert_eq!(run(7), run(7));
+}
+
+#[test]
+fn test_no_progress_without_threshold() {
+    let mut simulation = DeterministicSimulation::new(
+        0,
+        5,
+        vec![federation(0, vec![0, 1, 2, 3, 4], 4)],
+        FaultConfig {
+            partitions: vec![Partition {
+                from: Duration::from_secs(0),
+                until: Duration::from_secs(600),
+                groups: vec![vec![0, 1], vec![2, 3, 4]],
+            }],
+            ..FaultConfig::default()
+        },
+    );
+    // Neither side has the threshold while the network is partitioned.
+    simulation.run_for(Duration::from_secs(600));
+    assert_eq!(simulation.chain.height(), 0);
+
+    // The federation makes progress after the partition is healed.
+    assert!(simulation.run_until(2, Duration::from_secs(1200)));
+    assert!(simulation.conflicting_heights().is_empty());
 }